@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use enc_video::devices::ActivatedDevice;
 use enc_video::devices::{VideoDevices, activated_device::Output};
+use enc_video::frame::Frame;
 use enc_video::i_capture::ICapture;
 use enc_video::monitor::Monitor;
 use minifb::{Window, WindowOptions};
@@ -38,18 +39,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 guard.recv().await
             };
 
-            if let Some(raw_data) = data {
-                if raw_data.is_empty() {
+            if let Some(frame) = data {
+                if frame.data.is_empty() {
                     continue;
                 }
 
-                //convert to u32
+                //convert to u32, rows may be padded so use the stride to find each row
+                let stride = frame.stride as usize;
                 let mut frame_u32 = vec![0u32; width * height];
-                for i in 0..width * height {
-                    let b = raw_data[i * 4] as u32;
-                    let g = raw_data[i * 4 + 1] as u32;
-                    let r = raw_data[i * 4 + 2] as u32;
-                    frame_u32[i] = (r << 16) | (g << 8) | b;
+                for y in 0..height {
+                    for x in 0..width {
+                        let i = y * stride + x * 4;
+                        let b = frame.data[i] as u32;
+                        let g = frame.data[i + 1] as u32;
+                        let r = frame.data[i + 2] as u32;
+                        frame_u32[y * width + x] = (r << 16) | (g << 8) | b;
+                    }
                 }
 
                 //try sending, we do not need to send the data, as we can afford to lose frames
@@ -94,14 +99,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 /// This function is not really used within this, but shows how you can return an ICapture which is capable of being interchangeable with the Monitor and or VideoDevice.
 /// This allows you to use the same code in the main whether you use a Monitor or Camera.
-fn get_capture(cap_type: CaptureType) -> Box<Arc<dyn ICapture<CaptureOutput = Vec<u8>>>> {
+fn get_capture(cap_type: CaptureType) -> Box<Arc<dyn ICapture<CaptureOutput = Frame>>> {
     match cap_type {
         CaptureType::Monitor(id) => {
-            let monitor: Arc<Monitor>;
-
-            unsafe {
-                monitor = Monitor::from_monitor(id).expect("Could not get monitor {id}");
-            }
+            let monitor = Monitor::from_monitor(id).expect("Could not get monitor {id}");
 
             Box::new(monitor)
         }
@@ -0,0 +1,155 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+// the buffers waiting to be reused and how many may be kept
+struct PoolInner {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: AtomicUsize,
+}
+
+/// # Buffer Pool
+///
+/// Keeps a number of frame buffers around so they can be reused instead of allocating a new buffer for every frame.
+///
+/// Buffers taken from the pool are returned to it once they are dropped, if a buffer is held onto the pool simply allocates another.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<PoolInner>,
+}
+
+impl BufferPool {
+    /// Create a pool that keeps at most max_buffers buffers for reuse.
+    pub fn new(max_buffers: usize) -> Self {
+        BufferPool {
+            inner: Arc::new(PoolInner {
+                buffers: Mutex::new(vec![]),
+                max_buffers: AtomicUsize::new(max_buffers),
+            }),
+        }
+    }
+
+    /// # Set Max Buffers
+    ///
+    /// Sets how many buffers are kept for reuse, 0 disables pooling.
+    pub fn set_max_buffers(&self, max_buffers: usize) {
+        self.inner.max_buffers.store(max_buffers, Ordering::Relaxed);
+
+        //drop any buffers we should no longer keep
+        self.inner.buffers.lock().unwrap().truncate(max_buffers);
+    }
+
+    /// The number of buffers kept for reuse.
+    pub fn get_max_buffers(&self) -> usize {
+        self.inner.max_buffers.load(Ordering::Relaxed)
+    }
+
+    /// # Take
+    ///
+    /// Takes an empty buffer from the pool with room for at least capacity bytes, allocating one if the pool is empty.
+    pub fn take(&self, capacity: usize) -> PooledBuffer {
+        let mut data = self.inner.buffers.lock().unwrap().pop().unwrap_or_default();
+
+        data.clear();
+        data.reserve(capacity);
+
+        PooledBuffer {
+            data,
+            pool: Arc::downgrade(&self.inner),
+        }
+    }
+}
+
+/// # Pooled Buffer
+///
+/// The data of a frame, it can be used as a Vec<u8>.
+///
+/// If the buffer was taken from a BufferPool it is returned to the pool when dropped.
+#[derive(Debug)]
+pub struct PooledBuffer {
+    data: Vec<u8>,
+    pool: Weak<PoolInner>,
+}
+
+impl PooledBuffer {
+    /// Takes the data out of the buffer, it will not be returned to the pool.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        std::mem::take(&mut self.data)
+    }
+}
+
+impl std::fmt::Debug for PoolInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("max_buffers", &self.max_buffers)
+            .finish()
+    }
+}
+
+impl From<Vec<u8>> for PooledBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        PooledBuffer {
+            data,
+            pool: Weak::new(),
+        }
+    }
+}
+
+impl Clone for PooledBuffer {
+    /// Clones the data, the clone is not returned to any pool.
+    fn clone(&self) -> Self {
+        self.data.clone().into()
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+impl<'a> IntoIterator for &'a PooledBuffer {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl PartialEq<Vec<u8>> for PooledBuffer {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.data == *other
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let Some(pool) = self.pool.upgrade() else {
+            return;
+        };
+
+        //into_vec leaves nothing worth keeping
+        if self.data.capacity() == 0 {
+            return;
+        }
+
+        let mut buffers = pool.buffers.lock().unwrap();
+
+        if buffers.len() < pool.max_buffers.load(Ordering::Relaxed) {
+            buffers.push(std::mem::take(&mut self.data));
+        }
+    }
+}
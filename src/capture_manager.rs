@@ -0,0 +1,286 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    sync::{
+        Barrier, Mutex, mpsc,
+        mpsc::{Receiver, Sender},
+        watch,
+    },
+    task::JoinHandle,
+};
+
+use crate::{frame::Frame, i_capture::ICapture};
+
+/// # Source Event
+///
+/// Sent through the receiver of a CaptureManager, tagged with the id of the source it came from.
+#[derive(Debug)]
+pub enum SourceEvent {
+    /// A frame captured by the source
+    Frame { source: usize, frame: Frame },
+
+    /// The source stopped capturing, the error is set when it stopped because it failed.
+    ///
+    /// The other sources keep capturing.
+    Stopped {
+        source: usize,
+        error: Option<String>,
+    },
+}
+
+/// # Capture Manager
+///
+/// Runs several captures (for example a Monitor per screen) at once and sends all of their frames through one receiver.
+///
+/// Every frame is tagged with the id of its source, the id is returned when the source is added.
+pub struct CaptureManager {
+    sources: Vec<Arc<dyn ICapture<CaptureOutput = Frame>>>,
+
+    receiver: Arc<Mutex<Receiver<SourceEvent>>>,
+    sender: Sender<SourceEvent>,
+
+    // the task forwarding the frames of each source, empty when not capturing
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+
+    // set to true to stop every source
+    stopping: watch::Sender<bool>,
+}
+
+impl CaptureManager {
+    /// Create a manager without any sources.
+    pub fn new() -> Self {
+        //room for a few frames of every source
+        let (tx, rx) = mpsc::channel(8);
+
+        CaptureManager {
+            sources: vec![],
+            receiver: Arc::new(Mutex::new(rx)),
+            sender: tx,
+            tasks: Mutex::new(vec![]),
+            stopping: watch::channel(false).0,
+        }
+    }
+
+    /// # Add
+    ///
+    /// Adds a source to capture, returns the id its frames are tagged with.
+    ///
+    /// Sources added while capturing are started by the next start_all.
+    pub fn add(&mut self, source: Arc<dyn ICapture<CaptureOutput = Frame>>) -> usize {
+        self.sources.push(source);
+        self.sources.len() - 1
+    }
+
+    /// The source with the given id.
+    pub fn get_source(&self, source: usize) -> Option<&Arc<dyn ICapture<CaptureOutput = Frame>>> {
+        self.sources.get(source)
+    }
+
+    /// # Start All
+    ///
+    /// Starts capturing every source, each on its own task, and returns right away.
+    pub async fn start_all(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tasks = self.tasks.lock().await;
+
+        if !tasks.is_empty() {
+            return Err("you are already capturing data".into());
+        }
+
+        self.stopping.send_replace(false);
+
+        for (id, source) in self.sources.iter().enumerate() {
+            tasks.push(tokio::spawn(forward_source(
+                source.clone(),
+                id,
+                self.sender.clone(),
+                self.stopping.subscribe(),
+                None,
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// # Stop All
+    ///
+    /// Stops every source and waits for all of them to exit.
+    pub async fn stop_all(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tasks = self.tasks.lock().await;
+
+        if tasks.is_empty() {
+            return Err("Not sending any data".into());
+        }
+
+        self.stopping.send_replace(true);
+
+        for task in tasks.drain(..) {
+            task.await?;
+        }
+
+        Ok(())
+    }
+
+    /// # Get Receiver
+    ///
+    /// Get the receiver that the frames of every source are sent to.
+    pub fn clone_receiver(&self) -> Arc<Mutex<Receiver<SourceEvent>>> {
+        self.receiver.clone()
+    }
+}
+
+impl Default for CaptureManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// waits until stopping is set
+async fn stop_requested(stopping: &mut watch::Receiver<bool>) {
+    let _ = stopping.wait_for(|stopping| *stopping).await;
+}
+
+/// Captures a source and forwards its frames until stopping is set or the source stops, a Stopped event is sent last.
+///
+/// With a start barrier the capture is only started once every source waiting on it is ready, so they start together. The source's receiver is locked after the barrier, a source whose receiver is held elsewhere starts once it is released.
+pub(crate) async fn forward_source<T: ICapture<CaptureOutput = Frame> + ?Sized + 'static>(
+    source: Arc<T>,
+    id: usize,
+    forward: Sender<SourceEvent>,
+    mut stopping: watch::Receiver<bool>,
+    start: Option<Arc<Barrier>>,
+) {
+    //a receiver held elsewhere, such as by a task reading frames, only holds up this source and not the ones it starts with
+    if let Some(start) = start {
+        start.wait().await;
+    }
+
+    let receiver = source.clone_receiver();
+
+    let mut receiver = tokio::select! {
+        receiver = receiver.lock() => receiver,
+        _ = stop_requested(&mut stopping) => {
+            let _ = forward.try_send(SourceEvent::Stopped { source: id, error: None });
+            return;
+        }
+    };
+
+    //errors are kept as strings since boxed errors cannot be held between tasks
+    let capturing = source.clone().start_capturing();
+    let capture = async move { capturing.await.err().map(|e| e.to_string()) };
+    tokio::pin!(capture);
+
+    let mut stop_sent = false;
+
+    let error = loop {
+        tokio::select! {
+            //the capture is polled first so it has started before it is stopped
+            biased;
+
+            error = &mut capture => break error,
+            _ = stop_requested(&mut stopping), if !stop_sent => {
+                stop_sent = true;
+                let _ = source.clone().stop_capturing().await;
+            }
+            Some(frame) = receiver.recv() => {
+                let event = SourceEvent::Frame { source: id, frame };
+
+                //no one may be reading once stopping, so do not wait for room then
+                tokio::select! {
+                    _ = forward.send(event) => {}
+                    _ = stop_requested(&mut stopping) => {}
+                }
+            }
+        }
+    };
+
+    let event = SourceEvent::Stopped { source: id, error };
+
+    tokio::select! {
+        _ = forward.send(event) => {}
+        _ = stop_requested(&mut stopping) => {}
+    }
+}
+
+/// # Frame Matcher
+///
+/// Matches up frames of several sources that were captured at nearly the same time, such as the two cameras of a stereo rig (see CameraGroup).
+///
+/// Push every frame with the source it came from and when it was captured, a set with one frame of every source is returned once their times are all within the tolerance of each other.
+/// Frames that can no longer be matched, because every other source already sent a later frame, are dropped and counted as unmatched.
+pub struct FrameMatcher {
+    tolerance: Duration,
+
+    // the frames of each source not matched yet, oldest first
+    pending: Vec<VecDeque<(Instant, Frame)>>,
+
+    unmatched: u64,
+}
+
+impl FrameMatcher {
+    // how many frames of a source are held while another source sends none, older ones are dropped
+    const MAX_PENDING: usize = 32;
+
+    /// # New
+    ///
+    /// A matcher of frames of the number of sources, with ids from 0, whose capture times are at most the tolerance apart. Half the time between two frames is a good tolerance.
+    pub fn new(sources: usize, tolerance: Duration) -> Self {
+        FrameMatcher {
+            tolerance,
+            pending: (0..sources).map(|_| VecDeque::new()).collect(),
+            unmatched: 0,
+        }
+    }
+
+    /// # Push
+    ///
+    /// Adds a frame of the source captured at the time, returns the frames of every source by their id once they match.
+    ///
+    /// Frames of a source must be pushed in the order they were captured, frames of unknown sources are ignored.
+    pub fn push(&mut self, source: usize, frame: Frame, captured: Instant) -> Option<Vec<Frame>> {
+        let pending = self.pending.get_mut(source)?;
+
+        pending.push_back((captured, frame));
+
+        if pending.len() > Self::MAX_PENDING {
+            pending.pop_front();
+            self.unmatched += 1;
+        }
+
+        while self.pending.iter().all(|frames| !frames.is_empty()) {
+            let times = self.pending.iter().map(|frames| frames[0].0);
+            let (earliest, latest) = (times.clone().min()?, times.max()?);
+
+            if latest.duration_since(earliest) <= self.tolerance {
+                return self
+                    .pending
+                    .iter_mut()
+                    .map(|frames| frames.pop_front().map(|(_, frame)| frame))
+                    .collect();
+            }
+
+            //every other frame of its source is later still, the earliest frame can not be matched anymore
+            let source = self
+                .pending
+                .iter()
+                .position(|frames| frames[0].0 == earliest)?;
+            self.pending[source].pop_front();
+            self.unmatched += 1;
+        }
+
+        None
+    }
+
+    /// The number of frames dropped without a match.
+    pub fn unmatched(&self) -> u64 {
+        self.unmatched
+    }
+
+    /// Drops the frames waiting for a match, such as when the sources are started again.
+    pub fn clear(&mut self) {
+        self.pending.iter_mut().for_each(VecDeque::clear);
+    }
+}
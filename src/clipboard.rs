@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE},
+    Graphics::Gdi::{BI_BITFIELDS, BITMAPV5HEADER, LCS_GM_IMAGES},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock},
+        Ole::CF_DIBV5,
+    },
+    UI::ColorSystem::LCS_sRGB,
+};
+
+use crate::frame::{Frame, PixelFormat};
+
+// how many times the clipboard is tried while another application has it open, and the time between tries
+const OPEN_ATTEMPTS: u32 = 10;
+const OPEN_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// # Frame To DIB
+///
+/// The frame as a packed 32 bit DIB: a BITMAPV5HEADER followed by the rows from the bottom up, without row padding. This is the CF_DIBV5 clipboard format.
+///
+/// BGRA, RGBA, RGB32, RGB24, BGR24 and Gray8 frames of monitors and cameras can be converted, with or without row padding. YUV, MJPG, RGBA16F and 16 bit gray and depth frames are refused, tone map RGBA16F frames first (see tone_map_frame).
+///
+/// The image is always opaque, desktop duplication leaves the alpha of some content undefined and RGB32 has none.
+pub fn frame_to_dib(frame: &Frame) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if matches!(
+        frame.format,
+        PixelFormat::NV12
+            | PixelFormat::YUY2
+            | PixelFormat::I420
+            | PixelFormat::MJPG
+            | PixelFormat::RGBA16F
+            | PixelFormat::Gray16
+            | PixelFormat::Depth16
+    ) {
+        return Err(format!(
+            "{:?} frames can not be put on the clipboard, convert them first",
+            frame.format
+        )
+        .into());
+    }
+
+    if frame.width == 0 || frame.height == 0 {
+        return Err("the frame is empty".into());
+    }
+
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let pixel_bytes = frame.format.bytes_per_pixel() as usize;
+    let row_bytes = width * pixel_bytes;
+    let stride = frame.stride as usize;
+    let needed = stride * (height - 1) + row_bytes;
+
+    if stride < row_bytes || frame.data.len() < needed {
+        return Err(format!(
+            "the frame has {} bytes with a stride of {}, {needed} are needed for its size",
+            frame.data.len(),
+            frame.stride
+        )
+        .into());
+    }
+
+    let header = BITMAPV5HEADER {
+        bV5Size: size_of::<BITMAPV5HEADER>() as u32,
+        bV5Width: frame.width as i32,
+        //a positive height is a bottom up image, which every application reading the clipboard understands
+        bV5Height: frame.height as i32,
+        bV5Planes: 1,
+        bV5BitCount: 32,
+        bV5Compression: BI_BITFIELDS,
+        bV5SizeImage: (width * height * 4) as u32,
+        bV5RedMask: 0x00FF_0000,
+        bV5GreenMask: 0x0000_FF00,
+        bV5BlueMask: 0x0000_00FF,
+        bV5AlphaMask: 0xFF00_0000,
+        bV5CSType: LCS_sRGB.0 as u32,
+        bV5Intent: LCS_GM_IMAGES as u32,
+        ..Default::default()
+    };
+
+    let mut dib = Vec::with_capacity(header.bV5Size as usize + width * height * 4);
+
+    //the header is plain old data without padding
+    dib.extend_from_slice(unsafe {
+        std::slice::from_raw_parts(
+            &header as *const BITMAPV5HEADER as *const u8,
+            size_of::<BITMAPV5HEADER>(),
+        )
+    });
+
+    for row in frame.data[..needed].chunks(stride).rev() {
+        let pixels = row[..row_bytes].chunks_exact(pixel_bytes);
+
+        match frame.format {
+            PixelFormat::RGBA | PixelFormat::RGB24 => pixels
+                .for_each(|pixel| dib.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 0xFF])),
+            PixelFormat::Gray8 => pixels
+                .for_each(|pixel| dib.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 0xFF])),
+            //BGRA, RGB32 and BGR24
+            _ => pixels
+                .for_each(|pixel| dib.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 0xFF])),
+        }
+    }
+
+    Ok(dib)
+}
+
+/// # Copy To Clipboard
+///
+/// Replaces the contents of the clipboard with the frame as an image, so it can be pasted into other applications. See frame_to_dib for the frames that can be copied.
+///
+/// The image is set as CF_DIBV5, Windows converts it to CF_DIB and CF_BITMAP for applications that ask for those.
+///
+/// While another application has the clipboard open this waits for it a short time before failing.
+pub fn copy_to_clipboard(frame: &Frame) -> Result<(), Box<dyn std::error::Error>> {
+    let dib = frame_to_dib(frame)?;
+
+    unsafe {
+        let memory = GlobalAlloc(GMEM_MOVEABLE, dib.len())?;
+
+        let locked = GlobalLock(memory);
+        if locked.is_null() {
+            let error = windows::core::Error::from_thread();
+            let _ = GlobalFree(Some(memory));
+            return Err(error.into());
+        }
+
+        std::ptr::copy_nonoverlapping(dib.as_ptr(), locked as *mut u8, dib.len());
+
+        //fails with NO_ERROR once the memory is unlocked, there is nothing to handle
+        let _ = GlobalUnlock(memory);
+
+        if let Err(e) = open_clipboard() {
+            let _ = GlobalFree(Some(memory));
+            return Err(e.into());
+        }
+
+        let result = EmptyClipboard()
+            .and_then(|_| SetClipboardData(CF_DIBV5.0 as u32, Some(HANDLE(memory.0))));
+
+        let _ = CloseClipboard();
+
+        //the clipboard owns the memory once it was set, it is only ours to free when that failed
+        if let Err(e) = result {
+            let _ = GlobalFree(Some(memory));
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+// opens the clipboard, trying again while another application has it open
+unsafe fn open_clipboard() -> Result<(), windows::core::Error> {
+    let mut attempt = 1;
+
+    loop {
+        match unsafe { OpenClipboard(None) } {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt >= OPEN_ATTEMPTS => return Err(e),
+            Err(_) => {
+                attempt += 1;
+                std::thread::sleep(OPEN_RETRY_INTERVAL);
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use crate::frame::{ColorSpace, Frame, PixelFormat};
+
+pub(crate) mod simd;
+
+/// # Yuv Matrix
+///
+/// The coefficients YUV pixels were encoded with, needed to turn them back into RGB. Both are limited range, luma from 16 to 235 and chroma from 16 to 240, as cameras send them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum YuvMatrix {
+    /// The matrix of SD video, used by most webcams at 640x480 and below.
+    #[default]
+    Bt601,
+    /// The matrix of HD video, used by most cameras at 1280x720 and above.
+    Bt709,
+}
+
+impl YuvMatrix {
+    /// The matrix video of the size is usually encoded with, BT.709 from 1280x720 up and BT.601 below.
+    pub fn for_size(width: u32, height: u32) -> Self {
+        if width >= 1280 || height >= 720 {
+            YuvMatrix::Bt709
+        } else {
+            YuvMatrix::Bt601
+        }
+    }
+
+    // the factors of luma, of red chroma for red, of blue and red chroma for green and of blue chroma for blue, in 256ths
+    fn rgb_factors(&self) -> (i32, i32, i32, i32, i32) {
+        match self {
+            YuvMatrix::Bt601 => (298, 409, 100, 208, 516),
+            YuvMatrix::Bt709 => (298, 459, 55, 136, 541),
+        }
+    }
+
+    // the factors of red, green and blue in luma, blue chroma and red chroma, in 256ths
+    fn yuv_factors(&self) -> [[i32; 3]; 3] {
+        match self {
+            YuvMatrix::Bt601 => [[66, 129, 25], [-38, -74, 112], [112, -94, -18]],
+            YuvMatrix::Bt709 => [[47, 157, 16], [-26, -87, 112], [112, -102, -10]],
+        }
+    }
+
+    // the red, green and blue of a pixel
+    fn rgb(&self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let (luma, red_v, green_u, green_v, blue_u) = self.rgb_factors();
+        let (y, u, v) = (y as i32 - 16, u as i32 - 128, v as i32 - 128);
+
+        let y = luma * y + 128;
+        let clamp = |value: i32| (value >> 8).clamp(0, 255) as u8;
+
+        [
+            clamp(y + red_v * v),
+            clamp(y - green_u * u - green_v * v),
+            clamp(y + blue_u * u),
+        ]
+    }
+}
+
+/// # Set SIMD Enabled
+///
+/// Whether conversions use their AVX2 code on CPUs that have it, on by default. The scalar code gives the same results and is used on every other CPU.
+///
+/// Applies to the conversions of this module and to the BGRA to RGBA and Gray8 conversions of monitors, such as to compare their speed.
+pub fn set_simd_enabled(enabled: bool) {
+    simd::set_enabled(enabled);
+}
+
+/// # SIMD Enabled
+///
+/// Whether conversions currently use AVX2, false when it was turned off with set_simd_enabled or the CPU does not have it.
+pub fn simd_enabled() -> bool {
+    simd::enabled()
+}
+
+/// # Convert NV12
+///
+/// Converts NV12 data of the size to packed pixels of the format, without row padding. BGRA, RGBA, RGB32, RGB24 and BGR24 can be converted to, the alpha is always opaque.
+///
+/// The stride is the number of bytes in a row of the luma plane, the chroma plane starts right after height rows of it as Media Foundation lays NV12 out. Odd widths and heights are fine, the last column and row then share the chroma of the one before.
+///
+/// Works on any data, see nv12_to_bgra to convert a Frame. Fails for other formats and when the data is too short for the size.
+pub fn convert_nv12(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    matrix: YuvMatrix,
+    format: PixelFormat,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    check_rgb_format(PixelFormat::NV12, format)?;
+
+    let (width, height, stride) = (width as usize, height as usize, stride as usize);
+    let chroma_offset = check_nv12(data.len(), width, height, stride)?;
+    let pixel_bytes = format.bytes_per_pixel() as usize;
+
+    let mut converted = vec![0; width * height * pixel_bytes];
+
+    if width == 0 {
+        return Ok(converted);
+    }
+
+    for (y, out) in converted.chunks_exact_mut(width * pixel_bytes).enumerate() {
+        let luma = &data[y * stride..][..width];
+        let chroma = &data[chroma_offset + y / 2 * stride..][..width.div_ceil(2) * 2];
+
+        let converted = simd::nv12_row(luma, chroma, matrix, format, out);
+
+        for x in converted..width {
+            let rgb = matrix.rgb(luma[x], chroma[x / 2 * 2], chroma[x / 2 * 2 + 1]);
+            write_pixel(rgb, format, &mut out[x * pixel_bytes..]);
+        }
+    }
+
+    Ok(converted)
+}
+
+/// # Convert YUY2
+///
+/// Converts YUY2 data of the size to packed pixels of the format, like convert_nv12. Every two pixels are 4 bytes of luma, blue chroma, luma and red chroma, rows of odd widths end with a whole pair.
+///
+/// Fails for formats that can not be converted to and when the data is too short for the size.
+pub fn convert_yuy2(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    matrix: YuvMatrix,
+    format: PixelFormat,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    check_rgb_format(PixelFormat::YUY2, format)?;
+
+    let (width, height, stride) = (width as usize, height as usize, stride as usize);
+    let row_bytes = width.div_ceil(2) * 4;
+    let needed = match height {
+        0 => 0,
+        height => stride * (height - 1) + row_bytes,
+    };
+
+    if stride < row_bytes || data.len() < needed {
+        return Err(format!(
+            "the YUY2 data has {} bytes with a stride of {stride}, {needed} are needed for its size",
+            data.len()
+        )
+        .into());
+    }
+
+    let pixel_bytes = format.bytes_per_pixel() as usize;
+    let mut converted = vec![0; width * height * pixel_bytes];
+
+    if width == 0 {
+        return Ok(converted);
+    }
+
+    for (y, out) in converted.chunks_exact_mut(width * pixel_bytes).enumerate() {
+        let row = &data[y * stride..][..row_bytes];
+
+        let converted = simd::yuy2_row(row, matrix, format, out);
+
+        for x in converted..width {
+            let pair = &row[x / 2 * 4..][..4];
+            let rgb = matrix.rgb(row[x * 2], pair[1], pair[3]);
+            write_pixel(rgb, format, &mut out[x * pixel_bytes..]);
+        }
+    }
+
+    Ok(converted)
+}
+
+/// # BGRA To NV12
+///
+/// Converts BGRA (or RGB32) data of the size to NV12, the stride of the result is the width rounded up to an even number. Each chroma sample is the average of the 2x2 block of pixels it covers.
+///
+/// The inverse of convert_nv12, such as for handing frames to an encoder. Fails when the data is too short for the size.
+pub fn bgra_to_nv12(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    matrix: YuvMatrix,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let (width, height, stride) = (width as usize, height as usize, stride as usize);
+    let needed = match height {
+        0 => 0,
+        height => stride * (height - 1) + width * 4,
+    };
+
+    if stride < width * 4 || data.len() < needed {
+        return Err(format!(
+            "the data has {} bytes with a stride of {stride}, {needed} are needed for its size",
+            data.len()
+        )
+        .into());
+    }
+
+    let [luma, blue_chroma, red_chroma] = matrix.yuv_factors();
+    let apply = |factors: [i32; 3], [red, green, blue]: [i32; 3]| {
+        (factors[0] * red + factors[1] * green + factors[2] * blue + 128) >> 8
+    };
+
+    let pixel = |x: usize, y: usize| {
+        let pixel = &data[y * stride + x * 4..];
+        [pixel[2] as i32, pixel[1] as i32, pixel[0] as i32]
+    };
+
+    //a row of chroma holds a pair of bytes for every two pixels, odd widths pad the rows of luma to match
+    let nv12_stride = width.div_ceil(2) * 2;
+    let mut converted = Vec::with_capacity(nv12_stride * (height + height.div_ceil(2)));
+
+    for y in 0..height {
+        for x in 0..nv12_stride {
+            let pixel = pixel(x.min(width - 1), y);
+            converted.push((apply(luma, pixel) + 16).clamp(0, 255) as u8);
+        }
+    }
+
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            //the last column and row of odd sizes cover a single pixel
+            let block = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                .map(|(x, y)| pixel(x.min(width - 1), y.min(height - 1)));
+            let average = [0, 1, 2]
+                .map(|channel| (block.iter().map(|pixel| pixel[channel]).sum::<i32>() + 2) / 4);
+
+            converted.push((apply(blue_chroma, average) + 128).clamp(0, 255) as u8);
+            converted.push((apply(red_chroma, average) + 128).clamp(0, 255) as u8);
+        }
+    }
+
+    Ok(converted)
+}
+
+/// # NV12 To BGRA
+///
+/// Converts an NV12 frame, such as one of a camera set to Output::NV12, to a BGRA frame without row padding. See convert_nv12.
+///
+/// Everything else about the frame, such as its times and sequence, is kept. Fails for frames that are not NV12.
+pub fn nv12_to_bgra(frame: &Frame, matrix: YuvMatrix) -> Result<Frame, Box<dyn std::error::Error>> {
+    convert_nv12_frame(frame, matrix, PixelFormat::BGRA)
+}
+
+/// # YUY2 To BGRA
+///
+/// Converts a YUY2 frame, such as one of a camera set to Output::YUY2, to a BGRA frame without row padding. See convert_yuy2.
+///
+/// Everything else about the frame is kept. Fails for frames that are not YUY2.
+pub fn yuy2_to_bgra(frame: &Frame, matrix: YuvMatrix) -> Result<Frame, Box<dyn std::error::Error>> {
+    if frame.format != PixelFormat::YUY2 {
+        return Err(format!("{:?} frames are not YUY2", frame.format).into());
+    }
+
+    let data = convert_yuy2(
+        &frame.data,
+        frame.width,
+        frame.height,
+        frame.stride,
+        matrix,
+        PixelFormat::BGRA,
+    )?;
+
+    Ok(converted_frame(frame, PixelFormat::BGRA, data))
+}
+
+/// # NV12 To RGBA
+///
+/// Like nv12_to_bgra, but the frame is converted to RGBA.
+pub fn nv12_to_rgba(frame: &Frame, matrix: YuvMatrix) -> Result<Frame, Box<dyn std::error::Error>> {
+    convert_nv12_frame(frame, matrix, PixelFormat::RGBA)
+}
+
+/// # NV12 To RGB
+///
+/// Like nv12_to_bgra, but the frame is converted to 3 byte RGB24.
+pub fn nv12_to_rgb(frame: &Frame, matrix: YuvMatrix) -> Result<Frame, Box<dyn std::error::Error>> {
+    convert_nv12_frame(frame, matrix, PixelFormat::RGB24)
+}
+
+// converts the NV12 frame to the format
+fn convert_nv12_frame(
+    frame: &Frame,
+    matrix: YuvMatrix,
+    format: PixelFormat,
+) -> Result<Frame, Box<dyn std::error::Error>> {
+    if frame.format != PixelFormat::NV12 {
+        return Err(format!("{:?} frames are not NV12", frame.format).into());
+    }
+
+    let data = convert_nv12(
+        &frame.data,
+        frame.width,
+        frame.height,
+        frame.stride,
+        matrix,
+        format,
+    )?;
+
+    Ok(converted_frame(frame, format, data))
+}
+
+// the frame with its data converted to the format, keeping the rest of it
+fn converted_frame(frame: &Frame, format: PixelFormat, data: Vec<u8>) -> Frame {
+    Frame {
+        width: frame.width,
+        height: frame.height,
+        stride: frame.width * format.bytes_per_pixel(),
+        format,
+        timestamp: frame.timestamp,
+        sequence: frame.sequence,
+        data: data.into(),
+        accumulated_frames: frame.accumulated_frames,
+        last_present_time: frame.last_present_time,
+        dirty_rects: frame.dirty_rects.clone(),
+        move_rects: frame.move_rects.clone(),
+        protected_content: frame.protected_content,
+        rects_coalesced: frame.rects_coalesced,
+        pointer: frame.pointer.clone(),
+        dimensions_changed: frame.dimensions_changed,
+        color_space: Some(ColorSpace::Srgb),
+        sample_time: frame.sample_time,
+        sample_duration: frame.sample_duration,
+        stream_index: frame.stream_index,
+    }
+}
+
+// fails for formats YUV frames can not be converted to
+fn check_rgb_format(
+    from: PixelFormat,
+    format: PixelFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        PixelFormat::BGRA
+        | PixelFormat::RGBA
+        | PixelFormat::RGB32
+        | PixelFormat::RGB24
+        | PixelFormat::BGR24 => Ok(()),
+        _ => Err(format!("{from:?} can not be converted to {format:?}").into()),
+    }
+}
+
+// writes the pixel at the start of out in the format, the alpha is opaque
+fn write_pixel([red, green, blue]: [u8; 3], format: PixelFormat, out: &mut [u8]) {
+    match format {
+        PixelFormat::RGBA => out[..4].copy_from_slice(&[red, green, blue, 0xFF]),
+        PixelFormat::RGB24 => out[..3].copy_from_slice(&[red, green, blue]),
+        PixelFormat::BGR24 => out[..3].copy_from_slice(&[blue, green, red]),
+        //BGRA and RGB32
+        _ => out[..4].copy_from_slice(&[blue, green, red, 0xFF]),
+    }
+}
+
+// the offset of the chroma plane, failing when the data is too short for the size
+fn check_nv12(
+    len: usize,
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let chroma_offset = stride * height;
+    let needed = match height {
+        0 => 0,
+        height => chroma_offset + stride * (height.div_ceil(2) - 1) + width.div_ceil(2) * 2,
+    };
+
+    if stride < width.div_ceil(2) * 2 || len < needed {
+        return Err(format!(
+            "the NV12 data has {len} bytes with a stride of {stride}, {needed} are needed for its size"
+        )
+        .into());
+    }
+
+    Ok(chroma_offset)
+}
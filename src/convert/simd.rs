@@ -0,0 +1,328 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{convert::YuvMatrix, frame::PixelFormat};
+
+// cleared with set_simd_enabled to force the scalar code
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub(crate) fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// whether the AVX2 kernels are used, the detection macro only asks the CPU once
+pub(crate) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) && avx2_detected()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn avx2_detected() -> bool {
+    std::arch::is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2_detected() -> bool {
+    false
+}
+
+// how far red, green and blue are shifted into a 4 byte pixel of the format, None for formats the kernels do not write
+#[cfg(target_arch = "x86_64")]
+fn channel_shifts(format: PixelFormat) -> Option<[i32; 3]> {
+    match format {
+        PixelFormat::BGRA | PixelFormat::RGB32 => Some([16, 8, 0]),
+        PixelFormat::RGBA => Some([0, 8, 16]),
+        _ => None,
+    }
+}
+
+// the kernels below convert the first pixels of a row 8 at a time and return how many they converted, the caller converts the rest with the scalar code
+
+// converts the first pixels of a row of NV12 luma and its row of chroma to 4 byte pixels of the format
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn nv12_row(
+    luma: &[u8],
+    chroma: &[u8],
+    matrix: YuvMatrix,
+    format: PixelFormat,
+    out: &mut [u8],
+) -> usize {
+    if let Some(shifts) = channel_shifts(format)
+        && enabled()
+    {
+        let pixels = luma.len().min(chroma.len()).min(out.len() / 4) / 8 * 8;
+
+        //every slice holds the pixels read or written, checked above
+        unsafe { nv12_avx2(luma, chroma, matrix.rgb_factors(), shifts, out, pixels) };
+
+        return pixels;
+    }
+
+    0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn nv12_row(
+    _luma: &[u8],
+    _chroma: &[u8],
+    _matrix: YuvMatrix,
+    _format: PixelFormat,
+    _out: &mut [u8],
+) -> usize {
+    0
+}
+
+// converts the first pixels of a row of YUY2 to 4 byte pixels of the format
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn yuy2_row(
+    row: &[u8],
+    matrix: YuvMatrix,
+    format: PixelFormat,
+    out: &mut [u8],
+) -> usize {
+    if let Some(shifts) = channel_shifts(format)
+        && enabled()
+    {
+        let pixels = (row.len() / 2).min(out.len() / 4) / 8 * 8;
+
+        unsafe { yuy2_avx2(row, matrix.rgb_factors(), shifts, out, pixels) };
+
+        return pixels;
+    }
+
+    0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn yuy2_row(
+    _row: &[u8],
+    _matrix: YuvMatrix,
+    _format: PixelFormat,
+    _out: &mut [u8],
+) -> usize {
+    0
+}
+
+// copies the first BGRA pixels of the source to the destination, swapping red and blue (to RGBA) when asked and or'ing the alpha mask into the alpha
+#[cfg(all(feature = "monitor", target_arch = "x86_64"))]
+pub(crate) fn swizzle_row(
+    source: &[u8],
+    swap: bool,
+    alpha_mask: u8,
+    destination: &mut [u8],
+) -> usize {
+    if enabled() {
+        let pixels = source.len().min(destination.len()) / 4 / 8 * 8;
+
+        unsafe { swizzle_avx2(source, swap, alpha_mask, destination, pixels) };
+
+        return pixels;
+    }
+
+    0
+}
+
+#[cfg(all(feature = "monitor", not(target_arch = "x86_64")))]
+pub(crate) fn swizzle_row(
+    _source: &[u8],
+    _swap: bool,
+    _alpha_mask: u8,
+    _destination: &mut [u8],
+) -> usize {
+    0
+}
+
+// weighs the first BGRA pixels of the source to one byte of gray each, the weights are in 256ths as GrayWeights::fixed_point
+#[cfg(all(feature = "monitor", target_arch = "x86_64"))]
+pub(crate) fn gray_row(source: &[u8], weights: (u32, u32, u32), destination: &mut [u8]) -> usize {
+    if enabled() {
+        let pixels = (source.len() / 4).min(destination.len()) / 8 * 8;
+
+        unsafe { gray_avx2(source, weights, destination, pixels) };
+
+        return pixels;
+    }
+
+    0
+}
+
+#[cfg(all(feature = "monitor", not(target_arch = "x86_64")))]
+pub(crate) fn gray_row(
+    _source: &[u8],
+    _weights: (u32, u32, u32),
+    _destination: &mut [u8],
+) -> usize {
+    0
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn nv12_avx2(
+    luma: &[u8],
+    chroma: &[u8],
+    factors: (i32, i32, i32, i32, i32),
+    shifts: [i32; 3],
+    out: &mut [u8],
+    pixels: usize,
+) {
+    //the 4 pairs of chroma loaded for 8 pixels, each pair shared by two of them
+    let u_lanes = _mm256_setr_epi32(0, 0, 2, 2, 4, 4, 6, 6);
+    let v_lanes = _mm256_setr_epi32(1, 1, 3, 3, 5, 5, 7, 7);
+
+    for x in (0..pixels).step_by(8) {
+        unsafe {
+            let y = _mm256_cvtepu8_epi32(_mm_loadl_epi64(luma.as_ptr().add(x).cast()));
+            let uv = _mm256_cvtepu8_epi32(_mm_loadl_epi64(chroma.as_ptr().add(x).cast()));
+
+            let u = _mm256_permutevar8x32_epi32(uv, u_lanes);
+            let v = _mm256_permutevar8x32_epi32(uv, v_lanes);
+
+            let converted = yuv_pixels(y, u, v, factors, shifts);
+            _mm256_storeu_si256(out.as_mut_ptr().add(x * 4).cast(), converted);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn yuy2_avx2(
+    row: &[u8],
+    factors: (i32, i32, i32, i32, i32),
+    shifts: [i32; 3],
+    out: &mut [u8],
+    pixels: usize,
+) {
+    //16 bytes hold 8 pixels as luma, blue chroma, luma, red chroma
+    let luma_bytes = _mm_setr_epi8(0, 2, 4, 6, 8, 10, 12, 14, -1, -1, -1, -1, -1, -1, -1, -1);
+    let u_bytes = _mm_setr_epi8(1, 1, 5, 5, 9, 9, 13, 13, -1, -1, -1, -1, -1, -1, -1, -1);
+    let v_bytes = _mm_setr_epi8(3, 3, 7, 7, 11, 11, 15, 15, -1, -1, -1, -1, -1, -1, -1, -1);
+
+    for x in (0..pixels).step_by(8) {
+        unsafe {
+            let bytes = _mm_loadu_si128(row.as_ptr().add(x * 2).cast());
+
+            let y = _mm256_cvtepu8_epi32(_mm_shuffle_epi8(bytes, luma_bytes));
+            let u = _mm256_cvtepu8_epi32(_mm_shuffle_epi8(bytes, u_bytes));
+            let v = _mm256_cvtepu8_epi32(_mm_shuffle_epi8(bytes, v_bytes));
+
+            let converted = yuv_pixels(y, u, v, factors, shifts);
+            _mm256_storeu_si256(out.as_mut_ptr().add(x * 4).cast(), converted);
+        }
+    }
+}
+
+// the 8 opaque pixels of 8 luma and chroma values, computed exactly like YuvMatrix::rgb
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn yuv_pixels(
+    y: __m256i,
+    u: __m256i,
+    v: __m256i,
+    (luma, red_v, green_u, green_v, blue_u): (i32, i32, i32, i32, i32),
+    [red_shift, green_shift, blue_shift]: [i32; 3],
+) -> __m256i {
+    let y = _mm256_sub_epi32(y, _mm256_set1_epi32(16));
+    let y = _mm256_add_epi32(scale(y, luma), _mm256_set1_epi32(128));
+    let u = _mm256_sub_epi32(u, _mm256_set1_epi32(128));
+    let v = _mm256_sub_epi32(v, _mm256_set1_epi32(128));
+
+    let red = _mm256_add_epi32(y, scale(v, red_v));
+    let green = _mm256_sub_epi32(_mm256_sub_epi32(y, scale(u, green_u)), scale(v, green_v));
+    let blue = _mm256_add_epi32(y, scale(u, blue_u));
+
+    let alpha = _mm256_set1_epi32(0xFF00_0000u32 as i32);
+
+    _mm256_or_si256(
+        _mm256_or_si256(channel(red, red_shift), channel(green, green_shift)),
+        _mm256_or_si256(channel(blue, blue_shift), alpha),
+    )
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn scale(values: __m256i, factor: i32) -> __m256i {
+    _mm256_mullo_epi32(values, _mm256_set1_epi32(factor))
+}
+
+// a channel in 256ths clamped to a byte and shifted to its place in the pixel
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn channel(value: __m256i, shift: i32) -> __m256i {
+    let value = _mm256_srai_epi32::<8>(value);
+    let value = _mm256_min_epi32(
+        _mm256_max_epi32(value, _mm256_setzero_si256()),
+        _mm256_set1_epi32(255),
+    );
+
+    _mm256_sllv_epi32(value, _mm256_set1_epi32(shift))
+}
+
+#[cfg(feature = "monitor")]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn swizzle_avx2(
+    source: &[u8],
+    swap: bool,
+    alpha_mask: u8,
+    destination: &mut [u8],
+    pixels: usize,
+) {
+    let order = if swap {
+        _mm_setr_epi8(2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15)
+    } else {
+        _mm_setr_epi8(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15)
+    };
+
+    //the shuffle stays within each half of the register, so both halves use the same order
+    let order = _mm256_broadcastsi128_si256(order);
+    let alpha = _mm256_set1_epi32((alpha_mask as i32) << 24);
+
+    for x in (0..pixels).step_by(8) {
+        unsafe {
+            let bgra = _mm256_loadu_si256(source.as_ptr().add(x * 4).cast());
+            let converted = _mm256_or_si256(_mm256_shuffle_epi8(bgra, order), alpha);
+
+            _mm256_storeu_si256(destination.as_mut_ptr().add(x * 4).cast(), converted);
+        }
+    }
+}
+
+#[cfg(feature = "monitor")]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn gray_avx2(
+    source: &[u8],
+    (red, green, blue): (u32, u32, u32),
+    destination: &mut [u8],
+    pixels: usize,
+) {
+    let byte = _mm256_set1_epi32(0xFF);
+    let (red, green, blue) = (red as i32, green as i32, blue as i32);
+
+    //the low byte of each of the 8 sums, gathered into the first 8 bytes
+    let low_bytes = _mm256_broadcastsi128_si256(_mm_setr_epi8(
+        0, 4, 8, 12, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
+    ));
+    let halves = _mm256_setr_epi32(0, 4, 0, 0, 0, 0, 0, 0);
+
+    for x in (0..pixels).step_by(8) {
+        unsafe {
+            let bgra = _mm256_loadu_si256(source.as_ptr().add(x * 4).cast());
+
+            let luma = _mm256_add_epi32(
+                _mm256_add_epi32(
+                    scale(_mm256_and_si256(_mm256_srli_epi32::<16>(bgra), byte), red),
+                    scale(_mm256_and_si256(_mm256_srli_epi32::<8>(bgra), byte), green),
+                ),
+                scale(_mm256_and_si256(bgra, byte), blue),
+            );
+            let luma = _mm256_srli_epi32::<8>(_mm256_add_epi32(luma, _mm256_set1_epi32(128)));
+
+            let gray = _mm256_permutevar8x32_epi32(_mm256_shuffle_epi8(luma, low_bytes), halves);
+            _mm_storel_epi64(
+                destination.as_mut_ptr().add(x).cast(),
+                _mm256_castsi256_si128(gray),
+            );
+        }
+    }
+}
@@ -1,29 +1,149 @@
+#[cfg(feature = "monitor")]
+pub mod adapter_info;
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) mod blocking;
+#[cfg(feature = "camera")]
 pub mod camera;
+#[cfg(feature = "camera")]
+pub mod camera_builder;
+#[cfg(feature = "camera")]
+pub mod camera_controls;
+#[cfg(feature = "camera")]
+pub mod camera_format;
+#[cfg(feature = "camera")]
+pub mod camera_group;
+#[cfg(feature = "camera")]
+pub mod camera_transform;
+#[cfg(feature = "camera")]
 pub mod cameras;
+#[cfg(feature = "camera")]
+pub mod capture_error;
+pub mod capture_event;
+#[cfg(feature = "monitor")]
+pub mod capture_frames_error;
+pub mod capture_state;
+pub mod capture_stats;
+#[cfg(feature = "monitor")]
+pub mod color_info;
+pub mod delivery_policy;
+#[cfg(feature = "camera")]
+pub mod device_info;
+#[cfg(feature = "camera")]
+pub mod device_watcher;
 pub mod dimensions;
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) mod fps_counter;
+#[cfg(feature = "graphics-capture")]
+pub mod graphics_capture;
+pub mod latency_stats;
+#[cfg(feature = "monitor")]
 pub mod monitor;
+#[cfg(feature = "monitor")]
 pub mod monitor_frame;
+#[cfg(feature = "monitor")]
 pub mod monitor_info;
+#[cfg(feature = "monitor")]
+mod monitor_scaler;
+pub mod pointer;
+#[cfg(feature = "monitor")]
+pub mod rotation;
+#[cfg(feature = "monitor")]
+pub mod shared_texture;
+#[cfg(feature = "camera")]
+pub mod stream_info;
+#[cfg(feature = "camera")]
+mod video_device;
+#[cfg(feature = "monitor")]
+pub mod virtual_desktop;
 
+#[cfg(feature = "camera")]
 pub use crate::devices::camera::Camera;
+#[cfg(feature = "camera")]
+pub use crate::devices::camera_builder::CameraBuilder;
+#[cfg(feature = "camera")]
+pub use crate::devices::camera_controls::{
+    CameraControls, CameraProperty, ControlError, PropertyRange, PropertyValue,
+};
+#[cfg(feature = "camera")]
+pub use crate::devices::camera_format::{CameraFormat, NegotiatedFormat};
+#[cfg(feature = "camera")]
+pub use crate::devices::camera_group::CameraGroup;
+#[cfg(feature = "camera")]
+pub use crate::devices::camera_transform::CameraTransform;
+#[cfg(feature = "camera")]
 pub use crate::devices::cameras::Cameras;
+#[cfg(feature = "camera")]
+pub use crate::devices::capture_error::CaptureError;
+pub use crate::devices::capture_event::CaptureEvent;
+#[cfg(feature = "monitor")]
+pub use crate::devices::capture_frames_error::CaptureFramesError;
+pub use crate::devices::capture_state::CaptureState;
+pub use crate::devices::capture_stats::CaptureStats;
+#[cfg(feature = "monitor")]
+pub use crate::devices::color_info::ColorInfo;
+pub use crate::devices::delivery_policy::DeliveryPolicy;
+#[cfg(feature = "camera")]
+pub use crate::devices::device_info::DeviceInfo;
+#[cfg(feature = "camera")]
+pub use crate::devices::device_watcher::{DeviceEvent, DeviceWatcher};
 pub use crate::devices::dimensions::Dimensions;
+#[cfg(feature = "graphics-capture")]
+pub use crate::devices::graphics_capture::GraphicsCapture;
+pub use crate::devices::latency_stats::LatencyStats;
+#[cfg(feature = "monitor")]
 pub use crate::devices::monitor::Monitor;
-pub use crate::devices::monitor_frame::MonitorFrame;
-use crate::devices::monitor_info::MonitorInfo;
+#[cfg(feature = "monitor")]
+pub use crate::devices::monitor_frame::{FrameMetadata, MonitorFrame};
+#[cfg(feature = "monitor")]
+pub use crate::devices::pointer::PointerUpdate;
+#[cfg(feature = "monitor")]
+pub use crate::devices::shared_texture::SharedTexture;
+#[cfg(feature = "camera")]
+pub use crate::devices::stream_info::{StreamInfo, StreamKind};
+#[cfg(feature = "monitor")]
+pub use crate::devices::virtual_desktop::VirtualDesktopCapture;
+#[cfg(feature = "monitor")]
+use crate::devices::{adapter_info::AdapterInfo, monitor_info::MonitorInfo};
 
+#[cfg(feature = "camera")]
+use windows::Win32::{
+    Media::MediaFoundation::{
+        IMFActivate, MF_DEVSOURCE_ATTRIBUTE_FRIENDLY_NAME,
+        MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
+    },
+    System::Com::CoTaskMemFree,
+};
+#[cfg(feature = "monitor")]
+use std::collections::HashMap;
+#[cfg(feature = "monitor")]
 use windows::Win32::{
-    Graphics::Gdi::{DISPLAY_DEVICEW, EnumDisplayDevicesW},
-    Media::MediaFoundation::{IMFActivate, MF_DEVSOURCE_ATTRIBUTE_FRIENDLY_NAME},
-    UI::WindowsAndMessaging::{EDD_GET_DEVICE_INTERFACE_NAME, GetSystemMetrics, SM_CMONITORS},
+    Devices::Display::{
+        DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL, DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+        DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME, DISPLAYCONFIG_DEVICE_INFO_HEADER,
+        DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SDR_WHITE_LEVEL,
+        DISPLAYCONFIG_SOURCE_DEVICE_NAME, DISPLAYCONFIG_TARGET_DEVICE_NAME,
+        DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes, QDC_ONLY_ACTIVE_PATHS,
+        QueryDisplayConfig,
+    },
+    Foundation::{ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS},
+    Graphics::Dxgi::{CreateDXGIFactory1, DXGI_OUTPUT_DESC, IDXGIFactory1},
+    Graphics::Gdi::{
+        DEVMODEW, ENUM_CURRENT_SETTINGS, EnumDisplaySettingsW, GetMonitorInfoW, HMONITOR,
+        MONITORINFO, MONITORINFOEXW,
+    },
+    UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+    UI::WindowsAndMessaging::{GetSystemMetrics, MONITORINFOF_PRIMARY, SM_CMONITORS},
 };
+#[cfg(feature = "monitor")]
+use windows::core::PCWSTR;
 
 /// # Get Device Name
 ///
 /// From an activated device retrieves the name of the device that is friendly (meaning readible)
 ///
 /// This can be used to sort and find device names.
-pub unsafe fn get_device_name(device: &IMFActivate) -> Result<String, windows::core::Error> {
+#[cfg(feature = "camera")]
+pub fn get_device_name(device: &IMFActivate) -> Result<String, windows::core::Error> {
     unsafe {
         let mut name_len: u32 = 0;
         let mut pw_name: windows::core::PWSTR = windows::core::PWSTR::null();
@@ -40,56 +160,309 @@ pub unsafe fn get_device_name(device: &IMFActivate) -> Result<String, windows::c
     }
 }
 
+/// # Get Device Symbolic Link
+///
+/// From a device retrieves its symbolic link, a path that identifies the camera for as long as it is plugged into the same port.
+///
+/// Unlike the friendly name it is unique, two cameras of the same model have the same name but different links. See Camera::reconnect.
+#[cfg(feature = "camera")]
+pub fn get_device_symbolic_link(device: &IMFActivate) -> Result<String, windows::core::Error> {
+    unsafe {
+        let mut link_len: u32 = 0;
+        let mut pw_link: windows::core::PWSTR = windows::core::PWSTR::null();
+
+        device.GetAllocatedString(
+            &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
+            &mut pw_link,
+            &mut link_len,
+        )?;
+
+        let link = String::from_utf16_lossy(pw_link.as_wide());
+
+        //the string was allocated for us
+        CoTaskMemFree(Some(pw_link.0 as *const std::ffi::c_void));
+
+        Ok(link)
+    }
+}
+
 /// # Get Monitor Count
 ///
 /// The numer of display monitors on a desktop.
-pub unsafe fn get_monitor_count() -> i32 {
+#[cfg(feature = "monitor")]
+pub fn get_monitor_count() -> i32 {
     unsafe { GetSystemMetrics(SM_CMONITORS) }
 }
 
 /// # Get All Monitor Info
 ///
-/// Retrieves pertinent information about all monitors on your system and returns them as a Vec.
+/// Retrieves pertinent information about all monitors on your system and returns them as a Vec, with every field of MonitorInfo filled in.
 ///
-/// These can then be filitered through and be used to create a Monitor object.
-pub unsafe fn get_all_monitor_info() -> Vec<MonitorInfo> {
-    let mut monitors = vec![];
+/// These can then be filitered through and be used to create a Monitor object. The monitors are the same, in the same order, as those of Monitor::enumerate, which returns the error instead of an empty Vec when DXGI can not be enumerated.
+#[cfg(feature = "monitor")]
+pub fn get_all_monitor_info() -> Vec<MonitorInfo> {
+    list_adapters()
+        .map(|adapters| adapters.into_iter().flat_map(|adapter| adapter.monitors).collect())
+        .unwrap_or_default()
+}
+
+/// # List Adapters
+///
+/// Lists every GPU adapter on your system along with the monitors attached to each.
+///
+/// The monitor indices match Monitor::from_monitor and the adapter and output indices match Monitor::from_adapter_output.
+#[cfg(feature = "monitor")]
+pub fn list_adapters() -> Result<Vec<AdapterInfo>, windows::core::Error> {
+    let mut adapters = vec![];
+
+    //the index of the monitor across all adapters
+    let mut monitor_index = 0;
 
-    //the current index of monitor
-    let mut device_index: u32 = 0;
-    
     unsafe {
-        //loop over all monitors in the system
-        loop {
+        let factory: IDXGIFactory1 = CreateDXGIFactory1()?;
+        let friendly_names = friendly_monitor_names();
+        let white_levels = sdr_white_levels();
 
-            //generate device info
-            let mut device_info = DISPLAY_DEVICEW::default();
+        let mut adapter_index = 0;
 
-            //gets information about the display, returns false if the device index was out of bounds.
-            let exist = EnumDisplayDevicesW(
-                None,
-                device_index,
-                &mut device_info,
-                EDD_GET_DEVICE_INTERFACE_NAME,
-            ).as_bool();
-
-            // no more devices exist.
-            if !exist {
-                break;
+        //EnumAdapters1 fails with DXGI_ERROR_NOT_FOUND once we have run out of adapters
+        while let Ok(adapter) = factory.EnumAdapters1(adapter_index) {
+            let adapter_desc = adapter.GetDesc1()?;
+            let description = String::from_utf16_lossy(&adapter_desc.Description)
+                .trim_end_matches('\0')
+                .to_string();
+
+            let mut monitors = vec![];
+            let mut output_index = 0;
+
+            while let Ok(output) = adapter.EnumOutputs(output_index) {
+                let output_desc = output.GetDesc()?;
+
+                let name = String::from_utf16_lossy(&output_desc.DeviceName)
+                    .trim_end_matches('\0')
+                    .to_string();
+
+                let mut info = MonitorInfo::new(name, description.clone(), monitor_index);
+                info.adapter_index = adapter_index;
+                info.output_index = output_index;
+                info.desktop_coordinates = output_desc.DesktopCoordinates.into();
+                info.attached_to_desktop = output_desc.AttachedToDesktop.as_bool();
+                read_display_details(&mut info, &output_desc);
+
+                if let Some(friendly_name) = friendly_names.get(&info.name) {
+                    info.friendly_name = friendly_name.clone();
+                }
+
+                info.color_info = ColorInfo::from_output(&output);
+                info.sdr_white_level = white_levels.get(&info.name).copied();
+
+                monitors.push(info);
+
+                output_index += 1;
+                monitor_index += 1;
+            }
+
+            adapters.push(AdapterInfo {
+                index: adapter_index,
+                description,
+                luid: adapter_desc.AdapterLuid,
+                dedicated_video_memory: adapter_desc.DedicatedVideoMemory,
+                monitors,
+            });
+
+            adapter_index += 1;
+        }
+    }
+
+    Ok(adapters)
+}
+
+// fills in the primary flag, refresh rate and DPI of a monitor, anything that cannot be read keeps its default
+#[cfg(feature = "monitor")]
+unsafe fn read_display_details(info: &mut MonitorInfo, output_desc: &DXGI_OUTPUT_DESC) {
+    unsafe {
+        let mut monitor_info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+
+        if GetMonitorInfoW(output_desc.Monitor, &mut monitor_info).as_bool() {
+            info.is_primary = monitor_info.dwFlags & MONITORINFOF_PRIMARY != 0;
+        }
+
+        let mut mode = DEVMODEW {
+            dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+
+        //the device name is null terminated, so it can be passed as is
+        if EnumDisplaySettingsW(
+            PCWSTR(output_desc.DeviceName.as_ptr()),
+            ENUM_CURRENT_SETTINGS,
+            &mut mode,
+        )
+        .as_bool()
+        {
+            info.refresh_rate = mode.dmDisplayFrequency;
+        }
+
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+
+        if GetDpiForMonitor(output_desc.Monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).is_ok() {
+            info.dpi = dpi_x;
+            info.scale_factor = dpi_x as f32 / 96.0;
+        }
+    }
+}
+
+// the friendly names of the active monitors, such as "DELL U2720Q", keyed by their device name, such as \\.\DISPLAY1
+// monitors Windows has no friendly name for are left out, so callers fall back to the device name
+#[cfg(feature = "monitor")]
+pub(crate) unsafe fn friendly_monitor_names() -> HashMap<String, String> {
+    let mut names = HashMap::new();
+
+    unsafe {
+        for path in active_display_paths() {
+            let mut target = DISPLAYCONFIG_TARGET_DEVICE_NAME {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+                    size: std::mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32,
+                    adapterId: path.targetInfo.adapterId,
+                    id: path.targetInfo.id,
+                },
+                ..Default::default()
+            };
+
+            let Some(device_name) = source_device_name(&path) else {
+                continue;
+            };
+
+            if DisplayConfigGetDeviceInfo(&mut target.header) != 0 {
+                continue;
             }
 
-            //save and generate a MonitorInfo object
-            let device_name = String::from_utf16_lossy(&device_info.DeviceName);
-            let device_desc = String::from_utf16_lossy(&device_info.DeviceString);
+            let friendly_name = String::from_utf16_lossy(&target.monitorFriendlyDeviceName)
+                .trim_end_matches('\0')
+                .to_string();
 
-            let info = MonitorInfo::new(device_name, device_desc, device_index);
+            //a duplicated display has a path for every monitor showing it, the first one names it
+            if !friendly_name.is_empty() {
+                names.entry(device_name).or_insert(friendly_name);
+            }
+        }
+    }
+
+    names
+}
+
+// the brightness SDR content is shown at in nits, keyed by the device name of the monitor
+// while HDR is on it is the "SDR content brightness" slider of the display settings
+#[cfg(feature = "monitor")]
+pub(crate) unsafe fn sdr_white_levels() -> HashMap<String, f32> {
+    let mut levels = HashMap::new();
+
+    unsafe {
+        for path in active_display_paths() {
+            let mut white_level = DISPLAYCONFIG_SDR_WHITE_LEVEL {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+                    size: std::mem::size_of::<DISPLAYCONFIG_SDR_WHITE_LEVEL>() as u32,
+                    adapterId: path.targetInfo.adapterId,
+                    id: path.targetInfo.id,
+                },
+                ..Default::default()
+            };
+
+            let Some(device_name) = source_device_name(&path) else {
+                continue;
+            };
+
+            //not supported before Windows 10 1709
+            if DisplayConfigGetDeviceInfo(&mut white_level.header) != 0 {
+                continue;
+            }
 
-            //push
-            monitors.push(info);
+            //the level is a multiplier of 80 nits in thousandths
+            let nits = white_level.SDRWhiteLevel as f32 * 80.0 / 1000.0;
 
-            device_index += 1;
+            levels.entry(device_name).or_insert(nits);
         }
     }
 
-    monitors
+    levels
+}
+
+// the paths of the active displays, each leads from a source (a \\.\DISPLAY device) to the monitor plugged into it
+#[cfg(feature = "monitor")]
+unsafe fn active_display_paths() -> Vec<DISPLAYCONFIG_PATH_INFO> {
+    let (mut path_count, mut mode_count) = (0, 0);
+    let mut paths = vec![];
+    let mut modes = vec![];
+
+    unsafe {
+        //the displays can change between getting the sizes and the query, the buffers are then too small and we try again
+        loop {
+            if GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+                != ERROR_SUCCESS
+            {
+                return vec![];
+            }
+
+            paths.resize(path_count as usize, DISPLAYCONFIG_PATH_INFO::default());
+            modes.resize(mode_count as usize, DISPLAYCONFIG_MODE_INFO::default());
+
+            match QueryDisplayConfig(
+                QDC_ONLY_ACTIVE_PATHS,
+                &mut path_count,
+                paths.as_mut_ptr(),
+                &mut mode_count,
+                modes.as_mut_ptr(),
+                None,
+            ) {
+                ERROR_SUCCESS => break,
+                ERROR_INSUFFICIENT_BUFFER => continue,
+                _ => return vec![],
+            }
+        }
+    }
+
+    paths.truncate(path_count as usize);
+    paths
+}
+
+// the \\.\DISPLAY device name of the source of a path
+#[cfg(feature = "monitor")]
+unsafe fn source_device_name(path: &DISPLAYCONFIG_PATH_INFO) -> Option<String> {
+    let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME {
+        header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+            r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+            size: std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32,
+            adapterId: path.sourceInfo.adapterId,
+            id: path.sourceInfo.id,
+        },
+        ..Default::default()
+    };
+
+    if unsafe { DisplayConfigGetDeviceInfo(&mut source.header) } != 0 {
+        return None;
+    }
+
+    Some(
+        String::from_utf16_lossy(&source.viewGdiDeviceName)
+            .trim_end_matches('\0')
+            .to_string(),
+    )
+}
+
+// the device name of a monitor, such as \\.\DISPLAY1, None when the monitor is gone
+#[cfg(feature = "monitor")]
+pub(crate) unsafe fn monitor_device_name(monitor: HMONITOR) -> Option<String> {
+    let mut monitor_info = MONITORINFOEXW::default();
+    monitor_info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+
+    unsafe { GetMonitorInfoW(monitor, &mut monitor_info.monitorInfo).as_bool() }.then(|| {
+        String::from_utf16_lossy(&monitor_info.szDevice)
+            .trim_end_matches('\0')
+            .to_string()
+    })
 }
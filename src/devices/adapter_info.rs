@@ -0,0 +1,24 @@
+use windows::Win32::Foundation::LUID;
+
+use crate::devices::monitor_info::MonitorInfo;
+
+/// # Adapter Info
+///
+/// A GPU (or software adapter) on your system and the monitors attached to it, see list_adapters.
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    /// The index of the adapter, can be passed to Monitor::from_adapter_output.
+    pub index: u32,
+
+    /// The description of the adapter, usually the name of the GPU.
+    pub description: String,
+
+    /// Identifies the adapter, unique until the system restarts.
+    pub luid: LUID,
+
+    /// The number of bytes of memory only the GPU can use.
+    pub dedicated_video_memory: usize,
+
+    /// The monitors attached to the adapter, in the order of their output index.
+    pub monitors: Vec<MonitorInfo>,
+}
@@ -0,0 +1,12 @@
+// runs blocking work, such as waiting on a device or reading an image back from the GPU, on the blocking threads of tokio
+// so the threads running async tasks are never held up by it. A panic in the work is passed on to the caller
+pub(crate) async fn run_blocking<R, F>(work: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
@@ -1,207 +1,2298 @@
-use std::{pin::Pin, sync::Arc};
+use std::{
+    ffi::c_void,
+    pin::Pin,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use tokio::sync::{
-    Mutex,
+    Mutex, broadcast,
     mpsc::{self, Receiver, Sender},
+    watch,
 };
 use windows::Win32::{
-    Foundation::E_ABORT,
+    Foundation::{E_ABORT, E_FAIL, E_UNEXPECTED},
+    Media::KernelStreaming::{
+        IKsControl, KS_VideoControlFlag_Trigger, KSIDENTIFIER, KSIDENTIFIER_0, KSIDENTIFIER_0_0,
+        KSPROPERTY_TYPE_SET, KSPROPERTY_VIDEOCONTROL_MODE, KSPROPERTY_VIDEOCONTROL_MODE_S,
+        PINNAME_IMAGE, PINNAME_VIDEO_STILL, PROPSETID_VIDCAP_VIDEOCONTROL,
+    },
     Media::MediaFoundation::{
-        IMFAttributes, IMFMediaBuffer, IMFMediaSource, IMFSample, IMFSourceReader,
+        IMFActivate, IMFAttributes, IMFDXGIDeviceManager, IMFMediaBuffer, IMFMediaSource,
+        IMFMediaType, IMFSample, IMFSourceReader, IMFSourceReaderEx, IMFStreamDescriptor,
+        MF_DEVICESTREAM_ATTRIBUTE_FRAMESOURCE_TYPES, MF_DEVICESTREAM_STREAM_CATEGORY,
+        MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE, MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
+        MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
+        MF_E_HW_MFT_FAILED_START_STREAMING, MF_E_INVALIDMEDIATYPE, MF_E_SHUTDOWN,
+        MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED, MF_MT_DEFAULT_STRIDE, MF_MT_FRAME_RATE,
         MF_MT_FRAME_SIZE, MF_MT_MAJOR_TYPE, MF_MT_SUBTYPE, MF_READWRITE_ENABLE_HARDWARE_TRANSFORMS,
-        MF_SOURCE_READER_ALL_STREAMS, MF_SOURCE_READER_ENABLE_VIDEO_PROCESSING,
-        MF_SOURCE_READER_FIRST_VIDEO_STREAM, MFCreateAttributes, MFCreateMediaType,
-        MFCreateSourceReaderFromMediaSource, MFMediaType_Video, MFVideoFormat_NV12,
-        MFVideoFormat_RGB32,
+        MF_SOURCE_READER_ALL_STREAMS, MF_SOURCE_READER_CURRENT_TYPE_INDEX,
+        MF_SOURCE_READER_D3D_MANAGER, MF_SOURCE_READER_ENABLE_ADVANCED_VIDEO_PROCESSING,
+        MF_SOURCE_READER_ENABLE_VIDEO_PROCESSING, MF_SOURCE_READER_FIRST_VIDEO_STREAM,
+        MF_SOURCE_READERF_CURRENTMEDIATYPECHANGED, MF_SOURCE_READERF_ENDOFSTREAM,
+        MF_SOURCE_READERF_ERROR, MFCreateAttributes, MFCreateDeviceSource, MFCreateMediaType,
+        MFCreateSourceReaderFromMediaSource, MFEnumDeviceSources, MFFrameSourceTypes_Color,
+        MFFrameSourceTypes_Depth, MFFrameSourceTypes_Infrared, MFGetStrideForBitmapInfoHeader,
+        MFMediaType_Video, MFVideoFormat_ARGB32, MFVideoFormat_D16, MFVideoFormat_I420,
+        MFVideoFormat_L16, MFVideoFormat_MJPG, MFVideoFormat_NV12, MFVideoFormat_RGB24,
+        MFVideoFormat_RGB32, MFVideoFormat_YUY2,
+    },
+    System::Com::CoTaskMemFree,
+};
+use windows::core::{BOOL, GUID, HSTRING, Interface};
+
+use crate::{
+    devices::{
+        CaptureEvent, CaptureState, DeviceInfo, Dimensions,
+        blocking::run_blocking,
+        camera_controls::CameraControls,
+        camera_format::{CameraFormat, NegotiatedFormat},
+        camera_transform::CameraTransform,
+        capture_error::CaptureError,
+        capture_state::CaptureGuard,
+        capture_stats::{CaptureStats, StatsCounters},
+        delivery_policy::{
+            BROADCAST_CAPACITY, DEFAULT_CHANNEL_CAPACITY, Delivery, DeliveryPolicy,
+            check_channel_capacity, queued, take_receiver, try_deliver,
+        },
+        fps_counter::FpsCounter,
+        get_device_name, get_device_symbolic_link,
+        stream_info::{StreamInfo, StreamKind},
+        video_device::video_device_manager,
     },
+    frame::{Frame, Orientation, PixelFormat, flip_frame, orient_frame},
+    frame_diff::{ChangeFilter, DuplicateFilter, FrameHasher},
+    i_capture::ICapture,
 };
 
-use crate::{devices::Dimensions, i_capture::ICapture};
+/// Output Control
+///
+/// The format frames are read in. Formats the camera does not send itself are converted by the source reader on the CPU, reading the format the camera sends (often MJPG or YUY2) is far cheaper at high resolutions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Raw unprocesses data directly from the device
+    NV12,
+    /// Processes data as RGB32
+    RGB32,
+    /// Compressed JPEG images, sent as MJPG frames that have to be decoded
+    MJPG,
+    /// Packed YUV where every two pixels share their color, sent as YUY2 frames
+    YUY2,
+    /// Planar YUV where every 2x2 block of pixels shares its color, sent as I420 frames
+    I420,
+    /// Processes data as 24 bit RGB, sent as BGR24 frames
+    RGB24,
+    /// Processes data as 32 bit RGB with alpha, sent as BGRA frames
+    ARGB32,
+    /// 16 bit luma, sent as Gray16 frames. Infrared streams often send it, see Camera::select_stream
+    L16,
+    /// 16 bit depth, sent as Depth16 frames. Only depth streams send it, the source reader does not convert to or from it
+    D16,
+}
+
+impl Output {
+    // every output, named in errors when the camera does not support one
+    const ALL: [Output; 9] = [
+        Output::NV12,
+        Output::RGB32,
+        Output::MJPG,
+        Output::YUY2,
+        Output::I420,
+        Output::RGB24,
+        Output::ARGB32,
+        Output::L16,
+        Output::D16,
+    ];
+
+    /// The pixel format of the frames sent for this output, see PixelFormat::bytes_per_pixel and PixelFormat::is_compressed to read them.
+    pub fn pixel_format(&self) -> PixelFormat {
+        match self {
+            Output::NV12 => PixelFormat::NV12,
+            Output::RGB32 => PixelFormat::RGB32,
+            Output::MJPG => PixelFormat::MJPG,
+            Output::YUY2 => PixelFormat::YUY2,
+            Output::I420 => PixelFormat::I420,
+            Output::RGB24 => PixelFormat::BGR24,
+            Output::ARGB32 => PixelFormat::BGRA,
+            Output::L16 => PixelFormat::Gray16,
+            Output::D16 => PixelFormat::Depth16,
+        }
+    }
+
+    // the media foundation subtype the source reader is set to
+    fn subtype(&self) -> &'static GUID {
+        match self {
+            Output::NV12 => &MFVideoFormat_NV12,
+            Output::RGB32 => &MFVideoFormat_RGB32,
+            Output::MJPG => &MFVideoFormat_MJPG,
+            Output::YUY2 => &MFVideoFormat_YUY2,
+            Output::I420 => &MFVideoFormat_I420,
+            Output::RGB24 => &MFVideoFormat_RGB24,
+            Output::ARGB32 => &MFVideoFormat_ARGB32,
+            Output::L16 => &MFVideoFormat_L16,
+            Output::D16 => &MFVideoFormat_D16,
+        }
+    }
+
+    // the output of a media foundation subtype, None for subtypes no output is read in
+    fn from_subtype(subtype: &GUID) -> Option<Output> {
+        Output::ALL
+            .into_iter()
+            .find(|output| output.subtype() == subtype)
+    }
+
+    // the name of a media foundation subtype, as the output or else as its FourCC
+    fn subtype_name(subtype: &GUID) -> String {
+        if let Some(output) = Output::from_subtype(subtype) {
+            return format!("{output:?}");
+        }
+
+        //the video subtypes of other formats are their FourCC in the first 4 bytes
+        let fourcc = subtype.data1.to_le_bytes();
+
+        match fourcc.iter().all(|byte| byte.is_ascii_graphic()) {
+            true => String::from_utf8_lossy(&fourcc).into_owned(),
+            false => format!("{subtype:?}"),
+        }
+    }
+}
+
+/// # Sample Read
+///
+/// What reading from the camera returned, see Camera::read_sample.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SampleRead {
+    /// A sample of the camera, its data may be empty when the camera sent an empty sample.
+    Sample {
+        /// The data of the sample, laid out as Camera::negotiated_format says.
+        data: Vec<u8>,
+
+        /// The time of the sample counted from when the source reader started streaming, see Frame::sample_time.
+        time: Option<Duration>,
+
+        /// How long the sample lasts, when the camera reports it.
+        duration: Option<Duration>,
+
+        /// The index of the stream the sample was read from, see Camera::streams.
+        stream: u32,
+    },
+
+    /// The stream has a gap at the time, such as a frame the camera dropped (a stream tick). No sample comes with it.
+    Gap { timestamp: Option<Duration> },
+
+    /// The stream ended, no more samples follow.
+    EndOfStream,
+}
+
+impl SampleRead {
+    // what a read without a sample means, by the MF_SOURCE_READERF flags it came with
+    pub(crate) fn without_sample(flags: u32, timestamp: i64) -> Self {
+        if flags as i32 & MF_SOURCE_READERF_ENDOFSTREAM.0 != 0 {
+            return SampleRead::EndOfStream;
+        }
+
+        SampleRead::Gap {
+            timestamp: mf_time(timestamp),
+        }
+    }
+}
+
+// converts a media foundation time in 100 nanosecond units, None for negative times
+fn mf_time(time: i64) -> Option<Duration> {
+    let time = u64::try_from(time).ok()?;
+
+    Some(Duration::from_nanos(time.saturating_mul(100)))
+}
+
+// the stream cameras read frames from until another is selected, see Camera::select_stream
+const FIRST_VIDEO_STREAM: u32 = MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32;
+
+// how often an unplugged camera is looked for while waiting to reconnect it, see Camera::set_auto_reconnect
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// how long shutdown waits for the capture loop before the source is shut down under it, see Camera::shutdown
+const SHUTDOWN_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the source reader of a camera and the device's source it reads from, photos are triggered on the source
+#[derive(Clone)]
+struct CameraDevice {
+    reader: IMFSourceReader,
+    source: IMFMediaSource,
+
+    // whether the reader converts frames on the GPU, see Camera::is_gpu_converting
+    gpu_conversion: bool,
+}
+
+// the stream of a camera that sends still photos, see Camera::take_photo
+struct PhotoStream {
+    // the index of the stream on the source reader
+    index: u32,
+
+    // the pin of the stream on the device, photos are triggered on it
+    pin: u32,
+}
+
+// how the source reader of a camera is created, see CameraBuilder
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ReaderOptions {
+    // lets the reader decode and convert on the GPU
+    pub(crate) hardware_transforms: bool,
+
+    // lets the reader convert the camera's frames to outputs it does not send itself, on the CPU
+    pub(crate) video_processing: bool,
+
+    // has the reader convert on the GPU's video processor instead, when there is a D3D11 video device
+    pub(crate) gpu_conversion: bool,
+
+    // the transforms samples are passed through after the output, in order
+    pub(crate) transforms: Vec<CameraTransform>,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        ReaderOptions {
+            hardware_transforms: true,
+            video_processing: true,
+            gpu_conversion: false,
+            transforms: vec![],
+        }
+    }
+}
+
+// a media buffer locked for reading, it is unlocked again when dropped so every lock is matched by one unlock
+struct LockedBuffer<'a> {
+    buffer: &'a IMFMediaBuffer,
+    data: *const u8,
+    len: usize,
+}
+
+impl<'a> LockedBuffer<'a> {
+    unsafe fn lock(buffer: &'a IMFMediaBuffer) -> Result<Self, windows::core::Error> {
+        let mut data: *mut u8 = std::ptr::null_mut();
+        let mut current_length: u32 = 0;
+
+        unsafe { buffer.Lock(&mut data, None, Some(&mut current_length))? };
+
+        Ok(LockedBuffer {
+            buffer,
+            data,
+            len: current_length as usize,
+        })
+    }
+
+    // the valid data of the buffer
+    fn data(&self) -> &[u8] {
+        if self.data.is_null() {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Drop for LockedBuffer<'_> {
+    fn drop(&mut self) {
+        //errors cannot be returned from drop, unlocking a buffer we locked does not fail
+        let _ = unsafe { self.buffer.Unlock() };
+    }
+}
+
+/// # Activated Device
+///
+/// Allows for the capturing of data via a IMFSourceReader.
+///
+/// Created from an IMFMediaSource (which can be obtained through the VideoDevices struct.)
+///
+/// Allows you to capture data (vec<u8>) data from a connected video device from your windows machine.
+///
+/// This could be a webcam or some other type of video device. This data can then be pushed through a pipeline like OpenCV for data capturing or other sorts of projects.
+pub struct Camera {
+    // the source reader and the device's source, replaced when the camera is reconnected while capturing
+    device: std::sync::RwLock<CameraDevice>,
+
+    /// The receiver, can be used to grab data directly from the device. See take_receiver to own it instead.
+    pub receiver: Arc<Mutex<Receiver<Frame>>>,
+
+    // to send data
+    sender: Sender<Frame>,
+
+    // sends every frame to the subscribers, see subscribe
+    broadcast_sender: broadcast::Sender<Arc<Frame>>,
+
+    // holds the latest frame, see watch_latest
+    latest_sender: watch::Sender<Option<Arc<Frame>>>,
+
+    // whether frames are sent through the receiver, see set_receiver_output
+    receiver_output: AtomicBool,
+
+    // determines if the camera is capturing and sending data
+    is_capturing: Arc<Mutex<bool>>,
+
+    // whether the capture is running, stopping, or how it ended
+    state: std::sync::Mutex<CaptureState>,
+
+    // true while the capture loop is running, used to wait for it to stop
+    running: watch::Sender<bool>,
+
+    /// The type of output the camera will give back to the user, as asked for. See negotiated_format for the format the frames are sent in.
+    pub output: Output,
+
+    // identifies the device so it can be found again, None for cameras created from a media source with new
+    symbolic_link: Option<String>,
+
+    // how the source reader was created, a reconnected camera creates its reader the same way
+    reader_options: ReaderOptions,
+
+    // the stream frames are read from, MF_SOURCE_READER_FIRST_VIDEO_STREAM until another is selected. See select_stream
+    stream: AtomicU32,
+
+    // the media type the reader sends frames in, read back whenever it is set
+    negotiated: std::sync::Mutex<NegotiatedFormat>,
+
+    // set when the source reader changed the size of its frames, for the next frame sent
+    dimensions_changed: AtomicBool,
+
+    // when the source reader started streaming, the epoch of the sample times
+    reader_started: OnceLock<(Instant, SystemTime)>,
+
+    // the frames read and sent
+    stats: StatsCounters,
+
+    // the rate frames are sent at while capturing
+    fps: FpsCounter,
+
+    // holds back frames that barely changed, see set_min_change_ratio
+    change_filter: std::sync::Mutex<ChangeFilter>,
+
+    // holds back frames identical to the one before, see set_duplicate_suppression
+    duplicate_filter: std::sync::Mutex<DuplicateFilter>,
+
+    // what is done with a new frame while the receiver holds one it has not read, see set_delivery_policy
+    delivery_policy: std::sync::Mutex<DeliveryPolicy>,
+
+    // the frames thrown away at the start of a capture or by capture_frame, see set_warmup_frames
+    warmup_frames: AtomicU32,
+
+    // how long a capture waits for its unplugged camera to come back, None to end the capture instead. See set_auto_reconnect
+    auto_reconnect: std::sync::Mutex<Option<Duration>>,
+
+    // whether bottom up frames are turned top down before they are sent, see set_flip_bottom_up
+    flip_bottom_up: AtomicBool,
+
+    // how frames are mirrored, flipped and rotated before they are sent, see set_orientation
+    orientation: std::sync::Mutex<Orientation>,
+
+    // set once the device's source was shut down, see shutdown
+    shut_down: AtomicBool,
+
+    // capture events, such as the capture starting or stopping
+    events_receiver: Arc<Mutex<Receiver<CaptureEvent>>>,
+    events_sender: Sender<CaptureEvent>,
+}
+
+impl Camera {
+    /// Create a newly activated device from the IMFMediaSource provided by the activation and a name.
+    ///
+    /// The name should be the friendly name provided by the device before activation.
+    ///
+    /// Output is optional but will default to NV12 (raw)
+    ///
+    /// Fails when a source reader can not be created for the source or the camera does not support the output format.
+    pub fn new(
+        source: IMFMediaSource,
+        output: Option<Output>,
+    ) -> Result<Arc<Self>, windows::core::Error> {
+        Self::activate(source, output, None, None, ReaderOptions::default())
+    }
+
+    /// # New With Format
+    ///
+    /// Like new, but the camera sends frames at the resolution and frame rate of the format instead of the one it starts in (often 640x480).
+    ///
+    /// The format is matched against the formats the camera sends itself (see supported_formats): the size must be the same and the frame rate within half a frame per second.
+    /// When several match, the one already in the output's pixel format is picked so the source reader does not have to convert it.
+    ///
+    /// Fails when no format matches, the error lists the ones the camera has.
+    pub fn new_with_format(
+        source: IMFMediaSource,
+        output: Option<Output>,
+        format: &CameraFormat,
+    ) -> Result<Arc<Self>, windows::core::Error> {
+        Self::activate(source, output, Some(format), None, ReaderOptions::default())
+    }
+
+    // creates the source reader with the options, in the format, if any, and the output. The symbolic link of the device lets reconnect find it again
+    pub(crate) fn activate(
+        source: IMFMediaSource,
+        output: Option<Output>,
+        format: Option<&CameraFormat>,
+        symbolic_link: Option<String>,
+        reader_options: ReaderOptions,
+    ) -> Result<Arc<Self>, windows::core::Error> {
+        let output = output.unwrap_or(Output::NV12); //unwraps to NV12 by default
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+        let (events_tx, events_rx) = mpsc::channel(16);
+
+        unsafe {
+            let (device, negotiated) =
+                Self::open_reader(source, FIRST_VIDEO_STREAM, &output, format, &reader_options)?;
+
+            let activated = Camera {
+                device: std::sync::RwLock::new(device),
+                receiver: Arc::new(Mutex::new(rx)),
+                sender: tx,
+                broadcast_sender: broadcast::channel(BROADCAST_CAPACITY).0,
+                latest_sender: watch::channel(None).0,
+                receiver_output: AtomicBool::new(true),
+                is_capturing: Arc::new(Mutex::new(false)),
+                state: std::sync::Mutex::new(CaptureState::Idle),
+                running: watch::channel(false).0,
+                output,
+                symbolic_link,
+                reader_options,
+                stream: AtomicU32::new(FIRST_VIDEO_STREAM),
+                negotiated: std::sync::Mutex::new(negotiated),
+                dimensions_changed: AtomicBool::new(false),
+                reader_started: OnceLock::new(),
+                stats: StatsCounters::default(),
+                fps: FpsCounter::new(),
+                change_filter: std::sync::Mutex::new(ChangeFilter::default()),
+                duplicate_filter: std::sync::Mutex::new(DuplicateFilter::default()),
+                warmup_frames: AtomicU32::new(0),
+                auto_reconnect: std::sync::Mutex::new(None),
+                flip_bottom_up: AtomicBool::new(true),
+                orientation: std::sync::Mutex::new(Orientation::default()),
+                shut_down: AtomicBool::new(false),
+                delivery_policy: std::sync::Mutex::new(DeliveryPolicy::default()),
+                events_receiver: Arc::new(Mutex::new(events_rx)),
+                events_sender: events_tx,
+            };
+
+            return Ok(Arc::new(activated));
+        }
+    }
+
+    /// # With Channel Capacity
+    ///
+    /// Sets how many frames the receiver's channel holds, at least 1. Used right after activating the camera, see Monitor::with_channel_capacity.
+    ///
+    /// The frame channel holds 1 frame by default. A larger channel lets a receiver that reads in bursts, such as an encoder working in batches, fall behind for a moment without the capture waiting on it or dropping frames.
+    /// Each queued frame keeps its data in memory, so the channel can hold up to capacity times the frame size (about 3 MB for each NV12 frame at 1920x1080).
+    ///
+    /// Fails when the capacity is 0, or when the camera or its receiver is already shared (for example cloned or capturing).
+    pub fn with_channel_capacity(
+        mut self: Arc<Self>,
+        capacity: usize,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        check_channel_capacity(capacity)?;
+
+        let Some(camera) = Arc::get_mut(&mut self).filter(|c| Arc::strong_count(&c.receiver) == 1)
+        else {
+            return Err(
+                "the camera is already shared, set the channel capacity right after activating it"
+                    .into(),
+            );
+        };
+
+        let (sender, receiver) = mpsc::channel(capacity);
+        camera.sender = sender;
+        camera.receiver = Arc::new(Mutex::new(receiver));
+
+        Ok(self)
+    }
+
+    /// # Read Sample
+    ///
+    /// Using the existing media readers takes in the video stream to read from (defaults to the selected stream if None, see select_stream) a stream.
+    ///
+    /// Reads a sample of the stream, converts to a buffer and retrieves the underlying data along with its time. Gaps in the stream and its end are returned as such instead of as empty data.
+    ///
+    /// When the source reader changed its media type the negotiated format is read back first, with the events for it (see CaptureEvent::FormatChanged).
+    ///
+    /// Fails when the reader reports an error on the stream, and with MF_E_SHUTDOWN once the camera was shut down. Once the camera was unplugged, or the reader can not be used anymore, it fails with MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED (see CaptureEvent::is_device_lost and reconnect).
+    pub fn read_sample(
+        &self,
+        video_stream: Option<u32>,
+    ) -> Result<SampleRead, windows::core::Error> {
+        if self.is_shut_down() {
+            return Err(windows::core::Error::new(
+                MF_E_SHUTDOWN,
+                "the camera was shut down, activate it again to read from it",
+            ));
+        }
+
+        //initialize values for loading into the readsample func
+        let video_stream = video_stream.unwrap_or_else(|| self.stream());
+        let mut sample: Option<IMFSample> = None;
+        let buffer: Option<IMFMediaBuffer>;
+        let mut stream_index: u32 = 0;
+        let mut stream_flags: u32 = 0;
+        let mut time_stamp: i64 = 0;
+        let duration;
+
+        unsafe {
+            self.reader()
+                .ReadSample(
+                    video_stream,
+                    0,
+                    Some(&mut stream_index),
+                    Some(&mut stream_flags),
+                    Some(&mut time_stamp),
+                    Some(&mut sample),
+                )
+                .map_err(|e| self.disconnected_or(e))?;
+        }
+
+        let flags = stream_flags as i32;
+
+        //no more calls may be made to a reader after an error, it has to be activated again just like an unplugged camera
+        if flags & MF_SOURCE_READERF_ERROR.0 != 0 {
+            return Err(match self.is_connected() {
+                true => windows::core::Error::new(
+                    MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED,
+                    "the source reader stopped on an error in the camera stream",
+                ),
+                false => self.disconnected_error(),
+            });
+        }
+
+        //the sample that comes with the flag is already in the new media type
+        if flags & MF_SOURCE_READERF_CURRENTMEDIATYPECHANGED.0 != 0 {
+            self.media_type_changed()?;
+        }
+
+        let Some(sample) = sample else {
+            return Ok(SampleRead::without_sample(stream_flags, time_stamp));
+        };
+
+        unsafe {
+            duration = sample.GetSampleDuration().ok().and_then(mf_time);
+            buffer = Some(sample.ConvertToContiguousBuffer()?);
+        }
+
+        //ensure the buffer contains some value.
+        if buffer.is_none() {
+            return Err(E_FAIL.into());
+        }
+
+        let buffer = buffer.unwrap();
+
+        Ok(SampleRead::Sample {
+            data: Self::get_frame_data(&buffer)?,
+            time: mf_time(time_stamp),
+            duration,
+            stream: stream_index,
+        })
+    }
+
+    /// # Stop And Wait
+    ///
+    /// Stops capturing and waits for the capture loop to exit, see Monitor::stop_and_wait. A read waiting on the camera is cut short by the stop, so this does not wait for the camera's next frame.
+    ///
+    /// Returns an error if the loop did not exit within the timeout.
+    pub async fn stop_and_wait(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut running = self.running.subscribe();
+
+        self.clone().stop_capturing().await?;
+
+        match tokio::time::timeout(timeout, running.wait_for(|running| !running)).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("capture did not stop within {timeout:?}").into()),
+        }
+    }
+
+    /// # Shutdown
+    ///
+    /// Stops capturing, waiting for the capture loop to exit, and shuts down the device's media source so the camera is released right away (and its light turns off) instead of when the process exits.
+    ///
+    /// The camera can not be used afterwards: reading from it fails with MF_E_SHUTDOWN and capturing can not be started. The device can be activated again through Cameras (or reconnect), another application can open it as well.
+    /// Dropping the camera does the same, this is for releasing it while something still holds it, such as a CameraGroup or a task. Calling it again does nothing.
+    ///
+    /// Fails when the source can not be shut down.
+    pub async fn shutdown(self: &Arc<Self>) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_running() {
+            //a read the stop could not cut short fails once the source is shut down
+            let _ = self.stop_and_wait(SHUTDOWN_STOP_TIMEOUT).await;
+        }
+
+        self.shutdown_device()?;
+
+        Ok(())
+    }
+
+    /// Whether the camera was shut down with shutdown, it can not be read from anymore.
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::Acquire)
+    }
+
+    // flushes the reader and shuts down the source of the device, once
+    fn shutdown_device(&self) -> Result<(), windows::core::Error> {
+        if self.shut_down.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let device = self
+            .device
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        unsafe {
+            //the reader holds the source, it gives up its samples before the source goes away
+            let _ = device.reader.Flush(MF_SOURCE_READER_ALL_STREAMS.0 as u32);
+            device.source.Shutdown()
+        }
+    }
+
+    /// # Symbolic Link
+    ///
+    /// The symbolic link of the camera's device, which identifies it while it is plugged in (see get_device_symbolic_link). None for cameras created with new or new_with_format, they can not be found again.
+    pub fn symbolic_link(&self) -> Option<&str> {
+        self.symbolic_link.as_deref()
+    }
+
+    /// # Is Connected
+    ///
+    /// Whether the camera is still plugged in, it is looked up again by its symbolic link.
+    ///
+    /// A capture ends with a DeviceLost event once its camera was unplugged, see reconnect. Cameras without a symbolic link can not be looked up and are always reported as connected.
+    pub fn is_connected(&self) -> bool {
+        let Some(symbolic_link) = &self.symbolic_link else {
+            return true;
+        };
+
+        unsafe { Self::connected_links() }.is_ok_and(|links| links.contains(symbolic_link))
+    }
+
+    /// # Reconnect
+    ///
+    /// Activates the same camera again, found by its symbolic link. Used once the camera is back after a DeviceLost event, such as when it was unplugged and plugged back in, without enumerating the cameras again.
+    ///
+    /// The new camera reads the same stream in the same output at the negotiated size and frame rate of this one. It sends frames and events through the same receivers as this one, and frames to the same subscribers and latest frame watchers, so anything holding them keeps receiving once it is started.
+    /// Its other settings start at their defaults.
+    ///
+    /// Fails while the camera is still unplugged, while this camera is capturing, and for cameras created with new or new_with_format.
+    pub fn reconnect(&self) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        if self.is_running() {
+            return Err("the camera is still capturing, stop it before reconnecting".into());
+        }
+
+        let Some(symbolic_link) = &self.symbolic_link else {
+            return Err(
+                "the camera was not activated through Cameras, it can not be found again".into(),
+            );
+        };
+
+        if !self.is_connected() {
+            return Err("the camera is not connected".into());
+        }
+
+        let format = self.negotiated_format().format;
+
+        let mut camera = unsafe {
+            let source = Self::source_from_symbolic_link(symbolic_link)?;
+
+            Self::activate(
+                source,
+                Some(self.output),
+                Some(&format),
+                Some(symbolic_link.clone()),
+                self.reader_options.clone(),
+            )?
+        };
+
+        if let Some(stream) = self.selected_stream() {
+            camera.open_stream(stream, Some(&format))?;
+        }
+
+        //nothing else holds the new camera yet, so its channels can be swapped for ours
+        if let Some(new_camera) = Arc::get_mut(&mut camera) {
+            new_camera.sender = self.sender.clone();
+            new_camera.receiver = self.receiver.clone();
+            new_camera.broadcast_sender = self.broadcast_sender.clone();
+            new_camera.latest_sender = self.latest_sender.clone();
+            new_camera.events_sender = self.events_sender.clone();
+            new_camera.events_receiver = self.events_receiver.clone();
+        }
+
+        Ok(camera)
+    }
+
+    /// # Set Auto Reconnect
+    ///
+    /// Sets how long a capture waits for its camera to be plugged back in after it was unplugged, None (the default) ends the capture right away with a DeviceLost event.
+    ///
+    /// While waiting a Reconnecting event is sent and the receivers stay open. Once the camera is back it is activated again by its symbolic link, in the same output and negotiated format, a Reconnected event is sent and frames are sent as before, after the warm-up frames. When it is not back in time the capture ends with DeviceLost as it would without waiting.
+    ///
+    /// Cameras created with new or new_with_format have no symbolic link and are never reconnected. The sample times of the frames count from when the camera was reconnected.
+    pub fn set_auto_reconnect(&self, max_wait: Option<Duration>) {
+        *self.auto_reconnect.lock().unwrap() = max_wait;
+    }
+
+    /// How long a capture waits for its unplugged camera, see set_auto_reconnect.
+    pub fn get_auto_reconnect(&self) -> Option<Duration> {
+        *self.auto_reconnect.lock().unwrap()
+    }
+
+    // waits for the camera that was unplugged to come back and activates it again in place, see set_auto_reconnect
+    //
+    // false when capturing was stopped while waiting, the error the camera was lost with when it did not come back in time
+    async fn wait_for_reconnect(
+        self: &Arc<Self>,
+        error: windows::core::Error,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let (Some(max_wait), Some(_)) = (self.get_auto_reconnect(), &self.symbolic_link) else {
+            return Err(error.into());
+        };
+
+        self.send_event(CaptureEvent::Reconnecting);
+        let started = Instant::now();
+
+        loop {
+            if !*self.is_capturing.lock().await {
+                return Ok(false);
+            }
+
+            if self.is_connected() {
+                //activating waits on the driver, off the threads running async tasks
+                let camera = self.clone();
+
+                if run_blocking(move || camera.reopen()).await.is_ok() {
+                    self.send_event(CaptureEvent::Reconnected);
+                    return Ok(true);
+                }
+            }
+
+            let Some(remaining) = max_wait.checked_sub(started.elapsed()) else {
+                return Err(error.into());
+            };
+
+            tokio::time::sleep(remaining.min(RECONNECT_POLL_INTERVAL)).await;
+        }
+    }
+
+    // activates the camera again by its symbolic link and swaps in its new source and reader, everything else is kept
+    fn reopen(&self) -> Result<(), windows::core::Error> {
+        let Some(symbolic_link) = &self.symbolic_link else {
+            return Err(self.disconnected_error());
+        };
+
+        let previous = self.negotiated_format();
+
+        let (device, negotiated) = unsafe {
+            let source = Self::source_from_symbolic_link(symbolic_link)?;
+
+            Self::open_reader(
+                source,
+                self.stream(),
+                &self.output,
+                Some(&previous.format),
+                &self.reader_options,
+            )?
+        };
+
+        let lost = std::mem::replace(&mut *self.device.write().unwrap(), device);
+        *self.negotiated.lock().unwrap() = negotiated;
+
+        //the unplugged device's source is of no use anymore, it is shut down like a dropped camera's
+        let _ = unsafe { lost.source.Shutdown() };
+
+        //the camera may not offer the same size after it was plugged into another port
+        if negotiated.format.width != previous.format.width
+            || negotiated.format.height != previous.format.height
+        {
+            self.dimensions_changed.store(true, Ordering::Relaxed);
+            self.send_event(CaptureEvent::DimensionsChanged {
+                size: self.oriented_size(&negotiated.format),
+            });
+        }
+
+        Ok(())
+    }
+
+    // the error a capture ends with once its camera was unplugged, CaptureEvent::is_device_lost is true for it
+    fn disconnected_error(&self) -> windows::core::Error {
+        windows::core::Error::new(
+            MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED,
+            "the camera was disconnected",
+        )
+    }
+
+    // an unplugged camera fails with E_UNEXPECTED or MF_E_HW_MFT_FAILED_START_STREAMING on some drivers, they are reported as disconnected once the camera is gone
+    fn disconnected_or(&self, error: windows::core::Error) -> windows::core::Error {
+        match error.code() {
+            E_UNEXPECTED | MF_E_HW_MFT_FAILED_START_STREAMING if !self.is_connected() => {
+                self.disconnected_error()
+            }
+            _ => error,
+        }
+    }
+
+    // an error of a camera another application is using becomes CaptureError::DeviceBusy with the camera's name, other errors are kept as they are
+    fn busy_or(&self, error: windows::core::Error) -> Box<dyn std::error::Error + Send + Sync> {
+        if !CaptureError::is_busy(&error) {
+            return error.into();
+        }
+
+        CaptureError::from_error(error, &self.device_name()).into()
+    }
+
+    // the friendly name of the camera, looked up by its symbolic link
+    fn device_name(&self) -> String {
+        let name = self.symbolic_link.as_ref().and_then(|symbolic_link| {
+            unsafe { Self::connected_devices() }
+                .ok()?
+                .into_iter()
+                .find(|device| &device.symbolic_link == symbolic_link)
+                .map(|device| device.name)
+        });
+
+        name.unwrap_or_else(|| "the camera".to_string())
+    }
+
+    // attributes that select video capture devices, with room for the count of attributes
+    unsafe fn video_capture_attributes(count: u32) -> Result<IMFAttributes, windows::core::Error> {
+        unsafe {
+            let mut attributes: Option<IMFAttributes> = None;
+            MFCreateAttributes(&mut attributes, count)?;
+
+            let Some(attributes) = attributes else {
+                return Err(E_ABORT.into());
+            };
+
+            attributes.SetGUID(
+                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
+                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
+            )?;
+
+            Ok(attributes)
+        }
+    }
+
+    // creates the media source of the camera with the symbolic link, without enumerating the cameras
+    pub(crate) unsafe fn source_from_symbolic_link(
+        symbolic_link: &str,
+    ) -> Result<IMFMediaSource, windows::core::Error> {
+        unsafe {
+            let attributes = Self::video_capture_attributes(2)?;
+            attributes.SetString(
+                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
+                &HSTRING::from(symbolic_link),
+            )?;
+
+            MFCreateDeviceSource(&attributes)
+        }
+    }
+
+    // the symbolic links of the cameras plugged in right now
+    unsafe fn connected_links() -> Result<Vec<String>, windows::core::Error> {
+        let devices = unsafe { Self::connected_devices()? };
+
+        Ok(devices
+            .into_iter()
+            .map(|device| device.symbolic_link)
+            .collect())
+    }
+
+    // the cameras plugged in right now, indexed in the order they were enumerated
+    pub(crate) unsafe fn connected_devices() -> Result<Vec<DeviceInfo>, windows::core::Error> {
+        unsafe {
+            let attributes = Self::video_capture_attributes(1)?;
+
+            let mut pp_devices: *mut Option<IMFActivate> = std::ptr::null_mut();
+            let mut count: u32 = 0;
+
+            MFEnumDeviceSources(&attributes, &mut pp_devices, &mut count)?;
+
+            if pp_devices.is_null() {
+                return Ok(vec![]);
+            }
+
+            //the devices are taken out so each is released, then the array itself is freed
+            let devices = std::slice::from_raw_parts_mut(pp_devices, count as usize)
+                .iter_mut()
+                .filter_map(|device| device.take())
+                .enumerate()
+                .filter_map(|(index, device)| {
+                    Some(DeviceInfo {
+                        index,
+                        name: get_device_name(&device).ok()?,
+                        symbolic_link: get_device_symbolic_link(&device).ok()?,
+                    })
+                })
+                .collect();
+
+            CoTaskMemFree(Some(pp_devices as *const c_void));
+
+            Ok(devices)
+        }
+    }
+
+    /// # Capture Frame
+    ///
+    /// Takes a single frame without starting a capture, waiting up to the timeout for the camera to send one.
+    ///
+    /// Gaps in the stream are passed over, and the first frames after the stream starts are skipped as set_warmup_frames says, cameras often send dark frames until their exposure settles.
+    /// The frame is in the negotiated format, with its sample time.
+    ///
+    /// Fails if the camera is already capturing, read its receiver instead. A read still waiting when the timeout passes is cancelled, capturing can be started afterwards as usual.
+    pub async fn capture_frame(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<Frame, Box<dyn std::error::Error>> {
+        //held until the frame is read so a capture cannot start in the middle of it
+        let is_capturing = self.is_capturing.lock().await;
+
+        if *is_capturing {
+            return Err("the camera is already capturing, read frames from its receiver".into());
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut skipped = 0;
+
+        self.reader_started
+            .get_or_init(|| (Instant::now(), SystemTime::now()));
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("no frame was sent within {timeout:?}").into());
+            }
+
+            let camera = self.clone();
+            let read = run_blocking(move || camera.read_sample(None));
+            tokio::pin!(read);
+
+            let read = match tokio::time::timeout(remaining, &mut read).await {
+                Ok(read) => read.map_err(|e| self.busy_or(e) as Box<dyn std::error::Error>)?,
+                Err(_) => {
+                    //the pending read is cancelled so the reader is idle again once this returns
+                    let _ = self.flush_reader().await;
+                    let _ = read.await;
+
+                    return Err(format!("no frame was sent within {timeout:?}").into());
+                }
+            };
+
+            match read {
+                SampleRead::Sample {
+                    data,
+                    time,
+                    duration,
+                    stream,
+                } if !data.is_empty() => {
+                    if skipped < self.get_warmup_frames() {
+                        skipped += 1;
+                        continue;
+                    }
+
+                    return Ok(self.sample_frame(data, time, duration, stream));
+                }
+                SampleRead::Sample { .. } | SampleRead::Gap { .. } => continue,
+                SampleRead::EndOfStream => {
+                    return Err("the camera's stream ended before it sent a frame".into());
+                }
+            }
+        }
+    }
+
+    /// # Set Warmup Frames
+    ///
+    /// Sets how many frames are read and thrown away at the start of every capture and by capture_frame, so auto exposure and white balance can settle after the stream starts. 0 by default.
+    ///
+    /// Nothing is sent for them, not even to subscribers, and they are counted in CaptureStats::warmup instead of as dropped. For capture_frame they count towards the timeout, a few frames take a fraction of a second at the camera's frame rate.
+    pub fn set_warmup_frames(&self, frames: u32) {
+        self.warmup_frames.store(frames, Ordering::Relaxed);
+    }
+
+    /// The frames thrown away while the camera warms up, see set_warmup_frames.
+    pub fn get_warmup_frames(&self) -> u32 {
+        self.warmup_frames.load(Ordering::Relaxed)
+    }
+
+    /// # Set Flip Bottom Up
+    ///
+    /// Sets whether frames the camera sends from the bottom row up are flipped to top down as they are read, on by default. Many drivers send RGB32 bottom up, other formats are always top down.
+    ///
+    /// Turned off, the frames are sent as the camera sends them, NegotiatedFormat::bottom_up says which order their rows are in. The stride of the frames is positive either way.
+    pub fn set_flip_bottom_up(&self, flip: bool) {
+        self.flip_bottom_up.store(flip, Ordering::Relaxed);
+    }
+
+    /// Whether bottom up frames are flipped, see set_flip_bottom_up.
+    pub fn get_flip_bottom_up(&self) -> bool {
+        self.flip_bottom_up.load(Ordering::Relaxed)
+    }
+
+    /// # Set Orientation
+    ///
+    /// Sets how frames are mirrored, flipped and rotated before they are sent, such as mirroring the preview of a front facing camera or turning the frames of a sensor mounted upside down. Frames are sent as the camera sends them by default.
+    ///
+    /// Frames rotated by 90 or 270 degrees are sent with their width and height swapped, get_dimensions and DimensionsChanged events report the rotated size. Changing the rotation while capturing sends a DimensionsChanged event when the size changes. See orient_frame.
+    ///
+    /// Fails when the camera sends YUY2 or MJPG frames, which can not be oriented, pick another output. Frames of a format changed to later are sent as they are.
+    pub fn set_orientation(
+        &self,
+        orientation: Orientation,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let negotiated = self.negotiated_format();
+
+        if !orientation.is_identity()
+            && matches!(
+                negotiated.pixel_format,
+                PixelFormat::YUY2 | PixelFormat::MJPG
+            )
+        {
+            return Err(format!(
+                "{:?} frames can not be oriented, pick another output",
+                negotiated.pixel_format
+            )
+            .into());
+        }
+
+        let previous = std::mem::replace(&mut *self.orientation.lock().unwrap(), orientation);
+
+        if previous.rotation.swaps_dimensions() != orientation.rotation.swaps_dimensions() {
+            self.dimensions_changed.store(true, Ordering::Relaxed);
+            self.send_event(CaptureEvent::DimensionsChanged {
+                size: self.oriented_size(&negotiated.format),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// How frames are oriented before they are sent, see set_orientation.
+    pub fn get_orientation(&self) -> Orientation {
+        *self.orientation.lock().unwrap()
+    }
+
+    // the size frames of the format are sent at, once oriented
+    fn oriented_size(&self, format: &CameraFormat) -> Dimensions {
+        self.get_orientation().oriented_size(&Dimensions {
+            width: format.width,
+            height: format.height,
+        })
+    }
+
+    // turns the frame top down when it was read in the bottom up format and flipping is on, then orients it as set_orientation says
+    fn orient_frame(&self, frame: &mut Frame, format: &NegotiatedFormat) {
+        let mut orientation = self.get_orientation();
+
+        //flipping a bottom up frame is part of the same pass
+        if format.bottom_up && self.get_flip_bottom_up() {
+            orientation.flip_vertical = !orientation.flip_vertical;
+        }
+
+        if orientation.is_identity() {
+            return;
+        }
+
+        let only_flipped = Orientation {
+            flip_vertical: true,
+            ..Orientation::default()
+        };
+
+        if orientation == only_flipped && flip_frame(frame).is_ok() {
+            return;
+        }
+
+        //frames too short for their size are sent as they are, the receiver finds out from their length
+        if let Ok(oriented) = orient_frame(frame, orientation) {
+            *frame = oriented;
+        }
+    }
+
+    // a frame of the sample's data, laid out as negotiated
+    fn sample_frame(
+        &self,
+        data: Vec<u8>,
+        time: Option<Duration>,
+        duration: Option<Duration>,
+        stream: u32,
+    ) -> Frame {
+        let negotiated = self.negotiated_format();
+
+        let mut frame = Frame::new(
+            negotiated.format.width,
+            negotiated.format.height,
+            negotiated.stride,
+            negotiated.pixel_format,
+            data,
+        );
+        frame.dimensions_changed = self.dimensions_changed.swap(false, Ordering::Relaxed);
+        frame.sample_time = time;
+        frame.sample_duration = duration;
+        frame.stream_index = Some(stream);
+        self.orient_frame(&mut frame, &negotiated);
+
+        frame
+    }
+
+    // cancels a read waiting on the camera, flushing waits for it off the threads running async tasks
+    async fn flush_reader(self: &Arc<Self>) -> Result<(), windows::core::Error> {
+        let camera = self.clone();
+
+        run_blocking(move || unsafe {
+            camera.reader().Flush(MF_SOURCE_READER_ALL_STREAMS.0 as u32)
+        })
+        .await
+    }
+
+    /// # Sample Instant
+    ///
+    /// The Instant a sample time of a frame falls on (see Frame::sample_time), taking the time the source reader started streaming as the epoch. None before the camera first captured.
+    ///
+    /// The reader starts streaming when the camera is first captured and keeps its timeline when capturing is started again, so frames of every capture can be lined up with monitor frames and audio.
+    pub fn sample_instant(&self, sample_time: Duration) -> Option<Instant> {
+        self.reader_started
+            .get()
+            .map(|(instant, _)| *instant + sample_time)
+    }
+
+    /// # Sample System Time
+    ///
+    /// Like sample_instant but as a SystemTime, to line frames up with other processes or machines.
+    pub fn sample_system_time(&self, sample_time: Duration) -> Option<SystemTime> {
+        self.reader_started
+            .get()
+            .map(|(_, system_time)| *system_time + sample_time)
+    }
+
+    /// # Stats
+    ///
+    /// The number of frames read from the camera and sent through the receiver since it was activated.
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            queued: queued(&self.sender),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// # Current FPS
+    ///
+    /// The rate frames are sent at, averaged over about the last second. 0 while not capturing and until two frames were sent.
+    pub fn current_fps(&self) -> f64 {
+        self.fps.fps()
+    }
+
+    /// # Set Min Change Ratio
+    ///
+    /// When set, frames are only sent when at least this fraction of their pixels changed from the last frame sent, for NV12 only the brightness is compared.
+    ///
+    /// A pixel changed when any of its channels moved by more than the threshold, camera noise usually needs a threshold of a few steps. Frames held back are counted as skipped.
+    ///
+    /// None (the default) sends every frame.
+    pub fn set_min_change_ratio(&self, min_change_ratio: Option<f64>, threshold: u8) {
+        self.change_filter
+            .lock()
+            .unwrap()
+            .set(min_change_ratio, threshold);
+    }
+
+    /// # Set Duplicate Suppression
+    ///
+    /// When set, frames that hash the same as the frame read before them are not sent, see Monitor::set_duplicate_suppression.
+    ///
+    /// Camera noise changes nearly every frame, this mostly catches cameras that repeat a frame when they have no new one. None (the default) sends every frame.
+    pub fn set_duplicate_suppression(&self, hasher: Option<FrameHasher>) {
+        self.duplicate_filter.lock().unwrap().set(hasher);
+    }
+
+    /// # Set Delivery Policy
+    ///
+    /// Sets what is done with a new frame while the receiver still holds one it has not read, see DeliveryPolicy.
+    ///
+    /// DropOldest (the default) keeps reading the camera at its frame rate however slowly the receiver reads. Dropped and replaced frames are counted in CaptureStats::dropped.
+    pub fn set_delivery_policy(&self, policy: DeliveryPolicy) {
+        *self.delivery_policy.lock().unwrap() = policy;
+    }
+
+    /// The delivery policy, see set_delivery_policy.
+    pub fn get_delivery_policy(&self) -> DeliveryPolicy {
+        *self.delivery_policy.lock().unwrap()
+    }
+
+    /// # State
+    ///
+    /// Whether the camera is capturing, stopping, or how its last capture ended.
+    pub fn state(&self) -> CaptureState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Whether the capture loop is running, this includes while it is stopping.
+    pub fn is_running(&self) -> bool {
+        self.state().is_running()
+    }
+
+    /// # Take Receiver
+    ///
+    ///
+    /// Hands out the frame receiver to own, so it can be moved into a task and read without locking a mutex for every frame.
+    ///
+    /// Only the first call returns the receiver, later calls return None. None is also returned while the shared receiver is locked, such as by a task waiting on recv.
+    /// Once taken the shared receiver (the receiver field and clone_receiver) is closed and recv on it returns None.
+    ///
+    /// The DropOldest delivery policy can not take frames back out of an owned receiver, it drops the new frame instead like DropNewest.
+    pub fn take_receiver(&self) -> Option<Receiver<Frame>> {
+        take_receiver(&self.receiver)
+    }
+
+    /// # Subscribe
+    ///
+    /// A new receiver that gets every frame sent from now on, independent of the receiver and of other subscribers, see Monitor::subscribe.
+    ///
+    /// The camera never waits for subscribers, one more than 4 frames behind receives RecvError::Lagged with the number of frames it missed.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Frame>> {
+        self.broadcast_sender.subscribe()
+    }
+
+    /// # Watch Latest
+    ///
+    /// A receiver holding the latest frame the camera read, None until one is read after it was made. See Monitor::watch_latest.
+    pub fn watch_latest(&self) -> watch::Receiver<Option<Arc<Frame>>> {
+        self.latest_sender.subscribe()
+    }
+
+    /// # Set Receiver Output
+    ///
+    /// Whether frames are sent through the receiver, on by default. Turn it off when only subscribers read frames (see subscribe and watch_latest), frames read without any subscriber are then counted as dropped.
+    pub fn set_receiver_output(&self, receiver_output: bool) {
+        self.receiver_output
+            .store(receiver_output, Ordering::Relaxed);
+    }
+
+    /// Whether frames are sent through the receiver, see set_receiver_output.
+    pub fn get_receiver_output(&self) -> bool {
+        self.receiver_output.load(Ordering::Relaxed)
+    }
+
+    // whether anyone reads frames besides the receiver, see subscribe and watch_latest
+    fn has_subscribers(&self) -> bool {
+        self.broadcast_sender.receiver_count() > 0 || self.latest_sender.receiver_count() > 0
+    }
+
+    // sends the frame to the subscribers and makes it the latest frame
+    fn share_frame(&self, frame: Arc<Frame>) {
+        //fails only when the last subscriber was just dropped
+        let _ = self.broadcast_sender.send(frame.clone());
+        self.latest_sender.send_replace(Some(frame));
+    }
+
+    /// # Events
+    ///
+    /// The receiver for capture events, such as the capture starting, stopping or the camera being unplugged.
+    ///
+    /// Events are dropped when the receiver is not read and already holds 16 events.
+    pub fn events(&self) -> Arc<Mutex<Receiver<CaptureEvent>>> {
+        self.events_receiver.clone()
+    }
+
+    // sends an event without waiting, events are dropped when no one reads them
+    fn send_event(&self, event: CaptureEvent) {
+        let _ = self.events_sender.try_send(event);
+    }
+
+    // reads samples and sends them as frames until capturing is stopped
+    //
+    // ends without an error at the end of the stream
+    async fn capture_loop(
+        self: &Arc<Self>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        //the first frames of every capture are thrown away while the camera warms up
+        let mut warmup_left = self.get_warmup_frames();
+
+        loop {
+            //check if capturing, drop immediately
+            {
+                let is_capturing = self.is_capturing.lock().await;
+
+                if !*is_capturing {
+                    break;
+                }
+            }
+
+            //the reader streams from the first read on, its sample times count from then
+            self.reader_started
+                .get_or_init(|| (Instant::now(), SystemTime::now()));
+
+            //ReadSample waits for the camera to deliver a frame, off the threads running async tasks
+            let camera = self.clone();
+            let read = run_blocking(move || camera.read_sample(None)).await;
+
+            //stopping flushes the reader, the read it cut short returns without a sample or fails
+            if !*self.is_capturing.lock().await {
+                break;
+            }
+
+            let read = match read {
+                //an unplugged camera is waited for when auto reconnect is on, see set_auto_reconnect
+                Err(e) if CaptureEvent::is_device_lost(&e) => {
+                    if !self.wait_for_reconnect(e).await? {
+                        break;
+                    }
+
+                    warmup_left = self.get_warmup_frames();
+                    continue;
+                }
+                read => read.map_err(|e| self.busy_or(e))?,
+            };
+
+            let (data, time, duration, stream) = match read {
+                SampleRead::Sample {
+                    data,
+                    time,
+                    duration,
+                    stream,
+                } => (data, time, duration, stream),
+                SampleRead::Gap { timestamp } => {
+                    self.send_event(CaptureEvent::StreamGap { timestamp });
+                    continue;
+                }
+                SampleRead::EndOfStream => break,
+            };
+
+            if warmup_left > 0 {
+                warmup_left -= 1;
+                self.stats.add_warmup(1);
+                continue;
+            }
+
+            let mut frame = self.sample_frame(data, time, duration, stream);
+            frame.sequence = self.stats.next_sequence(1);
+            self.stats.add_captured(1);
+
+            if !self.duplicate_filter.lock().unwrap().passes(&frame) {
+                self.stats.add_suppressed(1);
+                continue;
+            }
+
+            if !self.change_filter.lock().unwrap().passes(&frame) {
+                self.stats.add_skipped(1);
+                continue;
+            }
+
+            if !self.get_receiver_output() {
+                if self.has_subscribers() {
+                    self.share_frame(Arc::new(frame));
+                    self.stats.add_delivered(1);
+                    self.fps.record();
+                } else {
+                    self.stats.add_dropped(1);
+                }
+
+                continue;
+            }
+
+            if self.has_subscribers() {
+                //the subscribers share one copy, the receiver gets the frame itself
+                self.share_frame(Arc::new(frame.clone()));
+            }
+
+            let policy = self.get_delivery_policy();
+
+            match try_deliver(policy, &self.sender, &self.receiver, frame) {
+                Delivery::Sent { replaced } => {
+                    if replaced {
+                        self.stats.add_replaced(1);
+                    }
+                }
+                Delivery::Dropped { replaced } => {
+                    if replaced {
+                        self.stats.add_replaced(1);
+                    }
+
+                    self.stats.add_dropped(1);
+                    continue;
+                }
+                Delivery::Wait(frame) => {
+                    if let Err(e) = self.sender.send(frame).await {
+                        self.stats.add_dropped(1);
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            self.stats.add_delivered(1);
+            self.fps.record();
+        }
+
+        Ok(())
+    }
+
+    // reads back the media type the reader changed to and sends the events for it, the next frame is marked when the size changed
+    fn media_type_changed(&self) -> Result<(), windows::core::Error> {
+        let negotiated = unsafe { Self::read_stream_format(&self.reader(), self.stream())? };
+        let previous = std::mem::replace(&mut *self.negotiated.lock().unwrap(), negotiated);
+
+        self.send_event(CaptureEvent::FormatChanged {
+            format: negotiated.pixel_format,
+        });
+
+        if negotiated.format.width != previous.format.width
+            || negotiated.format.height != previous.format.height
+        {
+            self.dimensions_changed.store(true, Ordering::Relaxed);
+            self.send_event(CaptureEvent::DimensionsChanged {
+                size: self.oriented_size(&negotiated.format),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// # Get Stride
+    ///
+    /// The number of bytes in a single row of the frames, read from the negotiated media type. It is positive for bottom up frames as well, see set_flip_bottom_up.
+    pub fn get_stride(&self) -> Result<u32, windows::core::Error> {
+        Ok(self.negotiated_format().stride)
+    }
+
+    /// # Negotiated Format
+    ///
+    /// The pixel format, size, stride and frame rate the source reader actually sends frames in, which may differ from what was asked for. Frames on the receiver are laid out as this says.
+    ///
+    /// It is read back whenever the format is set, at activation and by set_frame_rate, and when the reader changes it while capturing (with a FormatChanged event).
+    pub fn negotiated_format(&self) -> NegotiatedFormat {
+        *self.negotiated.lock().unwrap()
+    }
+
+    /// # Set Frame Rate
+    ///
+    /// Asks the camera to send numerator / denominator frames per second at its current resolution, such as 60, 1 or 15, 1.
+    ///
+    /// The camera's own format of this size and rate is selected, rates within half a frame per second match (see new_with_format). Drivers may still clamp the rate, read back what was negotiated with frame_rate.
+    ///
+    /// Fails while capturing, and when the camera has no such rate at this size. The error then names the closest rate it has.
+    pub fn set_frame_rate(
+        &self,
+        numerator: u32,
+        denominator: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_running() {
+            return Err("the frame rate can not be changed while capturing".into());
+        }
+
+        let Dimensions { width, height } = self.get_dimensions()?;
+        let format = CameraFormat::new(width, height, numerator, denominator);
 
-/// Output Control
-pub enum Output {
-    /// Raw unprocesses data directly from the device
-    NV12,
-    /// Processes data as RGB32
-    RGB32,
-}
+        unsafe {
+            let Some(media_type) =
+                Self::best_native_type(&self.reader(), self.stream(), &format, &self.output)
+            else {
+                let distance =
+                    |native: &CameraFormat| (native.frame_rate() - format.frame_rate()).abs();
+
+                let closest = Self::native_camera_formats(&self.reader(), self.stream())
+                    .into_iter()
+                    .filter(|native| native.width == width && native.height == height)
+                    .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+
+                return Err(match closest {
+                    Some(closest) => {
+                        format!("the camera can not send {format}, the closest it has is {closest}")
+                    }
+                    None => format!("the camera can not send {format}"),
+                }
+                .into());
+            };
 
-/// # Activated Device
-///
-/// Allows for the capturing of data via a IMFSourceReader.
-///
-/// Created from an IMFMediaSource (which can be obtained through the VideoDevices struct.)
-///
-/// Allows you to capture data (vec<u8>) data from a connected video device from your windows machine.
-///
-/// This could be a webcam or some other type of video device. This data can then be pushed through a pipeline like OpenCV for data capturing or other sorts of projects.
-pub struct Camera {
-    // source reader that allows to get the bytes from the device
-    media_reader: IMFSourceReader,
+            self.reader()
+                .SetCurrentMediaType(self.stream(), None, &media_type)?;
 
-    /// The receiver, can be used to grab data directly from the device.
-    pub receiver: Arc<Mutex<Receiver<Vec<u8>>>>,
+            //a new native type resets the output, it is converted from the new type again
+            Self::set_stream_output(&self.reader(), self.stream(), &self.output)?;
 
-    // to send data
-    sender: Sender<Vec<u8>>,
+            *self.negotiated.lock().unwrap() =
+                Self::read_stream_format(&self.reader(), self.stream())?;
+        }
 
-    // determines if the camera is capturing and sending data
-    is_capturing: Arc<Mutex<bool>>,
+        Ok(())
+    }
 
-    /// The type of output the camera will give back to the user
-    pub output: Output,
-}
+    /// # Frame Rate
+    ///
+    /// The frames per second the camera was set to send, as negotiated with the driver. See current_fps for the rate frames are actually sent at.
+    pub fn frame_rate(&self) -> Result<f64, windows::core::Error> {
+        let rate = unsafe {
+            self.reader()
+                .GetCurrentMediaType(self.stream())?
+                .GetUINT64(&MF_MT_FRAME_RATE)?
+        };
+
+        Ok(CameraFormat::new(0, 0, (rate >> 32) as u32, rate as u32).frame_rate())
+    }
 
-impl Camera {
-    /// Create a newly activated device from the IMFMediaSource provided by the activation and a name.
+    /// # Controls
     ///
-    /// The name should be the friendly name provided by the device before activation.
+    /// The image settings of the camera, such as its exposure and white balance, see CameraControls.
     ///
-    /// Output is optional but will default to NV12 (raw)
-    pub unsafe fn new(
-        source: IMFMediaSource,
-        output: Option<Output>,
-    ) -> Result<Arc<Self>, windows::core::Error> {
-        let output = output.unwrap_or(Output::NV12); //unwraps to NV12 by default
-        let (tx, rx) = mpsc::channel(1);
+    /// The controls are those of the device, a reconnected camera has controls of its own.
+    pub fn controls(&self) -> CameraControls {
+        CameraControls::new(&self.source())
+    }
 
-        unsafe {
-            let media_reader = Self::create_reader(&source)?;
+    /// # Has Photo Stream
+    ///
+    /// Whether the camera has a stream for still photos besides its video stream, see take_photo.
+    pub fn has_photo_stream(&self) -> bool {
+        unsafe { Self::photo_stream(&self.source()) }.is_some()
+    }
 
-            Self::set_stream_selection(&media_reader)?;
-            Self::set_output_format(&media_reader, &output)?;
+    /// # Photo Formats
+    ///
+    /// The resolutions the camera takes still photos at, each once in the order the camera lists them. Empty when the camera has no photo stream, take_photo then takes a frame of the video stream at its resolution.
+    ///
+    /// Photos are often larger than any video format, the frame rate of a photo format is usually 0.
+    pub fn photo_formats(&self) -> Vec<CameraFormat> {
+        let Some(photo_stream) = (unsafe { Self::photo_stream(&self.source()) }) else {
+            return vec![];
+        };
 
-            let activated = Camera {
-                media_reader,
-                receiver: Arc::new(Mutex::new(rx)),
-                sender: tx,
-                is_capturing: Arc::new(Mutex::new(false)),
-                output,
-            };
+        let mut formats: Vec<CameraFormat> = vec![];
 
-            return Ok(Arc::new(activated));
+        for media_type in unsafe { Self::stream_media_types(&self.reader(), photo_stream.index) } {
+            if let Some(format) = unsafe { CameraFormat::from_media_type(&media_type) }
+                && !formats.contains(&format)
+            {
+                formats.push(format);
+            }
         }
+
+        formats
     }
 
-    /// # Read Sample
+    /// # Max Photo Size
     ///
-    /// Using the existing media readers takes in the video stream to read from (defaults to first video stream if None) a stream.
+    /// The largest resolution the camera takes still photos at, the size take_photo takes them at. None when the camera has no photo stream.
+    pub fn max_photo_size(&self) -> Option<Dimensions> {
+        self.photo_formats()
+            .into_iter()
+            .max_by_key(|format| format.width as u64 * format.height as u64)
+            .map(|format| Dimensions {
+                width: format.width,
+                height: format.height,
+            })
+    }
+
+    /// # Take Photo
     ///
-    /// Reads a sample of the stream, converts to a buffer and retrieves the underlying data returned as Vec<u8>
+    /// Takes a still photo at the largest resolution of the camera's photo stream (see max_photo_size), waiting up to the timeout for it.
     ///
-    pub fn read_sample(&self, video_stream: Option<u32>) -> Result<Vec<u8>, windows::core::Error> {
-        //initialize values for loading into the readsample func
-        let video_stream = video_stream.unwrap_or(MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32);
-        let mut sample: Option<IMFSample> = None;
-        let buffer: Option<IMFMediaBuffer>;
-        let mut stream_index: u32 = 0;
-        let mut stream_flags: u32 = 0;
-        let mut time_stamp: i64 = 0;
+    /// The photo is in the camera's output when the source reader can convert to it, otherwise in the format the camera takes photos in (often MJPG), Frame::format says which.
+    /// Cameras without a photo stream take a frame of the video stream instead, see capture_frame.
+    ///
+    /// Fails if the camera is capturing, stop it first. The photo stream is turned off again afterwards, so capturing can be started as usual.
+    pub async fn take_photo(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<Frame, Box<dyn std::error::Error>> {
+        let Some(photo_stream) = (unsafe { Self::photo_stream(&self.source()) }) else {
+            return self.capture_frame(timeout).await;
+        };
+
+        //held until the photo is read so a capture cannot start in the middle of it
+        let is_capturing = self.is_capturing.lock().await;
+
+        if *is_capturing {
+            return Err("the camera is capturing, stop it before taking a photo".into());
+        }
+
+        let result = match unsafe { self.select_photo_stream(&photo_stream) } {
+            Ok(format) => self.read_photo(&photo_stream, format, timeout).await,
+            Err(e) => Err(e.into()),
+        };
+
+        //only the video stream is read while capturing
+        let _ = unsafe { self.reader().SetStreamSelection(photo_stream.index, false) };
+
+        result
+    }
 
+    // turns the photo stream on at its largest resolution, in the output when the reader can convert to it
+    unsafe fn select_photo_stream(
+        &self,
+        photo_stream: &PhotoStream,
+    ) -> Result<NegotiatedFormat, windows::core::Error> {
         unsafe {
-            self.media_reader.ReadSample(
-                video_stream,
-                0,
-                Some(&mut stream_index),
-                Some(&mut stream_flags),
-                Some(&mut time_stamp),
-                Some(&mut sample),
-            )?;
+            let largest = Self::stream_media_types(&self.reader(), photo_stream.index)
+                .into_iter()
+                .filter(|media_type| {
+                    media_type
+                        .GetGUID(&MF_MT_SUBTYPE)
+                        .is_ok_and(|subtype| Output::from_subtype(&subtype).is_some())
+                })
+                .max_by_key(|media_type| {
+                    media_type
+                        .GetUINT64(&MF_MT_FRAME_SIZE)
+                        .map_or(0, |size| (size >> 32) * (size & 0xFFFFFFFF))
+                });
+
+            let Some(largest) = largest else {
+                return Err(windows::core::Error::new(
+                    MF_E_INVALIDMEDIATYPE,
+                    "the camera takes photos in no format that can be read",
+                ));
+            };
 
-            if sample.is_none() {
-                return Ok(vec![]);
+            self.reader().SetStreamSelection(photo_stream.index, true)?;
+            self.reader()
+                .SetCurrentMediaType(photo_stream.index, None, &largest)?;
+
+            //the photo is kept in the camera's own format when the reader can not convert it
+            let output = MFCreateMediaType()?;
+            output.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+            output.SetGUID(&MF_MT_SUBTYPE, self.output.subtype())?;
+
+            if self
+                .reader()
+                .SetCurrentMediaType(photo_stream.index, None, &output)
+                .is_err()
+            {
+                self.reader()
+                    .SetCurrentMediaType(photo_stream.index, None, &largest)?;
             }
 
-            buffer = Some(sample.unwrap().ConvertToContiguousBuffer()?);
+            Self::read_stream_format(&self.reader(), photo_stream.index)
         }
+    }
 
-        //ensure the buffer contains some value.
-        if buffer.is_none() {
-            return Err(windows::Win32::Foundation::E_FAIL.into());
+    // triggers a photo and reads it from the photo stream, gaps are passed over until the timeout
+    async fn read_photo(
+        self: &Arc<Self>,
+        photo_stream: &PhotoStream,
+        format: NegotiatedFormat,
+        timeout: Duration,
+    ) -> Result<Frame, Box<dyn std::error::Error>> {
+        unsafe { self.trigger_photo(photo_stream) };
+
+        let deadline = Instant::now() + timeout;
+        let stream = photo_stream.index;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("no photo was taken within {timeout:?}").into());
+            }
+
+            let camera = self.clone();
+            let read = run_blocking(move || camera.read_sample(Some(stream)));
+            tokio::pin!(read);
+
+            let read = match tokio::time::timeout(remaining, &mut read).await {
+                Ok(read) => read.map_err(|e| self.busy_or(e) as Box<dyn std::error::Error>)?,
+                Err(_) => {
+                    //the pending read is cancelled so the reader is idle again once this returns
+                    let _ = self.flush_reader().await;
+                    let _ = read.await;
+
+                    return Err(format!("no photo was taken within {timeout:?}").into());
+                }
+            };
+
+            match read {
+                SampleRead::Sample {
+                    data,
+                    time,
+                    duration,
+                    stream,
+                } if !data.is_empty() => {
+                    let mut frame = Frame::new(
+                        format.format.width,
+                        format.format.height,
+                        format.stride,
+                        format.pixel_format,
+                        data,
+                    );
+                    frame.sample_time = time;
+                    frame.sample_duration = duration;
+                    frame.stream_index = Some(stream);
+                    self.orient_frame(&mut frame, &format);
+
+                    return Ok(frame);
+                }
+                SampleRead::Sample { .. } | SampleRead::Gap { .. } => continue,
+                SampleRead::EndOfStream => {
+                    return Err("the camera's photo stream ended before it took a photo".into());
+                }
+            }
         }
+    }
 
-        let buffer = buffer.unwrap();
+    // asks the camera to take a photo, cameras that send photos on their own do not need it and may refuse
+    unsafe fn trigger_photo(&self, photo_stream: &PhotoStream) {
+        let Ok(control) = self.source().cast::<IKsControl>() else {
+            return;
+        };
+
+        let mut mode = KSPROPERTY_VIDEOCONTROL_MODE_S {
+            Property: KSIDENTIFIER {
+                Anonymous: KSIDENTIFIER_0 {
+                    Anonymous: KSIDENTIFIER_0_0 {
+                        Set: PROPSETID_VIDCAP_VIDEOCONTROL,
+                        Id: KSPROPERTY_VIDEOCONTROL_MODE.0 as u32,
+                        Flags: KSPROPERTY_TYPE_SET,
+                    },
+                },
+            },
+            //the pin of the stream
+            StreamIndex: photo_stream.pin,
+            Mode: KS_VideoControlFlag_Trigger.0,
+        };
+
+        let size = size_of::<KSPROPERTY_VIDEOCONTROL_MODE_S>() as u32;
+        let mut returned = 0;
+
+        //a camera that can not be triggered sends its photos anyway or times out
+        let _ = unsafe {
+            control.KsProperty(
+                &mode.Property,
+                size,
+                &mut mode as *mut _ as *mut c_void,
+                size,
+                &mut returned,
+            )
+        };
+    }
+
+    // the stream for still photos, None when the camera only has video streams
+    unsafe fn photo_stream(source: &IMFMediaSource) -> Option<PhotoStream> {
+        unsafe {
+            let descriptor = source.CreatePresentationDescriptor().ok()?;
+
+            for index in 0..descriptor.GetStreamDescriptorCount().ok()? {
+                let mut selected = BOOL::default();
+                let mut stream: Option<IMFStreamDescriptor> = None;
+
+                if descriptor
+                    .GetStreamDescriptorByIndex(index, &mut selected, &mut stream)
+                    .is_err()
+                {
+                    continue;
+                }
+
+                let Some(stream) = stream else {
+                    continue;
+                };
+
+                let category = stream.GetGUID(&MF_DEVICESTREAM_STREAM_CATEGORY);
+
+                if category.is_ok_and(|category| {
+                    category == PINNAME_IMAGE || category == PINNAME_VIDEO_STILL
+                }) {
+                    return Some(PhotoStream {
+                        index,
+                        pin: stream.GetStreamIdentifier().ok()?,
+                    });
+                }
+            }
 
-        Ok(Self::get_frame_data(&buffer)?)
+            None
+        }
     }
 
-    pub fn get_frame_data(buffer: &IMFMediaBuffer) -> Result<Vec<u8>, windows::core::Error> {
-        let mut pcbmaxlength: u32 = 0;
-        let mut pcbcurrentlength: u32 = 0;
+    /// # Supported Formats
+    ///
+    /// The resolutions and frame rates the camera sends frames at on the selected stream, each once in the order the camera lists them. Pick one for Cameras::activate_device_with_format.
+    pub fn supported_formats(&self) -> Vec<CameraFormat> {
+        unsafe { Self::native_camera_formats(&self.reader(), self.stream()) }
+    }
+
+    /// # Streams
+    ///
+    /// The streams of the camera, in the order of their index. Most webcams have a single video stream, sometimes with a photo stream (see take_photo), while devices such as depth cameras and Windows Hello cameras also send infrared and depth images.
+    ///
+    /// Frames are read from the first video stream until another is selected with select_stream.
+    pub fn streams(&self) -> Vec<StreamInfo> {
+        let reader = self.reader();
+        let descriptor = unsafe { self.source().CreatePresentationDescriptor() }.ok();
+        let photo_stream = unsafe { Self::photo_stream(&self.source()) }.map(|photo| photo.index);
+
+        //the list ends with MF_E_INVALIDSTREAMNUMBER
+        (0..)
+            .map_while(|index| unsafe {
+                let media_type = reader.GetCurrentMediaType(index).ok()?;
+                let major_type = media_type.GetGUID(&MF_MT_MAJOR_TYPE).unwrap_or_default();
+                let subtype = media_type.GetGUID(&MF_MT_SUBTYPE).unwrap_or_default();
+
+                //cameras that send several kinds of images say which on the descriptor of each stream
+                let source_types = descriptor.as_ref().and_then(|descriptor| {
+                    let mut selected = BOOL::default();
+                    let mut stream: Option<IMFStreamDescriptor> = None;
+
+                    descriptor
+                        .GetStreamDescriptorByIndex(index, &mut selected, &mut stream)
+                        .ok()?;
+
+                    stream?
+                        .GetUINT32(&MF_DEVICESTREAM_ATTRIBUTE_FRAMESOURCE_TYPES)
+                        .ok()
+                });
+
+                let kind = match source_types.map(|types| types as i32) {
+                    Some(types) if types & MFFrameSourceTypes_Infrared.0 != 0 => {
+                        StreamKind::Infrared
+                    }
+                    Some(types) if types & MFFrameSourceTypes_Depth.0 != 0 => StreamKind::Depth,
+                    _ if photo_stream == Some(index) => StreamKind::Photo,
+                    Some(types) if types & MFFrameSourceTypes_Color.0 != 0 => StreamKind::Color,
+                    None if major_type == MFMediaType_Video => StreamKind::Color,
+                    _ => StreamKind::Other,
+                };
+
+                Some(StreamInfo {
+                    index,
+                    kind,
+                    major_type,
+                    subtype,
+                    subtype_name: Output::subtype_name(&subtype),
+                    size: CameraFormat::from_media_type(&media_type).map(|format| Dimensions {
+                        width: format.width,
+                        height: format.height,
+                    }),
+                    selected: reader
+                        .GetStreamSelection(index)
+                        .is_ok_and(|selected| selected.as_bool()),
+                })
+            })
+            .collect()
+    }
 
-        let mut ppbbuffer: *mut u8 = std::ptr::null_mut();
+    /// # Select Stream
+    ///
+    /// Reads frames from the video stream with the index instead, such as the infrared or depth stream of the camera (see streams). Only the selected stream is read while capturing, frames are tagged with it in Frame::stream_index.
+    ///
+    /// The stream is read in the camera's output when the source reader can convert to it, and otherwise in its own format when frames can be read in it, such as Depth16 frames of a depth stream. The negotiated format is read back for the stream, its pixel format says which.
+    ///
+    /// Fails while capturing, for streams that are not video streams, and when the stream can not be read in the output. The stream selected before is kept then.
+    pub fn select_stream(&self, index: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_running() {
+            return Err("the stream can not be changed while capturing".into());
+        }
 
-        unsafe {
-            buffer.Lock(
-                &mut ppbbuffer,
-                Some(&mut pcbmaxlength),
-                Some(&mut pcbcurrentlength),
-            )?;
+        self.open_stream(index, None)?;
+
+        Ok(())
+    }
+
+    /// The index of the stream selected with select_stream, None while frames are read from the first video stream.
+    pub fn selected_stream(&self) -> Option<u32> {
+        Some(self.stream()).filter(|stream| *stream != FIRST_VIDEO_STREAM)
+    }
+
+    // the stream frames are read from, see select_stream
+    fn stream(&self) -> u32 {
+        self.stream.load(Ordering::Relaxed)
+    }
 
-            //ppbbuffer now contains our video frame data.
+    // selects the stream in the format, if any, and the output, going back to the stream selected before when it fails
+    pub(crate) fn open_stream(
+        &self,
+        index: u32,
+        format: Option<&CameraFormat>,
+    ) -> Result<(), windows::core::Error> {
+        let reader = self.reader();
+
+        let major_type = unsafe {
+            reader
+                .GetCurrentMediaType(index)?
+                .GetGUID(&MF_MT_MAJOR_TYPE)?
+        };
+
+        if major_type != MFMediaType_Video {
+            return Err(windows::core::Error::new(
+                MF_E_INVALIDMEDIATYPE,
+                format!("stream {index} of the camera is not a video stream"),
+            ));
+        }
 
-            let frame_data =
-                std::slice::from_raw_parts(ppbbuffer, pcbcurrentlength as usize).to_vec();
+        let transforms = &self.reader_options.transforms;
 
-            buffer.Unlock()?;
+        match unsafe { Self::configure_stream(&reader, index, &self.output, format, transforms) } {
+            Ok(negotiated) => {
+                self.stream.store(index, Ordering::Relaxed);
+                *self.negotiated.lock().unwrap() = negotiated;
+
+                Ok(())
+            }
+            Err(e) => {
+                //the stream before is still in its format, it only has to be turned back on
+                let _ = unsafe { Self::set_stream_selection(&reader, self.stream()) };
 
-            Ok(frame_data)
+                Err(e)
+            }
         }
     }
 
-    // sets the output format for the receiver.
+    /// # Get Frame Data
+    ///
+    /// Copies the valid data out of a media buffer. The buffer is locked for the copy only and always unlocked again, also when the copy fails, so it can be locked again by whoever holds it next.
+    pub fn get_frame_data(buffer: &IMFMediaBuffer) -> Result<Vec<u8>, windows::core::Error> {
+        let locked = unsafe { LockedBuffer::lock(buffer)? };
+
+        //the buffer is unlocked when locked is dropped, however the copy ends
+        Ok(locked.data().to_vec())
+    }
+
+    // sets the output format for the receiver, the error names the formats the camera sends when the reader can not provide it.
     unsafe fn set_output_format(
         reader: &IMFSourceReader,
+        stream: u32,
         output: &Output,
     ) -> Result<(), windows::core::Error> {
         unsafe {
             let media_type = MFCreateMediaType()?;
 
             media_type.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+            media_type.SetGUID(&MF_MT_SUBTYPE, output.subtype())?;
+
+            if let Err(e) = reader.SetCurrentMediaType(stream, None, &media_type) {
+                return Err(windows::core::Error::new(
+                    e.code(),
+                    format!(
+                        "the camera can not send {output:?} frames, it sends {}: {}",
+                        Self::native_subtypes(reader, stream).join(", "),
+                        e.message()
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // sets the output format on the stream, a stream picked with select_stream is kept in its own format when the reader can not convert it, as depth streams can only be read as they are
+    unsafe fn set_stream_output(
+        reader: &IMFSourceReader,
+        stream: u32,
+        output: &Output,
+    ) -> Result<(), windows::core::Error> {
+        let result = unsafe { Self::set_output_format(reader, stream, output) };
+
+        if result.is_err() && stream != FIRST_VIDEO_STREAM {
+            let native = unsafe {
+                reader
+                    .GetNativeMediaType(stream, MF_SOURCE_READER_CURRENT_TYPE_INDEX.0 as u32)
+                    .and_then(|media_type| media_type.GetGUID(&MF_MT_SUBTYPE))
+            };
+
+            if let Some(native) = native.ok().and_then(|native| Output::from_subtype(&native)) {
+                return unsafe { Self::set_output_format(reader, stream, &native) };
+            }
+        }
 
-            let guid_value = match output {
-                Output::NV12 => &MFVideoFormat_NV12,
-                Output::RGB32 => &MFVideoFormat_RGB32,
+        result
+    }
+
+    // reads back the media type the stream was set to, failing when its subtype is not one frames can be read in
+    unsafe fn read_stream_format(
+        reader: &IMFSourceReader,
+        stream: u32,
+    ) -> Result<NegotiatedFormat, windows::core::Error> {
+        unsafe {
+            let media_type = reader.GetCurrentMediaType(stream)?;
+            let subtype = media_type.GetGUID(&MF_MT_SUBTYPE)?;
+
+            let Some(output) = Output::from_subtype(&subtype) else {
+                return Err(windows::core::Error::new(
+                    MF_E_INVALIDMEDIATYPE,
+                    format!(
+                        "the source reader sends {} frames, which can not be read",
+                        Output::subtype_name(&subtype)
+                    ),
+                ));
             };
 
-            media_type.SetGUID(&MF_MT_SUBTYPE, guid_value)?;
+            let Some(format) = CameraFormat::from_media_type(&media_type) else {
+                return Err(windows::core::Error::new(
+                    MF_E_INVALIDMEDIATYPE,
+                    "the source reader sends frames without a size",
+                ));
+            };
 
-            let first_video_stream = MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32;
-            reader.SetCurrentMediaType(first_video_stream, None, &media_type)?;
+            let pixel_format = output.pixel_format();
+
+            //the default stride is not always set by the device, it is then the stride of a bitmap of the subtype
+            //negative strides are bottom up images, the size of the row is the same
+            let stride = media_type
+                .GetUINT32(&MF_MT_DEFAULT_STRIDE)
+                .map(|stride| stride as i32)
+                .or_else(|_| MFGetStrideForBitmapInfoHeader(subtype.data1, format.width))
+                .unwrap_or((format.width * pixel_format.bytes_per_pixel()) as i32);
+
+            Ok(NegotiatedFormat {
+                pixel_format,
+                format,
+                stride: stride.unsigned_abs(),
+                bottom_up: stride < 0,
+            })
         }
+    }
 
-        Ok(())
+    // sets the native media type closest to the format, the error lists the formats the camera has
+    unsafe fn set_native_format(
+        reader: &IMFSourceReader,
+        stream: u32,
+        format: &CameraFormat,
+        output: &Output,
+    ) -> Result<(), windows::core::Error> {
+        let Some(media_type) = (unsafe { Self::best_native_type(reader, stream, format, output) })
+        else {
+            let supported: Vec<String> = unsafe { Self::native_camera_formats(reader, stream) }
+                .iter()
+                .map(|format| format.to_string())
+                .collect();
+
+            return Err(windows::core::Error::new(
+                MF_E_INVALIDMEDIATYPE,
+                format!(
+                    "the camera can not send {format}, it sends {}",
+                    supported.join(", ")
+                ),
+            ));
+        };
+
+        unsafe { reader.SetCurrentMediaType(stream, None, &media_type) }
+    }
+
+    // the native media type matching the format with the closest frame rate, preferring one in the output's subtype
+    unsafe fn best_native_type(
+        reader: &IMFSourceReader,
+        stream: u32,
+        format: &CameraFormat,
+        output: &Output,
+    ) -> Option<IMFMediaType> {
+        unsafe { Self::stream_media_types(reader, stream) }
+            .into_iter()
+            .filter_map(|media_type| {
+                let native = unsafe { CameraFormat::from_media_type(&media_type)? };
+                let distance = format.distance(&native)?;
+                let converted = unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) }
+                    .map_or(true, |subtype| subtype != *output.subtype());
+
+                Some((converted, distance, media_type))
+            })
+            .min_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap())
+            .map(|(_, _, media_type)| media_type)
+    }
+
+    // the media types the camera sends itself on the stream, one for every pixel format, size and frame rate
+    unsafe fn stream_media_types(reader: &IMFSourceReader, stream: u32) -> Vec<IMFMediaType> {
+        //the list ends with an error
+        (0..)
+            .map_while(|index| unsafe { reader.GetNativeMediaType(stream, index).ok() })
+            .collect()
+    }
+
+    // the names of the pixel formats the camera sends itself, each once
+    unsafe fn native_subtypes(reader: &IMFSourceReader, stream: u32) -> Vec<String> {
+        let mut subtypes: Vec<String> = vec![];
+
+        for media_type in unsafe { Self::stream_media_types(reader, stream) } {
+            let Ok(subtype) = (unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) }) else {
+                continue;
+            };
+
+            let name = Output::subtype_name(&subtype);
+
+            if !subtypes.contains(&name) {
+                subtypes.push(name);
+            }
+        }
+
+        subtypes
+    }
+
+    // the sizes and frame rates the camera sends itself, each once
+    unsafe fn native_camera_formats(reader: &IMFSourceReader, stream: u32) -> Vec<CameraFormat> {
+        let mut formats: Vec<CameraFormat> = vec![];
+
+        for media_type in unsafe { Self::stream_media_types(reader, stream) } {
+            if let Some(format) = unsafe { CameraFormat::from_media_type(&media_type) }
+                && !formats.contains(&format)
+            {
+                formats.push(format);
+            }
+        }
+
+        formats
     }
 
-    // set the stream selection, this is by default the first video stream from all rendering streams.
-    unsafe fn set_stream_selection(reader: &IMFSourceReader) -> Result<(), windows::core::Error> {
+    // set the stream selection, only the stream is read from all rendering streams. By default the first video stream.
+    unsafe fn set_stream_selection(
+        reader: &IMFSourceReader,
+        stream: u32,
+    ) -> Result<(), windows::core::Error> {
         unsafe {
             let all_streams = MF_SOURCE_READER_ALL_STREAMS.0 as u32;
             reader.SetStreamSelection(all_streams, false)?;
 
-            let first_video_stream = MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32;
-            reader.SetStreamSelection(first_video_stream, true)?;
+            reader.SetStreamSelection(stream, true)?;
+        }
+
+        Ok(())
+    }
+
+    // selects the stream alone in the format, if any, and the output, adds the transforms after it and reads back what it negotiated
+    unsafe fn configure_stream(
+        reader: &IMFSourceReader,
+        stream: u32,
+        output: &Output,
+        format: Option<&CameraFormat>,
+        transforms: &[CameraTransform],
+    ) -> Result<NegotiatedFormat, windows::core::Error> {
+        unsafe {
+            Self::set_stream_selection(reader, stream)?;
+
+            //a stream selected again still has the transforms added before, the reader sets up its own converters again with the output
+            if !transforms.is_empty() {
+                reader
+                    .cast::<IMFSourceReaderEx>()?
+                    .RemoveAllTransformsForStream(stream)?;
+            }
+
+            //the native format is set first, the output is then converted from it
+            if let Some(format) = format {
+                Self::set_native_format(reader, stream, format, output)?;
+            }
+
+            Self::set_stream_output(reader, stream, output)?;
+            Self::add_transforms(reader, stream, transforms)?;
+
+            Self::read_stream_format(reader, stream)
+        }
+    }
+
+    // adds the transforms to the end of the stream's chain in order, each is offered the frames of the one before
+    unsafe fn add_transforms(
+        reader: &IMFSourceReader,
+        stream: u32,
+        transforms: &[CameraTransform],
+    ) -> Result<(), windows::core::Error> {
+        if transforms.is_empty() {
+            return Ok(());
+        }
+
+        unsafe {
+            let reader_ex: IMFSourceReaderEx = reader.cast()?;
+
+            for (index, transform) in transforms.iter().enumerate() {
+                let media_type = reader.GetCurrentMediaType(stream)?;
+                let subtype = Output::subtype_name(&media_type.GetGUID(&MF_MT_SUBTYPE)?);
+
+                let offered = match CameraFormat::from_media_type(&media_type) {
+                    Some(format) => format!("{subtype} {format}"),
+                    None => subtype,
+                };
+
+                transform.add_to(&reader_ex, stream, index, &offered)?;
+            }
         }
 
         Ok(())
     }
 
-    // creates the IMFSource reader for video processing and enables hardware transforms
+    // creates the source reader of the source reading the stream in the format, if any, and the output, and reads back what it negotiated
+    //
+    // the reader converts on the GPU when the options ask for it and there is a D3D11 video device, and on the CPU otherwise
+    unsafe fn open_reader(
+        source: IMFMediaSource,
+        stream: u32,
+        output: &Output,
+        format: Option<&CameraFormat>,
+        reader_options: &ReaderOptions,
+    ) -> Result<(CameraDevice, NegotiatedFormat), windows::core::Error> {
+        unsafe {
+            let manager = match reader_options.gpu_conversion {
+                true => video_device_manager().ok(),
+                false => None,
+            };
+
+            //a reader that could not be created never owned the source, so it can be tried again without the device
+            let (reader, gpu_conversion) = match manager.and_then(|manager| {
+                Self::create_reader(&source, reader_options, Some(&manager)).ok()
+            }) {
+                Some(reader) => (reader, true),
+                None => (Self::create_reader(&source, reader_options, None)?, false),
+            };
+
+            let negotiated = Self::configure_stream(
+                &reader,
+                stream,
+                output,
+                format,
+                &reader_options.transforms,
+            )?;
+
+            Ok((
+                CameraDevice {
+                    reader,
+                    source,
+                    gpu_conversion,
+                },
+                negotiated,
+            ))
+        }
+    }
+
+    /// # Is GPU Converting
+    ///
+    /// Whether the camera's frames are converted to its output on the GPU, by the video processor of a D3D11 video device. See CameraBuilder::gpu_conversion.
+    ///
+    /// False when GPU conversion was not asked for, and when it was but no D3D11 video device was available, the source reader then converts on the CPU as it does by default.
+    pub fn is_gpu_converting(&self) -> bool {
+        self.device.read().unwrap().gpu_conversion
+    }
+
+    // the source reader, a reconnected camera has a new one
+    fn reader(&self) -> IMFSourceReader {
+        self.device.read().unwrap().reader.clone()
+    }
+
+    // the device's source the reader reads from
+    fn source(&self) -> IMFMediaSource {
+        self.device.read().unwrap().source.clone()
+    }
+
+    // creates the IMFSource reader, with video processing and hardware transforms as the options say
+    //
+    // with a device manager the reader converts with the GPU's video processor instead of video processing on the CPU
     unsafe fn create_reader(
         source: &IMFMediaSource,
+        reader_options: &ReaderOptions,
+        manager: Option<&IMFDXGIDeviceManager>,
     ) -> Result<IMFSourceReader, windows::core::Error> {
         unsafe {
             let mut options: Option<IMFAttributes> = None;
-            MFCreateAttributes(&mut options, 2)?;
+            MFCreateAttributes(&mut options, 3)?;
 
             if options.is_none() {
                 return Err(E_ABORT.into());
             }
 
             let attrs = options.unwrap();
-            attrs.SetUINT32(&MF_SOURCE_READER_ENABLE_VIDEO_PROCESSING, 1)?;
 
-            attrs.SetUINT32(&MF_READWRITE_ENABLE_HARDWARE_TRANSFORMS, 1)?;
+            //the two kinds of video processing can not be turned on together
+            match manager {
+                Some(manager) => {
+                    attrs.SetUnknown(&MF_SOURCE_READER_D3D_MANAGER, manager)?;
+                    attrs.SetUINT32(&MF_SOURCE_READER_ENABLE_ADVANCED_VIDEO_PROCESSING, 1)?;
+                }
+                None => attrs.SetUINT32(
+                    &MF_SOURCE_READER_ENABLE_VIDEO_PROCESSING,
+                    reader_options.video_processing as u32,
+                )?,
+            }
+
+            attrs.SetUINT32(
+                &MF_READWRITE_ENABLE_HARDWARE_TRANSFORMS,
+                reader_options.hardware_transforms as u32,
+            )?;
 
             let reader: IMFSourceReader = MFCreateSourceReaderFromMediaSource(source, &attrs)?;
 
@@ -211,18 +2302,17 @@ impl Camera {
 }
 
 impl ICapture for Camera {
-    type CaptureOutput = Vec<u8>;
+    type CaptureOutput = Frame;
 
     /// # Get Dimensions
     ///
     /// Get the device size of the video camera.
     fn get_dimensions(&self) -> Result<Dimensions, Box<dyn std::error::Error>> {
-        let first_video_stream = MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32;
         let size: Option<u64>;
 
         //create unsafe calls to get the media type and the dimensions store as a u64
         unsafe {
-            let media_type = self.media_reader.GetCurrentMediaType(first_video_stream)?;
+            let media_type = self.reader().GetCurrentMediaType(self.stream())?;
 
             size = Some(media_type.GetUINT64(&MF_MT_FRAME_SIZE)?);
         }
@@ -236,23 +2326,34 @@ impl ICapture for Camera {
         let width = (size >> 32) as u32;
         let height = (size & 0xFFFFFFFF) as u32;
 
-        Ok(Dimensions { width, height })
+        //frames are sent rotated, see set_orientation
+        Ok(self
+            .get_orientation()
+            .oriented_size(&Dimensions { width, height }))
     }
 
     /// ## Stop Captruing
     ///
     /// Safely stops capturing data.
+    ///
+    /// The source reader is flushed, so a read waiting on a camera that stopped sending (such as a closed privacy shutter) returns right away instead of holding the stop up. See Camera::stop_and_wait to wait for the capture to end.
     fn stop_capturing(
         self: Arc<Self>,
     ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
         Box::pin(async move {
-            let mut cap_guard = self.is_capturing.lock().await;
+            {
+                let mut cap_guard = self.is_capturing.lock().await;
 
-            if !*cap_guard {
-                return Err("already stopped.".into());
+                if !*cap_guard {
+                    return Err("already stopped.".into());
+                }
+
+                *cap_guard = false;
+                *self.state.lock().unwrap() = CaptureState::Stopping;
             }
 
-            *cap_guard = false;
+            //the stop was requested either way, when the flush fails the loop stops after the pending read
+            let _ = self.flush_reader().await;
 
             Ok(())
         })
@@ -266,13 +2367,19 @@ impl ICapture for Camera {
     ///
     /// ## Warning
     ///
-    /// This operation contains a loop and will block until stop_capturing is called...
+    /// This operation contains a loop and will not end until stop_capturing is called or the camera's stream ends. Samples are read on tokio's blocking threads, so other tasks on the runtime are not held up while waiting for the camera.
     ///
     /// You must start this on your main thread. You may then create a task that controls the stop_capturing function as this struct is send+sync safe.
+    ///
+    /// spawn_capturing runs this on its own task and returns a handle instead, it is the recommended way to capture.
     fn start_capturing(
         self: Arc<Self>,
     ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
         Box::pin(async move {
+            if self.is_shut_down() {
+                return Err("the camera was shut down, activate it again to capture".into());
+            }
+
             // lock the capguard, check if already capturing, if not set as true and continue
             {
                 let mut cap_guard = self.is_capturing.lock().await;
@@ -284,27 +2391,30 @@ impl ICapture for Camera {
                 *cap_guard = true;
             }
 
-            //clone all resources that need to be moved
-            let is_capturing_ref = self.is_capturing.clone();
-            let sender = self.sender.clone();
-            loop {
-                //check if capturing, drop immediately
-                {
-                    let is_capturing = is_capturing_ref.lock().await;
-
-                    if !*is_capturing {
-                        break;
-                    }
-                }
+            let guard = CaptureGuard::new(&self.is_capturing, Some(&self.state));
 
-                let first_video_stream = MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32;
+            //the negotiated media type does not change while capturing
+            *self.state.lock().unwrap() = CaptureState::Running;
+            self.fps.reset();
+            self.change_filter.lock().unwrap().reset();
+            self.duplicate_filter.lock().unwrap().reset();
+            self.running.send_replace(true);
+            self.send_event(CaptureEvent::Started);
 
-                let data = self.read_sample(Some(first_video_stream))?;
+            let result = self.capture_loop().await;
 
-                sender.send(data).await?;
+            //sent however the loop ended, even when the frame receiver was dropped
+            let error = result.as_ref().err().map(|e| e.as_ref() as _);
+            for event in CaptureEvent::ended(error) {
+                self.send_event(event);
             }
 
-            Ok(())
+            //the camera can be started again after an error
+            guard.finish(&result).await;
+            self.fps.reset();
+            self.running.send_replace(false);
+
+            result.map_err(|e| e as Box<dyn std::error::Error>)
         })
     }
 
@@ -316,6 +2426,17 @@ impl ICapture for Camera {
     }
 }
 
+impl Drop for Camera {
+    /// Shuts down the device's source, unless shutdown already did, so the camera is released as soon as it is dropped.
+    ///
+    /// The capture loop holds onto the camera, so it has already exited by the time the camera is dropped. Errors cannot be returned from drop, shutting down is best effort.
+    fn drop(&mut self) {
+        let _ = self.shutdown_device();
+    }
+}
+
+// the media source and source reader are Media Foundation objects, which are free threaded and may be called from any thread
+// the reader is read by one capture at a time, every other state of the camera is atomic or behind a mutex
 unsafe impl Send for Camera {}
 
 unsafe impl Sync for Camera {}
@@ -0,0 +1,267 @@
+use std::{sync::Arc, time::Duration};
+
+use windows::Win32::Media::MediaFoundation::IMFActivate;
+
+use crate::{
+    devices::{
+        Camera, CameraFormat, CameraTransform, Cameras,
+        camera::{Output, ReaderOptions},
+        delivery_policy::check_channel_capacity,
+        get_device_symbolic_link,
+    },
+    frame::Orientation,
+};
+
+/// # Camera Builder
+///
+/// Sets up how a camera is activated: its output, format and how its source reader is created. Obtained from Cameras::camera_builder, build activates the camera.
+///
+/// Every option is checked together when building, options that conflict fail with an error saying which to change.
+pub struct CameraBuilder<'a> {
+    device: &'a IMFActivate,
+    output: Output,
+    format: Option<CameraFormat>,
+    stream: Option<u32>,
+    frame_rate: Option<(u32, u32)>,
+    hardware_transforms: bool,
+
+    // None leaves it to the output, see video_processing
+    video_processing: Option<bool>,
+
+    gpu_conversion: bool,
+    transforms: Vec<CameraTransform>,
+
+    channel_capacity: Option<usize>,
+    warmup_frames: u32,
+    flip_bottom_up: bool,
+    orientation: Orientation,
+    auto_reconnect: Option<Duration>,
+}
+
+impl<'a> CameraBuilder<'a> {
+    /// # New
+    ///
+    /// A builder for the device, such as one of Cameras::devices. The camera is not activated until build is called.
+    pub fn new(device: &'a IMFActivate) -> Self {
+        CameraBuilder {
+            device,
+            output: Output::NV12,
+            format: None,
+            stream: None,
+            frame_rate: None,
+            hardware_transforms: true,
+            video_processing: None,
+            gpu_conversion: false,
+            transforms: vec![],
+            channel_capacity: None,
+            warmup_frames: 0,
+            flip_bottom_up: true,
+            orientation: Orientation::default(),
+            auto_reconnect: None,
+        }
+    }
+
+    /// # Output
+    ///
+    /// The format frames are read in, NV12 by default. See Output for which formats are converted.
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// # Format
+    ///
+    /// The resolution and frame rate the camera sends frames at, matched like Camera::new_with_format. The camera's own format is kept by default.
+    pub fn format(mut self, format: CameraFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// # Stream
+    ///
+    /// The index of the video stream frames are read from, such as the infrared or depth stream of the camera. The first video stream by default, see Camera::select_stream.
+    ///
+    /// A format set as well is matched against the formats of this stream.
+    pub fn stream(mut self, index: u32) -> Self {
+        self.stream = Some(index);
+        self
+    }
+
+    /// # Frame Rate
+    ///
+    /// The frames per second at the camera's own resolution, see Camera::set_frame_rate. Set the rate in format instead when setting a format.
+    pub fn frame_rate(mut self, numerator: u32, denominator: u32) -> Self {
+        self.frame_rate = Some((numerator, denominator));
+        self
+    }
+
+    /// # Hardware Transforms
+    ///
+    /// Whether the source reader may decode and convert frames on the GPU, on by default. Turning it off keeps the work on the CPU, for drivers whose GPU decoders misbehave.
+    pub fn hardware_transforms(mut self, enabled: bool) -> Self {
+        self.hardware_transforms = enabled;
+        self
+    }
+
+    /// # Video Processing
+    ///
+    /// Whether the source reader may convert the camera's frames on the CPU to an output the camera does not send itself, such as RGB32 from a YUY2 camera. On by default for uncompressed outputs.
+    ///
+    /// Turned off, only outputs the camera sends (or can decode to) can be read. It can not be turned on for MJPG, the reader only converts to uncompressed formats.
+    pub fn video_processing(mut self, enabled: bool) -> Self {
+        self.video_processing = Some(enabled);
+        self
+    }
+
+    /// # GPU Conversion
+    ///
+    /// Whether frames are converted to the output on the GPU, by the video processor of a D3D11 video device, instead of on the CPU. Off by default.
+    ///
+    /// The camera then sends its own format (such as NV12) and the GPU converts it, for RGB32 and ARGB32 outputs whose conversion is costly at high resolutions. Without a D3D11 video device the camera converts on the CPU as it would otherwise, see Camera::is_gpu_converting.
+    pub fn gpu_conversion(mut self, enabled: bool) -> Self {
+        self.gpu_conversion = enabled;
+        self
+    }
+
+    /// # Transform
+    ///
+    /// Passes the camera's frames through the Media Foundation transform, such as a denoiser, before they are sent. Transforms are applied in the order they were added, the first after the output.
+    ///
+    /// Building fails when a transform does not accept the frames of the output or the transform before it, the error names the transform and the format it was offered. See CameraTransform.
+    pub fn transform(mut self, transform: CameraTransform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// # Channel Capacity
+    ///
+    /// How many frames the receiver's channel holds, see Camera::with_channel_capacity.
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = Some(capacity);
+        self
+    }
+
+    /// # Warmup Frames
+    ///
+    /// How many frames are thrown away at the start of every capture while the camera's exposure settles, see Camera::set_warmup_frames.
+    pub fn warmup_frames(mut self, frames: u32) -> Self {
+        self.warmup_frames = frames;
+        self
+    }
+
+    /// # Flip Bottom Up
+    ///
+    /// Whether frames the camera sends bottom up are flipped to top down, on by default. See Camera::set_flip_bottom_up.
+    pub fn flip_bottom_up(mut self, flip: bool) -> Self {
+        self.flip_bottom_up = flip;
+        self
+    }
+
+    /// # Orientation
+    ///
+    /// How frames are mirrored, flipped and rotated before they are sent, see Camera::set_orientation. Building fails when the output can not be oriented.
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// # Auto Reconnect
+    ///
+    /// How long a capture waits for the camera to be plugged back in after it was unplugged, off by default. See Camera::set_auto_reconnect.
+    pub fn auto_reconnect(mut self, max_wait: Duration) -> Self {
+        self.auto_reconnect = Some(max_wait);
+        self
+    }
+
+    /// # Build
+    ///
+    /// Checks the options, then activates the camera, creates its source reader and sets the format and output on it.
+    ///
+    /// Fails when options conflict, and when the camera can not send the format, frame rate or output. The errors name the formats the camera has.
+    pub fn build(self) -> Result<Arc<Camera>, Box<dyn std::error::Error>> {
+        self.check()?;
+
+        let reader_options = ReaderOptions {
+            hardware_transforms: self.hardware_transforms,
+            video_processing: self
+                .video_processing
+                .unwrap_or(!self.output.pixel_format().is_compressed()),
+            gpu_conversion: self.gpu_conversion,
+            transforms: self.transforms,
+        };
+
+        let camera = unsafe {
+            let source = Cameras::activate_source(self.device)?;
+
+            //the format is set on the stream once it is selected
+            let format = self.format.as_ref().filter(|_| self.stream.is_none());
+
+            Camera::activate(
+                source,
+                Some(self.output),
+                format,
+                get_device_symbolic_link(self.device).ok(),
+                reader_options,
+            )?
+        };
+
+        if let Some(stream) = self.stream {
+            camera.open_stream(stream, self.format.as_ref())?;
+        }
+
+        if let Some((numerator, denominator)) = self.frame_rate {
+            camera.set_frame_rate(numerator, denominator)?;
+        }
+
+        camera.set_warmup_frames(self.warmup_frames);
+        camera.set_flip_bottom_up(self.flip_bottom_up);
+        camera.set_orientation(self.orientation)?;
+        camera.set_auto_reconnect(self.auto_reconnect);
+
+        match self.channel_capacity {
+            Some(capacity) => camera.with_channel_capacity(capacity),
+            None => Ok(camera),
+        }
+    }
+
+    // fails on options that conflict, before anything is activated
+    fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(capacity) = self.channel_capacity {
+            check_channel_capacity(capacity)?;
+        }
+
+        if let (Some(format), Some((numerator, denominator))) = (&self.format, self.frame_rate) {
+            let rate = CameraFormat::new(format.width, format.height, numerator, denominator);
+
+            return Err(format!(
+                "both a format and a frame rate were set, set the rate in the format instead, such as {rate}"
+            )
+            .into());
+        }
+
+        if self.video_processing == Some(true) && self.output.pixel_format().is_compressed() {
+            return Err(format!(
+                "video processing can not produce {:?} frames, leave video_processing unset or pick an uncompressed output",
+                self.output
+            )
+            .into());
+        }
+
+        if self.gpu_conversion && !matches!(self.output, Output::RGB32 | Output::ARGB32) {
+            return Err(format!(
+                "the GPU converts to RGB32 or ARGB32, not {:?}, pick one of those or turn gpu_conversion off",
+                self.output
+            )
+            .into());
+        }
+
+        if self.gpu_conversion && self.video_processing == Some(false) {
+            return Err(
+                "gpu_conversion converts frames, it can not be turned on with video_processing off"
+                    .into(),
+            );
+        }
+
+        Ok(())
+    }
+}
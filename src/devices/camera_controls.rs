@@ -0,0 +1,646 @@
+use std::ffi::c_void;
+
+use windows::{
+    Win32::Media::{
+        DirectShow::{
+            CameraControl_Exposure, CameraControl_Flags_Auto, CameraControl_Flags_Manual,
+            CameraControl_Focus, CameraControl_Pan, CameraControl_Roll, CameraControl_Tilt,
+            CameraControl_Zoom, IAMCameraControl, IAMVideoProcAmp, VideoProcAmp_Brightness,
+            VideoProcAmp_Contrast, VideoProcAmp_Flags_Auto, VideoProcAmp_Flags_Manual,
+            VideoProcAmp_Saturation, VideoProcAmp_Sharpness, VideoProcAmp_WhiteBalance,
+        },
+        KernelStreaming::{
+            IKsControl, KSIDENTIFIER, KSIDENTIFIER_0, KSIDENTIFIER_0_0,
+            KSPROPERTY_CAMERACONTROL_FLAGS_MANUAL, KSPROPERTY_CAMERACONTROL_PAN_RELATIVE,
+            KSPROPERTY_CAMERACONTROL_ROLL_RELATIVE, KSPROPERTY_CAMERACONTROL_S,
+            KSPROPERTY_CAMERACONTROL_TILT_RELATIVE, KSPROPERTY_CAMERACONTROL_ZOOM_RELATIVE,
+            KSPROPERTY_DESCRIPTION, KSPROPERTY_MEMBER_RANGES, KSPROPERTY_MEMBER_STEPPEDRANGES,
+            KSPROPERTY_MEMBERSHEADER, KSPROPERTY_STEPPING_LONG, KSPROPERTY_TYPE_BASICSUPPORT,
+            KSPROPERTY_TYPE_SET, KSPROPERTY_VIDCAP_CAMERACONTROL, PROPSETID_VIDCAP_CAMERACONTROL,
+        },
+        MediaFoundation::IMFMediaSource,
+    },
+    core::Interface,
+};
+
+/// # Camera Property
+///
+/// A setting of the camera's image, see CameraControls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CameraProperty {
+    Brightness,
+    Contrast,
+    Saturation,
+    Sharpness,
+
+    /// The color temperature in kelvin, can be left to the camera with set_white_balance_auto.
+    WhiteBalance,
+
+    /// The exposure time as a power of two in seconds, -6 is 1/64 of a second. Can be left to the camera with set_exposure_auto.
+    Exposure,
+
+    /// The distance the lens is focused at, the unit is up to the driver: usually larger values focus further away. Can be left to the camera with set_focus_auto.
+    Focus,
+
+    /// The horizontal angle in degrees, cameras that can turn have it. Can also be moved at a speed with move_relative.
+    Pan,
+
+    /// The vertical angle in degrees, see Pan.
+    Tilt,
+
+    /// The focal length in millimeters, larger values zoom in. See Pan.
+    Zoom,
+
+    /// The rotation around the lens in degrees, see Pan.
+    Roll,
+}
+
+impl CameraProperty {
+    /// Every property, in the order they are declared.
+    pub const ALL: [CameraProperty; 11] = [
+        CameraProperty::Brightness,
+        CameraProperty::Contrast,
+        CameraProperty::Saturation,
+        CameraProperty::Sharpness,
+        CameraProperty::WhiteBalance,
+        CameraProperty::Exposure,
+        CameraProperty::Focus,
+        CameraProperty::Pan,
+        CameraProperty::Tilt,
+        CameraProperty::Zoom,
+        CameraProperty::Roll,
+    ];
+
+    // whether the property is controlled through IAMCameraControl rather than IAMVideoProcAmp
+    fn is_camera_control(&self) -> bool {
+        matches!(
+            self,
+            CameraProperty::Exposure
+                | CameraProperty::Focus
+                | CameraProperty::Pan
+                | CameraProperty::Tilt
+                | CameraProperty::Zoom
+                | CameraProperty::Roll
+        )
+    }
+}
+
+/// # Property Range
+///
+/// The values a camera property can be set to, as the driver reports them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropertyRange {
+    /// The smallest value.
+    pub min: i32,
+
+    /// The largest value.
+    pub max: i32,
+
+    /// The difference between two values next to each other, values in between are rounded by the driver.
+    pub step: i32,
+
+    /// The value the camera starts at.
+    pub default: i32,
+
+    /// Whether the camera can set the property itself, see CameraControls::set_auto.
+    pub supports_auto: bool,
+}
+
+impl PropertyRange {
+    /// Whether the value is between min and max.
+    pub fn contains(&self, value: i32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+/// # Property Value
+///
+/// The current value of a camera property and whether the camera is setting it itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropertyValue {
+    /// The value, while auto is set this is the last value the camera picked.
+    pub value: i32,
+
+    /// Whether the camera sets the value itself.
+    pub auto: bool,
+}
+
+/// # Control Error
+///
+/// Why a camera property could not be read or set, see CameraControls.
+#[derive(Debug)]
+pub enum ControlError {
+    /// The camera does not have the property, other properties can still be used.
+    Unsupported(CameraProperty),
+
+    /// The camera can not set the property itself, it can only be set by hand.
+    AutoUnsupported(CameraProperty),
+
+    /// The camera can not move the property at a speed, it can only be set to a value. Only pan, tilt, zoom and roll can be moved.
+    RelativeUnsupported(CameraProperty),
+
+    /// The value is outside the range of the property.
+    OutOfRange {
+        property: CameraProperty,
+        value: i32,
+        range: PropertyRange,
+    },
+
+    /// The driver failed to read or set the property, such as when the camera was unplugged.
+    Failed(CameraProperty, windows::core::Error),
+}
+
+impl std::fmt::Display for ControlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlError::Unsupported(property) => {
+                write!(f, "the camera does not support {property:?}")
+            }
+            ControlError::AutoUnsupported(property) => {
+                write!(f, "the camera can not set {property:?} automatically")
+            }
+            ControlError::RelativeUnsupported(property) => {
+                write!(f, "the camera can not move {property:?} relatively")
+            }
+            ControlError::OutOfRange {
+                property,
+                value,
+                range,
+            } => write!(
+                f,
+                "{value} is outside the {property:?} range of {} to {}",
+                range.min, range.max
+            ),
+            ControlError::Failed(property, e) => write!(f, "{property:?} failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ControlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ControlError::Failed(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// the interface a property is controlled through and its id there
+enum Control<'a> {
+    ProcAmp(&'a IAMVideoProcAmp, i32),
+    Camera(&'a IAMCameraControl, i32),
+}
+
+// a named accessor of a property, a shorthand for get, set, set_auto, range or move_relative with it
+macro_rules! accessor {
+    (value $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self) -> Result<i32, ControlError> {
+            Ok(self.get(CameraProperty::$property)?.value)
+        }
+    };
+    (current $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self) -> Result<PropertyValue, ControlError> {
+            self.get(CameraProperty::$property)
+        }
+    };
+    (set $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self, value: i32) -> Result<(), ControlError> {
+            self.set(CameraProperty::$property, value)
+        }
+    };
+    (auto $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self, auto: bool) -> Result<(), ControlError> {
+            self.set_auto(CameraProperty::$property, auto)
+        }
+    };
+    (range $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self) -> Result<PropertyRange, ControlError> {
+            self.range(CameraProperty::$property)
+        }
+    };
+    (relative $name:ident, $property:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $name(&self, speed: i32) -> Result<(), ControlError> {
+            self.move_relative(CameraProperty::$property, speed)
+        }
+    };
+}
+
+/// # Camera Controls
+///
+/// The image settings of a camera: brightness, contrast, saturation, sharpness, white balance, exposure and focus, and the pan, tilt, zoom and roll of cameras that can move. Obtained from Camera::controls.
+///
+/// The settings are those of the device itself, they stay set while capturing and are shared with other applications using the camera. They can be read and set while the camera is capturing, the source reader is left as it is. Whether the camera has a property is checked per property, one it does not have fails with ControlError::Unsupported.
+pub struct CameraControls {
+    // brightness, contrast, saturation, sharpness and white balance
+    proc_amp: Option<IAMVideoProcAmp>,
+
+    // exposure, focus, pan, tilt, zoom and roll
+    camera_control: Option<IAMCameraControl>,
+
+    // the driver's properties, used for the relative moves IAMCameraControl does not have
+    ks_control: Option<IKsControl>,
+}
+
+impl CameraControls {
+    // the controls of the media source, a source without either interface has none of their properties
+    pub(crate) fn new(source: &IMFMediaSource) -> Self {
+        CameraControls {
+            proc_amp: source.cast::<IAMVideoProcAmp>().ok(),
+            camera_control: source.cast::<IAMCameraControl>().ok(),
+            ks_control: source.cast::<IKsControl>().ok(),
+        }
+    }
+
+    /// # Is Supported
+    ///
+    /// Whether the camera has the property.
+    pub fn is_supported(&self, property: CameraProperty) -> bool {
+        self.range(property).is_ok()
+    }
+
+    /// # Range
+    ///
+    /// The values the property can be set to and whether the camera can set it itself.
+    pub fn range(&self, property: CameraProperty) -> Result<PropertyRange, ControlError> {
+        let (mut min, mut max, mut step, mut default, mut flags) = (0, 0, 0, 0, 0);
+
+        let result = unsafe {
+            match self.control(property)? {
+                Control::ProcAmp(proc_amp, id) => {
+                    proc_amp.GetRange(id, &mut min, &mut max, &mut step, &mut default, &mut flags)
+                }
+                Control::Camera(camera_control, id) => camera_control.GetRange(
+                    id,
+                    &mut min,
+                    &mut max,
+                    &mut step,
+                    &mut default,
+                    &mut flags,
+                ),
+            }
+        };
+
+        //drivers refuse properties they do not have with a range of errors, none of them mean the controls are broken
+        if result.is_err() {
+            return Err(ControlError::Unsupported(property));
+        }
+
+        Ok(PropertyRange {
+            min,
+            max,
+            step,
+            default,
+            supports_auto: flags & Self::auto_flag(property) != 0,
+        })
+    }
+
+    /// # Get
+    ///
+    /// The current value of the property and whether the camera is setting it itself.
+    pub fn get(&self, property: CameraProperty) -> Result<PropertyValue, ControlError> {
+        self.range(property)?;
+
+        let (mut value, mut flags) = (0, 0);
+
+        unsafe {
+            match self.control(property)? {
+                Control::ProcAmp(proc_amp, id) => proc_amp.Get(id, &mut value, &mut flags),
+                Control::Camera(camera_control, id) => {
+                    camera_control.Get(id, &mut value, &mut flags)
+                }
+            }
+        }
+        .map_err(|e| ControlError::Failed(property, e))?;
+
+        Ok(PropertyValue {
+            value,
+            auto: flags & Self::auto_flag(property) != 0,
+        })
+    }
+
+    /// # Set
+    ///
+    /// Sets the property to the value by hand, which turns off the camera setting it itself.
+    ///
+    /// Fails with ControlError::OutOfRange for values outside of range, the driver rounds values between steps.
+    pub fn set(&self, property: CameraProperty, value: i32) -> Result<(), ControlError> {
+        let range = self.range(property)?;
+
+        if !range.contains(value) {
+            return Err(ControlError::OutOfRange {
+                property,
+                value,
+                range,
+            });
+        }
+
+        self.set_flags(property, value, false)
+    }
+
+    /// # Set Auto
+    ///
+    /// Leaves the property to the camera, or locks it at its current value when auto is false.
+    ///
+    /// Fails with ControlError::AutoUnsupported when turning on auto for a property the camera can not set itself.
+    pub fn set_auto(&self, property: CameraProperty, auto: bool) -> Result<(), ControlError> {
+        if auto && !self.range(property)?.supports_auto {
+            return Err(ControlError::AutoUnsupported(property));
+        }
+
+        //the value is passed along with the flags, the current one keeps the image as it is when locking
+        let current = self.get(property)?;
+
+        self.set_flags(property, current.value, auto)
+    }
+
+    /// # Relative Range
+    ///
+    /// The speeds the property can be moved at with move_relative, from min to max. The sign is the direction, the step is the smallest change in speed.
+    ///
+    /// Fails with ControlError::RelativeUnsupported when the camera can not move the property.
+    pub fn relative_range(&self, property: CameraProperty) -> Result<PropertyRange, ControlError> {
+        let (control, id) = self.relative_control(property)?;
+
+        //the description is followed by a single stepped range
+        #[repr(C)]
+        #[derive(Default)]
+        struct BasicSupport {
+            description: KSPROPERTY_DESCRIPTION,
+            members: KSPROPERTY_MEMBERSHEADER,
+            stepping: KSPROPERTY_STEPPING_LONG,
+        }
+
+        let request = KSPROPERTY_CAMERACONTROL_S {
+            Property: Self::ks_identifier(id, KSPROPERTY_TYPE_BASICSUPPORT),
+            ..Default::default()
+        };
+        let mut support = BasicSupport::default();
+        let mut returned = 0;
+
+        let result = unsafe {
+            control.KsProperty(
+                &request.Property,
+                size_of::<KSPROPERTY_CAMERACONTROL_S>() as u32,
+                &mut support as *mut _ as *mut c_void,
+                size_of::<BasicSupport>() as u32,
+                &mut returned,
+            )
+        };
+
+        //drivers without the property fail or leave out its range, either way it can not be moved
+        let has_range = (returned as usize) >= size_of::<BasicSupport>()
+            && support.members.MembersCount > 0
+            && matches!(
+                support.members.MembersFlags,
+                KSPROPERTY_MEMBER_RANGES | KSPROPERTY_MEMBER_STEPPEDRANGES
+            );
+
+        if result.is_err() || !has_range {
+            return Err(ControlError::RelativeUnsupported(property));
+        }
+
+        let bounds = unsafe { support.stepping.Bounds.Anonymous1 };
+
+        Ok(PropertyRange {
+            min: bounds.SignedMinimum,
+            max: bounds.SignedMaximum,
+            step: support.stepping.SteppingDelta as i32,
+            //the camera stands still until it is moved
+            default: 0,
+            supports_auto: false,
+        })
+    }
+
+    /// # Supports Relative
+    ///
+    /// Whether the camera can move the property at a speed, see move_relative.
+    pub fn supports_relative(&self, property: CameraProperty) -> bool {
+        self.relative_range(property).is_ok()
+    }
+
+    /// # Move Relative
+    ///
+    /// Starts moving pan, tilt, zoom or roll at the speed, the sign is the direction: negative pans left, tilts down, zooms out and rolls counterclockwise. The camera keeps moving until it reaches the end of its range or the speed is set to 0.
+    ///
+    /// Fails with ControlError::RelativeUnsupported when the camera can not move the property, set it to a value with set instead, and with ControlError::OutOfRange for speeds outside of relative_range.
+    pub fn move_relative(&self, property: CameraProperty, speed: i32) -> Result<(), ControlError> {
+        let range = self.relative_range(property)?;
+
+        if speed != 0 && !range.contains(speed) {
+            return Err(ControlError::OutOfRange {
+                property,
+                value: speed,
+                range,
+            });
+        }
+
+        let (control, id) = self.relative_control(property)?;
+
+        let mut request = KSPROPERTY_CAMERACONTROL_S {
+            Property: Self::ks_identifier(id, KSPROPERTY_TYPE_SET),
+            Value: speed,
+            Flags: KSPROPERTY_CAMERACONTROL_FLAGS_MANUAL as u32,
+            Capabilities: 0,
+        };
+
+        let size = size_of::<KSPROPERTY_CAMERACONTROL_S>() as u32;
+        let mut returned = 0;
+
+        unsafe {
+            control.KsProperty(
+                &request.Property,
+                size,
+                &mut request as *mut _ as *mut c_void,
+                size,
+                &mut returned,
+            )
+        }
+        .map_err(|e| ControlError::Failed(property, e))
+    }
+
+    accessor!(value brightness, Brightness, "The current brightness, see brightness_range for its values.");
+    accessor!(set set_brightness, Brightness, "Sets the brightness, see set.");
+    accessor!(range brightness_range, Brightness, "The values the brightness can be set to.");
+
+    accessor!(value contrast, Contrast, "The current contrast, see contrast_range for its values.");
+    accessor!(set set_contrast, Contrast, "Sets the contrast, see set.");
+    accessor!(range contrast_range, Contrast, "The values the contrast can be set to.");
+
+    accessor!(value saturation, Saturation, "The current saturation, see saturation_range for its values.");
+    accessor!(set set_saturation, Saturation, "Sets the saturation, see set.");
+    accessor!(range saturation_range, Saturation, "The values the saturation can be set to.");
+
+    accessor!(value sharpness, Sharpness, "The current sharpness, see sharpness_range for its values.");
+    accessor!(set set_sharpness, Sharpness, "Sets the sharpness, see set.");
+    accessor!(range sharpness_range, Sharpness, "The values the sharpness can be set to.");
+
+    accessor!(current white_balance, WhiteBalance, "The color temperature in kelvin and whether the camera is setting it itself.");
+    accessor!(set set_white_balance, WhiteBalance, "Locks the white balance at the color temperature in kelvin.");
+    accessor!(auto set_white_balance_auto, WhiteBalance, "Leaves the white balance to the camera, or locks it at its current temperature when auto is false.");
+    accessor!(range white_balance_range, WhiteBalance, "The values the white balance can be set to.");
+
+    accessor!(current exposure, Exposure, "The exposure time as a power of two in seconds and whether the camera is setting it itself.");
+    accessor!(set set_exposure, Exposure, "Locks the exposure time at 2 to the power of value seconds, -6 is 1/64 of a second.");
+    accessor!(auto set_exposure_auto, Exposure, "Leaves the exposure to the camera, or locks it at its current time when auto is false.");
+    accessor!(range exposure_range, Exposure, "The values the exposure can be set to.");
+
+    accessor!(current focus, Focus, "The distance the lens is focused at and whether the camera is focusing itself. Read it again to follow the camera while autofocus is on.");
+    accessor!(set set_focus, Focus, "Locks the focus at the value, which turns off autofocus. See focus_range for its values.");
+    accessor!(auto set_focus_auto, Focus, "Turns continuous autofocus on, or off to keep the lens where it is focused now.");
+    accessor!(range focus_range, Focus, "The values the focus can be set to.");
+
+    accessor!(value pan, Pan, "The current horizontal angle in degrees, see pan_range for its values.");
+    accessor!(set set_pan, Pan, "Turns the camera to the horizontal angle in degrees, see set.");
+    accessor!(range pan_range, Pan, "The angles the camera can pan to.");
+    accessor!(relative pan_relative, Pan, "Starts panning at the speed, negative to the left and 0 to stop. See move_relative.");
+
+    accessor!(value tilt, Tilt, "The current vertical angle in degrees, see tilt_range for its values.");
+    accessor!(set set_tilt, Tilt, "Tilts the camera to the vertical angle in degrees, see set.");
+    accessor!(range tilt_range, Tilt, "The angles the camera can tilt to.");
+    accessor!(relative tilt_relative, Tilt, "Starts tilting at the speed, negative down and 0 to stop. See move_relative.");
+
+    accessor!(value zoom, Zoom, "The current focal length in millimeters, see zoom_range for its values.");
+    accessor!(set set_zoom, Zoom, "Zooms to the focal length in millimeters, see set.");
+    accessor!(range zoom_range, Zoom, "The focal lengths the camera can zoom to.");
+    accessor!(relative zoom_relative, Zoom, "Starts zooming at the speed, negative out and 0 to stop. See move_relative.");
+
+    accessor!(value roll, Roll, "The current rotation in degrees, see roll_range for its values.");
+    accessor!(set set_roll, Roll, "Rotates the camera to the angle in degrees, see set.");
+    accessor!(range roll_range, Roll, "The angles the camera can roll to.");
+    accessor!(relative roll_relative, Roll, "Starts rolling at the speed, negative counterclockwise and 0 to stop. See move_relative.");
+
+    // sets the value with the auto or manual flag of the property
+    fn set_flags(
+        &self,
+        property: CameraProperty,
+        value: i32,
+        auto: bool,
+    ) -> Result<(), ControlError> {
+        let flags = if auto {
+            Self::auto_flag(property)
+        } else {
+            Self::manual_flag(property)
+        };
+
+        unsafe {
+            match self.control(property)? {
+                Control::ProcAmp(proc_amp, id) => proc_amp.Set(id, value, flags),
+                Control::Camera(camera_control, id) => camera_control.Set(id, value, flags),
+            }
+        }
+        .map_err(|e| ControlError::Failed(property, e))
+    }
+
+    // the interface the property is controlled through, Unsupported when the camera does not have it
+    fn control(&self, property: CameraProperty) -> Result<Control<'_>, ControlError> {
+        let control = match property {
+            CameraProperty::Brightness => self
+                .proc_amp
+                .as_ref()
+                .map(|proc_amp| Control::ProcAmp(proc_amp, VideoProcAmp_Brightness.0)),
+            CameraProperty::Contrast => self
+                .proc_amp
+                .as_ref()
+                .map(|proc_amp| Control::ProcAmp(proc_amp, VideoProcAmp_Contrast.0)),
+            CameraProperty::Saturation => self
+                .proc_amp
+                .as_ref()
+                .map(|proc_amp| Control::ProcAmp(proc_amp, VideoProcAmp_Saturation.0)),
+            CameraProperty::Sharpness => self
+                .proc_amp
+                .as_ref()
+                .map(|proc_amp| Control::ProcAmp(proc_amp, VideoProcAmp_Sharpness.0)),
+            CameraProperty::WhiteBalance => self
+                .proc_amp
+                .as_ref()
+                .map(|proc_amp| Control::ProcAmp(proc_amp, VideoProcAmp_WhiteBalance.0)),
+            CameraProperty::Exposure => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Exposure.0)),
+            CameraProperty::Focus => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Focus.0)),
+            CameraProperty::Pan => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Pan.0)),
+            CameraProperty::Tilt => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Tilt.0)),
+            CameraProperty::Zoom => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Zoom.0)),
+            CameraProperty::Roll => self
+                .camera_control
+                .as_ref()
+                .map(|camera_control| Control::Camera(camera_control, CameraControl_Roll.0)),
+        };
+
+        control.ok_or(ControlError::Unsupported(property))
+    }
+
+    // the driver's control and the id of the relative property, RelativeUnsupported for properties that can not be moved
+    fn relative_control(
+        &self,
+        property: CameraProperty,
+    ) -> Result<(&IKsControl, KSPROPERTY_VIDCAP_CAMERACONTROL), ControlError> {
+        let id = match property {
+            CameraProperty::Pan => KSPROPERTY_CAMERACONTROL_PAN_RELATIVE,
+            CameraProperty::Tilt => KSPROPERTY_CAMERACONTROL_TILT_RELATIVE,
+            CameraProperty::Zoom => KSPROPERTY_CAMERACONTROL_ZOOM_RELATIVE,
+            CameraProperty::Roll => KSPROPERTY_CAMERACONTROL_ROLL_RELATIVE,
+            _ => return Err(ControlError::RelativeUnsupported(property)),
+        };
+
+        let control = self
+            .ks_control
+            .as_ref()
+            .ok_or(ControlError::RelativeUnsupported(property))?;
+
+        Ok((control, id))
+    }
+
+    fn ks_identifier(id: KSPROPERTY_VIDCAP_CAMERACONTROL, flags: u32) -> KSIDENTIFIER {
+        KSIDENTIFIER {
+            Anonymous: KSIDENTIFIER_0 {
+                Anonymous: KSIDENTIFIER_0_0 {
+                    Set: PROPSETID_VIDCAP_CAMERACONTROL,
+                    Id: id.0 as u32,
+                    Flags: flags,
+                },
+            },
+        }
+    }
+
+    fn auto_flag(property: CameraProperty) -> i32 {
+        if property.is_camera_control() {
+            CameraControl_Flags_Auto.0
+        } else {
+            VideoProcAmp_Flags_Auto.0
+        }
+    }
+
+    fn manual_flag(property: CameraProperty) -> i32 {
+        if property.is_camera_control() {
+            CameraControl_Flags_Manual.0
+        } else {
+            VideoProcAmp_Flags_Manual.0
+        }
+    }
+}
+
+// the interfaces are queried from the camera's media source, Media Foundation objects are free threaded and may be called from any thread
+// the controls keep no state of their own, every call is a single property request the driver handles in turn
+unsafe impl Send for CameraControls {}
+
+unsafe impl Sync for CameraControls {}
@@ -0,0 +1,107 @@
+use windows::Win32::Media::MediaFoundation::{IMFMediaType, MF_MT_FRAME_RATE, MF_MT_FRAME_SIZE};
+
+use crate::frame::PixelFormat;
+
+// how far apart two frame rates can be and still match, so 29.97 matches a request for 30
+const FRAME_RATE_TOLERANCE: f64 = 0.5;
+
+/// # Camera Format
+///
+/// A resolution and frame rate a camera sends frames at, see Cameras::activate_device_with_format and Camera::supported_formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CameraFormat {
+    /// The width of the frames in pixels.
+    pub width: u32,
+
+    /// The height of the frames in pixels.
+    pub height: u32,
+
+    /// The frames per second are the numerator over the denominator, 30000 over 1001 for 29.97.
+    pub frame_rate_numerator: u32,
+
+    /// See frame_rate_numerator.
+    pub frame_rate_denominator: u32,
+}
+
+impl CameraFormat {
+    /// # New
+    ///
+    /// A format of the size at frame_rate_numerator / frame_rate_denominator frames per second, such as 1920, 1080, 30, 1.
+    pub fn new(
+        width: u32,
+        height: u32,
+        frame_rate_numerator: u32,
+        frame_rate_denominator: u32,
+    ) -> Self {
+        CameraFormat {
+            width,
+            height,
+            frame_rate_numerator,
+            frame_rate_denominator,
+        }
+    }
+
+    /// The frames per second.
+    pub fn frame_rate(&self) -> f64 {
+        self.frame_rate_numerator as f64 / self.frame_rate_denominator.max(1) as f64
+    }
+
+    // how far the frame rate of the other format is from this one, None when they are different sizes or the rates are too far apart
+    pub(crate) fn distance(&self, other: &CameraFormat) -> Option<f64> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        let distance = (self.frame_rate() - other.frame_rate()).abs();
+
+        (distance <= FRAME_RATE_TOLERANCE).then_some(distance)
+    }
+
+    // reads the size and frame rate of a media type, None when it has no size
+    pub(crate) unsafe fn from_media_type(media_type: &IMFMediaType) -> Option<Self> {
+        let size = unsafe { media_type.GetUINT64(&MF_MT_FRAME_SIZE).ok()? };
+
+        //some cameras leave the frame rate out, it is then reported as 0
+        let rate = unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE).unwrap_or(0) };
+
+        Some(CameraFormat {
+            width: (size >> 32) as u32,
+            height: size as u32,
+            frame_rate_numerator: (rate >> 32) as u32,
+            frame_rate_denominator: rate as u32,
+        })
+    }
+}
+
+impl std::fmt::Display for CameraFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let frame_rate = self.frame_rate();
+
+        //whole rates are written without decimals, 30 fps and 29.97 fps
+        if frame_rate.fract() == 0.0 {
+            write!(f, "{}x{} at {frame_rate} fps", self.width, self.height)
+        } else {
+            write!(f, "{}x{} at {frame_rate:.2} fps", self.width, self.height)
+        }
+    }
+}
+
+/// # Negotiated Format
+///
+/// The media type the source reader sends frames in, read back from the reader once it was set. See Camera::negotiated_format.
+///
+/// The frames sent on the receiver are laid out exactly as this says, the reader may negotiate another size or frame rate than was asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NegotiatedFormat {
+    /// The layout of the pixels of each frame, see PixelFormat::bytes_per_pixel and PixelFormat::is_compressed.
+    pub pixel_format: PixelFormat,
+
+    /// The size and frame rate of the frames.
+    pub format: CameraFormat,
+
+    /// The number of bytes in a single row, 0 for MJPG whose frames have no rows.
+    pub stride: u32,
+
+    /// Whether the rows are stored from the bottom of the image up, the reader reports this as a negative stride.
+    pub bottom_up: bool,
+}
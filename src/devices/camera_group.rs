@@ -0,0 +1,168 @@
+use std::{sync::Arc, time::Instant};
+
+use tokio::{
+    sync::{
+        Barrier, Mutex, mpsc,
+        mpsc::{Receiver, Sender},
+        watch,
+    },
+    task::JoinHandle,
+};
+
+use crate::{
+    capture_manager::{SourceEvent, forward_source},
+    devices::{Camera, Cameras, camera::Output},
+    frame::Frame,
+};
+
+/// # Camera Group
+///
+/// Captures several cameras at once, such as the two cameras of a stereo rig, starting them together and sending all of their frames through one receiver.
+///
+/// Every frame is tagged with the id of its camera, its index in cameras. Match up frames captured at the same time with a FrameMatcher and capture_instant.
+pub struct CameraGroup {
+    cameras: Vec<Arc<Camera>>,
+
+    receiver: Arc<Mutex<Receiver<SourceEvent>>>,
+    sender: Sender<SourceEvent>,
+
+    // the task forwarding the frames of each camera, empty when not capturing
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+
+    // set to true to stop every camera
+    stopping: watch::Sender<bool>,
+}
+
+impl CameraGroup {
+    /// # New
+    ///
+    /// A group of the cameras, their ids are their indices in the vec. The cameras are not started until start_all is called.
+    ///
+    /// Fails when there are no cameras.
+    pub fn new(cameras: Vec<Arc<Camera>>) -> Result<Self, Box<dyn std::error::Error>> {
+        if cameras.is_empty() {
+            return Err("no cameras to capture".into());
+        }
+
+        //room for a few frames of every camera
+        let (tx, rx) = mpsc::channel(cameras.len() * 4);
+
+        Ok(CameraGroup {
+            cameras,
+            receiver: Arc::new(Mutex::new(rx)),
+            sender: tx,
+            tasks: Mutex::new(vec![]),
+            stopping: watch::channel(false).0,
+        })
+    }
+
+    /// # Activate
+    ///
+    /// Activates the devices at the indices of Cameras::devices, in the output, and groups them in that order. See Cameras::activate_device.
+    ///
+    /// Fails when there are no indices, an index is out of range or a camera can not be activated, the cameras activated before are released again.
+    pub fn activate(
+        devices: &Cameras,
+        indices: &[usize],
+        output: Option<Output>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut cameras = vec![];
+
+        for &index in indices {
+            let Some(device) = devices.devices.get(index) else {
+                return Err(format!(
+                    "there is no camera at index {index}, {} were found",
+                    devices.devices.len()
+                )
+                .into());
+            };
+
+            cameras.push(devices.activate_device(device, output)?);
+        }
+
+        Self::new(cameras)
+    }
+
+    /// The cameras of the group, by their id.
+    pub fn cameras(&self) -> &[Arc<Camera>] {
+        &self.cameras
+    }
+
+    /// # Start All
+    ///
+    /// Starts capturing every camera, each on its own task, and returns right away.
+    ///
+    /// The captures wait for each other and are started at the same moment, after every task was ready. A camera whose receiver is locked elsewhere starts once it is released, without holding up the others.
+    ///
+    /// A camera that fails sends a Stopped event with its error, the others keep capturing.
+    pub async fn start_all(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tasks = self.tasks.lock().await;
+
+        if !tasks.is_empty() {
+            return Err("the cameras are already capturing".into());
+        }
+
+        self.stopping.send_replace(false);
+
+        let start = Arc::new(Barrier::new(self.cameras.len()));
+
+        for (id, camera) in self.cameras.iter().enumerate() {
+            tasks.push(tokio::spawn(forward_source(
+                camera.clone(),
+                id,
+                self.sender.clone(),
+                self.stopping.subscribe(),
+                Some(start.clone()),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// # Stop All
+    ///
+    /// Stops every camera and waits for all of them to exit, including cameras that already failed.
+    ///
+    /// Every camera is waited for even when one of their tasks panicked, the first panic is returned afterwards.
+    pub async fn stop_all(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tasks = self.tasks.lock().await;
+
+        if tasks.is_empty() {
+            return Err("the cameras are not capturing".into());
+        }
+
+        self.stopping.send_replace(true);
+
+        let mut result = Ok(());
+
+        for task in tasks.drain(..) {
+            if let Err(e) = task.await
+                && result.is_ok()
+            {
+                result = Err(e.into());
+            }
+        }
+
+        result
+    }
+
+    /// # Clone Receiver
+    ///
+    /// The receiver the frames of every camera are sent to, along with a Stopped event when a camera stops.
+    pub fn clone_receiver(&self) -> Arc<Mutex<Receiver<SourceEvent>>> {
+        self.receiver.clone()
+    }
+
+    /// # Capture Instant
+    ///
+    /// When the camera with the id captured the frame, from the time of its sample (see Camera::sample_instant). Frames without a sample time fall back to when they were read.
+    ///
+    /// Pass it to FrameMatcher::push to match up the frames of the cameras.
+    pub fn capture_instant(&self, camera: usize, frame: &Frame) -> Instant {
+        self.cameras
+            .get(camera)
+            .zip(frame.sample_time)
+            .and_then(|(camera, sample_time)| camera.sample_instant(sample_time))
+            .unwrap_or(frame.timestamp)
+    }
+}
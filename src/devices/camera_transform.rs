@@ -0,0 +1,84 @@
+use windows::{
+    Win32::{
+        Media::MediaFoundation::{IMFSourceReaderEx, IMFTransform, MFT_FRIENDLY_NAME_Attribute},
+        System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance, CoTaskMemFree},
+    },
+    core::{GUID, PWSTR},
+};
+
+/// # Camera Transform
+///
+/// A Media Foundation transform (MFT) the camera's samples are passed through before they become frames, such as a denoiser. Added with CameraBuilder::transform.
+///
+/// Transforms are added to the end of the source reader's chain for the stream, after the camera's output was set, so the first transform receives frames in the output and the last one decides the format frames are sent in (see Camera::negotiated_format).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CameraTransform {
+    /// A transform created by the application. It is shared by every source reader it is added to, such as the one of a reconnected camera.
+    Transform(IMFTransform),
+
+    /// The CLSID of a registered transform, a new one is created every time the camera's source reader is.
+    Clsid(GUID),
+}
+
+impl CameraTransform {
+    // adds the transform to the end of the stream's chain, the error names the transform and the format it was offered
+    pub(crate) unsafe fn add_to(
+        &self,
+        reader: &IMFSourceReaderEx,
+        stream: u32,
+        index: usize,
+        offered: &str,
+    ) -> Result<(), windows::core::Error> {
+        let result = unsafe {
+            match self {
+                CameraTransform::Transform(transform) => {
+                    reader.AddTransformForStream(stream, transform)
+                }
+                CameraTransform::Clsid(clsid) => {
+                    CoCreateInstance::<_, IMFTransform>(clsid, None, CLSCTX_INPROC_SERVER)
+                        .and_then(|transform| reader.AddTransformForStream(stream, &transform))
+                }
+            }
+        };
+
+        result.map_err(|e| {
+            windows::core::Error::new(
+                e.code(),
+                format!(
+                    "transform {index} ({}) could not be added to frames in {offered}: {}",
+                    self.name(),
+                    e.message()
+                ),
+            )
+        })
+    }
+
+    /// # Name
+    ///
+    /// The friendly name the transform gives itself, or else its CLSID, naming it in errors.
+    pub fn name(&self) -> String {
+        match self {
+            CameraTransform::Transform(transform) => unsafe { friendly_name(transform) }
+                .unwrap_or_else(|| "an IMFTransform without a name".to_string()),
+            CameraTransform::Clsid(clsid) => format!("{clsid:?}"),
+        }
+    }
+}
+
+// the MFT_FRIENDLY_NAME_Attribute of the transform, most transforms of Windows set it
+unsafe fn friendly_name(transform: &IMFTransform) -> Option<String> {
+    unsafe {
+        let attributes = transform.GetAttributes().ok()?;
+
+        let mut name = PWSTR::null();
+        let mut len = 0;
+        attributes
+            .GetAllocatedString(&MFT_FRIENDLY_NAME_Attribute, &mut name, &mut len)
+            .ok()?;
+
+        let friendly_name = String::from_utf16_lossy(name.as_wide());
+        CoTaskMemFree(Some(name.0 as *const _));
+
+        Some(friendly_name)
+    }
+}
@@ -1,8 +1,8 @@
-use std::{ffi::c_void, sync::Arc};
+use std::{ffi::c_void, sync::Arc, time::Duration};
 
 use windows::Win32::{
     Media::MediaFoundation::{
-        IMFActivate, IMFAttributes, MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
+        IMFActivate, IMFAttributes, IMFMediaSource, MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
         MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID, MFCreateAttributes, MFEnumDeviceSources,
     },
     System::Com::CoTaskMemFree,
@@ -10,7 +10,11 @@ use windows::Win32::{
 
 use windows::Win32::Foundation::E_FAIL;
 
-use crate::devices::{Camera, camera::Output};
+use crate::devices::{
+    Camera, CameraBuilder, CameraFormat, CaptureError, DeviceInfo, DeviceWatcher,
+    camera::{Output, ReaderOptions},
+    get_device_name, get_device_symbolic_link,
+};
 
 /// # Device
 ///
@@ -30,7 +34,9 @@ impl<'a> Cameras<'a> {
     /// Creates a new video devices struct.
     ///
     /// Aggregates all connected video devices on your window sytem and creates a struct containing them.
-    pub unsafe fn new() -> Result<Self, windows::core::Error> {
+    ///
+    /// COM must be initialized on the calling thread (CoInitializeEx with COINIT_MULTITHREADED), otherwise this fails with CO_E_NOTINITIALIZED. Fails with E_FAIL when no camera is connected.
+    pub fn new() -> Result<Self, windows::core::Error> {
         unsafe {
             let mut ppmfattributes: Option<IMFAttributes> = None;
 
@@ -71,23 +77,182 @@ impl<'a> Cameras<'a> {
     ///
     /// You may choose an Output type or None (for NV12) but this will set the type of output you will receive from the receiver.
     ///
+    /// The device can be activated again after its camera was unplugged and plugged back in, Camera::reconnect does so without this struct.
+    ///
     /// After activating any devices or after completing all operations with this struct you should call free_devices.
-    pub unsafe fn activate_device(
+    pub fn activate_device(
         &self,
         device: &IMFActivate,
         output_type: Option<Output>,
     ) -> Result<Arc<Camera>, windows::core::Error> {
         unsafe {
-            let media_src = device
-                .ActivateObject::<windows::Win32::Media::MediaFoundation::IMFMediaSource>()?;
+            let media_src = Self::activate_source(device)?;
 
-            Ok(Camera::new(media_src, output_type)?)
+            Camera::activate(
+                media_src,
+                output_type,
+                None,
+                get_device_symbolic_link(device).ok(),
+                ReaderOptions::default(),
+            )
+        }
+    }
+
+    /// # Activate With Retry
+    ///
+    /// Like activate_device, but while another application is using the camera activating is tried again, up to attempts times in all. The first retry waits for the delay, each one after twice as long as the one before.
+    ///
+    /// Fails with CaptureError::DeviceBusy and the camera's name when it is still busy after the last attempt, other errors are returned right away as CaptureError::Failed. Some cameras only report being busy once they start streaming, their capture then ends with DeviceBusy.
+    ///
+    /// The delays block the calling thread.
+    pub fn activate_with_retry(
+        &self,
+        device: &IMFActivate,
+        output_type: Option<Output>,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<Arc<Camera>, CaptureError> {
+        let mut attempt = 1;
+        let mut delay = delay;
+
+        loop {
+            match self.activate_device(device, output_type) {
+                Ok(camera) => return Ok(camera),
+                Err(e) if CaptureError::is_busy(&e) && attempt < attempts => {
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let name = get_device_name(device).unwrap_or_else(|_| "the camera".to_string());
+                    return Err(CaptureError::from_error(e, &name));
+                }
+            }
+        }
+    }
+
+    /// # Activate Device With Format
+    ///
+    /// Like activate_device, but the camera sends frames at the resolution and frame rate of the format instead of the one it starts in. See Camera::new_with_format for how the format is matched.
+    ///
+    /// Fails when the camera has no matching format, the error lists the formats it has. Camera::supported_formats lists them as well.
+    pub fn activate_device_with_format(
+        &self,
+        device: &IMFActivate,
+        output_type: Option<Output>,
+        format: &CameraFormat,
+    ) -> Result<Arc<Camera>, windows::core::Error> {
+        unsafe {
+            let media_src = Self::activate_source(device)?;
+
+            Camera::activate(
+                media_src,
+                output_type,
+                Some(format),
+                get_device_symbolic_link(device).ok(),
+                ReaderOptions::default(),
+            )
+        }
+    }
+
+    /// # Activate By Symbolic Link
+    ///
+    /// Activates the camera with the symbolic link, such as one stored from DeviceInfo::symbolic_link when the application last ran. The camera is found by its link directly, the cameras do not have to be enumerated first.
+    ///
+    /// Fails when no camera with the link is plugged in.
+    pub fn activate_by_symbolic_link(
+        symbolic_link: &str,
+        output_type: Option<Output>,
+    ) -> Result<Arc<Camera>, windows::core::Error> {
+        unsafe {
+            let media_src = Camera::source_from_symbolic_link(symbolic_link).map_err(|e| {
+                windows::core::Error::new(
+                    e.code(),
+                    format!(
+                        "no camera with the symbolic link {symbolic_link} is plugged in: {}",
+                        e.message()
+                    ),
+                )
+            })?;
+
+            Camera::activate(
+                media_src,
+                output_type,
+                None,
+                Some(symbolic_link.to_string()),
+                ReaderOptions::default(),
+            )
+        }
+    }
+
+    /// # Device Info
+    ///
+    /// The name and symbolic link of each camera, in the order of devices. Cameras whose name or link can not be read are left out.
+    pub fn device_info(&self) -> Vec<DeviceInfo> {
+        self.devices
+            .iter()
+            .enumerate()
+            .filter_map(|(index, device)| {
+                Some(DeviceInfo {
+                    index,
+                    name: get_device_name(device).ok()?,
+                    symbolic_link: get_device_symbolic_link(device).ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// # Watch
+    ///
+    /// Starts a watcher that sends an event whenever a camera is plugged in or unplugged, so a list of cameras can be kept without enumerating them again and again. See DeviceWatcher.
+    ///
+    /// The watcher first sends the cameras plugged in right now as added. It runs until it is stopped or dropped.
+    pub fn watch() -> Result<DeviceWatcher, windows::core::Error> {
+        DeviceWatcher::start()
+    }
+
+    // creates the media source of the device
+    //
+    // the source is detached from the device, so activating the device again creates a new source instead of handing back one that was shut down or unplugged
+    pub(crate) unsafe fn activate_source(
+        device: &IMFActivate,
+    ) -> Result<IMFMediaSource, windows::core::Error> {
+        unsafe {
+            let media_src = device.ActivateObject::<IMFMediaSource>()?;
+            device.DetachObject()?;
+
+            Ok(media_src)
+        }
+    }
+
+    /// # Camera Builder
+    ///
+    /// A builder for the camera at the index of devices, to set its output, format and source reader options before it is activated. See CameraBuilder.
+    ///
+    /// Fails when there is no camera at the index.
+    pub fn camera_builder(
+        &self,
+        index: usize,
+    ) -> Result<CameraBuilder<'a>, Box<dyn std::error::Error>> {
+        match self.devices.get(index) {
+            Some(device) => Ok(CameraBuilder::new(device)),
+            None => Err(format!(
+                "there is no camera at index {index}, {} are connected",
+                self.devices.len()
+            )
+            .into()),
         }
     }
 
     /// # Free Devices
     ///
     /// Uses CoTaskMemFree to free all devices that have been collected, this is essential for memory.
+    ///
+    /// Cameras already activated keep working. Every other function of the crate is safe, their failures are returned as errors.
+    ///
+    /// # Safety
+    ///
+    /// The references in devices point into the freed memory: they must not be used or activated afterwards, and the devices must only be freed once.
     pub unsafe fn free_devices(&self) {
         unsafe {
             if !self.pp_devices.is_null() {
@@ -0,0 +1,87 @@
+use windows::Win32::{
+    Foundation::{ERROR_BUSY, ERROR_SHARING_VIOLATION},
+    Media::MediaFoundation::{
+        MF_E_HW_MFT_FAILED_START_STREAMING, MF_E_VIDEO_RECORDING_DEVICE_PREEMPTED,
+    },
+};
+
+/// # Capture Error
+///
+/// Why a camera could not be activated or stopped capturing, for the errors worth telling apart, see Cameras::activate_with_retry.
+///
+/// A capture that ends because another application has the camera fails with DeviceBusy, other errors of a capture are returned as they are.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Another application is using the camera, such as a video call. Cameras can usually only stream to one application at a time, try again once it let go of the camera.
+    DeviceBusy {
+        /// The friendly name of the camera.
+        name: String,
+
+        /// The error the camera failed with.
+        error: windows::core::Error,
+    },
+
+    /// Any other error.
+    Failed(windows::core::Error),
+}
+
+impl CaptureError {
+    /// # Is Busy
+    ///
+    /// Whether the error is one cameras fail with while another application is using them.
+    ///
+    /// Some drivers fail with MF_E_HW_MFT_FAILED_START_STREAMING when unplugged as well, reading from such a camera reports it as disconnected instead (see CaptureEvent::is_device_lost).
+    pub fn is_busy(error: &windows::core::Error) -> bool {
+        let code = error.code();
+
+        code == MF_E_HW_MFT_FAILED_START_STREAMING
+            || code == MF_E_VIDEO_RECORDING_DEVICE_PREEMPTED
+            || code == ERROR_SHARING_VIOLATION.to_hresult()
+            || code == ERROR_BUSY.to_hresult()
+    }
+
+    /// # Windows Error
+    ///
+    /// The error the camera failed with, whatever the reason.
+    pub fn windows_error(&self) -> &windows::core::Error {
+        match self {
+            CaptureError::DeviceBusy { error, .. } => error,
+            CaptureError::Failed(error) => error,
+        }
+    }
+
+    // DeviceBusy for the camera with the name when the error says it is busy, Failed otherwise
+    pub(crate) fn from_error(error: windows::core::Error, name: &str) -> Self {
+        if Self::is_busy(&error) {
+            CaptureError::DeviceBusy {
+                name: name.to_string(),
+                error,
+            }
+        } else {
+            CaptureError::Failed(error)
+        }
+    }
+}
+
+impl From<windows::core::Error> for CaptureError {
+    fn from(error: windows::core::Error) -> Self {
+        CaptureError::Failed(error)
+    }
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::DeviceBusy { name, .. } => {
+                write!(f, "{name} is in use by another application")
+            }
+            CaptureError::Failed(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.windows_error())
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(feature = "camera")]
+use windows::Win32::Media::MediaFoundation::MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED;
+#[cfg(feature = "monitor")]
+use windows::Win32::{
+    Foundation::E_ACCESSDENIED,
+    Graphics::Dxgi::{
+        DXGI_ERROR_ACCESS_LOST, DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET,
+        DXGI_ERROR_NOT_CURRENTLY_AVAILABLE, DXGI_ERROR_NOT_FOUND, DXGI_ERROR_SESSION_DISCONNECTED,
+    },
+};
+
+use std::time::Duration;
+
+use crate::{devices::Dimensions, frame::PixelFormat};
+
+/// # Capture Event
+///
+/// Sent through the events receiver of a Monitor or Camera when the state of the capture changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CaptureEvent {
+    /// The capture started, frames will be sent through the receiver.
+    Started,
+
+    /// The capture stopped, sent every time the capture ends, even when it ended on an error.
+    Stopped,
+
+    /// The capture ended on an error, it is followed by Stopped.
+    FatalError { error: String },
+
+    /// The device was removed or reset, for example the GPU driver was updated, the monitor was disconnected or the camera was unplugged. It is followed by FatalError and Stopped.
+    ///
+    /// The Monitor or Camera must be created again to keep capturing, Monitor::reconnect and Camera::reconnect do so once the device is back and keep the receivers.
+    DeviceLost,
+
+    /// The camera was unplugged and the capture waits for it to be plugged back in, see Camera::set_auto_reconnect. No frames are sent meanwhile.
+    ///
+    /// It is followed by Reconnected, or by DeviceLost when the camera did not come back in time.
+    Reconnecting,
+
+    /// The camera was plugged back in and activated again, frames are sent as before.
+    Reconnected,
+
+    /// The capture lost access to the desktop and is paused, it keeps trying to get access back.
+    AccessLost { reason: AccessLostReason },
+
+    /// Access to the desktop is back and frames are sent again, the capture recovered on its own.
+    Resumed,
+
+    /// The device changed the format of its images, for example HDR was turned on. Frames from now on are sent in this format.
+    ///
+    /// Cameras send it whenever the source reader changes its media type while capturing, the stride or size may have changed instead, see Camera::negotiated_format.
+    FormatChanged { format: PixelFormat },
+
+    /// The size of the frames changed, for example the resolution or rotation of the monitor was changed. Frames from now on are sent at this size.
+    ///
+    /// The first frame at the new size has dimensions_changed set.
+    DimensionsChanged { size: Dimensions },
+
+    /// The camera's stream has a gap, such as a frame the camera dropped. No frame is sent for it.
+    ///
+    /// The timestamp is when the missing sample would have been, counted like Frame::sample_time.
+    StreamGap { timestamp: Option<Duration> },
+
+    /// The window followed with Monitor::follow_window moved to another monitor, named by its device name (for example `\\.\DISPLAY2`).
+    ///
+    /// No frames are sent while the window is entirely off the monitor, a Monitor created for the named monitor can follow the window from there.
+    WindowMoved { monitor: String },
+}
+
+impl CaptureEvent {
+    /// # Is Device Lost
+    ///
+    /// Whether the error means the device is gone and the capture cannot continue.
+    ///
+    /// Monitors end with DXGI_ERROR_NOT_FOUND once their monitor was disconnected, cameras with MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED once they were unplugged or their stream failed.
+    pub fn is_device_lost(error: &windows::core::Error) -> bool {
+        match error.code() {
+            #[cfg(feature = "monitor")]
+            DXGI_ERROR_DEVICE_REMOVED | DXGI_ERROR_DEVICE_RESET | DXGI_ERROR_NOT_FOUND => true,
+            #[cfg(feature = "camera")]
+            MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED => true,
+            _ => false,
+        }
+    }
+
+    // the events sent when a capture ends, the error (if any) is what ended it
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    pub(crate) fn ended(error: Option<&(dyn std::error::Error + 'static)>) -> Vec<Self> {
+        let mut events = vec![];
+
+        if let Some(error) = error {
+            let device_lost = error
+                .downcast_ref::<windows::core::Error>()
+                .is_some_and(CaptureEvent::is_device_lost);
+
+            if device_lost {
+                events.push(CaptureEvent::DeviceLost);
+            }
+
+            events.push(CaptureEvent::FatalError {
+                error: error.to_string(),
+            });
+        }
+
+        events.push(CaptureEvent::Stopped);
+
+        events
+    }
+}
+
+/// # Access Lost Reason
+///
+/// Why a capture lost access to the desktop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessLostReason {
+    /// The desktop was switched, for example to the secure desktop of a UAC prompt or the lock screen, or the display mode changed.
+    DesktopSwitched,
+
+    /// The desktop cannot be duplicated right now, usually because the secure desktop is still shown.
+    AccessDenied,
+
+    /// The remote session was disconnected.
+    SessionDisconnected,
+
+    /// The desktop is already duplicated by too many applications, or duplication is unavailable in this session.
+    NotAvailable,
+}
+
+impl AccessLostReason {
+    /// # From Error
+    ///
+    /// The reason access was lost if the error means the capture should wait and try again, None for any other error.
+    #[cfg(feature = "monitor")]
+    pub fn from_error(error: &windows::core::Error) -> Option<Self> {
+        match error.code() {
+            DXGI_ERROR_ACCESS_LOST => Some(AccessLostReason::DesktopSwitched),
+            E_ACCESSDENIED => Some(AccessLostReason::AccessDenied),
+            DXGI_ERROR_SESSION_DISCONNECTED => Some(AccessLostReason::SessionDisconnected),
+            DXGI_ERROR_NOT_CURRENTLY_AVAILABLE => Some(AccessLostReason::NotAvailable),
+            _ => None,
+        }
+    }
+}
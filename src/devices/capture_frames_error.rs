@@ -0,0 +1,30 @@
+use crate::frame::Frame;
+
+/// # Capture Frames Error
+///
+/// Returned by Monitor::capture_frames when a burst ends early, it holds the frames captured before the error.
+#[derive(Debug)]
+pub struct CaptureFramesError {
+    /// The frames captured before the error, in the order they were captured.
+    pub frames: Vec<Frame>,
+
+    /// What ended the burst, such as a frame timing out or access to the desktop being lost.
+    pub error: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl std::fmt::Display for CaptureFramesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the burst ended after {} frames: {}",
+            self.frames.len(),
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for CaptureFramesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
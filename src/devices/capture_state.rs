@@ -0,0 +1,104 @@
+#[cfg(any(feature = "monitor", feature = "camera"))]
+use tokio::sync::Mutex;
+
+/// # Capture State
+///
+/// Whether a Monitor or Camera is capturing, see Monitor::state and Camera::state.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CaptureState {
+    /// Not capturing, either never started or stopped without an error.
+    #[default]
+    Idle,
+
+    /// Capturing and sending frames.
+    Running,
+
+    /// A stop was requested and the capture is finishing the frame it is on.
+    Stopping,
+
+    /// The capture ended on an error, it can be started again.
+    Failed { error: String },
+}
+
+impl CaptureState {
+    /// Whether the capture loop is running, this includes while it is stopping.
+    pub fn is_running(&self) -> bool {
+        matches!(self, CaptureState::Running | CaptureState::Stopping)
+    }
+
+    // the state once a capture ended with the result
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    pub(crate) fn ended<E: std::fmt::Display>(result: &Result<(), E>) -> Self {
+        match result {
+            Ok(()) => CaptureState::Idle,
+            Err(e) => CaptureState::Failed {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+// clears the capturing flag of a capture however its loop ends, also when the future running it is dropped or the loop panics
+//
+// a loop that ends on its own calls finish, which waits for the flag's lock. Drop only covers the paths that never get there
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) struct CaptureGuard<'a> {
+    capturing: &'a Mutex<bool>,
+
+    // marked as failed when the loop did not finish, None for captures without a state
+    state: Option<&'a std::sync::Mutex<CaptureState>>,
+
+    finished: bool,
+}
+
+#[cfg(any(feature = "monitor", feature = "camera"))]
+impl<'a> CaptureGuard<'a> {
+    // guards a capture that just set its capturing flag
+    pub(crate) fn new(
+        capturing: &'a Mutex<bool>,
+        state: Option<&'a std::sync::Mutex<CaptureState>>,
+    ) -> Self {
+        CaptureGuard {
+            capturing,
+            state,
+            finished: false,
+        }
+    }
+
+    // clears the flag once the loop ended with the result, and sets the state for it
+    pub(crate) async fn finish<E: std::fmt::Display>(mut self, result: &Result<(), E>) {
+        *self.capturing.lock().await = false;
+
+        if let Some(state) = self.state {
+            *state.lock().unwrap() = CaptureState::ended(result);
+        }
+
+        self.finished = true;
+    }
+}
+
+#[cfg(any(feature = "monitor", feature = "camera"))]
+impl Drop for CaptureGuard<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        //the flag is only locked for moments, a stop holding it right now clears it itself
+        if let Ok(mut capturing) = self.capturing.try_lock() {
+            *capturing = false;
+        }
+
+        if let Some(state) = self.state {
+            let mut state = state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+            if state.is_running() {
+                *state = CaptureState::Failed {
+                    error: "the capture ended without finishing, it was dropped or panicked".into(),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(any(feature = "monitor", feature = "camera"))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// # Capture Stats
+///
+/// The counters of a capture at one point in time, see Monitor::stats and Camera::stats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CaptureStats {
+    /// The number of frames read from the device.
+    pub captured: u64,
+
+    /// The number of frames sent through the receiver.
+    pub delivered: u64,
+
+    /// The number of frames that were never sent, because the capture fell behind the device or was stopped before they could be sent.
+    ///
+    /// This includes the frames dropped or replaced by the delivery policy, see DeliveryPolicy.
+    pub dropped: u64,
+
+    /// The number of frames that were not sent on purpose, such as unchanged monitor frames (see Monitor::set_skip_unchanged).
+    pub skipped: u64,
+
+    /// The number of skipped frames that were identical to the frame before them, see Monitor::set_duplicate_suppression.
+    pub suppressed: u64,
+
+    /// The number of frames read and thrown away while the camera warmed up at the start of each capture, see Camera::set_warmup_frames.
+    ///
+    /// They are counted in captured, but not in dropped or skipped. Always 0 for monitors.
+    pub warmup: u64,
+
+    /// The number of frames waiting in the receiver's channel to be read when the stats were taken, at most the channel capacity (see Monitor::with_channel_capacity).
+    ///
+    /// A queue that is always full means the receiver is reading slower than frames are sent.
+    pub queued: u64,
+}
+
+// the counters behind CaptureStats, atomics so counting never waits on the locks of the capture loop
+#[cfg(any(feature = "monitor", feature = "camera"))]
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    captured: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    skipped: AtomicU64,
+    suppressed: AtomicU64,
+    warmup: AtomicU64,
+
+    // the sequence number of the last frame
+    sequence: AtomicU64,
+}
+
+#[cfg(any(feature = "monitor", feature = "camera"))]
+impl StatsCounters {
+    pub(crate) fn add_captured(&self, count: u64) {
+        self.captured.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_delivered(&self, count: u64) {
+        self.delivered.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_dropped(&self, count: u64) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    // frames taken back out of the channel before they were read, they were counted as delivered when they were sent
+    // the delivered count stops at zero should a replacement be counted before the delivery it undoes
+    pub(crate) fn add_replaced(&self, count: u64) {
+        let _ = self
+            .delivered
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |delivered| {
+                Some(delivered.saturating_sub(count))
+            });
+        self.add_dropped(count);
+    }
+
+    pub(crate) fn add_skipped(&self, count: u64) {
+        self.skipped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    // suppressed frames are skipped frames as well
+    pub(crate) fn add_suppressed(&self, count: u64) {
+        self.suppressed.fetch_add(count, Ordering::Relaxed);
+        self.add_skipped(count);
+    }
+
+    // warm-up frames were read from the device as well
+    #[cfg(feature = "camera")]
+    pub(crate) fn add_warmup(&self, count: u64) {
+        self.warmup.fetch_add(count, Ordering::Relaxed);
+        self.add_captured(count);
+    }
+
+    // moves the sequence on by count and returns the sequence number of the newest frame, the first frame is 1
+    pub(crate) fn next_sequence(&self, count: u64) -> u64 {
+        self.sequence.fetch_add(count, Ordering::Relaxed) + count
+    }
+
+    pub(crate) fn snapshot(&self) -> CaptureStats {
+        CaptureStats {
+            captured: self.captured.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            suppressed: self.suppressed.load(Ordering::Relaxed),
+            warmup: self.warmup.load(Ordering::Relaxed),
+            queued: 0,
+        }
+    }
+}
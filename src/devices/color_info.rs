@@ -0,0 +1,42 @@
+use windows::Win32::Graphics::Dxgi::{IDXGIOutput, IDXGIOutput6};
+use windows::core::Interface;
+
+use crate::frame::ColorSpace;
+
+/// # Color Info
+///
+/// The color space and brightness of a monitor as reported by DXGI, see MonitorInfo::color_info and Monitor::color_info.
+///
+/// The luminance values come from the monitor's EDID, some monitors report 0 or made up values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorInfo {
+    /// The color space the monitor is driven in, Hdr10 while HDR is on.
+    pub color_space: ColorSpace,
+
+    /// The bits of each color channel sent to the monitor, such as 8 or 10.
+    pub bits_per_color: u32,
+
+    /// The darkest the monitor can show in nits.
+    pub min_luminance: f32,
+
+    /// The brightest the monitor can show on a small part of the screen in nits.
+    pub max_luminance: f32,
+
+    /// The brightest the monitor can show across the whole screen in nits.
+    pub max_full_frame_luminance: f32,
+}
+
+impl ColorInfo {
+    // reads the color info of the output, None on Windows versions without IDXGIOutput6
+    pub(crate) unsafe fn from_output(output: &IDXGIOutput) -> Option<Self> {
+        let desc = unsafe { output.cast::<IDXGIOutput6>().ok()?.GetDesc1().ok()? };
+
+        Some(ColorInfo {
+            color_space: ColorSpace::from_dxgi(desc.ColorSpace.0),
+            bits_per_color: desc.BitsPerColor,
+            min_luminance: desc.MinLuminance,
+            max_luminance: desc.MaxLuminance,
+            max_full_frame_luminance: desc.MaxFullFrameLuminance,
+        })
+    }
+}
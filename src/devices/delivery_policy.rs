@@ -0,0 +1,111 @@
+#[cfg(any(feature = "monitor", feature = "camera"))]
+use tokio::sync::{
+    Mutex,
+    mpsc::{Receiver, Sender, error::TrySendError},
+};
+
+// the number of frames the receiver's channel holds unless with_channel_capacity sets another
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) const DEFAULT_CHANNEL_CAPACITY: usize = 1;
+
+// the number of frames kept for subscribers, a subscriber further behind than this misses frames and receives Lagged
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) const BROADCAST_CAPACITY: usize = 4;
+
+/// # Delivery Policy
+///
+/// What a capture does with a new frame while the receiver still holds a frame it has not read, see Monitor::set_delivery_policy and Camera::set_delivery_policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeliveryPolicy {
+    /// Wait until the receiver reads the frame it holds. A slow receiver slows the whole capture down, monitors merge the desktop updates presented meanwhile into the next frame.
+    Block,
+
+    /// Drop the new frame, the receiver reads the frame it already holds.
+    DropNewest,
+
+    /// Take the frame the receiver holds back out and send the new one instead, so the receiver always reads the newest frame. Best for live views.
+    ///
+    /// While the receiver is locked without being read the new frame is dropped instead, the frame it holds is about to be read then.
+    #[default]
+    DropOldest,
+}
+
+// what became of an output offered to a channel without waiting
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) enum Delivery<T> {
+    // the output was sent, replacing an output the receiver had not read when replaced is set
+    Sent { replaced: bool },
+
+    // the output was dropped as the policy says, replaced is set when an output the receiver had not read was taken back out as well
+    Dropped { replaced: bool },
+
+    // the policy is Block and the channel is full, or the receiver is gone. The caller waits on the channel with the output to find out
+    Wait(T),
+}
+
+// the frame channel of a capture needs room for at least one frame, see with_channel_capacity
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) fn check_channel_capacity(capacity: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if capacity == 0 {
+        return Err("the channel capacity must be at least 1".into());
+    }
+
+    Ok(())
+}
+
+// the number of outputs in the channel that were not read yet
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) fn queued<T>(sender: &Sender<T>) -> u64 {
+    (sender.max_capacity() - sender.capacity()) as u64
+}
+
+// takes the receiver out of its mutex, leaving a closed receiver behind so it is only handed out once
+//
+// the mutex is not waited on, None is returned while it is locked or the receiver was already taken
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) fn take_receiver<T>(receiver: &Mutex<Receiver<T>>) -> Option<Receiver<T>> {
+    let mut receiver = receiver.try_lock().ok()?;
+
+    //the receiver is only closed once it was taken, the sender stays with the capture
+    if receiver.is_closed() {
+        return None;
+    }
+
+    let (_, closed) = tokio::sync::mpsc::channel(1);
+    Some(std::mem::replace(&mut *receiver, closed))
+}
+
+// sends the output without waiting when the channel has room or the policy allows dropping a frame
+#[cfg(any(feature = "monitor", feature = "camera"))]
+pub(crate) fn try_deliver<T>(
+    policy: DeliveryPolicy,
+    sender: &Sender<T>,
+    receiver: &Mutex<Receiver<T>>,
+    output: T,
+) -> Delivery<T> {
+    let output = match sender.try_send(output) {
+        Ok(()) => return Delivery::Sent { replaced: false },
+        Err(TrySendError::Closed(output)) => return Delivery::Wait(output),
+        Err(TrySendError::Full(output)) => output,
+    };
+
+    match policy {
+        DeliveryPolicy::Block => Delivery::Wait(output),
+        DeliveryPolicy::DropNewest => Delivery::Dropped { replaced: false },
+        DeliveryPolicy::DropOldest => {
+            let Ok(mut receiver) = receiver.try_lock() else {
+                return Delivery::Dropped { replaced: false };
+            };
+
+            let replaced = receiver.try_recv().is_ok();
+            drop(receiver);
+
+            match sender.try_send(output) {
+                Ok(()) => Delivery::Sent { replaced },
+                Err(TrySendError::Closed(output)) => Delivery::Wait(output),
+                //another sender filled the channel first
+                Err(TrySendError::Full(_)) => Delivery::Dropped { replaced },
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+/// # Device Info
+///
+/// A camera found by Cameras, see Cameras::device_info.
+///
+/// Store the symbolic link to find the same camera again after the application restarts, see Cameras::activate_by_symbolic_link. Names are not unique and the order of the cameras changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// The index of the camera in Cameras::devices.
+    pub index: usize,
+
+    /// The friendly name of the camera, two cameras of the same model have the same name.
+    pub name: String,
+
+    /// Identifies the camera while it is plugged into the same port, see get_device_symbolic_link.
+    pub symbolic_link: String,
+}
@@ -0,0 +1,320 @@
+use std::{cell::RefCell, ffi::c_void, thread::JoinHandle};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use windows::{
+    Win32::{
+        Foundation::{E_FAIL, HANDLE, HWND, LPARAM, LRESULT, WPARAM},
+        Media::KernelStreaming::{KSCATEGORY_CAPTURE, KSCATEGORY_VIDEO_CAMERA},
+        System::{
+            Com::{COINIT_MULTITHREADED, CoInitializeEx, CoUninitialize},
+            LibraryLoader::GetModuleHandleW,
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DBT_DEVICEARRIVAL, DBT_DEVICEREMOVECOMPLETE,
+            DBT_DEVTYP_DEVICEINTERFACE, DEV_BROADCAST_DEVICEINTERFACE_W,
+            DEVICE_NOTIFY_WINDOW_HANDLE, DefWindowProcW, DestroyWindow, DispatchMessageW,
+            GetMessageW, HDEVNOTIFY, HWND_MESSAGE, KillTimer, MSG, PostMessageW, PostQuitMessage,
+            RegisterClassW, RegisterDeviceNotificationW, SetTimer, UnregisterDeviceNotification,
+            WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_DESTROY, WM_DEVICECHANGE, WM_TIMER,
+            WNDCLASSW,
+        },
+    },
+    core::{GUID, w},
+};
+
+use crate::devices::{Camera, DeviceInfo};
+
+// how long notifications are collected before the cameras are enumerated again, a camera arrives as several interfaces at once
+const SETTLE_TIME_MS: u32 = 200;
+
+// the id of the timer that waits out the notifications
+const SETTLE_TIMER: usize = 1;
+
+/// # Device Event
+///
+/// A camera that was plugged in or unplugged, see Cameras::watch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// A camera was plugged in. Activate it with Cameras::activate_by_symbolic_link, the index is its place among the cameras when the watcher enumerated them.
+    Added(DeviceInfo),
+
+    /// The camera with the symbolic link was unplugged.
+    Removed(String),
+}
+
+/// # Device Watcher
+///
+/// Sends an event whenever a camera is plugged in or unplugged, created with Cameras::watch.
+///
+/// The watcher runs on a thread of its own with a hidden window receiving the device notifications of Windows. A camera arriving as several devices at once is sent as a single event, and a camera is only sent again once it was removed.
+pub struct DeviceWatcher {
+    /// The events, starting with an Added event for every camera plugged in when watching started. Returns None once the watcher stopped.
+    pub receiver: UnboundedReceiver<DeviceEvent>,
+
+    // the hidden window, stored as its address so the watcher can be sent between threads
+    window: usize,
+
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    // starts the thread and waits until its window receives notifications
+    pub(crate) fn start() -> Result<Self, windows::core::Error> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (started_sender, started) = std::sync::mpsc::channel();
+
+        let thread = std::thread::spawn(move || unsafe { watch(sender, started_sender) });
+
+        let window = match started.recv() {
+            Ok(Ok(window)) => window,
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                return Err(e);
+            }
+            //the thread ended without saying why
+            Err(_) => return Err(E_FAIL.into()),
+        };
+
+        Ok(DeviceWatcher {
+            receiver,
+            window,
+            thread: Some(thread),
+        })
+    }
+
+    /// # Is Watching
+    ///
+    /// Whether the watcher is still sending events.
+    pub fn is_watching(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// # Stop
+    ///
+    /// Stops watching and waits for the watcher's thread to end, the receiver returns the events sent before and then None. The watcher is also stopped when it is dropped.
+    pub fn stop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+
+        //the window is closed on its own thread, which then leaves its message loop
+        unsafe {
+            let _ = PostMessageW(
+                Some(HWND(self.window as *mut c_void)),
+                WM_CLOSE,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+
+        let _ = thread.join();
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// what the window of this thread knows, the window procedure has no other way to reach it
+struct WatchState {
+    sender: UnboundedSender<DeviceEvent>,
+
+    // the symbolic links of the cameras sent as added and not removed since
+    links: Vec<String>,
+}
+
+thread_local! {
+    static STATE: RefCell<Option<WatchState>> = const { RefCell::new(None) };
+}
+
+// runs the hidden window until it is closed, the result of starting is sent back first
+unsafe fn watch(
+    sender: UnboundedSender<DeviceEvent>,
+    started: std::sync::mpsc::Sender<Result<usize, windows::core::Error>>,
+) {
+    unsafe {
+        //the cameras are enumerated on this thread
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let window = match create_window() {
+            Ok(window) => window,
+            Err(e) => {
+                let _ = started.send(Err(e));
+                CoUninitialize();
+                return;
+            }
+        };
+
+        //cameras register as video cameras, older drivers only as capture devices
+        let mut notifications = vec![];
+
+        for category in [KSCATEGORY_VIDEO_CAMERA, KSCATEGORY_CAPTURE] {
+            match register_notification(window, category) {
+                Ok(notification) => notifications.push(notification),
+                Err(e) => {
+                    for notification in notifications {
+                        let _ = UnregisterDeviceNotification(notification);
+                    }
+
+                    let _ = DestroyWindow(window);
+                    let _ = started.send(Err(e));
+                    CoUninitialize();
+                    return;
+                }
+            }
+        }
+
+        STATE.set(Some(WatchState {
+            sender,
+            links: vec![],
+        }));
+
+        //the cameras already plugged in are sent as added
+        refresh();
+
+        let _ = started.send(Ok(window.0 as usize));
+
+        let mut message = MSG::default();
+
+        //0 once the window was destroyed, -1 when the loop itself failed
+        while GetMessageW(&mut message, None, 0, 0).0 > 0 {
+            DispatchMessageW(&message);
+        }
+
+        for notification in notifications {
+            let _ = UnregisterDeviceNotification(notification);
+        }
+
+        //the sender is dropped with the state, the receiver returns None once it was read out
+        STATE.set(None);
+
+        CoUninitialize();
+    }
+}
+
+// creates the hidden window notifications are sent to
+unsafe fn create_window() -> Result<HWND, windows::core::Error> {
+    unsafe {
+        let instance = GetModuleHandleW(None)?;
+        let class_name = w!("win_video_device_watcher");
+
+        //registering again fails once a watcher registered the class, creating the window then finds it anyway
+        RegisterClassW(&WNDCLASSW {
+            lpfnWndProc: Some(window_procedure),
+            hInstance: instance.into(),
+            lpszClassName: class_name,
+            ..Default::default()
+        });
+
+        CreateWindowExW(
+            WINDOW_EX_STYLE(0),
+            class_name,
+            None,
+            WINDOW_STYLE(0),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            Some(instance.into()),
+            None,
+        )
+    }
+}
+
+// asks for the arrival and removal of devices of the interface category to be sent to the window
+unsafe fn register_notification(
+    window: HWND,
+    category: GUID,
+) -> Result<HDEVNOTIFY, windows::core::Error> {
+    let filter = DEV_BROADCAST_DEVICEINTERFACE_W {
+        dbcc_size: size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32,
+        dbcc_devicetype: DBT_DEVTYP_DEVICEINTERFACE.0,
+        dbcc_classguid: category,
+        ..Default::default()
+    };
+
+    unsafe {
+        RegisterDeviceNotificationW(
+            HANDLE(window.0),
+            &filter as *const _ as *const c_void,
+            DEVICE_NOTIFY_WINDOW_HANDLE,
+        )
+    }
+}
+
+unsafe extern "system" fn window_procedure(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        match message {
+            WM_DEVICECHANGE => {
+                //restarting the timer on every notification collects a burst of them into one enumeration
+                if matches!(
+                    wparam.0 as u32,
+                    DBT_DEVICEARRIVAL | DBT_DEVICEREMOVECOMPLETE
+                ) {
+                    SetTimer(Some(window), SETTLE_TIMER, SETTLE_TIME_MS, None);
+                }
+
+                LRESULT(1)
+            }
+            WM_TIMER if wparam.0 == SETTLE_TIMER => {
+                let _ = KillTimer(Some(window), SETTLE_TIMER);
+                refresh();
+
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = DestroyWindow(window);
+
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                PostQuitMessage(0);
+
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(window, message, wparam, lparam),
+        }
+    }
+}
+
+// enumerates the cameras and sends the ones that were added or removed since the last enumeration
+fn refresh() {
+    //a failed enumeration is tried again with the next notification, rather than reporting every camera as removed
+    let Ok(devices) = (unsafe { Camera::connected_devices() }) else {
+        return;
+    };
+
+    STATE.with_borrow_mut(|state| {
+        let Some(state) = state else {
+            return;
+        };
+
+        for link in &state.links {
+            if !devices.iter().any(|device| &device.symbolic_link == link) {
+                let _ = state.sender.send(DeviceEvent::Removed(link.clone()));
+            }
+        }
+
+        for device in &devices {
+            if !state.links.contains(&device.symbolic_link) {
+                let _ = state.sender.send(DeviceEvent::Added(device.clone()));
+            }
+        }
+
+        state.links = devices
+            .into_iter()
+            .map(|device| device.symbolic_link)
+            .collect();
+    });
+}
@@ -1,5 +1,5 @@
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 /// # Dimensions
 /// 
 /// Simply a container that has a width and height
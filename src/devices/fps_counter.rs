@@ -0,0 +1,92 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// how much of the average is made up by the last second of frames
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+// the frames per second of a running capture, an exponential moving average over about the last second
+// only the capture loop records frames, atomics so reading it never waits on the loop
+#[derive(Debug)]
+pub(crate) struct FpsCounter {
+    // the times are stored as nanoseconds since this instant
+    epoch: Instant,
+
+    // when the last frame was recorded, 0 before the first frame
+    last_frame: AtomicU64,
+
+    // the bits of the f64 average, 0 until two frames were recorded
+    fps: AtomicU64,
+}
+
+impl FpsCounter {
+    pub(crate) fn new() -> Self {
+        FpsCounter {
+            epoch: Instant::now(),
+            last_frame: AtomicU64::new(0),
+            fps: AtomicU64::new(0),
+        }
+    }
+
+    // forgets every frame, the fps reads 0 until two more frames are recorded
+    pub(crate) fn reset(&self) {
+        self.last_frame.store(0, Ordering::Relaxed);
+        self.fps.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record(&self) {
+        self.record_at(Instant::now());
+    }
+
+    pub(crate) fn record_at(&self, now: Instant) {
+        //never 0, that marks no frame being recorded yet
+        let now = self.nanos(now).max(1);
+        let last = self.last_frame.swap(now, Ordering::Relaxed);
+
+        if last == 0 || now <= last {
+            return;
+        }
+
+        let interval = Duration::from_nanos(now - last).as_secs_f64();
+        let fps = 1.0 / interval;
+
+        let mut average = f64::from_bits(self.fps.load(Ordering::Relaxed));
+
+        //longer intervals weigh more, so the average covers the same wall time at any frame rate
+        if average == 0.0 {
+            average = fps;
+        } else {
+            let weight = 1.0 - (-interval / FPS_WINDOW.as_secs_f64()).exp();
+            average += (fps - average) * weight;
+        }
+
+        self.fps.store(average.to_bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn fps(&self) -> f64 {
+        self.fps_at(Instant::now())
+    }
+
+    // a capture that stalls is brought down to the rate it would have if a frame came now
+    pub(crate) fn fps_at(&self, now: Instant) -> f64 {
+        let average = f64::from_bits(self.fps.load(Ordering::Relaxed));
+        let last = self.last_frame.load(Ordering::Relaxed);
+
+        if average == 0.0 || last == 0 {
+            return 0.0;
+        }
+
+        let since_last = Duration::from_nanos(self.nanos(now).saturating_sub(last)).as_secs_f64();
+
+        if since_last > 0.0 {
+            average.min(1.0 / since_last)
+        } else {
+            average
+        }
+    }
+
+    fn nanos(&self, time: Instant) -> u64 {
+        time.saturating_duration_since(self.epoch).as_nanos() as u64
+    }
+}
@@ -0,0 +1,565 @@
+use std::{
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::{
+    Mutex, Notify,
+    mpsc::{self, Receiver, Sender},
+};
+use windows::{
+    Foundation::TypedEventHandler,
+    Graphics::{
+        Capture::{
+            Direct3D11CaptureFrame, Direct3D11CaptureFramePool, GraphicsCaptureItem,
+            GraphicsCaptureSession,
+        },
+        DirectX::{Direct3D11::IDirect3DDevice, DirectXPixelFormat},
+        SizeInt32,
+    },
+    Win32::{
+        Foundation::HWND,
+        Graphics::{
+            Direct3D11::{
+                D3D11_MAP_READ, D3D11_MAPPED_SUBRESOURCE, D3D11_TEXTURE2D_DESC, ID3D11Device,
+                ID3D11DeviceContext, ID3D11Texture2D,
+            },
+            Dxgi::{Common::DXGI_FORMAT_B8G8R8A8_UNORM, IDXGIDevice},
+            Gdi::HMONITOR,
+        },
+        System::WinRT::{
+            Direct3D11::{CreateDirect3D11DeviceFromDXGIDevice, IDirect3DDxgiInterfaceAccess},
+            Graphics::Capture::IGraphicsCaptureItemInterop,
+        },
+    },
+    core::{IInspectable, Interface},
+};
+
+use crate::{
+    buffer_pool::BufferPool,
+    devices::{
+        CaptureEvent, CaptureState, Dimensions, Monitor,
+        capture_state::CaptureGuard,
+        capture_stats::{CaptureStats, StatsCounters},
+        delivery_policy::queued,
+        fps_counter::FpsCounter,
+    },
+    frame::{Frame, PixelFormat},
+    i_capture::ICapture,
+};
+
+// the number of frames the frame pool holds, one being read while the next is captured
+const FRAME_POOL_BUFFERS: i32 = 2;
+
+// how long the capture loop waits for a frame before checking if it should stop
+const FRAME_TIMEOUT: Duration = Duration::from_millis(500);
+
+// the number of frame buffers kept for reuse, enough for a frame in the channel, one being read and one spare
+const DEFAULT_BUFFER_POOL_SIZE: usize = 3;
+
+/// # Graphics Capture
+///
+/// Captures a window or a monitor through Windows.Graphics.Capture instead of desktop duplication, frames are sent through the receiver like a Monitor.
+///
+/// A window is captured on its own, windows covering it are not captured. The yellow border Windows draws around the captured item cannot be turned off.
+///
+/// Windows.Graphics.Capture needs Windows 10 1903 or later, check is_supported before creating one to fall back to Monitor. WinRT must be initialized on the
+/// calling thread (RoInitialize or CoInitializeEx with COINIT_MULTITHREADED), creating a capture fails with an error otherwise.
+///
+/// Frames are always BGRA without row padding, the pointer is drawn into them and Frame::pointer is None.
+pub struct GraphicsCapture {
+    item: GraphicsCaptureItem,
+
+    // the WinRT wrapper of the device, the frame pool creates its textures on it
+    direct3d_device: IDirect3DDevice,
+
+    device: ID3D11Device,
+
+    device_context: ID3D11DeviceContext,
+
+    // the texture the captured surface is copied to so the CPU can read it, replaced when the size changes
+    staging: std::sync::Mutex<Option<ID3D11Texture2D>>,
+
+    /// The receiver, frames are sent through it while capturing.
+    pub receiver: Arc<Mutex<Receiver<Frame>>>,
+
+    sender: Sender<Frame>,
+
+    // determines if the capture loop should keep running
+    is_capturing: Arc<Mutex<bool>>,
+
+    // wakes the capture loop when a stop is requested
+    stop_requested: Notify,
+
+    // whether the capture is running, stopping, or how it ended
+    state: std::sync::Mutex<CaptureState>,
+
+    // the frames captured, sent and dropped
+    stats: StatsCounters,
+
+    // the rate frames are sent at while capturing
+    fps: FpsCounter,
+
+    // frame buffers are reused once the receiver drops them
+    buffer_pool: BufferPool,
+
+    // capture events, such as the capture starting or stopping
+    events_receiver: Arc<Mutex<Receiver<CaptureEvent>>>,
+    events_sender: Sender<CaptureEvent>,
+
+    /// The name of the captured window or monitor, as shown by Windows.
+    pub name: String,
+}
+
+impl GraphicsCapture {
+    /// # Is Supported
+    ///
+    /// Whether Windows.Graphics.Capture is available on this system, when false Monitor can be used instead.
+    pub fn is_supported() -> bool {
+        //the class does not exist at all before Windows 10 1803, IsSupported fails there
+        GraphicsCaptureSession::IsSupported().unwrap_or(false)
+    }
+
+    /// # From Window
+    ///
+    /// Captures the window, frames follow it as it is moved or resized. The capture ends with an error once the window is closed.
+    pub fn from_window(hwnd: HWND) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
+        let item = unsafe { interop.CreateForWindow::<GraphicsCaptureItem>(hwnd)? };
+
+        Self::from_item(item)
+    }
+
+    /// # From Monitor
+    ///
+    /// Captures the monitor, the index is the same as for Monitor::from_monitor.
+    pub fn from_monitor(monitor: u32) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let outputs = unsafe { Monitor::enumerate_outputs()? };
+
+        let Some((_, output)) = outputs.get(monitor as usize) else {
+            return Err(format!(
+                "monitor index ({monitor}) fell outside of the max range of {}",
+                outputs.len()
+            )
+            .into());
+        };
+
+        let desc = unsafe { output.GetDesc()? };
+
+        Self::from_hmonitor(desc.Monitor)
+    }
+
+    /// # From HMONITOR
+    ///
+    /// Captures the monitor with the handle, such as one returned by MonitorFromWindow.
+    pub fn from_hmonitor(monitor: HMONITOR) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
+        let item = unsafe { interop.CreateForMonitor::<GraphicsCaptureItem>(monitor)? };
+
+        Self::from_item(item)
+    }
+
+    /// # From Item
+    ///
+    /// Captures the item, such as one picked by the user with a GraphicsCapturePicker.
+    pub fn from_item(item: GraphicsCaptureItem) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        if !Self::is_supported() {
+            return Err("Windows.Graphics.Capture is not supported on this system".into());
+        }
+
+        unsafe {
+            let (device, device_context) = Monitor::create_device(None)?;
+
+            let dxgi_device: IDXGIDevice = device.cast()?;
+            let direct3d_device: IDirect3DDevice =
+                CreateDirect3D11DeviceFromDXGIDevice(&dxgi_device)?.cast()?;
+
+            let (tx, rx) = mpsc::channel(1);
+            let (events_tx, events_rx) = mpsc::channel(16);
+
+            Ok(Arc::new(GraphicsCapture {
+                name: item.DisplayName()?.to_string(),
+                item,
+                direct3d_device,
+                device,
+                device_context,
+                staging: std::sync::Mutex::new(None),
+                receiver: Arc::new(Mutex::new(rx)),
+                sender: tx,
+                is_capturing: Arc::new(Mutex::new(false)),
+                stop_requested: Notify::new(),
+                state: std::sync::Mutex::new(CaptureState::Idle),
+                stats: StatsCounters::default(),
+                fps: FpsCounter::new(),
+                buffer_pool: BufferPool::new(DEFAULT_BUFFER_POOL_SIZE),
+                events_receiver: Arc::new(Mutex::new(events_rx)),
+                events_sender: events_tx,
+            }))
+        }
+    }
+
+    /// # Stats
+    ///
+    /// The number of frames captured and sent through the receiver since the capture was created.
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            queued: queued(&self.sender),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// # Current FPS
+    ///
+    /// The rate frames are sent at, averaged over about the last second. 0 while not capturing and until two frames were sent.
+    ///
+    /// Windows.Graphics.Capture only produces frames when the item changes, a still window sends no frames.
+    pub fn current_fps(&self) -> f64 {
+        self.fps.fps()
+    }
+
+    /// # State
+    ///
+    /// Whether the capture is running, stopping, or how its last capture ended.
+    pub fn state(&self) -> CaptureState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Whether the capture loop is running, this includes while it is stopping.
+    pub fn is_running(&self) -> bool {
+        self.state().is_running()
+    }
+
+    /// # Events
+    ///
+    /// The receiver for capture events, such as the capture starting, stopping or the size of the item changing.
+    ///
+    /// Events are dropped when the receiver is not read and already holds 16 events.
+    pub fn events(&self) -> Arc<Mutex<Receiver<CaptureEvent>>> {
+        self.events_receiver.clone()
+    }
+
+    /// # Set Buffer Pool Size
+    ///
+    /// Sets how many frame buffers are kept for reuse, 0 allocates a new buffer for every frame.
+    pub fn set_buffer_pool_size(&self, size: usize) {
+        self.buffer_pool.set_max_buffers(size);
+    }
+
+    // sends an event without waiting, events are dropped when no one reads them
+    fn send_event(&self, event: CaptureEvent) {
+        let _ = self.events_sender.try_send(event);
+    }
+
+    // creates the frame pool and session and sends frames until capturing is stopped or the item is closed
+    async fn capture_loop(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut pool_size = self.item.Size()?;
+
+        //free threaded so frames arrive without a dispatcher queue on the calling thread
+        let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
+            &self.direct3d_device,
+            DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            FRAME_POOL_BUFFERS,
+            pool_size,
+        )?;
+        let session = frame_pool.CreateCaptureSession(&self.item)?;
+
+        let frame_arrived = Arc::new(Notify::new());
+        let item_closed = Arc::new(AtomicBool::new(false));
+
+        let arrived = frame_arrived.clone();
+        let arrived_token = frame_pool.FrameArrived(&TypedEventHandler::<
+            Direct3D11CaptureFramePool,
+            IInspectable,
+        >::new(move |_, _| {
+            arrived.notify_one();
+            Ok(())
+        }))?;
+
+        let (closed, closed_arrived) = (item_closed.clone(), frame_arrived.clone());
+        let closed_token = self.item.Closed(&TypedEventHandler::<
+            GraphicsCaptureItem,
+            IInspectable,
+        >::new(move |_, _| {
+            closed.store(true, Ordering::Relaxed);
+            closed_arrived.notify_one();
+            Ok(())
+        }))?;
+
+        session.StartCapture()?;
+
+        let result = self
+            .send_frames(&frame_pool, &mut pool_size, &frame_arrived, &item_closed)
+            .await;
+
+        //stop the capture however the loop ended, the item can be captured again
+        let _ = self.item.RemoveClosed(closed_token);
+        let _ = frame_pool.RemoveFrameArrived(arrived_token);
+        let _ = session.Close();
+        let _ = frame_pool.Close();
+
+        result
+    }
+
+    async fn send_frames(
+        &self,
+        frame_pool: &Direct3D11CaptureFramePool,
+        pool_size: &mut SizeInt32,
+        frame_arrived: &Notify,
+        item_closed: &AtomicBool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        //the size of the last frame sent
+        let mut last_size: Option<Dimensions> = None;
+
+        loop {
+            tokio::select! {
+                _ = frame_arrived.notified() => {}
+                _ = self.stop_requested.notified() => {}
+                _ = tokio::time::sleep(FRAME_TIMEOUT) => {}
+            }
+
+            if !*self.is_capturing.lock().await {
+                break;
+            }
+
+            if item_closed.load(Ordering::Relaxed) {
+                return Err("the captured item was closed".into());
+            }
+
+            //only the newest frame is sent, the frames before it are dropped
+            let mut newest = None;
+            while let Ok(frame) = frame_pool.TryGetNextFrame() {
+                if newest.replace(frame).is_some() {
+                    self.stats.add_dropped(1);
+                }
+            }
+
+            let Some(captured) = newest else {
+                continue;
+            };
+
+            self.stats.add_captured(1);
+
+            //the frame pool keeps the size it was created with, it is recreated for frames of the new size
+            let content_size = captured.ContentSize()?;
+            if content_size != *pool_size {
+                *pool_size = content_size;
+                frame_pool.Recreate(
+                    &self.direct3d_device,
+                    DirectXPixelFormat::B8G8R8A8UIntNormalized,
+                    FRAME_POOL_BUFFERS,
+                    content_size,
+                )?;
+            }
+
+            let mut frame = unsafe { self.read_frame(&captured)? };
+            frame.sequence = self.stats.next_sequence(1);
+
+            //give the surface back to the frame pool before waiting on the receiver
+            drop(captured);
+
+            let size = Dimensions {
+                width: frame.width,
+                height: frame.height,
+            };
+
+            if last_size.as_ref().is_some_and(|last| *last != size) {
+                frame.dimensions_changed = true;
+                self.send_event(CaptureEvent::DimensionsChanged { size });
+            }
+
+            last_size = Some(size);
+
+            //a stop request ends the wait so the loop is never stuck on a full channel
+            let permit = tokio::select! {
+                permit = self.sender.reserve() => permit,
+                _ = self.stop_requested.notified() => {
+                    self.stats.add_dropped(1);
+                    break;
+                }
+            };
+
+            match permit {
+                Ok(permit) => {
+                    permit.send(frame);
+                    self.stats.add_delivered(1);
+                    self.fps.record();
+                }
+                Err(e) => {
+                    self.stats.add_dropped(1);
+                    return Err(format!("Failed to send frame: {}", e).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // copies the captured surface to the CPU as a BGRA frame without row padding
+    unsafe fn read_frame(
+        &self,
+        captured: &Direct3D11CaptureFrame,
+    ) -> Result<Frame, windows::core::Error> {
+        unsafe {
+            let access: IDirect3DDxgiInterfaceAccess = captured.Surface()?.cast()?;
+            let texture: ID3D11Texture2D = access.GetInterface()?;
+
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            texture.GetDesc(&mut desc);
+
+            //a frame captured just before a resize is smaller than the texture it is in
+            let content_size = captured.ContentSize()?;
+            let width = (content_size.Width.max(0) as u32).min(desc.Width);
+            let height = (content_size.Height.max(0) as u32).min(desc.Height);
+
+            let staging = self.staging_texture(&desc)?;
+            self.device_context.CopyResource(&staging, &texture);
+
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            self.device_context
+                .Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+
+            let row_bytes = width as usize * 4;
+            let mut data = self.buffer_pool.take(row_bytes * height as usize);
+
+            for y in 0..height as usize {
+                let row = std::slice::from_raw_parts(
+                    (mapped.pData as *const u8).add(y * mapped.RowPitch as usize),
+                    row_bytes,
+                );
+                data.extend_from_slice(row);
+            }
+
+            self.device_context.Unmap(&staging, 0);
+
+            Ok(Frame::new(
+                width,
+                height,
+                row_bytes as u32,
+                PixelFormat::BGRA,
+                data,
+            ))
+        }
+    }
+
+    // the staging texture for surfaces of the description, created again when the size changed
+    fn staging_texture(
+        &self,
+        desc: &D3D11_TEXTURE2D_DESC,
+    ) -> Result<ID3D11Texture2D, windows::core::Error> {
+        let mut staging = self.staging.lock().unwrap();
+
+        if let Some(texture) = staging.as_ref() {
+            let mut staging_desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe { texture.GetDesc(&mut staging_desc) };
+
+            if staging_desc.Width == desc.Width && staging_desc.Height == desc.Height {
+                return Ok(texture.clone());
+            }
+        }
+
+        let size = Dimensions {
+            width: desc.Width,
+            height: desc.Height,
+        };
+        let texture =
+            Monitor::create_staging_texture(&self.device, &size, DXGI_FORMAT_B8G8R8A8_UNORM)?;
+
+        *staging = Some(texture.clone());
+
+        Ok(texture)
+    }
+}
+
+impl ICapture for GraphicsCapture {
+    type CaptureOutput = Frame;
+
+    /// # Get Dimensions
+    ///
+    /// The current size of the captured window or monitor.
+    fn get_dimensions(&self) -> Result<Dimensions, Box<dyn std::error::Error>> {
+        let size = self.item.Size()?;
+
+        Ok(Dimensions {
+            width: size.Width.max(0) as u32,
+            height: size.Height.max(0) as u32,
+        })
+    }
+
+    /// # Stop Capturing
+    ///
+    /// Stops the capture, the frame being sent is dropped.
+    fn stop_capturing(
+        self: Arc<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
+        Box::pin(async move {
+            let mut capturing = self.is_capturing.lock().await;
+
+            if !*capturing {
+                return Err("already stopped.".into());
+            }
+
+            *capturing = false;
+            *self.state.lock().unwrap() = CaptureState::Stopping;
+            self.stop_requested.notify_one();
+
+            Ok(())
+        })
+    }
+
+    /// # Start Capturing
+    ///
+    /// Starts the capture and sends frames through the receiver until stop_capturing is called, see Monitor::start_capturing.
+    ///
+    /// spawn_capturing runs this on its own task and returns a handle instead, it is the recommended way to capture.
+    fn start_capturing(
+        self: Arc<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
+        Box::pin(async move {
+            {
+                let mut capturing = self.is_capturing.lock().await;
+
+                if *capturing {
+                    return Err("already capturing".into());
+                }
+
+                *capturing = true;
+            }
+
+            let guard = CaptureGuard::new(&self.is_capturing, Some(&self.state));
+
+            *self.state.lock().unwrap() = CaptureState::Running;
+            self.fps.reset();
+            self.send_event(CaptureEvent::Started);
+
+            let result = self.capture_loop().await;
+
+            //sent however the loop ended, even when the frame receiver was dropped
+            let error = result.as_ref().err().map(|e| e.as_ref() as _);
+            for event in CaptureEvent::ended(error) {
+                self.send_event(event);
+            }
+
+            guard.finish(&result).await;
+            self.fps.reset();
+
+            result.map_err(|e| e as Box<dyn std::error::Error>)
+        })
+    }
+
+    /// # Clone Receiver
+    ///
+    /// A reference to the receiver frames are sent through.
+    fn clone_receiver(&self) -> Arc<Mutex<Receiver<Self::CaptureOutput>>> {
+        self.receiver.clone()
+    }
+}
+
+// the D3D11 device is free threaded and the immediate context is multithread protected (see Monitor::create_device)
+// the staging texture is only used behind its lock and the WinRT objects are agile
+unsafe impl Send for GraphicsCapture {}
+
+unsafe impl Sync for GraphicsCapture {}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+#[cfg(feature = "monitor")]
+use std::{collections::VecDeque, sync::Mutex};
+
+/// # Latency Stats
+///
+/// How long the last frames took to get from being acquired to being sent, see Monitor::latency_stats.
+///
+/// Each stage is summarized on its own, the stages add up to the total except for the time a frame waits in a staging texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// The number of frames the stats are taken over.
+    pub frames: usize,
+
+    /// Copying the acquired image to a staging texture, 0 when the image is read straight from system memory.
+    pub copy: LatencySummary,
+
+    /// Mapping the image to the CPU, this includes waiting for the GPU to finish the copy.
+    pub map: LatencySummary,
+
+    /// Reading the mapped image into the frame, converting and rotating it.
+    pub read: LatencySummary,
+
+    /// Waiting for room in the channel and sending the frame.
+    pub send: LatencySummary,
+
+    /// From the frame being acquired to it being sent.
+    pub total: LatencySummary,
+}
+
+/// # Latency Summary
+///
+/// The average, 95th percentile and longest time of one stage over the last frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    /// The mean time of the stage over the frames the stats are taken over, the last 120 for Monitor::latency_stats.
+    pub average: Duration,
+
+    /// The time 95 percent of those frames took at most, the slowest 1 in 20 frames took longer.
+    pub p95: Duration,
+
+    /// The longest time of the stage over those frames.
+    pub max: Duration,
+}
+
+#[cfg(feature = "monitor")]
+impl LatencySummary {
+    // summarizes the samples, they are sorted in place
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        samples.sort_unstable();
+
+        let total: Duration = samples.iter().sum();
+        let p95 = (samples.len() * 95).div_ceil(100).max(1) - 1;
+
+        LatencySummary {
+            average: total / samples.len() as u32,
+            p95: samples[p95],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+// how long each stage of getting one frame to the receiver took
+#[cfg(feature = "monitor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FrameLatency {
+    pub(crate) copy: Duration,
+    pub(crate) map: Duration,
+    pub(crate) read: Duration,
+    pub(crate) send: Duration,
+    pub(crate) total: Duration,
+}
+
+// keeps the latency of the last frames, the oldest is forgotten once it is full
+// the lock is only held to push a sample, so recording every frame stays cheap
+#[cfg(feature = "monitor")]
+#[derive(Debug)]
+pub(crate) struct LatencyRecorder {
+    samples: Mutex<VecDeque<FrameLatency>>,
+    capacity: usize,
+}
+
+#[cfg(feature = "monitor")]
+impl LatencyRecorder {
+    pub(crate) fn new(capacity: usize) -> Self {
+        LatencyRecorder {
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub(crate) fn record(&self, latency: FrameLatency) {
+        let mut samples = self.samples.lock().unwrap();
+
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+
+        samples.push_back(latency);
+    }
+
+    pub(crate) fn stats(&self) -> LatencyStats {
+        //copy the samples out so the capture is not held up while they are sorted
+        let samples: Vec<FrameLatency> = self.samples.lock().unwrap().iter().copied().collect();
+
+        let summarize = |stage: fn(&FrameLatency) -> Duration| {
+            let mut durations: Vec<Duration> = samples.iter().map(stage).collect();
+            LatencySummary::from_samples(&mut durations)
+        };
+
+        LatencyStats {
+            frames: samples.len(),
+            copy: summarize(|latency| latency.copy),
+            map: summarize(|latency| latency.map),
+            read: summarize(|latency| latency.read),
+            send: summarize(|latency| latency.send),
+            total: summarize(|latency| latency.total),
+        }
+    }
+}
@@ -1,20 +1,45 @@
+use crate::devices::blocking::run_blocking;
+use crate::devices::capture_event::{AccessLostReason, CaptureEvent};
+use crate::devices::capture_frames_error::CaptureFramesError;
+use crate::devices::capture_state::{CaptureGuard, CaptureState};
+use crate::devices::capture_stats::{CaptureStats, StatsCounters};
+use crate::devices::color_info::ColorInfo;
+use crate::devices::delivery_policy::{
+    BROADCAST_CAPACITY, DEFAULT_CHANNEL_CAPACITY, Delivery, DeliveryPolicy, check_channel_capacity,
+    queued, take_receiver, try_deliver,
+};
+use crate::devices::fps_counter::FpsCounter;
+use crate::devices::latency_stats::{FrameLatency, LatencyRecorder, LatencyStats};
 use crate::devices::monitor_info::MonitorInfo;
+use crate::devices::{
+    friendly_monitor_names, list_adapters, monitor_device_name, sdr_white_levels,
+};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::sync::{Mutex, mpsc};
-use windows::Win32::Foundation::RECT;
+use tokio::sync::{Mutex, Notify, broadcast, mpsc, watch};
+use tokio::time::Instant;
+use windows::Win32::Foundation::{HWND, RECT};
 use windows::Win32::Graphics::Direct3D11::{
-    D3D11_BIND_FLAG, D3D11_CPU_ACCESS_READ, D3D11_MAP_READ, D3D11_MAPPED_SUBRESOURCE,
+    D3D11_BIND_FLAG, D3D11_BOX, D3D11_CPU_ACCESS_READ, D3D11_MAP_READ, D3D11_MAPPED_SUBRESOURCE,
     D3D11_RESOURCE_MISC_FLAG, D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING, ID3D11DeviceContext,
-    ID3D11Texture2D,
+    ID3D11Multithread, ID3D11Texture2D,
 };
-use windows::Win32::Graphics::Dxgi::Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_SAMPLE_DESC};
-use windows::Win32::Graphics::Dxgi::{DXGI_OUTDUPL_MOVE_RECT, IDXGIDevice, IDXGIOutput1};
+use windows::Win32::Graphics::Dwm::{DWMWA_EXTENDED_FRAME_BOUNDS, DwmGetWindowAttribute};
+use windows::Win32::Graphics::Dxgi::Common::{
+    DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_MODE_ROTATION,
+    DXGI_MODE_ROTATION_IDENTITY, DXGI_MODE_ROTATION_UNSPECIFIED, DXGI_SAMPLE_DESC,
+};
+use windows::Win32::Graphics::Dxgi::{DXGI_OUTDUPL_MOVE_RECT, IDXGIOutput1};
+use windows::Win32::Graphics::Gdi::{HMONITOR, MONITOR_DEFAULTTONEAREST, MonitorFromWindow};
+use windows::Win32::UI::WindowsAndMessaging::{GetWindowRect, IsIconic, IsWindow};
 use windows::Win32::{
     Foundation::HMODULE,
     Graphics::{
-        Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+        Direct3D::{D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_UNKNOWN},
         Direct3D11::{
             D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_SDK_VERSION, D3D11CreateDevice, ID3D11Device,
         },
@@ -23,32 +48,437 @@ use windows::Win32::{
 };
 use windows::core::Interface;
 
-use crate::devices::{Dimensions, get_monitor_count};
-use crate::devices::monitor_frame::MonitorFrame;
-use crate::i_capture::ICapture;
+use crate::buffer_pool::BufferPool;
+use crate::devices::Dimensions;
+use crate::devices::monitor_frame::{
+    MonitorFrame, metadata_bytes, metadata_count, reserve_metadata,
+};
+use crate::devices::monitor_scaler::MonitorScaler;
+use crate::devices::pointer::{PointerPosition, PointerShape, PointerUpdate};
+use crate::devices::rotation::{rotate_pixels_into, rotate_rect, rotated_size};
+use crate::devices::shared_texture::{SharedTexture, TexturePool};
+use crate::frame::{
+    Frame, GrayWeights, MoveRect, PixelConversion, PixelFormat, Rect, ScaleFilter, coalesce_rects,
+    convert_pixels, convert_rows, crop_frame_into, downscale_frame, pack_rows, qpc_now,
+    qpc_to_duration,
+};
+use crate::frame_diff::{ChangeFilter, DuplicateFilter, FrameHasher};
+use crate::i_capture::{CaptureFuture, CaptureHandle, ICapture};
 
 /// # Monitor
 ///
 /// Reprents a monitor on your device, you can simply create one by using the from_monitor function
+///
+/// ## Thread Safety
+///
+/// A Monitor can be shared between tasks and threads, any of its methods may be called while it is capturing.
+/// Its D3D11 immediate context is multithread protected and the staging textures are only used while holding the lock of the output, so calls never race on the GPU.
 pub struct Monitor {
     /// The IDXGIOutputDuplication interface accesses and manipulates the duplicated desktop image.
-    duplication_output: IDXGIOutputDuplication,
+    ///
+    /// None while access to the desktop is lost, see duplication.
+    duplication_output: std::sync::Mutex<Option<IDXGIOutputDuplication>>,
 
-    pub receiver: Arc<Mutex<Receiver<Vec<u8>>>>,
-    sender: Sender<Vec<u8>>,
+    // the output that is duplicated, used to duplicate it again after access is lost
+    monitor_output: IDXGIOutput1,
+
+    // capture events, such as losing access to the desktop
+    events_receiver: Arc<Mutex<Receiver<CaptureEvent>>>,
+    events_sender: Sender<CaptureEvent>,
+
+    /// The frame receiver shared behind a mutex, see take_receiver to own it instead.
+    pub receiver: Arc<Mutex<Receiver<Frame>>>,
+    sender: Sender<Frame>,
+
+    // sends every frame to the subscribers, see subscribe
+    broadcast_sender: broadcast::Sender<Arc<Frame>>,
+
+    // holds the latest frame, see watch_latest
+    latest_sender: watch::Sender<Option<Arc<Frame>>>,
+
+    // whether frames are sent through the receiver, see set_receiver_output
+    receiver_output: AtomicBool,
 
     is_sending: Arc<Mutex<bool>>,
 
+    // whether a frame was acquired from the duplication and not yet released
+    frame_acquired: AtomicBool,
+
+    // wakes the capture loop when it is waiting to send a frame and stop is requested
+    stop_requested: Notify,
+
+    // true while the capture loop is running, used to wait for it to stop
+    running: watch::Sender<bool>,
+
+    // whether the capture is running, stopping, or how it ended
+    state: std::sync::Mutex<CaptureState>,
+
+    // the most frames sent each second, 0 when not limited
+    max_fps: AtomicU32,
+
+    // wait for the vertical blank of the output before acquiring each frame, never set along with max_fps
+    vsync: AtomicBool,
+
+    // the time between the last two frames sent in nanoseconds, 0 until two frames were sent
+    frame_interval: AtomicU64,
+
+    // the time between snapshots in nanoseconds, see start_snapshots
+    snapshot_interval: AtomicU64,
+
+    // the QueryPerformanceCounter ticks when the capture was last started
+    capture_start: AtomicI64,
+
+    // release frames without sending them when nothing on screen changed
+    skip_unchanged: AtomicBool,
+
+    // what is done with a new frame while the receiver holds one it has not read, see set_delivery_policy
+    delivery_policy: std::sync::Mutex<DeliveryPolicy>,
+
+    // hold frames back until the duplication presented an image, see set_wait_for_content
+    wait_for_content: AtomicBool,
+
+    // whether the duplication presented an image since it was created, the frames before that are empty
+    content_presented: AtomicBool,
+
+    // holds back frames that barely changed, see set_min_change_ratio
+    change_filter: std::sync::Mutex<ChangeFilter>,
+
+    // holds back frames identical to the one before, see set_duplicate_suppression
+    duplicate_filter: std::sync::Mutex<DuplicateFilter>,
+
+    // the window the frames are cropped to, see follow_window
+    followed_window: std::sync::Mutex<Option<FollowedWindow>>,
+
+    // the most dirty rects a frame is sent with after coalescing them, 0 when they are not coalesced and usize::MAX when there is no limit
+    max_dirty_rects: AtomicUsize,
+
+    // the frames captured, sent, dropped and skipped
+    stats: StatsCounters,
+
+    // how long the last frames took from being acquired to being sent
+    latency: LatencyRecorder,
+
+    // the rate frames are sent at while capturing
+    fps: FpsCounter,
+
+    // reuses the buffers of frames that have been dropped
+    buffer_pool: BufferPool,
+
+    // send frames as shared textures instead of reading them back, see set_texture_output
+    texture_output: AtomicBool,
+
+    // reuses the shared textures that have been dropped
+    texture_pool: TexturePool,
+
+    /// The receiver for shared textures, frames are sent here instead of the receiver while texture output is on.
+    pub texture_receiver: Arc<Mutex<Receiver<SharedTexture>>>,
+    texture_sender: Sender<SharedTexture>,
+
+    // makes thumbnails of the frames that are sent, see set_thumbnails
+    thumbnails: std::sync::Mutex<Option<Thumbnails>>,
+
+    /// The receiver for thumbnails, small copies of the frames sent while thumbnails are on (see set_thumbnails).
+    pub thumbnail_receiver: Arc<Mutex<Receiver<Frame>>>,
+    thumbnail_sender: Sender<Frame>,
+
     frame: Arc<Mutex<MonitorFrame>>,
 
-    device_context: ID3D11DeviceContext,
+    /// The receiver for pointer updates, can be used to draw the pointer yourself.
+    pub pointer_receiver: Arc<Mutex<Receiver<PointerUpdate>>>,
+    pointer_sender: Sender<PointerUpdate>,
 
-    //texture that is used to copy from the GPU to CPU, expensive, so made on init
-    staging_texture: ID3D11Texture2D,
+    // the last known position and shape of the pointer
+    pointer: Arc<Mutex<PointerUpdate>>,
 
-    pub desktop_size: Dimensions,
+    device: ID3D11Device,
+
+    device_context: ID3D11DeviceContext,
+
+    // the staging texture, scaler and size of the frames that are sent, replaced when the output size changes
+    output: std::sync::Mutex<MonitorOutput>,
 
     pub name: String,
+
+    /// The name of the monitor as shown to users, such as "DELL U2720Q", the same as name when Windows does not know one.
+    pub friendly_name: String,
+}
+
+// a window followed by the capture, see Monitor::follow_window
+struct FollowedWindow {
+    hwnd: HWND,
+
+    // the monitor the window was last on, None until it was looked up
+    monitor: Option<HMONITOR>,
+
+    // the size of the last frame cropped to the window
+    size: Option<Dimensions>,
+}
+
+// thumbnails of the frames sent while capturing, see Monitor::set_thumbnails
+struct Thumbnails {
+    // the size the thumbnails are scaled to fit in
+    size: Dimensions,
+
+    // the shortest time between thumbnails
+    interval: Duration,
+
+    filter: ScaleFilter,
+
+    // when the last thumbnail was made, None until the first one
+    last_made: Option<Instant>,
+}
+
+// the number of frame buffers kept for reuse by default, enough for a frame in the channel, one being read and one being captured
+const DEFAULT_BUFFER_POOL_SIZE: usize = 3;
+
+// how long the capture loop waits for the desktop to present a frame before checking if it should stop
+const ACQUIRE_TIMEOUT_MS: u32 = 500;
+
+// the number of frames the latency stats are taken over
+const LATENCY_SAMPLES: usize = 120;
+
+// the time between snapshots until one is given, see start_snapshots
+const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+// the shortest time between snapshots
+const MIN_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(1);
+
+// how long to wait between attempts to duplicate the output again after access was lost
+const ACCESS_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+// the number of staging textures by default, two lets the GPU copy one frame while the CPU reads the other
+const DEFAULT_STAGING_TEXTURES: usize = 2;
+
+// above this part of the screen changing the whole texture is copied, many small copies can be slower than one large copy
+const DEFAULT_PARTIAL_COPY_THRESHOLD: f32 = 0.6;
+
+// everything needed to get an acquired image from the GPU to the CPU at the output size
+struct MonitorOutput {
+    //textures that are used to copy from the GPU to CPU, expensive, so made on init
+    //copies rotate through them so the GPU copy of one frame overlaps reading the previous one
+    staging_textures: Vec<ID3D11Texture2D>,
+
+    // the staging texture the next image is copied to
+    next_staging: usize,
+
+    // images copied to a staging texture that have not been read yet, oldest first
+    pending: VecDeque<PendingCopy>,
+
+    // the areas of each staging texture that changed on screen since it was last copied to, None when the whole texture must be copied
+    stale_regions: Vec<Option<Vec<Rect>>>,
+
+    // the largest part of the screen (0 to 1) that may change for only the changed regions to be copied
+    partial_copy_threshold: f32,
+
+    // the last frame read back, partial reads only update its changed rows. None when the next read must copy everything
+    last_frame: Option<Frame>,
+
+    // scales the acquired image before copying, None when sending frames at the monitor size
+    scaler: Option<MonitorScaler>,
+
+    // the size of the staging texture, this is the unrotated size of the frames that are sent
+    size: Dimensions,
+
+    // the size of the unrotated desktop image acquired from the duplication, changes with the display mode
+    surface_size: Dimensions,
+
+    // the rotation of the monitor, frames are rotated by it to match what is displayed
+    rotation: DXGI_MODE_ROTATION,
+
+    // where the monitor is on the virtual desktop
+    desktop_coordinates: Rect,
+
+    // the size of the frames changed, the next frame read is marked with it
+    dimensions_changed: bool,
+
+    // the format of the duplicated image, R16G16B16A16_FLOAT while the monitor shows HDR
+    surface_format: DXGI_FORMAT,
+
+    // the duplicated image is in system memory and can be mapped directly, common with WARP and remote sessions
+    surface_in_system_memory: bool,
+
+    // copy the rows without the padding the GPU adds to each row
+    strip_padding: bool,
+
+    // the pixel format SDR frames are converted to before they are sent
+    output_format: PixelFormat,
+
+    // set the alpha of every pixel of SDR frames to 255
+    opaque_alpha: bool,
+
+    // the weights of Gray8 frames
+    gray_weights: GrayWeights,
+}
+
+impl MonitorOutput {
+    // the format of the staging textures, the scaler always renders BGRA
+    fn staging_format(&self) -> DXGI_FORMAT {
+        if self.scaler.is_some() {
+            DXGI_FORMAT_B8G8R8A8_UNORM
+        } else {
+            self.surface_format
+        }
+    }
+
+    // the pixel format of the frames read from the staging textures
+    fn pixel_format(&self) -> PixelFormat {
+        if self.staging_format() == DXGI_FORMAT_R16G16B16A16_FLOAT {
+            PixelFormat::RGBA16F
+        } else {
+            PixelFormat::BGRA
+        }
+    }
+
+    // the pixel format of the frames that are sent, only BGRA is converted to the output format
+    fn frame_format(&self) -> PixelFormat {
+        match self.pixel_format() {
+            PixelFormat::BGRA => self.output_format,
+            format => format,
+        }
+    }
+
+    // the size of the frames that are sent, rotated to match what is displayed
+    fn frame_size(&self) -> Dimensions {
+        rotated_size(&self.size, self.rotation)
+    }
+
+    // forgets every copied image, the next copies and reads are of the whole texture
+    fn reset_staging(&mut self) {
+        self.pending.clear();
+        self.next_staging = 0;
+        self.stale_regions = vec![None; self.staging_textures.len()];
+        self.last_frame = None;
+    }
+}
+
+// whether the rects cover no more than the threshold of an image of the given size
+fn within_threshold(rects: &[Rect], size: &Dimensions, threshold: f32) -> bool {
+    let area: u64 = rects
+        .iter()
+        .map(|rect| rect.width() as u64 * rect.height() as u64)
+        .sum();
+
+    (area as f64) <= (size.width as f64 * size.height as f64) * threshold as f64
+}
+
+// the largest size of the same aspect ratio as the frame that fits in the bounds, frames smaller than the bounds keep their size
+fn fit_within(width: u32, height: u32, bounds: &Dimensions) -> Dimensions {
+    let scale = (bounds.width as f64 / width.max(1) as f64)
+        .min(bounds.height as f64 / height.max(1) as f64)
+        .min(1.0);
+
+    Dimensions {
+        width: ((width as f64 * scale).round() as u32).clamp(1, width.max(1)),
+        height: ((height as f64 * scale).round() as u32).clamp(1, height.max(1)),
+    }
+}
+
+// keeps a rect inside of an image of the given size
+fn clamp_rect(rect: &Rect, size: &Dimensions) -> Rect {
+    let (width, height) = (size.width as i32, size.height as i32);
+
+    Rect {
+        left: rect.left.clamp(0, width),
+        top: rect.top.clamp(0, height),
+        right: rect.right.clamp(0, width),
+        bottom: rect.bottom.clamp(0, height),
+    }
+}
+
+// a staging texture mapped to the CPU, it is unmapped when dropped so no return (or panic) can leave it mapped
+struct MappedStaging<'a> {
+    device_context: &'a ID3D11DeviceContext,
+    texture: &'a ID3D11Texture2D,
+    resource: D3D11_MAPPED_SUBRESOURCE,
+}
+
+impl<'a> MappedStaging<'a> {
+    unsafe fn map(
+        device_context: &'a ID3D11DeviceContext,
+        texture: &'a ID3D11Texture2D,
+    ) -> Result<Self, windows::core::Error> {
+        let mut resource = D3D11_MAPPED_SUBRESOURCE::default();
+
+        unsafe {
+            device_context.Map(texture, 0, D3D11_MAP_READ, 0, Some(&mut resource))?;
+        }
+
+        Ok(MappedStaging {
+            device_context,
+            texture,
+            resource,
+        })
+    }
+}
+
+impl Drop for MappedStaging<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device_context.Unmap(self.texture, 0);
+        }
+    }
+}
+
+// the desktop image of a duplication in system memory, unmapped when dropped like MappedStaging
+struct MappedDesktop<'a> {
+    duplication: &'a IDXGIOutputDuplication,
+    rect: DXGI_MAPPED_RECT,
+}
+
+impl<'a> MappedDesktop<'a> {
+    unsafe fn map(duplication: &'a IDXGIOutputDuplication) -> Result<Self, windows::core::Error> {
+        let rect = unsafe { duplication.MapDesktopSurface()? };
+
+        Ok(MappedDesktop { duplication, rect })
+    }
+}
+
+impl Drop for MappedDesktop<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.duplication.UnMapDesktopSurface();
+        }
+    }
+}
+
+// an image that was copied to a staging texture, along with the information of the frame it came from
+struct PendingCopy {
+    staging_index: usize,
+    timestamp: Instant,
+    dirty_rects: Vec<Rect>,
+    move_rects: Vec<MoveRect>,
+    sequence: u64,
+
+    // how long the image took to copy to the staging texture
+    copy_time: Duration,
+
+    accumulated_frames: u32,
+    last_present_time: i64,
+    protected_content: bool,
+    rects_coalesced: bool,
+    pointer: Option<PointerPosition>,
+
+    // the areas that changed since the previous frame, in the coordinates of the staging texture
+    changed_regions: Vec<Rect>,
+}
+
+impl PendingCopy {
+    // the frame read from the staging texture, with the information of the frame it was copied from
+    fn into_frame(self, (mut frame, mut latency): (Frame, FrameLatency)) -> (Frame, FrameLatency) {
+        latency.copy = self.copy_time;
+
+        frame.timestamp = self.timestamp.into_std();
+        frame.dirty_rects = self.dirty_rects;
+        frame.move_rects = self.move_rects;
+        frame.sequence = self.sequence;
+        frame.accumulated_frames = self.accumulated_frames;
+        frame.last_present_time = self.last_present_time;
+        frame.protected_content = self.protected_content;
+        frame.rects_coalesced = self.rects_coalesced;
+        frame.pointer = self.pointer;
+
+        (frame, latency)
+    }
 }
 
 impl Monitor {
@@ -57,241 +487,2125 @@ impl Monitor {
     /// Create device information for a given monitor of your system.
     ///
     /// Provides a Monitor struct that has the ability to duplicate the data and do other manipulation.
-    pub unsafe fn from_monitor_info(
+    ///
+    /// The monitor is opened by its adapter and output index (see from_adapter_output), so it is the same output on systems with several adapters.
+    /// Fails when the output at those indices is no longer the monitor of the info, such as after monitors were plugged in or out since it was enumerated.
+    pub fn from_monitor_info(
         monitor_info: MonitorInfo,
     ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
-        let index = monitor_info.index;
+        let monitor =
+            Self::from_adapter_output(monitor_info.adapter_index, monitor_info.output_index)?;
+
+        if monitor.name != monitor_info.name {
+            return Err(format!(
+                "monitor not found: adapter {} output {} is now {} instead of {}, enumerate the monitors again",
+                monitor_info.adapter_index, monitor_info.output_index, monitor.name, monitor_info.name
+            )
+            .into());
+        }
+
+        Ok(monitor)
+    }
+
+    /// # From Name
+    ///
+    /// Create a Monitor from the device name of the monitor, for example `\\.\DISPLAY2`.
+    ///
+    /// The name is compared exactly unless partial_match is true, in which case any monitor whose name contains the given name (ignoring case) is used.
+    ///
+    /// If no monitor matches an error listing the available monitor names is returned.
+    pub fn from_name(
+        name: &str,
+        partial_match: bool,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let monitors = Self::enumerate()?;
+
+        let lower_name = name.to_lowercase();
+        let found = monitors.iter().find(|info| {
+            if partial_match {
+                info.name.to_lowercase().contains(&lower_name)
+            } else {
+                info.name == name
+            }
+        });
+
+        match found {
+            Some(info) => Self::from_monitor(info.index),
+            None => {
+                let available: Vec<&str> = monitors.iter().map(|m| m.name.as_str()).collect();
+
+                Err(format!(
+                    "monitor not found: no monitor matched '{name}', available monitors: {available:?}"
+                )
+                .into())
+            }
+        }
+    }
+
+    /// # Enumerate
+    ///
+    /// Walks every DXGI adapter on your system and collects information about each output (monitor) attached to it.
+    ///
+    /// The index of each MonitorInfo is unique across all adapters and can be passed directly to from_monitor.
+    ///
+    /// To see which adapter each monitor belongs to use list_adapters.
+    pub fn enumerate() -> Result<Vec<MonitorInfo>, windows::core::Error> {
+        let adapters = list_adapters()?;
+
+        Ok(adapters
+            .into_iter()
+            .flat_map(|adapter| adapter.monitors)
+            .collect())
+    }
+
+    // walks all adapters and their outputs, the position in the returned vec is the monitor index used by from_monitor
+    // this must walk them in the same order as list_adapters
+    pub(crate) unsafe fn enumerate_outputs()
+    -> Result<Vec<(IDXGIAdapter1, IDXGIOutput1)>, windows::core::Error> {
+        let mut outputs = vec![];
+
+        unsafe {
+            let factory: IDXGIFactory1 = CreateDXGIFactory1()?;
 
-        unsafe { Self::from_monitor(index) }
+            let mut adapter_index = 0;
+
+            //EnumAdapters1 fails with DXGI_ERROR_NOT_FOUND once we have run out of adapters
+            while let Ok(adapter) = factory.EnumAdapters1(adapter_index) {
+                let mut output_index = 0;
+
+                while let Ok(output) = adapter.EnumOutputs(output_index) {
+                    outputs.push((adapter.clone(), output.cast::<IDXGIOutput1>()?));
+                    output_index += 1;
+                }
+
+                adapter_index += 1;
+            }
+        }
+
+        Ok(outputs)
     }
 
     /// ## From Monitor
     ///
     /// Create device information for a given monitor of your system.
     ///
+    /// The monitor index is based on all outputs of all adapters, see enumerate for the available monitors.
+    ///
     /// Provides a Monitor struct that has the ability to duplicate the data and do other manipulation.
-    pub unsafe fn from_monitor(monitor: u32) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+    ///
+    /// Desktop duplication needs no COM initialization and works from any thread. Fails when the index is out of range, or when the output can not be duplicated, such as from a service without a desktop or while too many applications duplicate it.
+    pub fn from_monitor(monitor: u32) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
         unsafe {
-            let max_monitors = get_monitor_count() as u32;
+            let mut outputs = Self::enumerate_outputs()?;
+            let max_monitors = outputs.len() as u32;
 
-            if monitor > max_monitors {
+            if monitor >= max_monitors {
                 return Err(format!(
                     "monitor index ({monitor}) fell outside of the max range of {max_monitors}"
                 )
                 .into());
             }
 
-            //choose default adapater
-            let adapter = None;
+            let (adapter, monitor_output1) = outputs.swap_remove(monitor as usize);
+
+            Self::from_output(adapter, monitor_output1)
+        }
+    }
+
+    /// # From Adapter Output
+    ///
+    /// Create a Monitor from an output of a specific GPU adapter, both indices are zero based.
+    ///
+    /// This is useful on systems with more than one GPU (such as laptops with hybrid graphics) to choose the GPU the monitor is duplicated on.
+    pub fn from_adapter_output(
+        adapter_index: u32,
+        output_index: u32,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        unsafe {
+            let factory: IDXGIFactory1 = CreateDXGIFactory1()?;
+
+            let adapter = factory.EnumAdapters1(adapter_index).map_err(|e| {
+                format!("adapter not found: no adapter at index {adapter_index} ({e})")
+            })?;
+
+            let output = adapter.EnumOutputs(output_index).map_err(|e| {
+                let description = adapter
+                    .GetDesc1()
+                    .map(|desc| {
+                        String::from_utf16_lossy(&desc.Description)
+                            .trim_end_matches('\0')
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+
+                format!(
+                    "output not found: adapter {adapter_index} ({description}) has no output at index {output_index} ({e})"
+                )
+            })?;
+
+            Self::from_output(adapter, output.cast()?)
+        }
+    }
+
+    /// # With Channel Capacity
+    ///
+    /// Sets how many frames the receiver's channel holds, at least 1. Used right after creating the monitor, such as `Monitor::from_monitor(0)?.with_channel_capacity(8)?`.
+    ///
+    /// The frame channel holds 1 frame by default. A larger channel lets a receiver that reads in bursts, such as an encoder working in batches, fall behind for a moment without the capture waiting on it or dropping frames.
+    /// Each queued frame keeps its data in memory, so the channel can hold up to capacity times the frame size (about 8 MB for each frame of a 1920x1080 BGRA monitor).
+    ///
+    /// The buffer pool grows with it so queued frames do not cost an allocation each, see set_buffer_pool_size. CaptureStats::queued reports how full the channel is.
+    ///
+    /// Fails when the capacity is 0, or when the monitor or its receiver is already shared (for example cloned or capturing).
+    pub fn with_channel_capacity(
+        mut self: Arc<Self>,
+        capacity: usize,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        check_channel_capacity(capacity)?;
+
+        let Some(monitor) = Arc::get_mut(&mut self).filter(|m| Arc::strong_count(&m.receiver) == 1)
+        else {
+            return Err(
+                "the monitor is already shared, set the channel capacity right after creating it"
+                    .into(),
+            );
+        };
+
+        let (sender, receiver) = mpsc::channel(capacity);
+        monitor.sender = sender;
+        monitor.receiver = Arc::new(Mutex::new(receiver));
+
+        //room for every queued frame, one being read and one being captured
+        monitor.buffer_pool.set_max_buffers(capacity + 2);
+
+        Ok(self)
+    }
+
+    // creates the device on the adapter and duplicates the output
+    unsafe fn from_output(
+        adapter: IDXGIAdapter1,
+        monitor_output1: IDXGIOutput1,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        unsafe {
+            //use the adapter the monitor is connected to
+            let adapter: IDXGIAdapter = adapter.cast()?;
+
+            //DuplicateOutput only accepts a device on the output's adapter, so there is nothing to fall back to, such as WARP
+            let (device, device_context) =
+                Self::create_device(Some(&adapter)).map_err(|e| {
+                    format!(
+                        "failed to create a D3D11 device on the monitor's adapter, desktop duplication needs one ({e})"
+                    )
+                })?;
+
+            let desc = monitor_output1.GetDesc()?;
+
+            let surface_size = Self::surface_size(&desc);
+
+            let name = Self::output_name(&desc);
+
+            let dup_output = Self::duplicate(&monitor_output1, &device)?;
+            let dup_desc = dup_output.GetDesc();
+
+            let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+            let (texture_tx, texture_rx) = mpsc::channel(1);
+            let (thumbnail_tx, thumbnail_rx) = mpsc::channel(1);
+
+            //pointer updates are small and should not be missed, give them some room.
+            let (pointer_tx, pointer_rx) = mpsc::channel(32);
+
+            let staging_textures = Self::create_staging_textures(
+                &device,
+                &surface_size,
+                dup_desc.ModeDesc.Format,
+                DEFAULT_STAGING_TEXTURES,
+            )?;
+
+            let (events_tx, events_rx) = mpsc::channel(16);
+
+            Ok(Arc::new(Self {
+                duplication_output: std::sync::Mutex::new(Some(dup_output)),
+                monitor_output: monitor_output1,
+                events_receiver: Arc::new(Mutex::new(events_rx)),
+                events_sender: events_tx,
+                sender: tx,
+                receiver: Arc::new(Mutex::new(rx)),
+                broadcast_sender: broadcast::channel(BROADCAST_CAPACITY).0,
+                latest_sender: watch::channel(None).0,
+                receiver_output: AtomicBool::new(true),
+                is_sending: Arc::new(Mutex::new(false)),
+                frame_acquired: AtomicBool::new(false),
+                stop_requested: Notify::new(),
+                running: watch::channel(false).0,
+                state: std::sync::Mutex::new(CaptureState::Idle),
+                max_fps: AtomicU32::new(0),
+                vsync: AtomicBool::new(false),
+                frame_interval: AtomicU64::new(0),
+                snapshot_interval: AtomicU64::new(DEFAULT_SNAPSHOT_INTERVAL.as_nanos() as u64),
+                capture_start: AtomicI64::new(0),
+                skip_unchanged: AtomicBool::new(false),
+                delivery_policy: std::sync::Mutex::new(DeliveryPolicy::default()),
+                wait_for_content: AtomicBool::new(true),
+                content_presented: AtomicBool::new(false),
+                max_dirty_rects: AtomicUsize::new(0),
+                change_filter: std::sync::Mutex::new(ChangeFilter::default()),
+                duplicate_filter: std::sync::Mutex::new(DuplicateFilter::default()),
+                followed_window: std::sync::Mutex::new(None),
+                stats: StatsCounters::default(),
+                latency: LatencyRecorder::new(LATENCY_SAMPLES),
+                fps: FpsCounter::new(),
+                buffer_pool: BufferPool::new(DEFAULT_BUFFER_POOL_SIZE),
+                texture_output: AtomicBool::new(false),
+                texture_pool: TexturePool::new(DEFAULT_BUFFER_POOL_SIZE),
+                texture_receiver: Arc::new(Mutex::new(texture_rx)),
+                texture_sender: texture_tx,
+                thumbnails: std::sync::Mutex::new(None),
+                thumbnail_receiver: Arc::new(Mutex::new(thumbnail_rx)),
+                thumbnail_sender: thumbnail_tx,
+                frame: Arc::new(Mutex::new(MonitorFrame::default())),
+                pointer_receiver: Arc::new(Mutex::new(pointer_rx)),
+                pointer_sender: pointer_tx,
+                pointer: Arc::new(Mutex::new(PointerUpdate::default())),
+                device_context,
+                output: std::sync::Mutex::new(MonitorOutput {
+                    stale_regions: vec![None; staging_textures.len()],
+                    staging_textures,
+                    next_staging: 0,
+                    pending: VecDeque::new(),
+                    partial_copy_threshold: DEFAULT_PARTIAL_COPY_THRESHOLD,
+                    last_frame: None,
+                    scaler: None,
+                    size: surface_size,
+                    surface_size,
+                    rotation: desc.Rotation,
+                    desktop_coordinates: desc.DesktopCoordinates.into(),
+                    dimensions_changed: false,
+                    surface_format: dup_desc.ModeDesc.Format,
+                    surface_in_system_memory: dup_desc.DesktopImageInSystemMemory.as_bool(),
+                    strip_padding: true,
+                    output_format: PixelFormat::BGRA,
+                    opaque_alpha: false,
+                    gray_weights: GrayWeights::default(),
+                }),
+                device,
+                friendly_name: friendly_monitor_names()
+                    .remove(&name)
+                    .unwrap_or_else(|| name.clone()),
+                name,
+            }))
+        }
+    }
+
+    // duplicates the output, HDR monitors are duplicated as 16 bit float images instead of being clipped to BGRA
+    unsafe fn duplicate(
+        output: &IDXGIOutput1,
+        device: &ID3D11Device,
+    ) -> Result<IDXGIOutputDuplication, windows::core::Error> {
+        //DuplicateOutput1 needs Windows 10 1703 and a DPI aware process, DuplicateOutput works everywhere
+        let formats = [DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_B8G8R8A8_UNORM];
+
+        unsafe {
+            match output.cast::<IDXGIOutput5>() {
+                Ok(output5) => match output5.DuplicateOutput1(device, 0, &formats) {
+                    Ok(duplication) => Ok(duplication),
+                    //access errors are the same for both, only fall back when DuplicateOutput1 is unsupported
+                    Err(e) if AccessLostReason::from_error(&e).is_some() => Err(e),
+                    Err(_) => output.DuplicateOutput(device),
+                },
+                Err(_) => output.DuplicateOutput(device),
+            }
+        }
+    }
+
+    // creates a D3D11 device, and its context, on the adapter or on the default hardware adapter when there is none
+    pub(crate) unsafe fn create_device(
+        adapter: Option<&IDXGIAdapter>,
+    ) -> Result<(ID3D11Device, ID3D11DeviceContext), windows::core::Error> {
+        //when an adapter is provided the driver type must be unknown, the hardware driver of the adapter is used.
+        let driver_type = match adapter {
+            Some(_) => D3D_DRIVER_TYPE_UNKNOWN,
+            None => D3D_DRIVER_TYPE_HARDWARE,
+        };
+
+        //add support for duplication
+        let flags = D3D11_CREATE_DEVICE_BGRA_SUPPORT;
+
+        //use default
+        let p_feature_levels = None;
+
+        //device should be unwrappable after the following function call:
+        let mut device = None;
+
+        //this is okay to be null since we are using a NON-Software type for the driver_type
+        //A handle to a DLL that implements a software rasterizer. If DriverType is D3D_DRIVER_TYPE_SOFTWARE, Software must not be NULL.
+        let module_handle = HMODULE(std::ptr::null_mut());
+
+        let mut device_context: Option<ID3D11DeviceContext> = None;
+
+        unsafe {
+            D3D11CreateDevice(
+                adapter,
+                driver_type,
+                module_handle,
+                flags,
+                p_feature_levels,
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None, //we do not need to determine the feature level at this time
+                Some(&mut device_context),
+            )?;
+        }
+
+        let (device, device_context) = (device.unwrap(), device_context.unwrap());
+
+        //the immediate context is not free threaded, once protected every call into it is serialized by D3D
+        //so the capture loop, screenshots and the scaler can never use it at the same time
+        let multithread: ID3D11Multithread = device_context.cast()?;
+        unsafe {
+            let _ = multithread.SetMultithreadProtected(true);
+        }
+
+        Ok((device, device_context))
+    }
 
-            //A hardware driver, which implements Direct3D features in hardware.
-            //This is the primary driver that you should use in your Direct3D applications because it provides the best performance.
-            let driver_type = D3D_DRIVER_TYPE_HARDWARE;
+    // creates the given number of staging textures
+    fn create_staging_textures(
+        device: &ID3D11Device,
+        device_size: &Dimensions,
+        format: DXGI_FORMAT,
+        count: usize,
+    ) -> Result<Vec<ID3D11Texture2D>, windows::core::Error> {
+        (0..count.max(1))
+            .map(|_| Self::create_staging_texture(device, device_size, format))
+            .collect()
+    }
 
-            //add support for duplication
-            let flags = D3D11_CREATE_DEVICE_BGRA_SUPPORT;
+    /// creates a texture that can be used to copy GPU based monitor data to the CPU
+    pub(crate) fn create_staging_texture(
+        device: &ID3D11Device,
+        device_size: &Dimensions,
+        format: DXGI_FORMAT,
+    ) -> Result<ID3D11Texture2D, windows::core::Error> {
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: device_size.width,
+            Height: device_size.height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: format, // must match the duplicated image, BGRA or R16G16B16A16_FLOAT for HDR
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_STAGING, // Crucial: Allows CPU reading
+            BindFlags: D3D11_BIND_FLAG(0).0 as u32, // Staging textures have no bind flags
+            CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32, // Allows us to call .Map()
+            MiscFlags: D3D11_RESOURCE_MISC_FLAG(0).0 as u32,
+        };
+
+        let mut staging_texture = None;
+        unsafe {
+            device.CreateTexture2D(&desc, None, Some(&mut staging_texture))?;
+        }
+
+        Ok(staging_texture.unwrap())
+    }
+
+    /// # Set Output Size
+    ///
+    /// Scales every frame to the given size on the GPU before it is copied to the CPU.
+    ///
+    /// Copying large monitors to the CPU is expensive, downscaling first greatly reduces the amount of data read each frame.
+    ///
+    /// Once set the receiver delivers frames of this size and get_dimensions reports it, desktop_size remains the size of the monitor.
+    ///
+    /// The size is of the displayed (rotated) frame, like desktop_size.
+    ///
+    /// Scaled frames are always BGRA, HDR images are converted by the video processor.
+    pub fn set_output_size(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if width == 0 || height == 0 {
+            return Err(format!("output size ({width}x{height}) must not be zero").into());
+        }
+
+        let mut output = self.output.lock().unwrap();
+
+        //scaling happens before rotating, so the size must be unrotated as well
+        let size = rotated_size(&Dimensions { width, height }, output.rotation);
+
+        let previous = output.frame_size();
+        unsafe { self.resize_output(&mut output, size)? };
+        self.check_dimensions(&mut output, previous);
+
+        Ok(())
+    }
+
+    // marks the next frame and sends DimensionsChanged when the size of the frames is no longer the previous size
+    fn check_dimensions(&self, output: &mut MonitorOutput, previous: Dimensions) {
+        let size = output.frame_size();
+
+        if size != previous {
+            output.dimensions_changed = true;
+            self.send_event(CaptureEvent::DimensionsChanged { size });
+        }
+    }
+
+    // scales the output to the unrotated size and recreates the staging textures for it
+    unsafe fn resize_output(
+        &self,
+        output: &mut MonitorOutput,
+        size: Dimensions,
+    ) -> Result<(), windows::core::Error> {
+        //no need to scale when the output is the size of the monitor
+        output.scaler = if size == output.surface_size {
+            None
+        } else {
+            unsafe {
+                Some(MonitorScaler::new(
+                    &self.device,
+                    &self.device_context,
+                    &output.surface_size,
+                    &size,
+                )?)
+            }
+        };
+
+        let count = output.staging_textures.len();
+        output.staging_textures =
+            Self::create_staging_textures(&self.device, &size, output.staging_format(), count)?;
+
+        output.size = size;
+
+        //anything waiting to be read was copied at the old size
+        output.reset_staging();
+
+        Ok(())
+    }
+
+    /// # Desktop Size
+    ///
+    /// The size of the monitor as displayed, frames may be scaled to a different size see set_output_size.
+    ///
+    /// This follows changes of the resolution or rotation of the monitor, see CaptureEvent::DimensionsChanged.
+    pub fn desktop_size(&self) -> Dimensions {
+        let output = self.output.lock().unwrap();
+
+        rotated_size(&output.surface_size, output.rotation)
+    }
+
+    /// Where the monitor is on the virtual desktop, monitors left of or above the primary monitor have negative coordinates.
+    pub fn desktop_coordinates(&self) -> Rect {
+        self.output.lock().unwrap().desktop_coordinates
+    }
+
+    /// The rotation of the monitor, frames are rotated to match what is displayed before they are sent.
+    pub fn rotation(&self) -> DXGI_MODE_ROTATION {
+        self.output.lock().unwrap().rotation
+    }
+
+    /// # Color Info
+    ///
+    /// The color space, bit depth and brightness of the monitor as Windows reports them now, None before Windows 10 1703.
+    ///
+    /// The color space changes to Hdr10 when HDR is turned on, frames are then sent as RGBA16F in scRGB (see Frame::color_space).
+    pub fn color_info(&self) -> Option<ColorInfo> {
+        unsafe { ColorInfo::from_output(&self.monitor_output) }
+    }
+
+    /// # SDR White Level
+    ///
+    /// The brightness SDR content is shown at in nits while HDR is on, divide it by 80 for the white level of tone_map_frame.
+    ///
+    /// Read from the display settings on every call, None before Windows 10 1709.
+    pub fn sdr_white_level(&self) -> Option<f32> {
+        unsafe { sdr_white_levels().remove(&self.name) }
+    }
+
+    /// # Set Staging Textures
+    ///
+    /// Sets how many staging textures frames are copied through (2 by default).
+    ///
+    /// With more than one texture the GPU copies the newest frame while the previous frame is read by the CPU, this improves the frame rate of large monitors.
+    ///
+    /// Each extra texture delays the frames sent by one acquired frame. 1 sends every frame as soon as it is acquired.
+    pub fn set_staging_textures(&self, count: usize) -> Result<(), windows::core::Error> {
+        let mut output = self.output.lock().unwrap();
+
+        output.staging_textures = Self::create_staging_textures(
+            &self.device,
+            &output.size,
+            output.staging_format(),
+            count,
+        )?;
+        output.reset_staging();
+
+        Ok(())
+    }
+
+    /// # Set Partial Copy Threshold
+    ///
+    /// When only a small part of the screen changes just the changed regions are copied from the GPU and read by the CPU.
+    ///
+    /// The threshold is the largest part of the screen (0 to 1, 0.6 by default) that may change before the whole screen is copied instead, as many small copies can be slower than one large one.
+    ///
+    /// 0 always copies the whole screen. Scaled frames are always copied whole.
+    pub fn set_partial_copy_threshold(&self, threshold: f32) {
+        self.output.lock().unwrap().partial_copy_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// # Reset Output Size
+    ///
+    /// Stops scaling frames, the receiver will deliver frames at the size of the monitor again.
+    pub fn reset_output_size(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let size = self.desktop_size();
+
+        self.set_output_size(size.width, size.height)
+    }
+
+    /// # Set Max FPS
+    ///
+    /// Limits how many frames are sent each second, None sends frames as fast as the monitor updates.
+    ///
+    /// The capture waits until the next frame is due before acquiring it, so no frames are held while waiting.
+    ///
+    /// Limiting the frame rate turns off vsync, see set_vsync.
+    pub fn set_max_fps(&self, max_fps: Option<u32>) {
+        if max_fps.is_some() {
+            self.vsync.store(false, Ordering::Relaxed);
+        }
+
+        self.max_fps.store(max_fps.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// # Get Max FPS
+    ///
+    /// The most frames sent each second, None if the frame rate is not limited.
+    pub fn get_max_fps(&self) -> Option<u32> {
+        match self.max_fps.load(Ordering::Relaxed) {
+            0 => None,
+            max_fps => Some(max_fps),
+        }
+    }
+
+    /// # Set VSync
+    ///
+    /// When true, the capture waits for the vertical blank of the monitor before acquiring each frame so that at most one frame is sent per refresh, lined up with the display.
+    ///
+    /// The wait happens on a blocking thread, the async runtime is not stalled. Turning vsync on removes the frame rate limit, see set_max_fps.
+    pub fn set_vsync(&self, vsync: bool) {
+        if vsync {
+            self.max_fps.store(0, Ordering::Relaxed);
+        }
+
+        self.vsync.store(vsync, Ordering::Relaxed);
+    }
+
+    /// Whether the capture waits for the vertical blank before acquiring each frame.
+    pub fn get_vsync(&self) -> bool {
+        self.vsync.load(Ordering::Relaxed)
+    }
+
+    /// # Get Frame Interval
+    ///
+    /// The time between the captures of the last two frames that were sent, this can be used to check the pacing of the capture.
+    ///
+    /// None until two frames were sent.
+    pub fn get_frame_interval(&self) -> Option<Duration> {
+        match self.frame_interval.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// # Set Snapshot Interval
+    ///
+    /// Sets the time between snapshots, see start_snapshots. This can be changed while taking snapshots, it applies from the next snapshot on.
+    ///
+    /// Intervals shorter than a millisecond are raised to a millisecond.
+    pub fn set_snapshot_interval(&self, interval: Duration) {
+        let interval = interval.max(MIN_SNAPSHOT_INTERVAL);
+        self.snapshot_interval
+            .store(interval.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// The time between snapshots, one second until set.
+    pub fn get_snapshot_interval(&self) -> Duration {
+        Duration::from_nanos(self.snapshot_interval.load(Ordering::Relaxed))
+    }
+
+    /// # Current FPS
+    ///
+    /// The rate frames are sent at, averaged over about the last second. 0 while not capturing and until two frames were sent.
+    ///
+    /// When the screen stops changing no frames are sent and this drops accordingly, unless the monitor is taking snapshots.
+    pub fn current_fps(&self) -> f64 {
+        self.fps.fps()
+    }
+
+    /// # Latency Stats
+    ///
+    /// The average, 95th percentile and longest time the last 120 frames took from being acquired to being sent, broken down into copying, mapping, reading and sending.
+    ///
+    /// Always measured, the cost is a few Instant samples per frame.
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
+    /// # Present Time Since Start
+    ///
+    /// How long after the capture was started the desktop presented the image of the frame, this can be used as the presentation timestamp when encoding.
+    ///
+    /// None when the frame has no new image (only the pointer changed) or the image was presented before the capture started.
+    pub fn present_time_since_start(&self, frame: &Frame) -> Option<Duration> {
+        let start = self.capture_start.load(Ordering::Relaxed);
+
+        if frame.last_present_time == 0 || frame.last_present_time < start {
+            return None;
+        }
+
+        Some(qpc_to_duration(frame.last_present_time - start))
+    }
+
+    /// # Set Skip Unchanged
+    ///
+    /// When true, frames where nothing on screen changed (for example only the pointer moved) are released without being copied or sent.
+    ///
+    /// This greatly reduces the work done for mostly idle screens, the number of skipped frames can be read with get_skipped_frames.
+    pub fn set_skip_unchanged(&self, skip_unchanged: bool) {
+        self.skip_unchanged.store(skip_unchanged, Ordering::Relaxed);
+    }
+
+    /// # Set Delivery Policy
+    ///
+    /// Sets what is done with a new frame or shared texture while the receiver still holds one it has not read, see DeliveryPolicy.
+    ///
+    /// DropOldest (the default) keeps the capture running at the rate of the desktop however slowly the receiver reads. Dropped and replaced frames are counted in CaptureStats::dropped.
+    pub fn set_delivery_policy(&self, policy: DeliveryPolicy) {
+        *self.delivery_policy.lock().unwrap() = policy;
+    }
+
+    /// The delivery policy, see set_delivery_policy.
+    pub fn get_delivery_policy(&self) -> DeliveryPolicy {
+        *self.delivery_policy.lock().unwrap()
+    }
+
+    /// # Set Wait For Content
+    ///
+    /// The first frames of a new duplication, after the monitor is created or access to the desktop came back, often have no desktop image yet and are all zeros.
+    ///
+    /// When true (the default) no frame is sent until the desktop presents an image or reports a dirty rect, the frames before are counted as skipped. Set to false to send every frame that is acquired.
+    pub fn set_wait_for_content(&self, wait_for_content: bool) {
+        self.wait_for_content
+            .store(wait_for_content, Ordering::Relaxed);
+    }
+
+    // whether the frame is held back because the duplication has not presented an image yet
+    fn awaiting_content(&self, monitor_frame: &MonitorFrame) -> bool {
+        if monitor_frame.frame_info.LastPresentTime != 0 || !monitor_frame.dirty_rects().is_empty()
+        {
+            self.content_presented.store(true, Ordering::Relaxed);
+        }
+
+        self.wait_for_content.load(Ordering::Relaxed)
+            && !self.content_presented.load(Ordering::Relaxed)
+    }
+
+    /// # Set Coalesce Dirty Rects
+    ///
+    /// When true, the dirty rects of each frame are merged with coalesce_rects before the frame is sent, limited to max_regions rects when given.
+    ///
+    /// Frames with merged rects have rects_coalesced set, as their dirty rects may cover pixels that did not change. Off by default.
+    pub fn set_coalesce_dirty_rects(&self, coalesce: bool, max_regions: Option<usize>) {
+        let max_dirty_rects = match (coalesce, max_regions) {
+            (false, _) => 0,
+            (true, Some(max_regions)) => max_regions.max(1),
+            (true, None) => usize::MAX,
+        };
+
+        self.max_dirty_rects
+            .store(max_dirty_rects, Ordering::Relaxed);
+    }
+
+    /// # Set Min Change Ratio
+    ///
+    /// When set, frames are only sent when at least this fraction of their pixels changed from the last frame sent, see compare_frames for how pixels are compared.
+    ///
+    /// A pixel changed when any of its color channels moved by more than the threshold. Frames held back are counted as skipped, the first frame of a capture is always sent.
+    ///
+    /// This compares every frame to a copy of the last one sent, None (the default) turns it off.
+    pub fn set_min_change_ratio(&self, min_change_ratio: Option<f64>, threshold: u8) {
+        self.change_filter
+            .lock()
+            .unwrap()
+            .set(min_change_ratio, threshold);
+    }
+
+    /// # Set Duplicate Suppression
+    ///
+    /// When set, frames that hash the same as the frame captured before them are not sent. Some applications present without changing any pixels, so a new frame is not always a new image.
+    ///
+    /// Use hash_frame to hash every pixel or sample_hash_frame to hash a part of each frame at large sizes, or any FrameHasher. Suppressed frames are counted in CaptureStats::suppressed as well as skipped.
+    ///
+    /// None (the default) sends every frame.
+    pub fn set_duplicate_suppression(&self, hasher: Option<FrameHasher>) {
+        self.duplicate_filter.lock().unwrap().set(hasher);
+    }
+
+    /// # Set Thumbnails
+    ///
+    /// Sends a downscaled copy of a frame through thumbnail_receiver at most once every interval, next to the full frames sent through the receiver. Useful for previews of the capture.
+    ///
+    /// The thumbnails keep the aspect ratio of the frames and fit in the size, see downscale_frame for the filters. They are only made from the frames that are sent, so a still screen sends no thumbnails unless snapshots are taken.
+    ///
+    /// Thumbnails never wait: one is dropped while the receiver still holds one that was not read. No thumbnails are made while texture output is on.
+    ///
+    /// None (the default) turns thumbnails off.
+    pub fn set_thumbnails(
+        &self,
+        size: Option<Dimensions>,
+        interval: Duration,
+        filter: ScaleFilter,
+    ) {
+        *self.thumbnails.lock().unwrap() = size.map(|size| Thumbnails {
+            size,
+            interval,
+            filter,
+            last_made: None,
+        });
+    }
+
+    // sends a thumbnail of the frame when one is due, without waiting for room in the channel
+    fn send_thumbnail(&self, frame: &Frame) {
+        let mut thumbnails = self.thumbnails.lock().unwrap();
+
+        let Some(thumbnails) = thumbnails.as_mut() else {
+            return;
+        };
+
+        let now = Instant::now();
+
+        if thumbnails
+            .last_made
+            .is_some_and(|last_made| now.duration_since(last_made) < thumbnails.interval)
+        {
+            return;
+        }
+
+        //a full channel drops the thumbnail before any work is done
+        let Ok(permit) = self.thumbnail_sender.try_reserve() else {
+            return;
+        };
+
+        thumbnails.last_made = Some(now);
+
+        let size = fit_within(frame.width, frame.height, &thumbnails.size);
+        permit.send(downscale_frame(frame, &size, thumbnails.filter));
+    }
+
+    /// # Follow Window
+    ///
+    /// Crops the frames that are sent to the window, following it as it is moved or resized. The window's extended frame bounds are used, which leaves out its drop shadow.
+    ///
+    /// Parts of the window that are off this monitor are cut off. While the window is minimized or entirely on another monitor no frames are sent (they are counted as skipped).
+    ///
+    /// When the window moves to another monitor a WindowMoved event is sent with the name of that monitor, a Monitor created for it can follow the window from there.
+    ///
+    /// A frame of a new size has dimensions_changed set, with a DimensionsChanged event. The capture ends with an error if the window is closed.
+    ///
+    /// Windows covering the followed window are captured too, as desktop duplication captures what is on screen.
+    ///
+    /// Fails when the handle is not a window, such as one that was already closed.
+    pub fn follow_window(&self, hwnd: HWND) -> Result<(), Box<dyn std::error::Error>> {
+        if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+            return Err(format!("{hwnd:?} is not a window").into());
+        }
+
+        *self.followed_window.lock().unwrap() = Some(FollowedWindow {
+            hwnd,
+            monitor: None,
+            size: None,
+        });
+
+        Ok(())
+    }
+
+    /// # Stop Following Window
+    ///
+    /// Sends whole frames of the monitor again, see follow_window.
+    pub fn stop_following_window(&self) {
+        *self.followed_window.lock().unwrap() = None;
+    }
+
+    /// The window the frames are cropped to, see follow_window.
+    pub fn followed_window(&self) -> Option<HWND> {
+        self.followed_window
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|window| window.hwnd)
+    }
+
+    /// # Get Skipped Frames
+    ///
+    /// The number of frames that were not sent because nothing on screen changed.
+    pub fn get_skipped_frames(&self) -> u64 {
+        self.stats.snapshot().skipped
+    }
+
+    /// # Stats
+    ///
+    /// The number of frames captured, delivered, dropped and skipped since the monitor was created.
+    ///
+    /// Dropped frames include frames the desktop presented while the capture was too far behind to acquire them, for example because the receiver was not read.
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            queued: queued(&self.sender),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// # Set Buffer Pool Size
+    ///
+    /// Frame data is reused once a frame is dropped, this sets how many buffers are kept for reuse (3 by default).
+    ///
+    /// Frames can be held onto for as long as needed, new buffers are allocated when none are free. Setting this to 0 allocates every frame.
+    pub fn set_buffer_pool_size(&self, size: usize) {
+        self.buffer_pool.set_max_buffers(size);
+    }
+
+    /// # Set Texture Output
+    ///
+    /// When true, frames stay on the GPU and are sent through the texture receiver as SharedTextures instead of being read back to the CPU. Off by default, takes effect the next time capturing starts.
+    ///
+    /// Shared textures are the desktop image as it was acquired: they are not scaled, rotated, converted or cropped to a followed window, and set_min_change_ratio does not apply.
+    /// Nothing is sent through the frame receiver while this is on.
+    pub fn set_texture_output(&self, texture_output: bool) {
+        self.texture_output.store(texture_output, Ordering::Relaxed);
+    }
+
+    /// # Get Texture Output
+    ///
+    /// Whether frames are sent as shared textures, see set_texture_output.
+    pub fn get_texture_output(&self) -> bool {
+        self.texture_output.load(Ordering::Relaxed)
+    }
+
+    /// # Set Texture Pool Size
+    ///
+    /// Shared textures are reused once they are dropped, this sets how many are kept for reuse (3 by default).
+    ///
+    /// New textures are created when none are free. Only textures the consumer released with CAPTURE_KEY are reused, the others are released instead (see SharedTexture).
+    pub fn set_texture_pool_size(&self, size: usize) {
+        self.texture_pool.set_max_textures(size);
+    }
+
+    /// # Set Strip Padding
+    ///
+    /// GPUs may pad each row of the frame, by default this padding is removed so frames are tightly packed (stride is width * bytes per pixel).
+    ///
+    /// Setting this to false sends the rows as they are mapped, avoiding an extra copy, the stride of the frame is then the row pitch of the GPU.
+    pub fn set_strip_padding(&self, strip_padding: bool) {
+        self.output.lock().unwrap().strip_padding = strip_padding;
+    }
+
+    /// # Set Output Format
+    ///
+    /// Sets the pixel format of the frames that are sent, BGRA by default.
+    ///
+    /// RGBA swaps the red and blue channels, RGB24 drops the alpha channel and Gray8 keeps only the brightness (see set_gray_weights). Converted frames are always tightly packed.
+    ///
+    /// HDR frames are always sent as RGBA16F, see tone_map_frame.
+    pub fn set_output_format(&self, format: PixelFormat) -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            PixelFormat::BGRA | PixelFormat::RGBA | PixelFormat::RGB24 | PixelFormat::Gray8 => {}
+            _ => return Err(format!("monitor frames can not be converted to {format:?}").into()),
+        }
+
+        self.output.lock().unwrap().output_format = format;
+
+        Ok(())
+    }
+
+    /// # Set Opaque Alpha
+    ///
+    /// The alpha of duplicated frames is not meaningful and is often 0, which shows as transparent or black when the frame is blended.
+    ///
+    /// When true the alpha of every pixel is set to 255 in the same pass that strips the row padding. Off by default, most consumers will want it on.
+    ///
+    /// HDR frames are not changed.
+    pub fn set_opaque_alpha(&self, opaque_alpha: bool) {
+        let mut output = self.output.lock().unwrap();
+
+        output.opaque_alpha = opaque_alpha;
+
+        //the unchanged areas of the last frame still have the old alpha
+        output.last_frame = None;
+    }
+
+    /// # Set Gray Weights
+    ///
+    /// The weights of red, green and blue in the brightness of Gray8 frames, BT.601 by default. See set_output_format.
+    pub fn set_gray_weights(&self, gray_weights: GrayWeights) {
+        let mut output = self.output.lock().unwrap();
+
+        output.gray_weights = gray_weights;
+
+        //the unchanged areas of the last frame were weighed the old way
+        output.last_frame = None;
+    }
+
+    /// # Get Output Format
+    ///
+    /// The pixel format SDR frames are sent in.
+    pub fn get_output_format(&self) -> PixelFormat {
+        self.output.lock().unwrap().output_format
+    }
+
+    // copies the acquired image, scaled to the output size, to the next staging texture
+    // then reads back the oldest copied image to the CPU, None while the staging textures are still being filled
+    unsafe fn copy_image(
+        &self,
+        monitor_frame: &MonitorFrame,
+        sequence: u64,
+        acquired: Instant,
+        pointer: Option<PointerPosition>,
+    ) -> Result<Option<(Frame, FrameLatency)>, windows::core::Error> {
+        let mut output = self.output.lock().unwrap();
+        let image = monitor_frame.acquired_image.as_ref().unwrap();
+        let staging_index = output.next_staging;
+
+        //everything that changed on screen, moved areas are changed at their destination
+        let changed_regions: Vec<Rect> = monitor_frame
+            .dirty_regions()
+            .chain(
+                monitor_frame
+                    .moved_regions()
+                    .map(|move_rect| move_rect.destination),
+            )
+            .map(|rect| clamp_rect(&rect, &output.surface_size))
+            .collect();
+
+        //dirty and move rects are in the coordinates of the unrotated monitor, move them to the coordinates of the frame
+        let to_frame = |rect: Rect| {
+            let rect = rect.scale(&output.surface_size, &output.size);
+            rotate_rect(&rect, &output.size, output.rotation)
+        };
+
+        let mut dirty_rects: Vec<Rect> = monitor_frame.dirty_regions().map(to_frame).collect();
+        let mut move_rects = vec![];
+
+        let moves = monitor_frame.moved_regions();
+
+        if output.scaler.is_some() {
+            //scaled moves do not land on whole pixels, send the moved areas as dirty instead
+            dirty_rects.extend(moves.map(|move_rect| to_frame(move_rect.destination)));
+        } else {
+            move_rects = moves
+                .map(|move_rect| {
+                    let source = to_frame(move_rect.source());
+
+                    MoveRect {
+                        source_x: source.left,
+                        source_y: source.top,
+                        destination: to_frame(move_rect.destination),
+                    }
+                })
+                .collect();
+        }
+
+        let mut rects_coalesced = monitor_frame.frame_info.RectsCoalesced.as_bool();
+
+        let max_dirty_rects = self.max_dirty_rects.load(Ordering::Relaxed);
+
+        if max_dirty_rects > 0 {
+            dirty_rects = coalesce_rects(&dirty_rects, Some(max_dirty_rects));
+            rects_coalesced = true;
+        }
+
+        let mut copy = PendingCopy {
+            staging_index,
+            timestamp: acquired,
+            dirty_rects,
+            move_rects,
+            sequence,
+            copy_time: Duration::ZERO,
+            accumulated_frames: monitor_frame.frame_info.AccumulatedFrames,
+            last_present_time: monitor_frame.frame_info.LastPresentTime,
+            protected_content: monitor_frame.frame_info.ProtectedContentMaskedOut.as_bool(),
+            rects_coalesced,
+            pointer,
+            changed_regions,
+        };
+
+        //the desktop image is already in system memory, it is read directly instead of through a staging texture
+        if output.scaler.is_none() && output.surface_in_system_memory {
+            let read = self.map_desktop_surface(&mut output, &copy.changed_regions)?;
+
+            return Ok(Some(copy.into_frame(read)));
+        }
+
+        //the staging texture is missing the changes of this frame and of every frame since it was last copied to
+        let copy_regions = match &output.stale_regions[staging_index] {
+            Some(stale) if output.scaler.is_none() => {
+                let mut regions = stale.clone();
+                regions.extend_from_slice(&copy.changed_regions);
+
+                within_threshold(&regions, &output.size, output.partial_copy_threshold)
+                    .then_some(regions)
+            }
+            _ => None,
+        };
+
+        let copy_started = std::time::Instant::now();
+
+        unsafe {
+            let staging_texture = &output.staging_textures[staging_index];
+
+            match (&output.scaler, copy_regions) {
+                (Some(scaler), _) => {
+                    self.device_context
+                        .CopyResource(staging_texture, scaler.scale(image)?);
+                }
+                (None, Some(regions)) => {
+                    for region in regions.iter().filter(|r| r.width() > 0 && r.height() > 0) {
+                        let copy_box = D3D11_BOX {
+                            left: region.left as u32,
+                            top: region.top as u32,
+                            front: 0,
+                            right: region.right as u32,
+                            bottom: region.bottom as u32,
+                            back: 1,
+                        };
+
+                        self.device_context.CopySubresourceRegion(
+                            staging_texture,
+                            0,
+                            copy_box.left,
+                            copy_box.top,
+                            0,
+                            image,
+                            0,
+                            Some(&copy_box),
+                        );
+                    }
+                }
+                (None, None) => self.device_context.CopyResource(staging_texture, image),
+            }
+
+            //flush the context of the copied resource.
+            self.device_context.Flush();
+        }
+
+        copy.copy_time = copy_started.elapsed();
+
+        //the copied texture is now up to date, the others are missing this frame's changes
+        for (index, stale) in output.stale_regions.iter_mut().enumerate() {
+            if index == staging_index {
+                *stale = Some(vec![]);
+            } else if let Some(stale) = stale {
+                stale.extend_from_slice(&copy.changed_regions);
+            }
+        }
+
+        output.next_staging = (staging_index + 1) % output.staging_textures.len();
+
+        output.pending.push_back(copy);
+
+        //every staging texture must be filled before the oldest is read, this gives the GPU time to copy
+        if output.pending.len() < output.staging_textures.len() {
+            return Ok(None);
+        }
+
+        let pending = output.pending.pop_front().unwrap();
+
+        let read =
+            self.map_resource(&mut output, pending.staging_index, &pending.changed_regions)?;
+
+        Ok(Some(pending.into_frame(read)))
+    }
+
+    /// Using the device's context map the staging texture to contain the monitor frame data
+    ///
+    /// Once mapped copy from the raw frame data into a Frame, rotating it if the monitor is rotated
+    ///
+    /// When only a small area changed since the last frame just the changed rows are copied into the last frame
+    fn map_resource(
+        &self,
+        output: &mut MonitorOutput,
+        staging_index: usize,
+        changed_regions: &[Rect],
+    ) -> Result<(Frame, FrameLatency), windows::core::Error> {
+        //the texture is held on its own so the output can be updated while it is mapped
+        let staging_texture = output.staging_textures[staging_index].clone();
+
+        //we now have access to the data, it is unmapped once the guard is dropped
+        //mapping waits for the GPU to finish copying to the texture
+        let map_started = std::time::Instant::now();
+        let mapped = unsafe { MappedStaging::map(&self.device_context, &staging_texture)? };
+        let map_time = map_started.elapsed();
+        let mapped_resource = mapped.resource;
+
+        let row_pitch = mapped_resource.RowPitch as usize;
+        let total_size_bytes = row_pitch * output.size.height as usize;
+
+        //the data stays mapped until the guard is dropped, after the image is read
+        let raw = unsafe {
+            std::slice::from_raw_parts(mapped_resource.pData as *const u8, total_size_bytes)
+        };
+
+        Ok(self.timed_read(output, raw, row_pitch, changed_regions, map_time))
+    }
+
+    /// Read the desktop image straight from system memory, without copying it to a staging texture
+    ///
+    /// The frames are laid out exactly like those read from a staging texture
+    fn map_desktop_surface(
+        &self,
+        output: &mut MonitorOutput,
+        changed_regions: &[Rect],
+    ) -> Result<(Frame, FrameLatency), windows::core::Error> {
+        let duplication = self.duplication()?;
+
+        //unmapped once the guard is dropped, after the image is read
+        let map_started = std::time::Instant::now();
+        let mapped = unsafe { MappedDesktop::map(&duplication)? };
+        let map_time = map_started.elapsed();
+
+        let row_pitch = mapped.rect.Pitch as usize;
+        let total_size_bytes = row_pitch * output.size.height as usize;
+
+        let raw =
+            unsafe { std::slice::from_raw_parts(mapped.rect.pBits as *const u8, total_size_bytes) };
+
+        Ok(self.timed_read(output, raw, row_pitch, changed_regions, map_time))
+    }
+
+    // reads the mapped image, measuring how long the read took
+    fn timed_read(
+        &self,
+        output: &mut MonitorOutput,
+        raw: &[u8],
+        row_pitch: usize,
+        changed_regions: &[Rect],
+        map_time: Duration,
+    ) -> (Frame, FrameLatency) {
+        let read_started = std::time::Instant::now();
+        let frame = self.read_image(output, raw, row_pitch, changed_regions);
+
+        let latency = FrameLatency {
+            map: map_time,
+            read: read_started.elapsed(),
+            ..Default::default()
+        };
+
+        (frame, latency)
+    }
+
+    // copies the mapped image into a Frame, converting and rotating it, or only the changed regions into the last frame
+    fn read_image(
+        &self,
+        output: &mut MonitorOutput,
+        raw: &[u8],
+        row_pitch: usize,
+        changed_regions: &[Rect],
+    ) -> Frame {
+        let size = rotated_size(&output.size, output.rotation);
+        let (width, height) = (size.width as usize, size.height as usize);
+
+        //the format of the staging texture and of the frame that is sent
+        let source_format = output.pixel_format();
+        let source_bytes = source_format.bytes_per_pixel() as usize;
+        let format = output.frame_format();
+        let opaque_alpha = output.opaque_alpha && source_format == PixelFormat::BGRA;
+        let convert = format != source_format || opaque_alpha;
+        let conversion = PixelConversion {
+            format,
+            opaque_alpha,
+            gray_weights: output.gray_weights,
+        };
+        let pixel_bytes = format.bytes_per_pixel() as usize;
+
+        let mut data = self.buffer_pool.take(width * height * pixel_bytes);
+        let stride: usize;
+
+        let unrotated = output.rotation == DXGI_MODE_ROTATION_IDENTITY
+            || output.rotation == DXGI_MODE_ROTATION_UNSPECIFIED;
+
+        //partial reads need the last frame, so keep it only when they can be used
+        let keep_last_frame =
+            unrotated && output.scaler.is_none() && output.partial_copy_threshold > 0.0;
+        let expected_stride = if output.strip_padding || convert {
+            width * pixel_bytes
+        } else {
+            row_pitch
+        };
+
+        let last_frame = output.last_frame.as_mut().filter(|last_frame| {
+            keep_last_frame
+                && last_frame.format == format
+                && last_frame.stride as usize == expected_stride
+                && within_threshold(changed_regions, &size, output.partial_copy_threshold)
+        });
+        let partial_read = last_frame.is_some();
+
+        if let Some(last_frame) = last_frame {
+            //only the changed rows need to be read from the GPU's memory
+            for region in changed_regions {
+                let (left, right) = (region.left as usize, region.right as usize);
+
+                for y in region.top as usize..region.bottom as usize {
+                    let source = y * row_pitch;
+                    let destination = y * expected_stride;
+
+                    convert_pixels(
+                        &raw[source + left * source_bytes..source + right * source_bytes],
+                        conversion,
+                        &mut last_frame.data
+                            [destination + left * pixel_bytes..destination + right * pixel_bytes],
+                    );
+                }
+            }
+
+            data.extend_from_slice(&last_frame.data);
+            stride = expected_stride;
+        } else if unrotated {
+            //converting drops the padding in the same pass
+            if convert {
+                stride = width * pixel_bytes;
+                convert_rows(raw, row_pitch, width, height, conversion, &mut data);
+            } else if output.strip_padding {
+                stride = width * pixel_bytes;
+                pack_rows(raw, row_pitch, stride, height, &mut data);
+            } else {
+                data.extend_from_slice(raw);
+                stride = row_pitch;
+            }
+        } else if convert {
+            //the mapped rows are converted with their own pitch, then the packed result is rotated
+            let (source_width, source_height) =
+                (output.size.width as usize, output.size.height as usize);
+            let mut converted = self
+                .buffer_pool
+                .take(source_width * source_height * pixel_bytes);
+            convert_rows(
+                raw,
+                row_pitch,
+                source_width,
+                source_height,
+                conversion,
+                &mut converted,
+            );
+
+            rotate_pixels_into(
+                &converted,
+                source_width * pixel_bytes,
+                &output.size,
+                output.rotation,
+                pixel_bytes,
+                &mut data,
+            );
+
+            stride = width * pixel_bytes;
+        } else {
+            //rotated frames are tightly packed
+            rotate_pixels_into(
+                raw,
+                row_pitch,
+                &output.size,
+                output.rotation,
+                source_bytes,
+                &mut data,
+            );
+
+            stride = width * pixel_bytes;
+        }
+
+        //a full read replaces the last frame
+        if !keep_last_frame {
+            output.last_frame = None;
+        } else if !partial_read {
+            output.last_frame = Some(Frame::new(
+                size.width,
+                size.height,
+                stride as u32,
+                format,
+                data.to_vec(),
+            ));
+        }
+
+        let mut frame = Frame::new(size.width, size.height, stride as u32, format, data);
+        frame.dimensions_changed = std::mem::take(&mut output.dimensions_changed);
+
+        frame
+    }
+
+    // the current duplication of the output, fails with DXGI_ERROR_ACCESS_LOST while access to the desktop is lost
+    fn duplication(&self) -> Result<IDXGIOutputDuplication, windows::core::Error> {
+        self.duplication_output
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| DXGI_ERROR_ACCESS_LOST.into())
+    }
+
+    /// # State
+    ///
+    /// Whether the monitor is capturing, stopping, or how its last capture ended.
+    pub fn state(&self) -> CaptureState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Whether the capture loop is running, this includes while it is stopping.
+    pub fn is_running(&self) -> bool {
+        self.state().is_running()
+    }
+
+    /// # Take Receiver
+    ///
+    ///
+    /// Hands out the frame receiver to own, so it can be moved into a task and read without locking a mutex for every frame.
+    ///
+    /// Only the first call returns the receiver, later calls return None. None is also returned while the shared receiver is locked, such as by a task waiting on recv.
+    /// Once taken the shared receiver (the receiver field and clone_receiver) is closed and recv on it returns None.
+    ///
+    /// The DropOldest delivery policy can not take frames back out of an owned receiver, it drops the new frame instead like DropNewest.
+    pub fn take_receiver(&self) -> Option<Receiver<Frame>> {
+        take_receiver(&self.receiver)
+    }
+
+    /// # Subscribe
+    ///
+    /// A new receiver that gets every frame sent from now on, independent of the receiver and of other subscribers. Lets one capture feed several consumers, such as a recorder and a live preview.
+    ///
+    /// Subscribers share one copy of each frame behind an Arc. The capture never waits for them: a subscriber more than 4 frames behind misses the oldest ones and its next recv returns RecvError::Lagged with the number missed.
+    ///
+    /// Frames are sent to subscribers alongside the receiver, turn off set_receiver_output to only send them to subscribers.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Frame>> {
+        self.broadcast_sender.subscribe()
+    }
+
+    /// # Watch Latest
+    ///
+    /// A receiver holding the latest frame, for consumers that only care about the most recent one such as a preview window. None until a frame is sent after it was made.
+    ///
+    /// Every frame overwrites the one before, nothing queues up and the capture never waits: read it with borrow_and_update at your own pace, or wait for the next one with changed. Frames sent while it was not read are missed.
+    ///
+    /// Works alongside the receiver and subscribe, the frame is shared behind an Arc. Turn off set_receiver_output to only send frames this way.
+    pub fn watch_latest(&self) -> watch::Receiver<Option<Arc<Frame>>> {
+        self.latest_sender.subscribe()
+    }
+
+    /// # Set Receiver Output
+    ///
+    /// Whether frames are sent through the receiver, on by default. Turn it off when only subscribers read frames (see subscribe and watch_latest), which saves copying each frame for them.
+    ///
+    /// While off, frames sent without any subscriber are counted as dropped.
+    pub fn set_receiver_output(&self, receiver_output: bool) {
+        self.receiver_output
+            .store(receiver_output, Ordering::Relaxed);
+    }
+
+    /// Whether frames are sent through the receiver, see set_receiver_output.
+    pub fn get_receiver_output(&self) -> bool {
+        self.receiver_output.load(Ordering::Relaxed)
+    }
+
+    /// # Events
+    ///
+    /// The receiver for capture events, such as the capture starting, stopping, or pausing while the secure desktop is shown.
+    ///
+    /// Events are dropped when the receiver is not read and already holds 16 events.
+    pub fn events(&self) -> Arc<Mutex<Receiver<CaptureEvent>>> {
+        self.events_receiver.clone()
+    }
+
+    // sends an event without waiting, events are dropped when no one reads them
+    fn send_event(&self, event: CaptureEvent) {
+        let _ = self.events_sender.try_send(event);
+    }
+
+    // when the error means access to the desktop was lost, waits until the output can be duplicated again
+    // any other error is returned as is
+    pub(crate) async unsafe fn recover_access(
+        &self,
+        error: windows::core::Error,
+    ) -> Result<(), windows::core::Error> {
+        let Some(reason) = AccessLostReason::from_error(&error) else {
+            return Err(unsafe { self.disconnected_or(error) });
+        };
+
+        self.send_event(CaptureEvent::AccessLost { reason });
+
+        //the old duplication is useless, it must be released before the output can be duplicated again
+        *self.duplication_output.lock().unwrap() = None;
+        self.frame_acquired.store(false, Ordering::Relaxed);
+        self.frame.lock().await.acquired_image = None;
+
+        //the staging textures hold images of the old duplication
+        self.output.lock().unwrap().reset_staging();
+
+        loop {
+            match unsafe { Self::duplicate(&self.monitor_output, &self.device) } {
+                Ok(duplication) => {
+                    //changing the resolution or rotation loses access, the images now come at the new size
+                    unsafe { self.update_display_mode()? };
+
+                    //turning HDR on or off loses access, the images now come in the other format
+                    let desc = unsafe { duplication.GetDesc() };
+                    self.set_surface_desc(&desc)?;
+
+                    *self.duplication_output.lock().unwrap() = Some(duplication);
+                    self.content_presented.store(false, Ordering::Relaxed);
+                    self.send_event(CaptureEvent::Resumed);
+
+                    return Ok(());
+                }
+                //still on the secure desktop, try again in a moment unless capturing was stopped
+                Err(e) if AccessLostReason::from_error(&e).is_some() => {
+                    if !*self.is_sending.lock().await {
+                        return Ok(());
+                    }
+
+                    //an unplugged monitor keeps failing the same way, it will not come back to this output
+                    if !self.is_connected() {
+                        return Err(self.disconnected_error());
+                    }
+
+                    tokio::time::sleep(ACCESS_RETRY_INTERVAL).await;
+                }
+                Err(e) => return Err(unsafe { self.disconnected_or(e) }),
+            }
+        }
+    }
+
+    /// # Is Connected
+    ///
+    /// Whether the monitor is still connected and part of the desktop, it is looked up again by its device name.
+    ///
+    /// A capture ends with a DeviceLost event once its monitor is disconnected, see reconnect.
+    pub fn is_connected(&self) -> bool {
+        unsafe { Self::find_output(&self.name) }.is_ok_and(|output| output.is_some())
+    }
+
+    /// # Reconnect
+    ///
+    /// Creates a Monitor for the same display, found again by its device name. Used once the monitor is back after a DeviceLost event, such as when it was unplugged and plugged back in.
+    ///
+    /// The new monitor sends frames, shared textures, thumbnails, pointer updates and events through the same receivers as this one, and frames to the same subscribers and latest frame watchers, so anything holding them keeps receiving once it is started.
+    /// Its other settings start at their defaults.
+    ///
+    /// Fails while the monitor is still disconnected, and while this monitor is capturing.
+    pub fn reconnect(&self) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        if self.is_running() {
+            return Err("the monitor is still capturing, stop it before reconnecting".into());
+        }
+
+        let Some((adapter, output)) = (unsafe { Self::find_output(&self.name)? }) else {
+            return Err(format!("monitor {} is not connected", self.name).into());
+        };
+
+        let mut monitor = unsafe { Self::from_output(adapter, output)? };
+
+        //nothing else holds the new monitor yet, so its channels can be swapped for ours
+        if let Some(new_monitor) = Arc::get_mut(&mut monitor) {
+            new_monitor.sender = self.sender.clone();
+            new_monitor.receiver = self.receiver.clone();
+            new_monitor.broadcast_sender = self.broadcast_sender.clone();
+            new_monitor.latest_sender = self.latest_sender.clone();
+            new_monitor.texture_sender = self.texture_sender.clone();
+            new_monitor.texture_receiver = self.texture_receiver.clone();
+            new_monitor.thumbnail_sender = self.thumbnail_sender.clone();
+            new_monitor.thumbnail_receiver = self.thumbnail_receiver.clone();
+            new_monitor.pointer_sender = self.pointer_sender.clone();
+            new_monitor.pointer_receiver = self.pointer_receiver.clone();
+            new_monitor.events_sender = self.events_sender.clone();
+            new_monitor.events_receiver = self.events_receiver.clone();
+        }
+
+        Ok(monitor)
+    }
+
+    // finds the output with the device name among the outputs attached to the desktop
+    unsafe fn find_output(
+        name: &str,
+    ) -> Result<Option<(IDXGIAdapter1, IDXGIOutput1)>, windows::core::Error> {
+        let outputs = unsafe { Self::enumerate_outputs()? };
+
+        Ok(outputs.into_iter().find(|(_, output)| {
+            unsafe { output.GetDesc() }.is_ok_and(|desc| {
+                desc.AttachedToDesktop.as_bool() && Self::output_name(&desc) == name
+            })
+        }))
+    }
+
+    // the device name of an output, such as \\.\DISPLAY1
+    fn output_name(desc: &DXGI_OUTPUT_DESC) -> String {
+        String::from_utf16_lossy(&desc.DeviceName)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    // the error a capture ends with once its monitor was disconnected, CaptureEvent::is_device_lost is true for it
+    fn disconnected_error(&self) -> windows::core::Error {
+        windows::core::Error::new(
+            DXGI_ERROR_NOT_FOUND,
+            format!("monitor {} was disconnected", self.name),
+        )
+    }
+
+    // an unplugged monitor fails with whatever error the driver picks, it is reported as disconnected instead
+    unsafe fn disconnected_or(&self, error: windows::core::Error) -> windows::core::Error {
+        match self.is_connected() {
+            true => error,
+            false => self.disconnected_error(),
+        }
+    }
+
+    // reads the size, rotation and position of the output again, recreating the staging textures when the size changed
+    // frames at the monitor size follow the new mode, scaled frames keep the size that was set
+    unsafe fn update_display_mode(&self) -> Result<(), windows::core::Error> {
+        let desc = unsafe { self.monitor_output.GetDesc()? };
+        let surface_size = Self::surface_size(&desc);
+
+        let mut output = self.output.lock().unwrap();
+        output.desktop_coordinates = desc.DesktopCoordinates.into();
+
+        if surface_size == output.surface_size && desc.Rotation == output.rotation {
+            return Ok(());
+        }
+
+        let previous = output.frame_size();
+        let size = match output.scaler {
+            Some(_) => rotated_size(&previous, desc.Rotation),
+            None => surface_size,
+        };
+
+        output.surface_size = surface_size;
+        output.rotation = desc.Rotation;
+
+        unsafe { self.resize_output(&mut output, size)? };
+        self.check_dimensions(&mut output, previous);
+
+        Ok(())
+    }
+
+    // the size of the unrotated desktop image of the output, for portrait monitors its width and height are swapped
+    fn surface_size(desc: &DXGI_OUTPUT_DESC) -> Dimensions {
+        let coordinates = &desc.DesktopCoordinates;
+        let desktop_size = Dimensions {
+            width: (coordinates.right - coordinates.left) as u32,
+            height: (coordinates.bottom - coordinates.top) as u32,
+        };
+
+        rotated_size(&desktop_size, desc.Rotation)
+    }
+
+    // recreates the staging textures when the format of the duplicated image changed
+    fn set_surface_desc(&self, desc: &DXGI_OUTDUPL_DESC) -> Result<(), windows::core::Error> {
+        let mut output = self.output.lock().unwrap();
+        let surface_format = desc.ModeDesc.Format;
+
+        output.surface_in_system_memory = desc.DesktopImageInSystemMemory.as_bool();
+
+        if output.surface_format == surface_format {
+            return Ok(());
+        }
+
+        output.surface_format = surface_format;
+
+        //scaled frames stay BGRA
+        if output.scaler.is_none() {
+            self.send_event(CaptureEvent::FormatChanged {
+                format: output.frame_format(),
+            });
+        }
+
+        let count = output.staging_textures.len();
+        output.staging_textures = Self::create_staging_textures(
+            &self.device,
+            &output.size,
+            output.staging_format(),
+            count,
+        )?;
+        output.reset_staging();
+
+        Ok(())
+    }
+
+    /// # Capture Frame
+    ///
+    /// Takes a single screenshot without starting a capture, waiting up to the timeout for the desktop to present an image.
+    ///
+    /// The whole frame is always read, at the output size and in the output format. Fails if the monitor is already capturing, read its receiver instead.
+    ///
+    /// The frame is released before returning, capturing can be started afterwards as usual.
+    pub async fn capture_frame(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<Frame, Box<dyn std::error::Error>> {
+        //held until the screenshot is taken so a capture cannot start in the middle of it
+        let is_sending = self.is_sending.lock().await;
+
+        if *is_sending {
+            return Err("the monitor is already capturing, read frames from its receiver".into());
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("no frame was presented within {timeout:?}").into());
+            }
+
+            let timeout_ms = (remaining.as_millis() as u32).clamp(1, ACQUIRE_TIMEOUT_MS);
+
+            match unsafe { self.snapshot(timeout_ms).await } {
+                Ok(Some(frame)) => return Ok(frame),
+                Ok(None) => continue,
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// # Capture Frames
+    ///
+    /// Captures count frames in a row without starting a capture, waiting up to timeout_per_frame for each one to be presented.
+    ///
+    /// Each frame is read whole, at the output size and in the output format and cropped to the followed window, and released before the next one is acquired.
+    /// Nothing is sent through a channel so no frame is dropped on the way. The desktop can still present faster than frames are read: the sequence starts at 1 for the first frame and skips ahead by the frames presented in between, see Frame::accumulated_frames.
+    ///
+    /// Fails if the monitor is already capturing. When a frame times out or the duplication fails, the burst ends and the error holds the frames captured so far.
+    pub async fn capture_frames(
+        self: &Arc<Self>,
+        count: usize,
+        timeout_per_frame: Duration,
+    ) -> Result<Vec<Frame>, CaptureFramesError> {
+        //held for the whole burst so a capture cannot start in the middle of it
+        let is_sending = self.is_sending.lock().await;
+
+        let mut frames: Vec<Frame> = Vec::with_capacity(count);
+
+        if *is_sending {
+            return Err(CaptureFramesError {
+                frames,
+                error: "the monitor is already capturing, read frames from its receiver".into(),
+            });
+        }
+
+        while frames.len() < count {
+            let mut frame = match unsafe { self.next_burst_frame(timeout_per_frame).await } {
+                Ok(frame) => frame,
+                Err(error) => return Err(CaptureFramesError { frames, error }),
+            };
+
+            //the first frame accumulates everything presented before the burst started
+            frame.sequence = match frames.last() {
+                Some(last) => last.sequence + (frame.accumulated_frames as u64).max(1),
+                None => 1,
+            };
+
+            frames.push(frame);
+        }
+
+        Ok(frames)
+    }
+
+    // waits for the next presented frame of a burst, frames where the followed window is not shown are passed over
+    async unsafe fn next_burst_frame(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<Frame, Box<dyn std::error::Error + Send + Sync>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("no frame was presented within {timeout:?}").into());
+            }
+
+            let timeout_ms = (remaining.as_millis() as u32).clamp(1, ACQUIRE_TIMEOUT_MS);
+
+            let frame = match unsafe { self.snapshot(timeout_ms).await } {
+                Ok(Some(frame)) => frame,
+                Ok(None) => continue,
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            if let Some(frame) = unsafe { self.crop_to_window(frame)? } {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// # Start Snapshots
+    ///
+    /// Starts taking a snapshot of the monitor every interval and sending it to the receiver, instead of sending every frame the desktop presents.
+    ///
+    /// Each snapshot is a single acquire and copy of the whole frame. When nothing was presented since the last snapshot it is sent again with a new timestamp, so a frame is sent on every tick.
+    /// Ticks missed because a snapshot or the receiver was slow are skipped rather than taken back to back.
+    ///
+    /// The max fps and vsync settings do not apply. The interval can be changed while running with set_snapshot_interval and snapshots are stopped with stop_capturing, like cloning.
+    pub fn start_snapshots(self: Arc<Self>, interval: Duration) -> CaptureFuture {
+        self.set_snapshot_interval(interval);
+
+        Box::pin(async move { self.run(unsafe { self.snapshot_loop() }).await })
+    }
+
+    /// # Spawn Snapshots
+    ///
+    /// The same as start_snapshots but on its own task, returning right away with a handle to stop it.
+    pub fn spawn_snapshots(self: Arc<Self>, interval: Duration) -> CaptureHandle<Self> {
+        let snapshots = self.clone().start_snapshots(interval);
+
+        CaptureHandle::from_future(self, snapshots)
+    }
+
+    // acquires one frame and reads the whole of it, None when the desktop has not presented an image yet
+    async unsafe fn snapshot(
+        self: &Arc<Self>,
+        timeout_ms: u32,
+    ) -> Result<Option<Frame>, windows::core::Error> {
+        let monitor_frame = match unsafe { self.acquire_data(timeout_ms).await } {
+            Ok(monitor_frame) => monitor_frame,
+            Err(e) => {
+                //the frame may have been acquired before reading it failed
+                if self.frame_acquired.load(Ordering::Relaxed) {
+                    let _ = unsafe { self.release_frames().await };
+                }
+
+                return Err(e);
+            }
+        };
+
+        let acquired = Instant::now();
+        let pointer = self.frame_pointer().await;
+
+        //right after duplicating the output the first frame may have no image yet
+        let presented = monitor_frame.frame_info.LastPresentTime != 0;
+        *self.frame.lock().await = monitor_frame;
+
+        let frame = if presented {
+            //read like capture_next, off the threads running async tasks
+            let monitor = self.clone();
+            let frame = run_blocking(move || {
+                let frame_lock = monitor.frame.blocking_lock();
+                monitor.read_whole_frame(&frame_lock, acquired, pointer)
+            })
+            .await;
+
+            Some(frame)
+        } else {
+            None
+        };
+
+        unsafe { self.release_frames().await? };
+
+        frame.transpose()
+    }
+
+    // copies the whole acquired image through the first staging texture and reads it, outside of the capture pipeline
+    fn read_whole_frame(
+        &self,
+        monitor_frame: &MonitorFrame,
+        acquired: Instant,
+        pointer: Option<PointerPosition>,
+    ) -> Result<Frame, windows::core::Error> {
+        let mut output = self.output.lock().unwrap();
+        let image = monitor_frame.acquired_image.as_ref().unwrap();
+
+        //the staging textures and last frame no longer match what the pipeline expects, start it over
+        output.reset_staging();
+
+        let result = unsafe { self.copy_whole_image(&mut output, image) };
+
+        //a later capture must not build on this frame
+        output.reset_staging();
+
+        let (mut frame, _) = result?;
+        frame.timestamp = acquired.into_std();
+        frame.accumulated_frames = monitor_frame.frame_info.AccumulatedFrames;
+        frame.last_present_time = monitor_frame.frame_info.LastPresentTime;
+        frame.protected_content = monitor_frame.frame_info.ProtectedContentMaskedOut.as_bool();
+        frame.pointer = pointer;
+
+        Ok(frame)
+    }
+
+    // copies the whole image, scaled to the output size, to the first staging texture and reads it back
+    unsafe fn copy_whole_image(
+        &self,
+        output: &mut MonitorOutput,
+        image: &ID3D11Texture2D,
+    ) -> Result<(Frame, FrameLatency), windows::core::Error> {
+        unsafe {
+            let staging_texture = &output.staging_textures[0];
+
+            match &output.scaler {
+                Some(scaler) => self
+                    .device_context
+                    .CopyResource(staging_texture, scaler.scale(image)?),
+                None => self.device_context.CopyResource(staging_texture, image),
+            }
+
+            self.device_context.Flush();
+        }
+
+        self.map_resource(output, 0, &[])
+    }
+
+    // releases the frames and readies the monitor for another batch of duplication
+    async unsafe fn release_frames(&self) -> Result<(), windows::core::Error> {
+        unsafe {
+            //release the frames
+            self.duplication()?.ReleaseFrame()?;
+        }
+        self.frame_acquired.store(false, Ordering::Relaxed);
+        self.frame.lock().await.acquired_image = None;
+        Ok(())
+    }
+
+    /// # Stop And Wait
+    ///
+    /// Stops capturing and waits for the capture loop to exit, once this returns the duplication is idle and every frame is released.
+    ///
+    /// Returns an error if the loop did not exit within the timeout.
+    pub async fn stop_and_wait(
+        self: &Arc<Self>,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut running = self.running.subscribe();
+
+        self.clone().stop_capturing().await?;
+
+        match tokio::time::timeout(timeout, running.wait_for(|running| !running)).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("capture did not stop within {timeout:?}").into()),
+        }
+    }
 
-            //use default
-            let p_feature_levels = None;
+    /// # Get Pointer
+    ///
+    /// The last known position and shape of the mouse pointer on this monitor.
+    ///
+    /// Pointer updates are also sent through the pointer receiver while capturing, this can be used if an update was missed.
+    pub async fn get_pointer(&self) -> PointerUpdate {
+        self.pointer.lock().await.clone()
+    }
 
-            //device should be unwrappable after the following function call:
-            let mut device = None;
+    // crops the frame to the followed window, None when the window is minimized or not on this monitor
+    unsafe fn crop_to_window(
+        &self,
+        frame: Frame,
+    ) -> Result<Option<Frame>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut followed_window = self.followed_window.lock().unwrap();
 
-            //this is okay to be null since we are using a NON-Software type for the driver_type
-            //A handle to a DLL that implements a software rasterizer. If DriverType is D3D_DRIVER_TYPE_SOFTWARE, Software must not be NULL.
-            let module_handle = HMODULE(std::ptr::null_mut());
+        let Some(window) = followed_window.as_mut() else {
+            return Ok(Some(frame));
+        };
 
-            let mut device_context: Option<ID3D11DeviceContext> = None;
+        unsafe {
+            if !IsWindow(Some(window.hwnd)).as_bool() {
+                *followed_window = None;
+                return Err("the followed window was closed".into());
+            }
 
-            D3D11CreateDevice(
-                adapter,
-                driver_type,
-                module_handle,
-                flags,
-                p_feature_levels,
-                D3D11_SDK_VERSION,
-                Some(&mut device),
-                None, //we do not need to determine the feature level at this time
-                Some(&mut device_context), //we do no tneed the device context at this time
-            )?;
+            let monitor = MonitorFromWindow(window.hwnd, MONITOR_DEFAULTTONEAREST);
 
-            let device: ID3D11Device = device.unwrap();
+            //only sent when the window leaves, not for every frame it is away
+            if window.monitor != Some(monitor) {
+                window.monitor = Some(monitor);
 
-            let dxgi_device: IDXGIDevice = device.cast()?;
-            let adapter: IDXGIAdapter = dxgi_device.GetAdapter()?;
+                if monitor != self.monitor_output.GetDesc()?.Monitor
+                    && let Some(name) = monitor_device_name(monitor)
+                {
+                    self.send_event(CaptureEvent::WindowMoved { monitor: name });
+                }
+            }
 
-            let monitor_output: windows::Win32::Graphics::Dxgi::IDXGIOutput =
-                adapter.EnumOutputs(monitor)?;
+            if IsIconic(window.hwnd).as_bool() {
+                return Ok(None);
+            }
 
-            let monitor_output1: IDXGIOutput1 = monitor_output.cast()?;
+            //the extended frame bounds leave out the invisible resize borders, GetWindowRect includes them
+            let mut bounds = RECT::default();
+            if DwmGetWindowAttribute(
+                window.hwnd,
+                DWMWA_EXTENDED_FRAME_BOUNDS,
+                &mut bounds as *mut RECT as *mut _,
+                std::mem::size_of::<RECT>() as u32,
+            )
+            .is_err()
+            {
+                GetWindowRect(window.hwnd, &mut bounds)?;
+            }
 
-            let desc = monitor_output1.GetDesc()?;
+            //the window is in desktop coordinates, move it to the coordinates of the frame
+            let desktop = self.desktop_coordinates();
+            let Some(visible) = Rect::from(bounds).intersect(&desktop) else {
+                return Ok(None);
+            };
 
-            //get the size of the monitor
-            let coordinates = &desc.DesktopCoordinates;
-            let device_size = Dimensions {
-                width: (coordinates.right - coordinates.left) as u32,
-                height: (coordinates.bottom - coordinates.top) as u32,
+            let frame_size = Dimensions {
+                width: frame.width,
+                height: frame.height,
             };
+            let crop = visible
+                .offset(-desktop.left, -desktop.top)
+                .scale(&self.desktop_size(), &frame_size);
+
+            let data = self.buffer_pool.take(
+                crop.width() as usize
+                    * crop.height() as usize
+                    * frame.format.bytes_per_pixel() as usize,
+            );
+            let mut cropped = crop_frame_into(&frame, &crop, data);
 
-            let dup_output = monitor_output1.DuplicateOutput(&device)?;
+            let size = Dimensions {
+                width: cropped.width,
+                height: cropped.height,
+            };
 
-            let (tx, rx) = mpsc::channel(1);
+            if window.size.as_ref().is_some_and(|last| *last != size) {
+                cropped.dimensions_changed = true;
+                self.send_event(CaptureEvent::DimensionsChanged { size });
+            }
 
-            let staging_texture = Self::create_staging_texture(&device, &device_size)?;
+            window.size = Some(size);
 
-            Ok(Arc::new(Self {
-                duplication_output: dup_output,
-                sender: tx,
-                receiver: Arc::new(Mutex::new(rx)),
-                is_sending: Arc::new(Mutex::new(false)),
-                frame: Arc::new(Mutex::new(MonitorFrame::default())),
-                device_context: device_context.unwrap(),
-                staging_texture,
-                desktop_size: device_size,
-                name: String::from_utf16_lossy(&desc.DeviceName),
-            }))
+            Ok(Some(cropped))
         }
     }
 
-    /// creates a texture that can be used to copy GPU based monitor data to the CPU
-    fn create_staging_texture(
-        device: &ID3D11Device,
-        device_size: &Dimensions,
-    ) -> Result<ID3D11Texture2D, windows::core::Error> {
-        let desc = D3D11_TEXTURE2D_DESC {
-            Width: device_size.width,
-            Height: device_size.height,
-            MipLevels: 1,
-            ArraySize: 1,
-            Format: DXGI_FORMAT_B8G8R8A8_UNORM, // Standard for Desktop Duplication
-            SampleDesc: DXGI_SAMPLE_DESC {
-                Count: 1,
-                Quality: 0,
-            },
-            Usage: D3D11_USAGE_STAGING, // Crucial: Allows CPU reading
-            BindFlags: D3D11_BIND_FLAG(0).0 as u32, // Staging textures have no bind flags
-            CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32, // Allows us to call .Map()
-            MiscFlags: D3D11_RESOURCE_MISC_FLAG(0).0 as u32,
-        };
-
-        let mut staging_texture = None;
-        unsafe {
-            device.CreateTexture2D(&desc, None, Some(&mut staging_texture))?;
-        }
+    // the last known pointer position, moved from the monitor to the coordinates of the frames that are sent
+    async fn frame_pointer(&self) -> Option<PointerPosition> {
+        let position = self.pointer.lock().await.position.clone()?;
+        let output = self.output.lock().unwrap();
+        let desktop_size = rotated_size(&output.surface_size, output.rotation);
+        let frame_size = rotated_size(&output.size, output.rotation);
 
-        Ok(staging_texture.unwrap())
+        Some(position.scale(&desktop_size, &frame_size))
     }
 
-    /// Using the device's context map the staging texture to contain the monitor frame data
-    ///
-    /// Once mapped copy from the raw frame data into a Vec<u8>
-    fn map_resource(&self) -> Result<Vec<u8>, windows::core::Error> {
-        //we now have access to the data
-        let mut mapped_resource = D3D11_MAPPED_SUBRESOURCE::default();
+    // reads the pointer position and shape of the acquired frame and sends any changes through the pointer sender
+    async unsafe fn update_pointer(
+        &self,
+        frame_info: &DXGI_OUTDUPL_FRAME_INFO,
+    ) -> Result<(), windows::core::Error> {
+        let mut update = PointerUpdate::default();
 
-        unsafe {
-            self.device_context.Map(
-                &self.staging_texture,
-                0,
-                D3D11_MAP_READ,
-                0,
-                Some(&mut mapped_resource),
-            )?;
+        //the position is only valid when the mouse was updated
+        if frame_info.LastMouseUpdateTime != 0 {
+            update.position = Some(frame_info.PointerPosition.into());
         }
 
-        let row_pitch = mapped_resource.RowPitch as usize;
-        let total_size_bytes = row_pitch * self.desktop_size.height as usize;
-
-        let data: Option<Vec<u8>>;
-
-        unsafe {
-            data = Some(
-                std::slice::from_raw_parts(mapped_resource.pData as *const u8, total_size_bytes)
-                    .to_vec(),
-            );
+        //the shape only changed when there is a buffer for it
+        if frame_info.PointerShapeBufferSize > 0 {
+            let mut buffer = vec![0u8; frame_info.PointerShapeBufferSize as usize];
+            let mut required_size = 0;
+            let mut shape_info = DXGI_OUTDUPL_POINTER_SHAPE_INFO::default();
+
+            unsafe {
+                self.duplication()?.GetFramePointerShape(
+                    buffer.len() as u32,
+                    buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                    &mut required_size,
+                    &mut shape_info,
+                )?;
+            }
 
-            //release all data.
-            self.device_context.Unmap(&self.staging_texture, 0);
+            buffer.truncate(required_size as usize);
+            update.shape = Some(PointerShape::new(&shape_info, buffer));
         }
 
-        if data.is_none() {
-            return Err(windows::Win32::Foundation::E_FAIL.into());
+        if update.position.is_none() && update.shape.is_none() {
+            return Ok(());
         }
 
-        Ok(data.unwrap())
-    }
+        {
+            let mut pointer = self.pointer.lock().await;
 
-    // releases the frames and readies the monitor for another batch of duplication
-    async unsafe fn release_frames(&self) -> Result<(), windows::core::Error> {
-        unsafe {
-            //release the frames
-            self.duplication_output.ReleaseFrame()?;
+            if update.position.is_some() {
+                pointer.position = update.position.clone();
+            }
+
+            if update.shape.is_some() {
+                pointer.shape = update.shape.clone();
+            }
         }
-        self.frame.lock().await.acquired_image = None;
+
+        //never block capturing on the pointer, the last state can always be retrieved with get_pointer
+        let _ = self.pointer_sender.try_send(update);
+
         Ok(())
     }
 
     /// acquires a monitory frame based on previous monitor frames
-    async unsafe fn acquire_data(&self) -> Result<MonitorFrame, windows::core::Error> {
-        let timeout_ms = 500;
-        let mut desktop_resource = None;
-        let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
+    async unsafe fn acquire_data(
+        &self,
+        timeout_ms: u32,
+    ) -> Result<MonitorFrame, windows::core::Error> {
+        let duplication = self.duplication()?;
+
+        //waits up to the timeout for the desktop to present, off the threads running async tasks
+        let (frame_info, desktop_resource) = run_blocking(move || {
+            let mut desktop_resource = None;
+            let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
+
+            unsafe {
+                duplication.AcquireNextFrame(timeout_ms, &mut frame_info, &mut desktop_resource)
+            }
+            .map(|_| (frame_info, desktop_resource))
+        })
+        .await?;
 
-        unsafe {
-            self.duplication_output.AcquireNextFrame(
-                timeout_ms,
-                &mut frame_info,
-                &mut desktop_resource,
-            )?;
-        }
+        //from here on the frame must be released, even if reading it fails
+        self.frame_acquired.store(true, Ordering::Relaxed);
 
         let desktop_resource = desktop_resource.unwrap();
         let acquired_image = Some(desktop_resource.cast::<ID3D11Texture2D>()?);
 
-        let mut frame_lock = self.frame.lock().await;
-        let required_bytes = frame_info.TotalMetadataBufferSize as usize;
-
-        if required_bytes > frame_lock.metadata_size as usize {
-            let move_unit = std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>();
-            let dirty_unit = std::mem::size_of::<RECT>();
+        //reuse the buffers of the previous frame, they are only grown when a frame has more metadata
+        let (mut moved_buffer, mut dirty_buffer) = {
+            let mut frame_lock = self.frame.lock().await;
 
-            frame_lock.moved_buffer.resize(
-                (required_bytes + move_unit - 1) / move_unit,
-                DXGI_OUTDUPL_MOVE_RECT::default(),
-            );
-            frame_lock.dirty_buffer.resize(
-                (required_bytes + dirty_unit - 1) / dirty_unit,
-                RECT::default(),
-            );
-            frame_lock.metadata_size = required_bytes as u32;
-        }
+            (
+                std::mem::take(&mut frame_lock.moved_buffer),
+                std::mem::take(&mut frame_lock.dirty_buffer),
+            )
+        };
 
-        let metadata_size = frame_lock.metadata_size;
-        let mut moved_buffer = std::mem::take(&mut frame_lock.moved_buffer);
-        let mut dirty_buffer = std::mem::take(&mut frame_lock.dirty_buffer);
-        drop(frame_lock);
+        reserve_metadata(&frame_info, &mut moved_buffer, &mut dirty_buffer);
 
+        let metadata_size = frame_info.TotalMetadataBufferSize;
         let mut move_bytes_returned = 0;
         let mut dirty_bytes_returned = 0;
 
+        //the buffer sizes are passed in bytes, they come from the buffers themselves so they are always accurate
         unsafe {
-            self.duplication_output.GetFrameMoveRects(
-                metadata_size,
+            self.duplication()?.GetFrameMoveRects(
+                metadata_bytes(&moved_buffer),
                 moved_buffer.as_mut_ptr(),
                 &mut move_bytes_returned,
             )?;
 
-            self.duplication_output.GetFrameDirtyRects(
-                metadata_size,
+            self.duplication()?.GetFrameDirtyRects(
+                metadata_bytes(&dirty_buffer),
                 dirty_buffer.as_mut_ptr(),
                 &mut dirty_bytes_returned,
             )?;
         }
 
-        let moved_count =
-            move_bytes_returned / std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>() as u32;
-        let dirty_count = dirty_bytes_returned / std::mem::size_of::<RECT>() as u32;
+        unsafe {
+            self.update_pointer(&frame_info).await?;
+        }
+
+        let moved_count = metadata_count::<DXGI_OUTDUPL_MOVE_RECT>(move_bytes_returned);
+        let dirty_count = metadata_count::<RECT>(dirty_bytes_returned);
 
         Ok(MonitorFrame {
             acquired_image,
@@ -306,13 +2620,15 @@ impl Monitor {
 }
 
 impl ICapture for Monitor {
-    type CaptureOutput = Vec<u8>;
+    type CaptureOutput = Frame;
 
     /// # Get Dimensions
     ///
-    /// Clones the demisions of the monitor
+    /// Clones the demisions of the frames sent, this is the size of the monitor unless an output size was set.
     fn get_dimensions(&self) -> Result<Dimensions, Box<dyn std::error::Error>> {
-        Ok(self.desktop_size.clone())
+        let output = self.output.lock().unwrap();
+
+        Ok(rotated_size(&output.size, output.rotation))
     }
 
     /// # Stop Cloning
@@ -330,6 +2646,11 @@ impl ICapture for Monitor {
             }
 
             *is_sending = false;
+            *self.state.lock().unwrap() = CaptureState::Stopping;
+
+            //the loop may be waiting on a full channel
+            self.stop_requested.notify_one();
+
             Ok(())
         })
     }
@@ -344,82 +2665,539 @@ impl ICapture for Monitor {
     ///
     /// ## Warning
     ///
-    /// It is very important to note that this operation may only occurr on the main thread and is thread blocking.
+    /// The future does not end until capturing is stopped. Waiting for frames and reading them back run on tokio's blocking threads, so other tasks on the runtime are not held up by it.
     ///
     /// You must start a task that reads the data before starting cloning, you can then stop cloning the data inside of the newly started task.
+    ///
+    /// spawn_capturing runs this on its own task and returns a handle instead, it is the recommended way to capture.
+    ///
+    /// A monitor captures once at a time, starting it while it is capturing (or taking snapshots) fails with an error.
     fn start_capturing(
         self: Arc<Self>,
     ) -> std::pin::Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>>
     {
-        Box::pin(async move {
+        Box::pin(async move { self.run(unsafe { self.capture_loop() }).await })
+    }
+
+    fn clone_receiver(&self) -> Arc<Mutex<Receiver<Self::CaptureOutput>>> {
+        self.receiver.clone()
+    }
+}
+
+impl Monitor {
+    // acquires the next frame and copies it, None when there is nothing to send for it
+    async unsafe fn capture_next(
+        self: &Arc<Self>,
+    ) -> Result<Option<(Frame, FrameLatency)>, windows::core::Error> {
+        unsafe {
+            //retrieve the monitor frame currently, using the previous monitor frame on the self
+            let monitor_frame = self.acquire_data(ACQUIRE_TIMEOUT_MS).await?;
+            let acquired = Instant::now();
+            let pointer = self.frame_pointer().await;
+
+            //the desktop may have presented several frames since the last acquire, only the newest is kept
+            let accumulated = (monitor_frame.frame_info.AccumulatedFrames as u64).max(1);
+            let sequence = self.stats.next_sequence(accumulated);
+            self.stats.add_captured(1);
+            self.stats.add_dropped(accumulated - 1);
+
+            //no new image was presented and nothing was moved or dirtied
+            let unchanged = monitor_frame.frame_info.LastPresentTime == 0
+                && monitor_frame.frame_info.TotalMetadataBufferSize == 0;
+
+            if self.awaiting_content(&monitor_frame)
+                || (unchanged && self.skip_unchanged.load(Ordering::Relaxed))
+            {
+                *self.frame.lock().await = monitor_frame;
+                self.release_frames().await?;
+                self.stats.add_skipped(1);
+                return Ok(None);
+            }
+
+            // update our current monitor frame with the newly acquired one
+            *self.frame.lock().await = monitor_frame;
+
+            //mapping the staging texture waits on the GPU and the rows are copied on the CPU, neither may hold up async tasks
+            let monitor = self.clone();
+            let data = run_blocking(move || {
+                let frame_lock = monitor.frame.blocking_lock();
+                monitor.copy_image(&frame_lock, sequence, acquired, pointer)
+            })
+            .await;
+
+            //the image is copied (or failed to be), release it before any error is returned
+            self.release_frames().await?;
+
+            //the first images only fill the staging textures
+            data
+        }
+    }
+
+    // acquires the next frame and copies it to a shared texture, None when there is nothing to send for it
+    async unsafe fn capture_texture(&self) -> Result<Option<SharedTexture>, windows::core::Error> {
+        unsafe {
+            let monitor_frame = self.acquire_data(ACQUIRE_TIMEOUT_MS).await?;
+
+            let accumulated = (monitor_frame.frame_info.AccumulatedFrames as u64).max(1);
+            let sequence = self.stats.next_sequence(accumulated);
+            self.stats.add_captured(1);
+            self.stats.add_dropped(accumulated - 1);
+
+            let unchanged = monitor_frame.frame_info.LastPresentTime == 0
+                && monitor_frame.frame_info.TotalMetadataBufferSize == 0;
+
+            let texture = if self.awaiting_content(&monitor_frame)
+                || (unchanged && self.skip_unchanged.load(Ordering::Relaxed))
             {
-                let mut sending_lock = self.is_sending.lock().await;
+                self.stats.add_skipped(1);
+                Ok(None)
+            } else {
+                self.copy_to_shared(&monitor_frame, sequence).map(Some)
+            };
+
+            *self.frame.lock().await = monitor_frame;
+
+            //the image is copied (or failed to be), release it before any error is returned
+            self.release_frames().await?;
+
+            texture
+        }
+    }
+
+    // copies the acquired image to a shared texture from the pool and hands the keyed mutex to the consumer
+    unsafe fn copy_to_shared(
+        &self,
+        monitor_frame: &MonitorFrame,
+        sequence: u64,
+    ) -> Result<SharedTexture, windows::core::Error> {
+        let image = monitor_frame.acquired_image.as_ref().unwrap();
 
-                if *sending_lock {
-                    return Err("you are already cloning data".into());
+        unsafe {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            image.GetDesc(&mut desc);
+
+            let size = Dimensions {
+                width: desc.Width,
+                height: desc.Height,
+            };
+
+            //taken with the capture key, the copy is only seen by the consumer once it is released with the read key
+            let mut texture = self.texture_pool.take(&self.device, &size, desc.Format)?;
+
+            self.device_context.CopyResource(texture.texture(), image);
+            texture.keyed_mutex().ReleaseSync(SharedTexture::READ_KEY)?;
+
+            texture.rotation = self.rotation();
+            texture.sequence = sequence;
+            texture.dirty_rects = monitor_frame
+                .dirty_regions()
+                .chain(
+                    monitor_frame
+                        .moved_regions()
+                        .map(|move_rect| move_rect.destination),
+                )
+                .map(|rect| clamp_rect(&rect, &size))
+                .collect();
+
+            Ok(texture)
+        }
+    }
+
+    // acquires frames and sends them as shared textures until capturing is stopped, see set_texture_output
+    async unsafe fn texture_loop(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut next_frame = Instant::now();
+
+        loop {
+            if !*self.is_sending.lock().await {
+                break;
+            }
+
+            let max_fps = self.get_max_fps();
+
+            if max_fps.is_some() {
+                tokio::time::sleep_until(next_frame).await;
+            } else if self.get_vsync() {
+                let output = self.monitor_output.clone();
+                run_blocking(move || unsafe { output.WaitForVBlank() }).await?;
+            }
+
+            let texture = match unsafe { self.capture_texture().await } {
+                Ok(Some(texture)) => texture,
+                Ok(None) => continue,
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => continue,
+                Err(e) => {
+                    unsafe { self.recover_access(e).await? };
+                    continue;
                 }
+            };
 
-                *sending_lock = true;
+            if !self
+                .send_output(&self.texture_sender, &self.texture_receiver, texture)
+                .await?
+            {
+                break;
             }
 
-            loop {
-                //take the lock, the value, and drop
-                let is_sending_currently = { *self.is_sending.lock().await };
-                if !is_sending_currently {
-                    break;
+            if let Some(max_fps) = max_fps {
+                next_frame += Duration::from_secs(1) / max_fps;
+                next_frame = next_frame.max(Instant::now());
+            }
+        }
+
+        Ok(())
+    }
+
+    // marks the monitor as capturing and runs the loop, sending the lifecycle events and cleaning up however it ends
+    async fn run(
+        &self,
+        capture: impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        {
+            let mut sending_lock = self.is_sending.lock().await;
+
+            if *sending_lock {
+                return Err("you are already cloning data".into());
+            }
+
+            *sending_lock = true;
+
+            //set under the lock so a stop right after starting is not overwritten
+            *self.state.lock().unwrap() = CaptureState::Running;
+        }
+
+        //clears the flag however the loop ends, so the monitor can always be started again
+        let guard = CaptureGuard::new(&self.is_sending, Some(&self.state));
+
+        self.capture_start.store(qpc_now(), Ordering::Relaxed);
+        self.fps.reset();
+        self.change_filter.lock().unwrap().reset();
+        self.duplicate_filter.lock().unwrap().reset();
+        self.running.send_replace(true);
+        self.send_event(CaptureEvent::Started);
+
+        let result = capture.await;
+
+        //sent however the loop ended, even when the frame receiver was dropped
+        let error = result.as_ref().err().map(|e| e.as_ref() as _);
+        for event in CaptureEvent::ended(error) {
+            self.send_event(event);
+        }
+
+        //the loop may have ended on an error while still holding a frame
+        if self.frame_acquired.load(Ordering::Relaxed) {
+            let _ = unsafe { self.release_frames().await };
+        }
+
+        guard.finish(&result).await;
+        self.fps.reset();
+        self.running.send_replace(false);
+
+        result.map_err(|e| e as Box<dyn std::error::Error>)
+    }
+
+    // whether anyone reads frames besides the receiver, see subscribe and watch_latest
+    fn has_subscribers(&self) -> bool {
+        self.broadcast_sender.receiver_count() > 0 || self.latest_sender.receiver_count() > 0
+    }
+
+    // sends the frame to the subscribers and makes it the latest frame
+    fn share_frame(&self, frame: Arc<Frame>) {
+        //fails only when the last subscriber was just dropped
+        let _ = self.broadcast_sender.send(frame.clone());
+        self.latest_sender.send_replace(Some(frame));
+    }
+
+    // sends the frame to the subscribers and through the receiver, false when a stop request ended the wait for room in the channel
+    async fn send_frame(
+        &self,
+        frame: Frame,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.get_receiver_output() {
+            if self.has_subscribers() {
+                self.share_frame(Arc::new(frame));
+                self.stats.add_delivered(1);
+                self.fps.record();
+            } else {
+                self.stats.add_dropped(1);
+            }
+
+            return Ok(true);
+        }
+
+        if self.has_subscribers() {
+            //the subscribers share one copy, the receiver gets the frame itself
+            self.share_frame(Arc::new(frame.clone()));
+        }
+
+        self.send_output(&self.sender, &self.receiver, frame).await
+    }
+
+    // sends the output as the delivery policy says, a frame or a shared texture
+    async fn send_output<T: Send>(
+        &self,
+        sender: &Sender<T>,
+        receiver: &Mutex<Receiver<T>>,
+        output: T,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let output = match try_deliver(self.get_delivery_policy(), sender, receiver, output) {
+            Delivery::Sent { replaced } => {
+                if replaced {
+                    self.stats.add_replaced(1);
                 }
 
-                unsafe {
-                    //retrieve the monitor frame currently, using the previous monitor frame on the self
-                    let monitor_frame = self.acquire_data().await;
+                self.stats.add_delivered(1);
+                self.fps.record();
+                return Ok(true);
+            }
+            Delivery::Dropped { replaced } => {
+                if replaced {
+                    self.stats.add_replaced(1);
+                }
 
-                    if let Err(e) = monitor_frame {
-                        //this is forgiveable, just no new data was accquired within the specified window time.
-                        if e.code() == DXGI_ERROR_WAIT_TIMEOUT.into() {
-                            continue;
-                        }
+                self.stats.add_dropped(1);
+                return Ok(true);
+            }
+            Delivery::Wait(output) => output,
+        };
 
-                        // this is another error.
-                        return Err(e.into());
+        //a stop request ends the wait so the loop is never stuck on a full channel
+        let permit = loop {
+            tokio::select! {
+                permit = sender.reserve() => break Some(permit),
+                _ = self.stop_requested.notified() => {
+                    //a stop requested before this frame leaves a stale notification behind
+                    if !*self.is_sending.lock().await {
+                        break None;
                     }
+                }
+            }
+        };
+
+        match permit {
+            Some(Ok(permit)) => {
+                permit.send(output);
+                self.stats.add_delivered(1);
+                self.fps.record();
+                Ok(true)
+            }
+            Some(Err(e)) => {
+                self.stats.add_dropped(1);
+                Err(format!("Failed to send frame: {}", e).into())
+            }
+            None => {
+                self.stats.add_dropped(1);
+                Ok(false)
+            }
+        }
+    }
 
-                    let monitor_frame = monitor_frame.unwrap();
+    // takes and sends a snapshot every interval until capturing is stopped
+    async unsafe fn snapshot_loop(
+        self: &Arc<Self>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut next_tick = Instant::now();
 
-                    // update our current monitor frame with the newly acquired one
-                    let mut frame_lock = self.frame.lock().await;
-                    *frame_lock = monitor_frame;
+        //sent again when nothing was presented since it was taken
+        let mut last_snapshot: Option<Frame> = None;
 
-                    self.device_context.CopyResource(
-                        &self.staging_texture,
-                        frame_lock.acquired_image.as_ref().unwrap(),
-                    );
+        loop {
+            let stop_requested = tokio::select! {
+                _ = tokio::time::sleep_until(next_tick) => false,
+                _ = self.stop_requested.notified() => true,
+            };
+
+            if !*self.is_sending.lock().await {
+                break;
+            }
+
+            //a stop requested before snapshots were started leaves a stale notification behind
+            if stop_requested {
+                continue;
+            }
 
-                    drop(frame_lock);
+            //only the image presented by the time of the tick is wanted, unless there is nothing to send yet
+            let timeout_ms = match last_snapshot {
+                Some(_) => 0,
+                None => ACQUIRE_TIMEOUT_MS,
+            };
 
-                    //flush the context of the copied resource.
-                    self.device_context.Flush();
+            let frame = match unsafe { self.snapshot(timeout_ms).await } {
+                Ok(Some(mut frame)) => {
+                    frame.sequence = self.stats.next_sequence(1);
+                    self.stats.add_captured(1);
 
-                    let data = self.map_resource()?;
+                    last_snapshot = Some(frame.clone());
+                    Some(frame)
+                }
+                //nothing new was presented, the screen still shows the last snapshot
+                Ok(None) => None,
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => None,
+                Err(e) => {
+                    unsafe { self.recover_access(e).await? };
+
+                    //the format or contents of the desktop may have changed while access was lost
+                    last_snapshot = None;
+                    continue;
+                }
+            };
 
-                    let send_res = self.sender.send(data).await;
+            let pointer = self.frame_pointer().await;
+            let frame = frame.or_else(|| {
+                last_snapshot.clone().map(|mut frame| {
+                    frame.timestamp = std::time::Instant::now();
+                    frame.dirty_rects.clear();
+                    frame.move_rects.clear();
+                    frame.pointer = pointer;
+                    frame
+                })
+            });
+
+            let frame = match frame {
+                Some(frame) => unsafe { self.crop_to_window(frame)? },
+                None => None,
+            };
 
-                    self.release_frames().await?;
+            if let Some(frame) = frame {
+                self.send_thumbnail(&frame);
 
-                    if let Err(e) = send_res {
-                        return Err(format!("Failed to send frame: {}", e).into());
-                    }
+                if !self.send_frame(frame).await? {
+                    break;
                 }
             }
 
-            Ok(())
-        })
+            //read every tick so the interval can be changed while running
+            let interval = self.get_snapshot_interval();
+            let now = Instant::now();
+
+            next_tick += interval;
+            while next_tick <= now {
+                next_tick += interval;
+            }
+        }
+
+        Ok(())
     }
 
-    fn clone_receiver(&self) -> Arc<Mutex<Receiver<Self::CaptureOutput>>> {
-        self.receiver.clone()
+    // acquires, copies and sends frames until capturing is stopped
+    // errors are Send so they can be held while the frame is released
+    async unsafe fn capture_loop(
+        self: &Arc<Self>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.get_texture_output() {
+            return unsafe { self.texture_loop().await };
+        }
+
+        //when the next frame is due, only used when the frame rate is limited
+        let mut next_frame = Instant::now();
+
+        //when the last sent frame was captured, to measure the frame interval
+        let mut last_timestamp: Option<std::time::Instant> = None;
+
+        loop {
+            //take the lock, the value, and drop
+            let is_sending_currently = { *self.is_sending.lock().await };
+            if !is_sending_currently {
+                break;
+            }
+
+            let max_fps = self.get_max_fps();
+
+            if max_fps.is_some() {
+                tokio::time::sleep_until(next_frame).await;
+            } else if self.get_vsync() {
+                //WaitForVBlank blocks the thread until the next refresh of the monitor
+                let output = self.monitor_output.clone();
+                run_blocking(move || unsafe { output.WaitForVBlank() }).await?;
+            }
+
+            let (data, mut latency) = match unsafe { self.capture_next().await } {
+                Ok(Some(read)) => read,
+                //nothing to send yet
+                Ok(None) => continue,
+                //this is forgiveable, just no new data was accquired within the specified window time.
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => continue,
+                //the secure desktop or lock screen took the desktop away, wait until it can be duplicated again
+                Err(e) => {
+                    unsafe { self.recover_access(e).await? };
+                    continue;
+                }
+            };
+
+            let Some(data) = (unsafe { self.crop_to_window(data)? }) else {
+                self.stats.add_skipped(1);
+                continue;
+            };
+
+            if !self.duplicate_filter.lock().unwrap().passes(&data) {
+                self.stats.add_suppressed(1);
+                continue;
+            }
+
+            if !self.change_filter.lock().unwrap().passes(&data) {
+                self.stats.add_skipped(1);
+                continue;
+            }
+
+            self.send_thumbnail(&data);
+
+            let timestamp = data.timestamp;
+            let send_started = std::time::Instant::now();
+
+            if !self.send_frame(data).await? {
+                break;
+            }
+
+            latency.send = send_started.elapsed();
+            latency.total = timestamp.elapsed();
+            self.latency.record(latency);
+
+            if let Some(last_timestamp) = last_timestamp {
+                let interval = timestamp.duration_since(last_timestamp).as_nanos() as u64;
+                self.frame_interval
+                    .store(interval.max(1), Ordering::Relaxed);
+            }
+            last_timestamp = Some(timestamp);
+
+            //schedule from the last due time so the rate does not drift, unless we fell behind
+            if let Some(max_fps) = max_fps {
+                next_frame += Duration::from_secs(1) / max_fps;
+                next_frame = next_frame.max(Instant::now());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Monitor {
+    /// Leaves the duplication in a state where the monitor can be duplicated again.
+    ///
+    /// The capture loop holds onto the monitor, so it has already exited by the time the monitor is dropped. Anything it left behind is released here.
+    fn drop(&mut self) {
+        //nothing can be waiting on the lock once the monitor is being dropped
+        if let Ok(mut is_sending) = self.is_sending.try_lock() {
+            *is_sending = false;
+        }
+
+        //staging textures are never left mapped, see MappedStaging
+        //errors cannot be returned from drop, releasing is best effort
+        if *self.frame_acquired.get_mut() {
+            unsafe {
+                let duplication = self
+                    .duplication_output
+                    .get_mut()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+                if let Some(duplication) = duplication {
+                    let _ = duplication.ReleaseFrame();
+                }
+            }
+        }
     }
 }
 
+// the D3D11 device is free threaded and the immediate context is multithread protected (see create_device)
+// the duplication and the staging textures are only used behind their locks
 unsafe impl Send for Monitor {}
 
 unsafe impl Sync for Monitor {}
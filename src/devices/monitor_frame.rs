@@ -2,41 +2,144 @@ use windows::Win32::Foundation::*;
 use windows::Win32::Graphics::Direct3D11::*;
 use windows::Win32::Graphics::Dxgi::*;
 
+use crate::frame::{MoveRect, Rect};
+
 /// # Monitor Frame
 /// 
 /// Represents a captured singular frame from a Monitor struct.
+///
+/// The frame holds the acquired texture, keep its metadata with clone_metadata instead of holding onto the frame.
+#[derive(Default)]
 pub struct MonitorFrame {
     /// The image acquired from the monitor
     pub acquired_image: Option<ID3D11Texture2D>,
-    /// The size of the buffers.
+    /// The size in bytes of the frame's metadata (move and dirty rects), the buffers are at least this large.
     pub metadata_size: u32,
 
-    /// Frames that moved
-    pub moved_buffer: Vec<DXGI_OUTDUPL_MOVE_RECT>,
+    // the move rects of the frame, larger than the number returned, see move_rects
+    pub(crate) moved_buffer: Vec<DXGI_OUTDUPL_MOVE_RECT>,
+
+    // the dirty rects of the frame, larger than the number returned, see dirty_rects
+    pub(crate) dirty_buffer: Vec<RECT>,
+
+    // the number of dirty rects written to the start of the dirty buffer
+    pub(crate) dirty_count: u32,
+
+    // the number of move rects written to the start of the moved buffer
+    pub(crate) moved_count: u32,
+
+    /// Info from the frame, containing meta data.
+    pub frame_info: DXGI_OUTDUPL_FRAME_INFO,
+}
 
-    /// Dirty frames from the monitor
-    pub dirty_buffer: Vec<RECT>,
+/// # Frame Metadata
+///
+/// The metadata of a MonitorFrame without its texture or buffers, cheap to clone and keep around such as for comparing with the next frame. See MonitorFrame::clone_metadata.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameMetadata {
+    /// The areas of the desktop image that changed, see MonitorFrame::dirty_rects.
+    pub dirty_rects: Vec<Rect>,
 
-    /// Count of the dirty frames
-    pub dirty_count: u32,
+    /// The areas of the desktop image that were moved, see MonitorFrame::move_rects.
+    pub move_rects: Vec<MoveRect>,
 
-    /// Moved frames count
-    pub moved_count: u32,
+    /// The size in bytes of the frame's metadata as reported by the duplication.
+    pub metadata_size: u32,
 
     /// Info from the frame, containing meta data.
     pub frame_info: DXGI_OUTDUPL_FRAME_INFO,
 }
 
-impl Default for MonitorFrame {
-    fn default() -> Self {
-        Self {
-            acquired_image: None,
-            moved_buffer: vec![],
-            dirty_buffer: vec![],
-            metadata_size: 0,
-            dirty_count: 0,
-            moved_count: 0,
-            frame_info: DXGI_OUTDUPL_FRAME_INFO::default(),
+impl MonitorFrame {
+    /// # Dirty Rects
+    ///
+    /// The areas of the desktop image that changed since the previous frame, the entries of the buffer past the returned count are left out.
+    pub fn dirty_rects(&self) -> &[RECT] {
+        &self.dirty_buffer[..(self.dirty_count as usize).min(self.dirty_buffer.len())]
+    }
+
+    /// # Move Rects
+    ///
+    /// The areas of the desktop image that were moved since the previous frame, the entries of the buffer past the returned count are left out.
+    pub fn move_rects(&self) -> &[DXGI_OUTDUPL_MOVE_RECT] {
+        &self.moved_buffer[..(self.moved_count as usize).min(self.moved_buffer.len())]
+    }
+
+    /// The dirty rects as Rects, see dirty_rects.
+    pub fn dirty_regions(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.dirty_rects().iter().map(|rect| Rect::from(*rect))
+    }
+
+    /// The move rects as MoveRects, see move_rects.
+    pub fn moved_regions(&self) -> impl Iterator<Item = MoveRect> + '_ {
+        self.move_rects().iter().map(|move_rect| MoveRect::from(*move_rect))
+    }
+
+    /// # Clone Metadata
+    ///
+    /// Copies the rects and frame info of the frame, without the texture and only the rects that were returned.
+    pub fn clone_metadata(&self) -> FrameMetadata {
+        FrameMetadata {
+            dirty_rects: self.dirty_regions().collect(),
+            move_rects: self.moved_regions().collect(),
+            metadata_size: self.metadata_size,
+            frame_info: self.frame_info,
         }
     }
 }
+
+impl std::fmt::Debug for MonitorFrame {
+    //the buffers are left out, they hold stale entries past the counts
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let info = &self.frame_info;
+
+        f.debug_struct("MonitorFrame")
+            .field("acquired_image", &self.acquired_image.is_some())
+            .field("metadata_size", &self.metadata_size)
+            .field("dirty_count", &self.dirty_count)
+            .field("moved_count", &self.moved_count)
+            .field("last_present_time", &info.LastPresentTime)
+            .field("last_mouse_update_time", &info.LastMouseUpdateTime)
+            .field("accumulated_frames", &info.AccumulatedFrames)
+            .field("rects_coalesced", &info.RectsCoalesced.as_bool())
+            .field(
+                "protected_content_masked_out",
+                &info.ProtectedContentMaskedOut.as_bool(),
+            )
+            .field("pointer_visible", &info.PointerPosition.Visible.as_bool())
+            .finish()
+    }
+}
+
+/// # Reserve Metadata
+///
+/// Grows the move and dirty buffers so each can hold all of the frame's metadata.
+///
+/// TotalMetadataBufferSize is in bytes, so the number of elements needed depends on the size of each element.
+pub(crate) fn reserve_metadata(
+    frame_info: &DXGI_OUTDUPL_FRAME_INFO,
+    moved_buffer: &mut Vec<DXGI_OUTDUPL_MOVE_RECT>,
+    dirty_buffer: &mut Vec<RECT>,
+) {
+    let required_bytes = frame_info.TotalMetadataBufferSize as usize;
+
+    let moved_len = required_bytes.div_ceil(std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>());
+    if moved_buffer.len() < moved_len {
+        moved_buffer.resize(moved_len, DXGI_OUTDUPL_MOVE_RECT::default());
+    }
+
+    let dirty_len = required_bytes.div_ceil(std::mem::size_of::<RECT>());
+    if dirty_buffer.len() < dirty_len {
+        dirty_buffer.resize(dirty_len, RECT::default());
+    }
+}
+
+/// The size of a metadata buffer in bytes, this is what GetFrameMoveRects and GetFrameDirtyRects expect.
+pub(crate) fn metadata_bytes<T>(buffer: &[T]) -> u32 {
+    std::mem::size_of_val(buffer) as u32
+}
+
+/// The number of elements written to a metadata buffer from the number of bytes returned.
+pub(crate) fn metadata_count<T>(bytes_returned: u32) -> u32 {
+    bytes_returned / std::mem::size_of::<T>() as u32
+}
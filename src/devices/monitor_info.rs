@@ -1,22 +1,93 @@
+use crate::{devices::{ColorInfo, Dimensions}, frame::Rect};
+
 /// # Monitor Info
 /// 
 /// Pertinent info on a monitor, can be used for selection and creation of a Monitor struct
+#[derive(Clone, Debug)]
 pub struct MonitorInfo {
 
     /// The device name of the adapter or monitor.
     pub name: String,
 
+    /// The name of the monitor as shown to users, such as "DELL U2720Q".
+    ///
+    /// The same as name when Windows does not know a friendly name for the monitor.
+    pub friendly_name: String,
+
     /// The description of the display adapter or the display monitor
     pub description: String,
 
     /// The monitor index. Based on all of your monitors.
     /// 
     /// For example if you have two monitors this may be 0 or 1 and so on
-    pub index: u32
+    pub index: u32,
+
+    /// The index of the adapter the monitor is attached to, can be passed to Monitor::from_adapter_output.
+    ///
+    /// Left at its default by MonitorInfo::new, every enumeration fills it in.
+    pub adapter_index: u32,
+
+    /// The index of the monitor on its adapter, can be passed to Monitor::from_adapter_output.
+    ///
+    /// Left at its default by MonitorInfo::new, every enumeration fills it in.
+    pub output_index: u32,
+
+    /// Where the monitor is on the virtual desktop, monitors left of or above the primary monitor have negative coordinates.
+    pub desktop_coordinates: Rect,
+
+    /// Whether the monitor is part of the desktop, detached monitors cannot be captured.
+    pub attached_to_desktop: bool,
+
+    /// Whether this is the primary monitor, its top left is always at 0, 0 of the desktop.
+    pub is_primary: bool,
+
+    /// The refresh rate of the current display mode in hertz, 0 if unknown.
+    pub refresh_rate: u32,
+
+    /// The effective DPI of the monitor, 96 is a scale of 100%.
+    pub dpi: u32,
+
+    /// The scale the monitor is displayed at, for example 1.5 for 150%.
+    pub scale_factor: f32,
+
+    /// The color space, bit depth and brightness of the monitor, None when Windows cannot report them (before Windows 10 1703).
+    ///
+    /// Left at its default by MonitorInfo::new, every enumeration fills it in.
+    pub color_info: Option<ColorInfo>,
+
+    /// The brightness SDR content is shown at in nits while HDR is on, divide it by 80 for the white level of tone_map_frame.
+    ///
+    /// None when it cannot be read, before Windows 10 1709.
+    pub sdr_white_level: Option<f32>,
 }
 
 impl MonitorInfo {
     pub fn new(name: String, desc: String, index: u32) -> Self {
-        return MonitorInfo { name, description: desc, index };
+        return MonitorInfo {
+            friendly_name: name.clone(),
+            name,
+            description: desc,
+            index,
+            adapter_index: 0,
+            output_index: 0,
+            desktop_coordinates: Rect::default(),
+            attached_to_desktop: false,
+            is_primary: false,
+            refresh_rate: 0,
+            dpi: 96,
+            scale_factor: 1.0,
+            color_info: None,
+            sdr_white_level: None,
+        };
+    }
+
+    /// # Size
+    ///
+    /// The size of the monitor on the desktop, this is the desktop_size of a Monitor created from it.
+    pub fn size(&self) -> Dimensions {
+        Dimensions {
+            width: self.desktop_coordinates.width(),
+            height: self.desktop_coordinates.height(),
+        }
     }
 }
@@ -0,0 +1,161 @@
+use std::mem::ManuallyDrop;
+
+use windows::Win32::Graphics::Direct3D11::{
+    D3D11_BIND_RENDER_TARGET, D3D11_TEX2D_VPIV, D3D11_TEX2D_VPOV, D3D11_TEXTURE2D_DESC,
+    D3D11_USAGE_DEFAULT, D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE, D3D11_VIDEO_PROCESSOR_CONTENT_DESC,
+    D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC, D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0,
+    D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC, D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0,
+    D3D11_VIDEO_PROCESSOR_STREAM, D3D11_VIDEO_USAGE_PLAYBACK_NORMAL,
+    D3D11_VPIV_DIMENSION_TEXTURE2D, D3D11_VPOV_DIMENSION_TEXTURE2D, ID3D11Device,
+    ID3D11DeviceContext, ID3D11Texture2D, ID3D11VideoContext, ID3D11VideoDevice,
+    ID3D11VideoProcessor, ID3D11VideoProcessorEnumerator, ID3D11VideoProcessorOutputView,
+};
+use windows::Win32::Graphics::Dxgi::Common::{
+    DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_RATIONAL, DXGI_SAMPLE_DESC,
+};
+use windows::core::Interface;
+
+use crate::devices::Dimensions;
+
+/// # Monitor Scaler
+///
+/// Scales acquired monitor images on the GPU using the D3D11 video processor.
+///
+/// The scaled image is written to an output texture which can then be copied to a staging texture, this avoids copying the full size image to the CPU.
+pub(crate) struct MonitorScaler {
+    video_device: ID3D11VideoDevice,
+    video_context: ID3D11VideoContext,
+
+    enumerator: ID3D11VideoProcessorEnumerator,
+    processor: ID3D11VideoProcessor,
+
+    // the texture the scaled image is rendered to, and the view the processor writes through
+    output_texture: ID3D11Texture2D,
+    output_view: ID3D11VideoProcessorOutputView,
+}
+
+impl MonitorScaler {
+    /// Create a scaler that scales images of the input size to the output size.
+    pub(crate) unsafe fn new(
+        device: &ID3D11Device,
+        device_context: &ID3D11DeviceContext,
+        input_size: &Dimensions,
+        output_size: &Dimensions,
+    ) -> Result<Self, windows::core::Error> {
+        unsafe {
+            let video_device: ID3D11VideoDevice = device.cast()?;
+            let video_context: ID3D11VideoContext = device_context.cast()?;
+
+            //the frame rate is only a hint for the processor, it does not limit the capture
+            let frame_rate = DXGI_RATIONAL {
+                Numerator: 60,
+                Denominator: 1,
+            };
+
+            let content_desc = D3D11_VIDEO_PROCESSOR_CONTENT_DESC {
+                InputFrameFormat: D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                InputFrameRate: frame_rate,
+                InputWidth: input_size.width,
+                InputHeight: input_size.height,
+                OutputFrameRate: frame_rate,
+                OutputWidth: output_size.width,
+                OutputHeight: output_size.height,
+                Usage: D3D11_VIDEO_USAGE_PLAYBACK_NORMAL,
+            };
+
+            let enumerator = video_device.CreateVideoProcessorEnumerator(&content_desc)?;
+            let processor = video_device.CreateVideoProcessor(&enumerator, 0)?;
+
+            let texture_desc = D3D11_TEXTURE2D_DESC {
+                Width: output_size.width,
+                Height: output_size.height,
+                MipLevels: 1,
+                ArraySize: 1,
+                Format: DXGI_FORMAT_B8G8R8A8_UNORM, // same format as the duplicated desktop
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_RENDER_TARGET.0 as u32, // the video processor renders into it
+                CPUAccessFlags: 0,
+                MiscFlags: 0,
+            };
+
+            let mut output_texture = None;
+            device.CreateTexture2D(&texture_desc, None, Some(&mut output_texture))?;
+            let output_texture: ID3D11Texture2D = output_texture.unwrap();
+
+            let view_desc = D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC {
+                ViewDimension: D3D11_VPOV_DIMENSION_TEXTURE2D,
+                Anonymous: D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0 {
+                    Texture2D: D3D11_TEX2D_VPOV { MipSlice: 0 },
+                },
+            };
+
+            let mut output_view = None;
+            video_device.CreateVideoProcessorOutputView(
+                &output_texture,
+                &enumerator,
+                &view_desc,
+                Some(&mut output_view),
+            )?;
+
+            Ok(Self {
+                video_device,
+                video_context,
+                enumerator,
+                processor,
+                output_texture,
+                output_view: output_view.unwrap(),
+            })
+        }
+    }
+
+    /// Scales the input image into the output texture, the returned texture is only valid until the next call.
+    pub(crate) unsafe fn scale(
+        &self,
+        input: &ID3D11Texture2D,
+    ) -> Result<&ID3D11Texture2D, windows::core::Error> {
+        let input_desc = D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC {
+            FourCC: 0,
+            ViewDimension: D3D11_VPIV_DIMENSION_TEXTURE2D,
+            Anonymous: D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0 {
+                Texture2D: D3D11_TEX2D_VPIV {
+                    MipSlice: 0,
+                    ArraySlice: 0,
+                },
+            },
+        };
+
+        unsafe {
+            let mut input_view = None;
+            self.video_device.CreateVideoProcessorInputView(
+                input,
+                &self.enumerator,
+                &input_desc,
+                Some(&mut input_view),
+            )?;
+
+            let mut stream = D3D11_VIDEO_PROCESSOR_STREAM {
+                Enable: true.into(),
+                pInputSurface: ManuallyDrop::new(input_view),
+                ..Default::default()
+            };
+
+            let blt = self.video_context.VideoProcessorBlt(
+                &self.processor,
+                &self.output_view,
+                0,
+                std::slice::from_ref(&stream),
+            );
+
+            //the stream does not release the input view on its own
+            ManuallyDrop::drop(&mut stream.pInputSurface);
+
+            blt?;
+        }
+
+        Ok(&self.output_texture)
+    }
+}
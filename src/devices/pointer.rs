@@ -0,0 +1,113 @@
+#[cfg(feature = "monitor")]
+use windows::Win32::Graphics::Dxgi::{
+    DXGI_OUTDUPL_POINTER_POSITION, DXGI_OUTDUPL_POINTER_SHAPE_INFO, DXGI_OUTDUPL_POINTER_SHAPE_TYPE,
+};
+
+use crate::devices::Dimensions;
+
+/// # Pointer Position
+///
+/// Where the mouse pointer is on the monitor and if it is currently visible.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PointerPosition {
+    /// The x coordinate of the top left of the pointer, relative to the monitor.
+    pub x: i32,
+
+    /// The y coordinate of the top left of the pointer, relative to the monitor.
+    pub y: i32,
+
+    /// Whether the pointer is shown on this monitor.
+    pub visible: bool,
+}
+
+impl PointerPosition {
+    /// # Scale
+    ///
+    /// Moves the position on an image of the from size to the same place on an image of the to size.
+    pub fn scale(&self, from: &Dimensions, to: &Dimensions) -> PointerPosition {
+        if from == to {
+            return self.clone();
+        }
+
+        let scale_x = to.width as f64 / from.width as f64;
+        let scale_y = to.height as f64 / from.height as f64;
+
+        PointerPosition {
+            x: (self.x as f64 * scale_x).floor() as i32,
+            y: (self.y as f64 * scale_y).floor() as i32,
+            visible: self.visible,
+        }
+    }
+}
+
+#[cfg(feature = "monitor")]
+impl From<DXGI_OUTDUPL_POINTER_POSITION> for PointerPosition {
+    fn from(position: DXGI_OUTDUPL_POINTER_POSITION) -> Self {
+        PointerPosition {
+            x: position.Position.x,
+            y: position.Position.y,
+            visible: position.Visible.as_bool(),
+        }
+    }
+}
+
+/// # Pointer Shape
+///
+/// The raw image of the mouse pointer, this can be used to draw the pointer yourself.
+///
+/// How the buffer should be read depends on the shape type (monochrome, color or masked color).
+#[cfg(feature = "monitor")]
+#[derive(Clone, Debug)]
+pub struct PointerShape {
+    /// The type of pointer shape, determines the layout of the buffer
+    pub shape_type: DXGI_OUTDUPL_POINTER_SHAPE_TYPE,
+
+    /// Width of the pointer in pixels
+    pub width: u32,
+
+    /// Height of the pointer in pixels, for monochrome pointers this contains both the AND and XOR masks.
+    pub height: u32,
+
+    /// The number of bytes in a single row of the buffer
+    pub pitch: u32,
+
+    /// The x position of the hot spot (where the click happens) relative to the top left of the pointer
+    pub hot_spot_x: i32,
+
+    /// The y position of the hot spot (where the click happens) relative to the top left of the pointer
+    pub hot_spot_y: i32,
+
+    /// Raw pointer shape data
+    pub buffer: Vec<u8>,
+}
+
+#[cfg(feature = "monitor")]
+impl PointerShape {
+    /// Create a pointer shape from the shape info and data returned by GetFramePointerShape
+    pub fn new(info: &DXGI_OUTDUPL_POINTER_SHAPE_INFO, buffer: Vec<u8>) -> Self {
+        PointerShape {
+            shape_type: DXGI_OUTDUPL_POINTER_SHAPE_TYPE(info.Type as i32),
+            width: info.Width,
+            height: info.Height,
+            pitch: info.Pitch,
+            hot_spot_x: info.HotSpot.x,
+            hot_spot_y: info.HotSpot.y,
+            buffer,
+        }
+    }
+}
+
+/// # Pointer Update
+///
+/// Sent by a Monitor when the pointer moved or changed shape.
+///
+/// The position is only present when the mouse was updated, the shape is only present when the pointer shape changed.
+#[cfg(feature = "monitor")]
+#[derive(Clone, Debug, Default)]
+pub struct PointerUpdate {
+    /// The new position of the pointer, if it was updated
+    pub position: Option<PointerPosition>,
+
+    /// The new shape of the pointer, if it changed
+    pub shape: Option<PointerShape>,
+}
@@ -0,0 +1,122 @@
+use windows::Win32::Graphics::Dxgi::Common::{
+    DXGI_MODE_ROTATION, DXGI_MODE_ROTATION_ROTATE90, DXGI_MODE_ROTATION_ROTATE180,
+    DXGI_MODE_ROTATION_ROTATE270,
+};
+
+use crate::{devices::Dimensions, frame::Rect};
+
+/// # Swaps Dimensions
+///
+/// Whether the width and height of an image are swapped by the rotation (90 or 270 degrees).
+pub fn swaps_dimensions(rotation: DXGI_MODE_ROTATION) -> bool {
+    rotation == DXGI_MODE_ROTATION_ROTATE90 || rotation == DXGI_MODE_ROTATION_ROTATE270
+}
+
+/// # Rotated Size
+///
+/// The size of an image after the rotation is applied.
+pub fn rotated_size(size: &Dimensions, rotation: DXGI_MODE_ROTATION) -> Dimensions {
+    if swaps_dimensions(rotation) {
+        Dimensions {
+            width: size.height,
+            height: size.width,
+        }
+    } else {
+        *size
+    }
+}
+
+/// # Rotate Frame
+///
+/// Rotates a BGRA frame clockwise by the rotation of the monitor so that it matches what is displayed on screen.
+///
+/// The size is the size of the unrotated frame and the row pitch is the number of bytes in each of its rows.
+///
+/// The returned frame has no row padding, it is always width * 4 bytes per row of the rotated size.
+pub fn rotate_frame(
+    data: &[u8],
+    row_pitch: usize,
+    size: &Dimensions,
+    rotation: DXGI_MODE_ROTATION,
+) -> Vec<u8> {
+    let mut rotated = vec![];
+    rotate_frame_into(data, row_pitch, size, rotation, &mut rotated);
+
+    rotated
+}
+
+/// # Rotate Frame Into
+///
+/// The same as rotate_frame but the rotated frame is written to the given buffer, replacing its contents.
+pub fn rotate_frame_into(
+    data: &[u8],
+    row_pitch: usize,
+    size: &Dimensions,
+    rotation: DXGI_MODE_ROTATION,
+    rotated: &mut Vec<u8>,
+) {
+    rotate_pixels_into(data, row_pitch, size, rotation, 4, rotated);
+}
+
+// rotates a frame of any number of bytes per pixel, HDR frames have 8
+pub(crate) fn rotate_pixels_into(
+    data: &[u8],
+    row_pitch: usize,
+    size: &Dimensions,
+    rotation: DXGI_MODE_ROTATION,
+    pixel_bytes: usize,
+    rotated: &mut Vec<u8>,
+) {
+    let (width, height) = (size.width as usize, size.height as usize);
+    let out_size = rotated_size(size, rotation);
+    let out_width = out_size.width as usize;
+
+    rotated.clear();
+    rotated.resize(width * height * pixel_bytes, 0);
+
+    for y in 0..out_size.height as usize {
+        for x in 0..out_width {
+            //find the pixel of the unrotated frame that lands at x, y
+            let (src_x, src_y) = match rotation {
+                DXGI_MODE_ROTATION_ROTATE90 => (y, height - 1 - x),
+                DXGI_MODE_ROTATION_ROTATE180 => (width - 1 - x, height - 1 - y),
+                DXGI_MODE_ROTATION_ROTATE270 => (width - 1 - y, x),
+                _ => (x, y),
+            };
+
+            let src = src_y * row_pitch + src_x * pixel_bytes;
+            let dst = (y * out_width + x) * pixel_bytes;
+
+            rotated[dst..dst + pixel_bytes].copy_from_slice(&data[src..src + pixel_bytes]);
+        }
+    }
+}
+
+/// # Rotate Rect
+///
+/// Rotates a rect of an unrotated frame of the given size the same way rotate_frame rotates the pixels.
+pub fn rotate_rect(rect: &Rect, size: &Dimensions, rotation: DXGI_MODE_ROTATION) -> Rect {
+    let (width, height) = (size.width as i32, size.height as i32);
+
+    match rotation {
+        DXGI_MODE_ROTATION_ROTATE90 => Rect {
+            left: height - rect.bottom,
+            top: rect.left,
+            right: height - rect.top,
+            bottom: rect.right,
+        },
+        DXGI_MODE_ROTATION_ROTATE180 => Rect {
+            left: width - rect.right,
+            top: height - rect.bottom,
+            right: width - rect.left,
+            bottom: height - rect.top,
+        },
+        DXGI_MODE_ROTATION_ROTATE270 => Rect {
+            left: rect.top,
+            top: width - rect.right,
+            right: rect.bottom,
+            bottom: width - rect.left,
+        },
+        _ => *rect,
+    }
+}
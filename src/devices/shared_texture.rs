@@ -0,0 +1,279 @@
+use std::{
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, S_OK},
+    Graphics::{
+        Direct3D11::{
+            D3D11_BIND_RENDER_TARGET, D3D11_BIND_SHADER_RESOURCE,
+            D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX, D3D11_RESOURCE_MISC_SHARED_NTHANDLE,
+            D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT, ID3D11Device, ID3D11Texture2D,
+        },
+        Dxgi::{
+            Common::{DXGI_FORMAT, DXGI_MODE_ROTATION, DXGI_SAMPLE_DESC},
+            DXGI_SHARED_RESOURCE_READ, DXGI_SHARED_RESOURCE_WRITE, IDXGIKeyedMutex, IDXGIResource1,
+        },
+    },
+};
+use windows::core::{Interface, PCWSTR};
+
+use crate::{devices::Dimensions, frame::Rect};
+
+// a shareable texture with its keyed mutex and NT handle, the handle is closed when it is dropped
+struct PooledTexture {
+    texture: ID3D11Texture2D,
+    keyed_mutex: IDXGIKeyedMutex,
+    handle: HANDLE,
+    size: Dimensions,
+    format: DXGI_FORMAT,
+}
+
+impl PooledTexture {
+    unsafe fn new(
+        device: &ID3D11Device,
+        size: &Dimensions,
+        format: DXGI_FORMAT,
+    ) -> Result<Self, windows::core::Error> {
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: size.width,
+            Height: size.height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_DEFAULT,
+            //bound so other devices can render with it, NT handles need a bind flag
+            BindFlags: (D3D11_BIND_SHADER_RESOURCE.0 | D3D11_BIND_RENDER_TARGET.0) as u32,
+            CPUAccessFlags: 0,
+            MiscFlags: (D3D11_RESOURCE_MISC_SHARED_NTHANDLE.0
+                | D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX.0) as u32,
+        };
+
+        unsafe {
+            let mut texture = None;
+            device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+            let texture = texture.unwrap();
+
+            let keyed_mutex: IDXGIKeyedMutex = texture.cast()?;
+            let handle = texture.cast::<IDXGIResource1>()?.CreateSharedHandle(
+                None,
+                DXGI_SHARED_RESOURCE_READ.0 | DXGI_SHARED_RESOURCE_WRITE.0,
+                PCWSTR::null(),
+            )?;
+
+            Ok(PooledTexture {
+                texture,
+                keyed_mutex,
+                handle,
+                size: *size,
+                format,
+            })
+        }
+    }
+
+    // takes the keyed mutex for the capture without waiting, false while another device still holds it
+    // AcquireSync returns WAIT_TIMEOUT as a success code, so the result is checked for S_OK itself
+    unsafe fn try_acquire(&self) -> bool {
+        let acquire = self.keyed_mutex.vtable().AcquireSync;
+
+        unsafe { acquire(self.keyed_mutex.as_raw(), SharedTexture::CAPTURE_KEY, 0) == S_OK }
+    }
+}
+
+impl Drop for PooledTexture {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+// the textures waiting to be reused and how many may be kept
+struct TexturePoolInner {
+    textures: Mutex<Vec<PooledTexture>>,
+    max_textures: AtomicUsize,
+}
+
+// keeps shareable textures around for reuse, the same as BufferPool does for CPU buffers
+#[derive(Clone)]
+pub(crate) struct TexturePool {
+    inner: Arc<TexturePoolInner>,
+}
+
+impl TexturePool {
+    pub(crate) fn new(max_textures: usize) -> Self {
+        TexturePool {
+            inner: Arc::new(TexturePoolInner {
+                textures: Mutex::new(vec![]),
+                max_textures: AtomicUsize::new(max_textures),
+            }),
+        }
+    }
+
+    pub(crate) fn set_max_textures(&self, max_textures: usize) {
+        self.inner
+            .max_textures
+            .store(max_textures, Ordering::Relaxed);
+
+        //drop any textures we should no longer keep
+        self.inner.textures.lock().unwrap().truncate(max_textures);
+    }
+
+    // takes a texture of the size and format held by the capture (CAPTURE_KEY), creating one when none is free
+    // textures of another size or format, or whose keyed mutex was not released back with CAPTURE_KEY (still held or never acquired by the consumer), are dropped
+    pub(crate) unsafe fn take(
+        &self,
+        device: &ID3D11Device,
+        size: &Dimensions,
+        format: DXGI_FORMAT,
+    ) -> Result<SharedTexture, windows::core::Error> {
+        let mut reused = None;
+
+        {
+            let mut textures = self.inner.textures.lock().unwrap();
+
+            while let Some(texture) = textures.pop() {
+                if texture.size == *size
+                    && texture.format == format
+                    && unsafe { texture.try_acquire() }
+                {
+                    reused = Some(texture);
+                    break;
+                }
+            }
+        }
+
+        let texture = match reused {
+            Some(texture) => texture,
+            None => unsafe {
+                let texture = PooledTexture::new(device, size, format)?;
+
+                //a new keyed mutex starts out on key 0, free for the capture
+                texture
+                    .keyed_mutex
+                    .AcquireSync(SharedTexture::CAPTURE_KEY, 0)?;
+                texture
+            },
+        };
+
+        Ok(SharedTexture {
+            width: size.width,
+            height: size.height,
+            format,
+            rotation: DXGI_MODE_ROTATION::default(),
+            timestamp: Instant::now(),
+            sequence: 0,
+            dirty_rects: vec![],
+            texture: Some(texture),
+            pool: Arc::downgrade(&self.inner),
+        })
+    }
+}
+
+/// # Shared Texture
+///
+/// A monitor frame left on the GPU, sent through Monitor::texture_receiver when texture output is on (see Monitor::set_texture_output).
+///
+/// The texture can be opened on any D3D11 device with ID3D11Device1::OpenSharedResource1 and the handle, or used directly on the monitor's device.
+/// Access is guarded by its keyed mutex: acquire it with READ_KEY before using the texture and release it with CAPTURE_KEY when done.
+///
+/// The texture goes back to the monitor's pool when this is dropped, so it must not be used after that. The pool keeps as many textures as Monitor::set_texture_pool_size allows, the rest are released right away.
+///
+/// A pooled texture is only reused when its keyed mutex was released with CAPTURE_KEY. Textures still held with READ_KEY, or never acquired at all so the key is still READ_KEY, are released the next time the capture needs a texture, as are textures of an old size or format.
+pub struct SharedTexture {
+    /// The width of the texture in pixels
+    pub width: u32,
+
+    /// The height of the texture in pixels
+    pub height: u32,
+
+    /// The format of the texture, B8G8R8A8_UNORM or R16G16B16A16_FLOAT for monitors showing HDR.
+    pub format: DXGI_FORMAT,
+
+    /// The rotation of the monitor, the texture is the desktop image before it is rotated (see rotation::rotate_rect).
+    pub rotation: DXGI_MODE_ROTATION,
+
+    /// When the frame was captured
+    pub timestamp: Instant,
+
+    /// Increases with every frame of the capture starting at 1, see Frame::sequence.
+    pub sequence: u64,
+
+    /// The areas of the texture that changed since the previous frame, moved areas included. In the coordinates of the unrotated texture.
+    pub dirty_rects: Vec<Rect>,
+
+    // always Some until dropped
+    texture: Option<PooledTexture>,
+
+    pool: Weak<TexturePoolInner>,
+}
+
+impl SharedTexture {
+    /// The key the capture holds the keyed mutex with while it copies a frame in, release it with this key once done with the texture.
+    pub const CAPTURE_KEY: u64 = 0;
+
+    /// The key the keyed mutex is released with once the frame is copied in, acquire it with this key to read the texture.
+    pub const READ_KEY: u64 = 1;
+
+    /// The texture, it was created on the monitor's device.
+    pub fn texture(&self) -> &ID3D11Texture2D {
+        &self.pooled().texture
+    }
+
+    /// The keyed mutex guarding the texture, see CAPTURE_KEY and READ_KEY.
+    pub fn keyed_mutex(&self) -> &IDXGIKeyedMutex {
+        &self.pooled().keyed_mutex
+    }
+
+    /// The NT handle of the texture, it stays owned by the texture and must not be closed.
+    pub fn handle(&self) -> HANDLE {
+        self.pooled().handle
+    }
+
+    fn pooled(&self) -> &PooledTexture {
+        self.texture.as_ref().unwrap()
+    }
+}
+
+impl std::fmt::Debug for SharedTexture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedTexture")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("format", &self.format)
+            .field("sequence", &self.sequence)
+            .finish()
+    }
+}
+
+impl Drop for SharedTexture {
+    fn drop(&mut self) {
+        let Some(texture) = self.texture.take() else {
+            return;
+        };
+
+        let Some(pool) = self.pool.upgrade() else {
+            return;
+        };
+
+        let mut textures = pool.textures.lock().unwrap();
+
+        if textures.len() < pool.max_textures.load(Ordering::Relaxed) {
+            textures.push(texture);
+        }
+    }
+}
+
+// the texture is only written while the capture holds its keyed mutex and the D3D11 device is free threaded
+// the NT handle can be used from any thread
+unsafe impl Send for PooledTexture {}
+
+unsafe impl Sync for PooledTexture {}
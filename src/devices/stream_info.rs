@@ -0,0 +1,53 @@
+use windows::core::GUID;
+
+use crate::devices::Dimensions;
+
+/// # Stream Kind
+///
+/// What a stream of a camera shows, see StreamInfo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamKind {
+    /// The color image, the stream cameras are read from by default.
+    Color,
+
+    /// An infrared image, such as the one Windows Hello cameras recognize faces in. Often sent as 8 bit gray (L8).
+    Infrared,
+
+    /// The distance of every pixel from the camera, often sent as 16 bit values (D16).
+    Depth,
+
+    /// Still photos, see Camera::take_photo.
+    Photo,
+
+    /// A stream of any other kind, such as audio or metadata.
+    Other,
+}
+
+/// # Stream Info
+///
+/// A stream of a camera, see Camera::streams. Devices such as depth cameras and Windows Hello cameras send color, infrared and depth images on streams of their own.
+///
+/// Pick a video stream with Camera::select_stream, frames say which stream they came from in Frame::stream_index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// The index of the stream on the camera's source reader.
+    pub index: u32,
+
+    /// What the stream shows. Cameras that do not say are Color for video streams and Photo for their photo stream.
+    pub kind: StreamKind,
+
+    /// The Media Foundation major type of the stream, MFMediaType_Video for the streams frames can be read from.
+    pub major_type: GUID,
+
+    /// The Media Foundation subtype the stream is currently read in, such as MFVideoFormat_NV12.
+    pub subtype: GUID,
+
+    /// The subtype as the output it is read in, or else as its FourCC, such as NV12, L8 or D16.
+    pub subtype_name: String,
+
+    /// The size of the stream's frames, None for streams without a size such as audio.
+    pub size: Option<Dimensions>,
+
+    /// Whether the stream is read from, only the camera's selected stream is while capturing.
+    pub selected: bool,
+}
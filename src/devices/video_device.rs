@@ -0,0 +1,55 @@
+use windows::{
+    Win32::{
+        Foundation::HMODULE,
+        Graphics::{
+            Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+            Direct3D11::{
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_CREATE_DEVICE_VIDEO_SUPPORT,
+                D3D11_SDK_VERSION, D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext,
+                ID3D11Multithread, ID3D11VideoDevice,
+            },
+        },
+        Media::MediaFoundation::{IMFDXGIDeviceManager, MFCreateDXGIDeviceManager},
+    },
+    core::Interface,
+};
+
+// a D3D11 device manager for the source reader of a camera, its device has a video device the reader converts frames on
+//
+// fails when the GPU has no D3D11 video device, such as with the basic display driver, cameras then convert on the CPU
+pub(crate) unsafe fn video_device_manager() -> Result<IMFDXGIDeviceManager, windows::core::Error> {
+    let mut device: Option<ID3D11Device> = None;
+    let mut device_context: Option<ID3D11DeviceContext> = None;
+
+    unsafe {
+        D3D11CreateDevice(
+            None,
+            D3D_DRIVER_TYPE_HARDWARE,
+            HMODULE(std::ptr::null_mut()),
+            D3D11_CREATE_DEVICE_VIDEO_SUPPORT | D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            Some(&mut device_context),
+        )?;
+
+        let (device, device_context) = (device.unwrap(), device_context.unwrap());
+
+        //devices created with video support can still lack the video processor
+        device.cast::<ID3D11VideoDevice>()?;
+
+        //media foundation uses the device from its own threads
+        let multithread: ID3D11Multithread = device_context.cast()?;
+        let _ = multithread.SetMultithreadProtected(true);
+
+        let mut reset_token = 0;
+        let mut manager: Option<IMFDXGIDeviceManager> = None;
+        MFCreateDXGIDeviceManager(&mut reset_token, &mut manager)?;
+
+        let manager = manager.unwrap();
+        manager.ResetDevice(&device, reset_token)?;
+
+        Ok(manager)
+    }
+}
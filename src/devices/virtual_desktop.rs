@@ -0,0 +1,356 @@
+use std::{pin::Pin, sync::Arc};
+
+use tokio::sync::{
+    Mutex, mpsc,
+    mpsc::{Receiver, Sender},
+    watch,
+};
+
+use crate::{
+    buffer_pool::BufferPool,
+    capture_manager::{SourceEvent, forward_source},
+    devices::{Dimensions, Monitor, capture_state::CaptureGuard, pointer::PointerPosition},
+    frame::{Frame, PixelFormat, Rect, tone_map_frame},
+    i_capture::ICapture,
+};
+
+/// # Virtual Desktop Capture
+///
+/// Captures every monitor at once and sends them combined as one frame of the whole virtual desktop.
+///
+/// Each monitor is placed at its desktop coordinates inside a frame the size of the area covering all monitors, anything not covered by a monitor is black.
+///
+/// Monitors update at their own rate, every frame contains the most recent image of each monitor. A monitor that is disconnected or fails shows as black instead of ending the capture.
+///
+/// The combined frame is BGRA, a monitor set to send another output format shows as black as well.
+pub struct VirtualDesktopCapture {
+    monitors: Vec<Arc<Monitor>>,
+
+    // where each monitor is placed in the combined frame
+    placements: Vec<Rect>,
+
+    // the size of the combined frame
+    size: Dimensions,
+
+    receiver: Arc<Mutex<Receiver<Frame>>>,
+    sender: Sender<Frame>,
+
+    is_sending: Arc<Mutex<bool>>,
+
+    // set to true when the capture should stop, every monitor and the combining loop watch it
+    stopping: watch::Sender<bool>,
+
+    // reuses the buffers of combined frames that have been dropped
+    buffer_pool: BufferPool,
+}
+
+impl VirtualDesktopCapture {
+    /// # New
+    ///
+    /// Create a capture of every monitor that is part of the desktop.
+    pub fn new() -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let mut monitors = vec![];
+
+        for info in Monitor::enumerate()? {
+            if info.attached_to_desktop {
+                monitors.push(Monitor::from_monitor(info.index)?);
+            }
+        }
+
+        Self::from_monitors(monitors)
+    }
+
+    /// # From Monitors
+    ///
+    /// Create a capture that combines the given monitors, the combined frame covers only these monitors.
+    ///
+    /// The combined frame is BGRA, the monitors must keep their default output format.
+    pub fn from_monitors(
+        monitors: Vec<Arc<Monitor>>,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        if monitors.is_empty() {
+            return Err("no monitors to capture".into());
+        }
+
+        //the area covering every monitor
+        let coordinates = monitors.iter().map(|monitor| monitor.desktop_coordinates());
+        let bounds = coordinates.clone().reduce(|bounds, rect| Rect {
+            left: bounds.left.min(rect.left),
+            top: bounds.top.min(rect.top),
+            right: bounds.right.max(rect.right),
+            bottom: bounds.bottom.max(rect.bottom),
+        });
+        let bounds = bounds.unwrap();
+
+        let placements = coordinates
+            .map(|rect| Rect {
+                left: rect.left - bounds.left,
+                top: rect.top - bounds.top,
+                right: rect.right - bounds.left,
+                bottom: rect.bottom - bounds.top,
+            })
+            .collect();
+
+        let (tx, rx) = mpsc::channel(1);
+
+        Ok(Arc::new(VirtualDesktopCapture {
+            monitors,
+            placements,
+            size: Dimensions {
+                width: bounds.width(),
+                height: bounds.height(),
+            },
+            receiver: Arc::new(Mutex::new(rx)),
+            sender: tx,
+            is_sending: Arc::new(Mutex::new(false)),
+            stopping: watch::channel(false).0,
+            buffer_pool: BufferPool::new(3),
+        }))
+    }
+
+    /// The monitors that are combined, in the order they were given.
+    pub fn monitors(&self) -> &[Arc<Monitor>] {
+        &self.monitors
+    }
+}
+
+impl ICapture for VirtualDesktopCapture {
+    type CaptureOutput = Frame;
+
+    /// # Get Dimensions
+    ///
+    /// The size of the combined frame, this is the area covering every monitor.
+    fn get_dimensions(&self) -> Result<Dimensions, Box<dyn std::error::Error>> {
+        Ok(self.size)
+    }
+
+    /// # Stop Capturing
+    ///
+    /// Stops capturing every monitor.
+    fn stop_capturing(
+        self: Arc<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
+        Box::pin(async move {
+            let mut is_sending = self.is_sending.lock().await;
+
+            if !*is_sending {
+                return Err("Not sending any data".into());
+            }
+
+            *is_sending = false;
+            self.stopping.send_replace(true);
+
+            Ok(())
+        })
+    }
+
+    /// # Start Capturing
+    ///
+    /// Starts capturing every monitor and sends the combined frames to the receiver until stopped.
+    ///
+    /// Like a Monitor this runs until stop_capturing is called, spawn_capturing runs it on its own task instead.
+    fn start_capturing(
+        self: Arc<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>> {
+        Box::pin(async move {
+            {
+                let mut sending_lock = self.is_sending.lock().await;
+
+                if *sending_lock {
+                    return Err("you are already cloning data".into());
+                }
+
+                *sending_lock = true;
+            }
+
+            let guard = CaptureGuard::new(&self.is_sending, None);
+
+            self.stopping.send_replace(false);
+            let mut stopping = self.stopping.subscribe();
+
+            //every monitor forwards its frames here, tagged with the index of the monitor
+            let (forward_tx, mut forward_rx) = mpsc::channel(self.monitors.len() * 2);
+
+            let forwarders: Vec<_> = self
+                .monitors
+                .iter()
+                .enumerate()
+                .map(|(index, monitor)| {
+                    tokio::spawn(forward_source(
+                        monitor.clone(),
+                        index,
+                        forward_tx.clone(),
+                        self.stopping.subscribe(),
+                        None,
+                    ))
+                })
+                .collect();
+            drop(forward_tx);
+
+            // set when the capture ended because a frame could not be sent
+            let mut send_error = None;
+
+            let stride = self.size.width as usize * 4;
+            let mut canvas = vec![0u8; stride * self.size.height as usize];
+
+            //the pointer is on at most one monitor, kept along with the monitor that last reported it
+            let mut pointer: Option<(usize, PointerPosition)> = None;
+
+            //ends once every monitor has stopped
+            while let Some(update) = forward_rx.recv().await {
+                let mut dirty_rects = vec![];
+                let mut protected_content = false;
+                let mut rects_coalesced = false;
+
+                //apply every update that has arrived so one frame is sent for all of them
+                let mut update = Some(update);
+                while let Some(event) = update {
+                    match event {
+                        SourceEvent::Frame { source, frame } => {
+                            //the desktop is one BGRA frame, HDR monitors are brought down to SDR
+                            let frame = tone_map_frame(frame, 1.0);
+                            let placement = &self.placements[source];
+
+                            //a frame that can not be placed shows the monitor as black rather than ending the capture
+                            if !place_frame(&mut canvas, stride, &frame, placement) {
+                                clear_area(&mut canvas, stride, placement);
+                            }
+                            protected_content |= frame.protected_content;
+                            rects_coalesced |= frame.rects_coalesced;
+
+                            match &frame.pointer {
+                                Some(position) if position.visible => {
+                                    let position = PointerPosition {
+                                        x: position.x + placement.left,
+                                        y: position.y + placement.top,
+                                        visible: true,
+                                    };
+                                    pointer = Some((source, position));
+                                }
+                                //the pointer left the monitor it was last seen on
+                                Some(_) => {
+                                    if let Some((_, position)) =
+                                        pointer.as_mut().filter(|(from, _)| *from == source)
+                                    {
+                                        position.visible = false;
+                                    }
+                                }
+                                None => {}
+                            }
+                            dirty_rects.extend(frame.dirty_rects.iter().map(|rect| Rect {
+                                left: rect.left + placement.left,
+                                top: rect.top + placement.top,
+                                right: rect.right + placement.left,
+                                bottom: rect.bottom + placement.top,
+                            }));
+                        }
+                        //the monitor stopped or failed, it is shown as black from now on
+                        SourceEvent::Stopped { source, .. } => {
+                            let placement = &self.placements[source];
+
+                            clear_area(&mut canvas, stride, placement);
+                            dirty_rects.push(*placement);
+                        }
+                    }
+
+                    update = forward_rx.try_recv().ok();
+                }
+
+                let mut data = self.buffer_pool.take(canvas.len());
+                data.extend_from_slice(&canvas);
+
+                let mut frame = Frame::new(
+                    self.size.width,
+                    self.size.height,
+                    stride as u32,
+                    PixelFormat::BGRA,
+                    data,
+                );
+                frame.dirty_rects = dirty_rects;
+                frame.protected_content = protected_content;
+                frame.rects_coalesced = rects_coalesced;
+                frame.pointer = pointer.as_ref().map(|(_, position)| position.clone());
+
+                //a stop request ends the wait so the capture is never stuck on a full channel
+                tokio::select! {
+                    permit = self.sender.reserve() => match permit {
+                        Ok(permit) => permit.send(frame),
+                        Err(e) => {
+                            send_error = Some(format!("Failed to send frame: {}", e));
+                            self.stopping.send_replace(true);
+                            break;
+                        }
+                    },
+                    _ = stopping.changed() => break,
+                }
+            }
+
+            //the forwarders are waiting for their monitors to stop, the frames they still have are dropped
+            drop(forward_rx);
+
+            for forwarder in forwarders {
+                let _ = forwarder.await;
+            }
+
+            let result = match send_error {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
+
+            guard.finish(&result).await;
+
+            result.map_err(|e| e.into())
+        })
+    }
+
+    fn clone_receiver(&self) -> Arc<Mutex<Receiver<Self::CaptureOutput>>> {
+        self.receiver.clone()
+    }
+}
+
+// copies a monitor frame into its place in the combined frame, false if the frame is not BGRA or its data is too short to copy
+pub(crate) fn place_frame(
+    canvas: &mut [u8],
+    stride: usize,
+    frame: &Frame,
+    placement: &Rect,
+) -> bool {
+    //the desktop is BGRA, monitors set to another output format can not be placed
+    if !matches!(frame.format, PixelFormat::BGRA | PixelFormat::RGB32) {
+        return false;
+    }
+
+    //scaled monitors may not fill their place
+    let width = frame.width.min(placement.width()) as usize;
+    let height = frame.height.min(placement.height()) as usize;
+    let row_bytes = width * 4;
+
+    if height == 0 {
+        return true;
+    }
+
+    let frame_stride = frame.stride as usize;
+    if frame_stride < row_bytes || frame.data.len() < (height - 1) * frame_stride + row_bytes {
+        return false;
+    }
+
+    for y in 0..height {
+        let source = y * frame_stride;
+        let destination = (placement.top as usize + y) * stride + placement.left as usize * 4;
+
+        canvas[destination..destination + row_bytes]
+            .copy_from_slice(&frame.data[source..source + row_bytes]);
+    }
+
+    true
+}
+
+// turns the area of a monitor black
+fn clear_area(canvas: &mut [u8], stride: usize, placement: &Rect) {
+    let row_bytes = placement.width() as usize * 4;
+
+    for y in placement.top as usize..placement.bottom as usize {
+        let start = y * stride + placement.left as usize * 4;
+        canvas[start..start + row_bytes].fill(0);
+    }
+}
@@ -0,0 +1,1427 @@
+use std::time::Instant;
+
+use std::time::Duration;
+
+use windows::Win32::{
+    Foundation::RECT,
+    System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency},
+};
+
+#[cfg(feature = "monitor")]
+use windows::Win32::Graphics::Dxgi::DXGI_OUTDUPL_MOVE_RECT;
+
+#[cfg(feature = "monitor")]
+use crate::convert::simd;
+use crate::{
+    buffer_pool::PooledBuffer,
+    devices::{Dimensions, pointer::PointerPosition},
+};
+
+/// # Pixel Format
+///
+/// The layout of the pixel data inside of a Frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 4 bytes per pixel in the order blue, green, red, alpha. Sent by monitors.
+    BGRA,
+    /// A plane of 8 bit luma followed by a half height plane of interleaved chroma, sent by cameras.
+    NV12,
+    /// 4 bytes per pixel in the order blue, green, red, unused. Sent by cameras.
+    RGB32,
+    /// 4 bytes per pixel in the order red, green, blue, alpha. Sent by monitors set to this output format.
+    RGBA,
+    /// 3 bytes per pixel in the order red, green, blue. Sent by monitors set to this output format.
+    RGB24,
+    /// 8 bytes per pixel of 16 bit floats in the order red, green, blue, alpha. Sent by monitors showing HDR.
+    ///
+    /// The colors are linear scRGB, 1.0 is the brightness of SDR white (80 nits) and HDR highlights go above it. See tone_map_frame.
+    RGBA16F,
+    /// 1 byte per pixel of luma (brightness). Sent by monitors set to this output format, see GrayWeights.
+    Gray8,
+    /// 3 bytes per pixel in the order blue, green, red. Sent by cameras set to Output::RGB24.
+    BGR24,
+    /// Packed YUV where every two pixels share their chroma, 4 bytes in the order luma, blue chroma, luma, red chroma. Sent by cameras.
+    YUY2,
+    /// A plane of 8 bit luma followed by a half width, half height plane of blue chroma and then one of red chroma. Sent by cameras.
+    I420,
+    /// A JPEG image of any size with a stride of 0, it has to be decoded before its pixels can be read. Sent by cameras.
+    MJPG,
+    /// 2 bytes per pixel of 16 bit luma, little endian. Sent by cameras set to Output::L16, often by their infrared stream. See frame_as_u16.
+    Gray16,
+    /// 2 bytes per pixel of the distance of each pixel from the camera as a 16 bit value, little endian. Sent by depth streams, see Output::D16 and frame_as_u16.
+    ///
+    /// Depth cameras usually send millimeters, with 0 where the distance is unknown.
+    Depth16,
+}
+
+impl PixelFormat {
+    /// The number of bytes each pixel takes, for NV12 and I420 this is the size of a pixel of the luma plane.
+    ///
+    /// 0 for compressed formats, their frames have no fixed size.
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            PixelFormat::BGRA | PixelFormat::RGB32 | PixelFormat::RGBA => 4,
+            PixelFormat::RGB24 | PixelFormat::BGR24 => 3,
+            PixelFormat::YUY2 | PixelFormat::Gray16 | PixelFormat::Depth16 => 2,
+            PixelFormat::NV12 | PixelFormat::I420 | PixelFormat::Gray8 => 1,
+            PixelFormat::RGBA16F => 8,
+            PixelFormat::MJPG => 0,
+        }
+    }
+
+    /// Whether the data is compressed (MJPG) instead of holding the pixels themselves.
+    pub fn is_compressed(&self) -> bool {
+        *self == PixelFormat::MJPG
+    }
+}
+
+/// # Gray Weights
+///
+/// How much each of red, green and blue adds to the brightness of a Gray8 pixel, see Monitor::set_gray_weights.
+///
+/// The weights are applied to the sRGB encoded values, as video luma is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GrayWeights {
+    /// 0.299 red, 0.587 green and 0.114 blue, the weights of SD video and of most image libraries.
+    #[default]
+    Bt601,
+    /// 0.2126 red, 0.7152 green and 0.0722 blue, the weights of HD video and of sRGB.
+    Bt709,
+}
+
+impl GrayWeights {
+    // the weights of red, green and blue in 256ths, they add up to 256 so white stays 255
+    #[cfg(feature = "monitor")]
+    fn fixed_point(&self) -> (u32, u32, u32) {
+        match self {
+            GrayWeights::Bt601 => (77, 150, 29),
+            GrayWeights::Bt709 => (54, 183, 19),
+        }
+    }
+}
+
+/// # Color Space
+///
+/// How the colors of a frame or a monitor are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB, the color space of SDR monitors and of 8 bit frames.
+    Srgb,
+    /// Linear scRGB with the primaries of sRGB, 1.0 is the brightness of SDR white (80 nits). The color space of RGBA16F frames.
+    Scrgb,
+    /// HDR10, the PQ curve with BT.2020 primaries. Monitors are driven in it while HDR is on.
+    Hdr10,
+    /// Any other DXGI_COLOR_SPACE_TYPE, by its value.
+    Other(i32),
+}
+
+impl ColorSpace {
+    /// The color space of a DXGI_COLOR_SPACE_TYPE value.
+    pub fn from_dxgi(color_space: i32) -> Self {
+        //RGB_FULL_G22_NONE_P709, RGB_FULL_G10_NONE_P709 and RGB_FULL_G2084_NONE_P2020
+        match color_space {
+            0 => ColorSpace::Srgb,
+            1 => ColorSpace::Scrgb,
+            12 => ColorSpace::Hdr10,
+            other => ColorSpace::Other(other),
+        }
+    }
+
+    // the color space frames of the format are in, YUV, JPEG and 16 bit values have none of these
+    pub(crate) fn of_format(format: PixelFormat) -> Option<Self> {
+        match format {
+            PixelFormat::RGBA16F => Some(ColorSpace::Scrgb),
+            PixelFormat::NV12
+            | PixelFormat::YUY2
+            | PixelFormat::I420
+            | PixelFormat::MJPG
+            | PixelFormat::Gray16
+            | PixelFormat::Depth16 => None,
+            _ => Some(ColorSpace::Srgb),
+        }
+    }
+}
+
+/// # Frame
+///
+/// A single captured image along with everything needed to read it.
+///
+/// Sent through the receiver of a Monitor or Camera.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    /// The width of the image in pixels
+    pub width: u32,
+
+    /// The height of the image in pixels
+    pub height: u32,
+
+    /// The number of bytes in a single row of the data, this may be larger than width * bytes per pixel.
+    pub stride: u32,
+
+    /// The layout of the pixels in the data
+    pub format: PixelFormat,
+
+    /// When the frame was captured
+    pub timestamp: Instant,
+
+    /// Increases with every frame of the capture starting at 1, a gap between two received frames means frames were dropped.
+    ///
+    /// Monitors count every frame the desktop presented, even the ones the capture fell too far behind to acquire. 0 for frames not sent by a capture.
+    pub sequence: u64,
+
+    /// The raw pixel data, this can be used as a Vec<u8>
+    pub data: PooledBuffer,
+
+    /// The areas of the frame that changed since the previous frame, in the coordinates of the data.
+    ///
+    /// Always empty for cameras.
+    pub dirty_rects: Vec<Rect>,
+
+    /// Areas of the previous frame that were moved (for example a dragged window), in the coordinates of the data.
+    ///
+    /// Moves should be applied before the dirty rects, see apply_move_rects. Always empty for cameras.
+    pub move_rects: Vec<MoveRect>,
+
+    /// The number of desktop updates merged into the frame since the previous frame was acquired.
+    ///
+    /// 0 when only the pointer changed, always 0 for cameras.
+    pub accumulated_frames: u32,
+
+    /// When the desktop last presented an image, in QueryPerformanceCounter ticks.
+    ///
+    /// 0 when no new image was presented (only the pointer changed), always 0 for cameras. See qpc_to_duration.
+    pub last_present_time: i64,
+
+    /// Whether protected content (such as DRM video) was blacked out in the frame.
+    ///
+    /// Always false for cameras.
+    pub protected_content: bool,
+
+    /// Whether the dirty rects were merged together, by the system or by Monitor::set_coalesce_dirty_rects. When set the dirty rects may cover pixels that did not change.
+    ///
+    /// Always false for cameras.
+    pub rects_coalesced: bool,
+
+    /// Where the mouse pointer was when the frame was captured, in the coordinates of the data. The pointer is never drawn into the data.
+    ///
+    /// When the pointer did not move it is where it was last seen. None until the position is known, always None for cameras.
+    pub pointer: Option<PointerPosition>,
+
+    /// Whether this is the first frame since the size of the frames changed, such as when the resolution of the monitor changed.
+    ///
+    /// Cameras set it when the source reader changed the size of its frames while capturing.
+    pub dimensions_changed: bool,
+
+    /// The color space of the data when the frame was captured, it changes from Srgb to Scrgb when HDR is turned on (see tone_map_frame).
+    ///
+    /// The color space the monitor itself is driven in is on Monitor::color_info. None for YUV and MJPG frames.
+    pub color_space: Option<ColorSpace>,
+
+    /// When the camera captured the frame, the time of its sample counted from when the source reader started streaming. See Camera::sample_instant to line it up with other clocks.
+    ///
+    /// Always None for monitors.
+    pub sample_time: Option<Duration>,
+
+    /// How long the frame's sample lasts, when the camera reports it.
+    ///
+    /// Always None for monitors.
+    pub sample_duration: Option<Duration>,
+
+    /// The index of the camera's stream the frame was read from, to tell frames of its color, infrared and depth streams apart. See Camera::streams.
+    ///
+    /// Always None for monitors.
+    pub stream_index: Option<u32>,
+}
+
+impl Frame {
+    /// Create a frame captured now.
+    pub fn new(
+        width: u32,
+        height: u32,
+        stride: u32,
+        format: PixelFormat,
+        data: impl Into<PooledBuffer>,
+    ) -> Self {
+        Frame {
+            width,
+            height,
+            stride,
+            format,
+            timestamp: Instant::now(),
+            sequence: 0,
+            data: data.into(),
+            accumulated_frames: 0,
+            last_present_time: 0,
+            dirty_rects: vec![],
+            move_rects: vec![],
+            protected_content: false,
+            rects_coalesced: false,
+            pointer: None,
+            dimensions_changed: false,
+            color_space: ColorSpace::of_format(format),
+            sample_time: None,
+            sample_duration: None,
+            stream_index: None,
+        }
+    }
+}
+
+/// # Rect
+///
+/// A rectangle area of a frame in pixels, the right and bottom edges are exclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    /// The width of the rect in pixels
+    pub fn width(&self) -> u32 {
+        (self.right - self.left).max(0) as u32
+    }
+
+    /// The height of the rect in pixels
+    pub fn height(&self) -> u32 {
+        (self.bottom - self.top).max(0) as u32
+    }
+
+    /// # Scale
+    ///
+    /// Scales a rect of an image of the from size to the same area of an image of the to size.
+    ///
+    /// The rect is grown to whole pixels so that it always covers the scaled area.
+    pub fn scale(&self, from: &Dimensions, to: &Dimensions) -> Rect {
+        if from == to {
+            return *self;
+        }
+
+        let scale_x = to.width as f64 / from.width as f64;
+        let scale_y = to.height as f64 / from.height as f64;
+
+        Rect {
+            left: (self.left as f64 * scale_x).floor() as i32,
+            top: (self.top as f64 * scale_y).floor() as i32,
+            right: ((self.right as f64 * scale_x).ceil() as i32).min(to.width as i32),
+            bottom: ((self.bottom as f64 * scale_y).ceil() as i32).min(to.height as i32),
+        }
+    }
+
+    /// # Intersect
+    ///
+    /// The area covered by both rects, None when they do not overlap.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        };
+
+        (rect.width() > 0 && rect.height() > 0).then_some(rect)
+    }
+
+    // the rect moved by x and y
+    pub(crate) fn offset(&self, x: i32, y: i32) -> Rect {
+        Rect {
+            left: self.left + x,
+            top: self.top + y,
+            right: self.right + x,
+            bottom: self.bottom + y,
+        }
+    }
+}
+
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        Rect {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+/// # Move Rect
+///
+/// An area of the previous frame that was moved to a new position in the current frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveRect {
+    /// The x coordinate of the top left of the area in the previous frame
+    pub source_x: i32,
+
+    /// The y coordinate of the top left of the area in the previous frame
+    pub source_y: i32,
+
+    /// Where the area is in the current frame, it is the same size as the source area
+    pub destination: Rect,
+}
+
+impl MoveRect {
+    /// The area that was moved in the previous frame
+    pub fn source(&self) -> Rect {
+        Rect {
+            left: self.source_x,
+            top: self.source_y,
+            right: self.source_x + self.destination.width() as i32,
+            bottom: self.source_y + self.destination.height() as i32,
+        }
+    }
+}
+
+#[cfg(feature = "monitor")]
+impl From<DXGI_OUTDUPL_MOVE_RECT> for MoveRect {
+    fn from(move_rect: DXGI_OUTDUPL_MOVE_RECT) -> Self {
+        MoveRect {
+            source_x: move_rect.SourcePoint.x,
+            source_y: move_rect.SourcePoint.y,
+            destination: move_rect.DestinationRect.into(),
+        }
+    }
+}
+
+/// # Apply Move Rects
+///
+/// Performs the moves of a new frame on the previous frame, this can be used to keep your own copy of the screen up to date.
+///
+/// After applying the moves only the dirty rects of the new frame need to be copied from it.
+///
+/// The previous frame must be a frame sent by a Monitor, either BGRA or RGBA16F.
+pub fn apply_move_rects(prev_frame: &mut Frame, moves: &[MoveRect]) {
+    let stride = prev_frame.stride as usize;
+    let pixel_bytes = prev_frame.format.bytes_per_pixel() as usize;
+
+    for move_rect in moves {
+        let source = move_rect.source();
+        let destination = &move_rect.destination;
+        let row_bytes = destination.width() as usize * pixel_bytes;
+
+        //the areas may overlap so copy the source out first
+        let mut moved = Vec::with_capacity(row_bytes * destination.height() as usize);
+
+        for y in source.top..source.bottom {
+            let start = y as usize * stride + source.left as usize * pixel_bytes;
+            moved.extend_from_slice(&prev_frame.data[start..start + row_bytes]);
+        }
+
+        for (row, y) in (destination.top..destination.bottom).enumerate() {
+            let start = y as usize * stride + destination.left as usize * pixel_bytes;
+            prev_frame.data[start..start + row_bytes]
+                .copy_from_slice(&moved[row * row_bytes..(row + 1) * row_bytes]);
+        }
+    }
+}
+
+/// # Flip Frame
+///
+/// Reverses the order of the rows of the frame in place, turning a bottom up image (see NegotiatedFormat::bottom_up) top down and back. Row padding stays where it is.
+///
+/// Only frames whose pixels are stored in a single plane of rows can be flipped, NV12, I420 and MJPG frames are refused.
+pub fn flip_frame(frame: &mut Frame) -> Result<(), Box<dyn std::error::Error>> {
+    if matches!(
+        frame.format,
+        PixelFormat::NV12 | PixelFormat::I420 | PixelFormat::MJPG
+    ) {
+        return Err(format!("{:?} frames can not be flipped", frame.format).into());
+    }
+
+    if frame.width == 0 || frame.height == 0 {
+        return Ok(());
+    }
+
+    let (height, stride) = (frame.height as usize, frame.stride as usize);
+    let row_bytes = frame.width as usize * frame.format.bytes_per_pixel() as usize;
+    let needed = stride * (height - 1) + row_bytes;
+
+    if stride < row_bytes || frame.data.len() < needed {
+        return Err(format!(
+            "the frame has {} bytes with a stride of {}, {needed} are needed for its size",
+            frame.data.len(),
+            frame.stride
+        )
+        .into());
+    }
+
+    let data = &mut frame.data[..needed];
+
+    for top in 0..height / 2 {
+        let bottom = height - 1 - top;
+
+        //the bottom row is split off so both rows can be borrowed at once
+        let (upper, lower) = data.split_at_mut(bottom * stride);
+        upper[top * stride..top * stride + row_bytes].swap_with_slice(&mut lower[..row_bytes]);
+    }
+
+    Ok(())
+}
+
+/// # Rotation
+///
+/// How far a frame is turned clockwise, see Orientation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    /// Not turned.
+    #[default]
+    None,
+    /// A quarter turn clockwise, the width and height are swapped.
+    Rotate90,
+    /// Upside down.
+    Rotate180,
+    /// Three quarter turns clockwise (a quarter turn counterclockwise), the width and height are swapped.
+    Rotate270,
+}
+
+impl Rotation {
+    /// Whether the width and height of a frame are swapped by the rotation (90 or 270 degrees).
+    pub fn swaps_dimensions(&self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+}
+
+/// # Orientation
+///
+/// How a frame is turned around, such as mirroring the preview of a front facing camera or righting a sensor mounted upside down. See orient_frame and Camera::set_orientation.
+///
+/// The frame is mirrored and flipped first, then rotated clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Reverses the pixels of every row, left becomes right.
+    pub mirror_horizontal: bool,
+
+    /// Reverses the order of the rows, the top becomes the bottom.
+    pub flip_vertical: bool,
+
+    /// The clockwise rotation applied after mirroring and flipping.
+    pub rotation: Rotation,
+}
+
+impl Orientation {
+    /// Whether the orientation leaves frames as they are.
+    pub fn is_identity(&self) -> bool {
+        *self == Orientation::default()
+    }
+
+    /// The size of a frame of the size once it is oriented, the width and height are swapped by 90 and 270 degree rotations.
+    pub fn oriented_size(&self, size: &Dimensions) -> Dimensions {
+        match self.rotation.swaps_dimensions() {
+            true => Dimensions {
+                width: size.height,
+                height: size.width,
+            },
+            false => *size,
+        }
+    }
+
+    // the rect of a frame of the size where it lands once the frame is oriented
+    fn orient_rect(&self, rect: &Rect, width: i32, height: i32) -> Rect {
+        let mut rect = *rect;
+
+        if self.mirror_horizontal {
+            (rect.left, rect.right) = (width - rect.right, width - rect.left);
+        }
+
+        if self.flip_vertical {
+            (rect.top, rect.bottom) = (height - rect.bottom, height - rect.top);
+        }
+
+        match self.rotation {
+            Rotation::None => rect,
+            Rotation::Rotate90 => Rect {
+                left: height - rect.bottom,
+                top: rect.left,
+                right: height - rect.top,
+                bottom: rect.right,
+            },
+            Rotation::Rotate180 => Rect {
+                left: width - rect.right,
+                top: height - rect.bottom,
+                right: width - rect.left,
+                bottom: height - rect.top,
+            },
+            Rotation::Rotate270 => Rect {
+                left: rect.top,
+                top: width - rect.right,
+                right: rect.bottom,
+                bottom: width - rect.left,
+            },
+        }
+    }
+
+    // the pixel of a frame of the size where it lands once the frame is oriented
+    fn orient_point(&self, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+        let x = if self.mirror_horizontal {
+            width - 1 - x
+        } else {
+            x
+        };
+        let y = if self.flip_vertical {
+            height - 1 - y
+        } else {
+            y
+        };
+
+        match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Rotate90 => (height - 1 - y, x),
+            Rotation::Rotate180 => (width - 1 - x, height - 1 - y),
+            Rotation::Rotate270 => (y, width - 1 - x),
+        }
+    }
+}
+
+/// # Orient Frame
+///
+/// Mirrors, flips and rotates the frame as the orientation says into a new frame without row padding. Frames rotated by 90 or 270 degrees have their width and height swapped.
+///
+/// The dirty rects and the pointer are moved with the pixels, move rects are sent as dirty instead.
+///
+/// NV12 and I420 frames are oriented plane by plane and must have an even width and height. YUY2 frames, whose pairs of pixels share their color, and MJPG frames are refused, read them in another output or decode them first.
+pub fn orient_frame(
+    frame: &Frame,
+    orientation: Orientation,
+) -> Result<Frame, Box<dyn std::error::Error>> {
+    if matches!(frame.format, PixelFormat::YUY2 | PixelFormat::MJPG) {
+        return Err(format!("{:?} frames can not be oriented", frame.format).into());
+    }
+
+    let (width, height, stride) = (
+        frame.width as usize,
+        frame.height as usize,
+        frame.stride as usize,
+    );
+    let planar = matches!(frame.format, PixelFormat::NV12 | PixelFormat::I420);
+
+    if planar && (!width.is_multiple_of(2) || !height.is_multiple_of(2)) {
+        return Err(format!(
+            "{:?} frames are oriented in 2x2 blocks, {width}x{height} is not an even size",
+            frame.format
+        )
+        .into());
+    }
+
+    let pixel_bytes = frame.format.bytes_per_pixel() as usize;
+
+    //the bytes up to the end of the last row of the last plane
+    let needed = match (frame.format, height) {
+        (_, 0) => 0,
+        (PixelFormat::NV12, _) => stride * height + stride * (height / 2 - 1) + width,
+        (PixelFormat::I420, _) => {
+            stride * height + stride / 2 * (height / 2) * 2 - stride / 2 + width / 2
+        }
+        _ => stride * (height - 1) + width * pixel_bytes,
+    };
+
+    if stride < width * pixel_bytes || frame.data.len() < needed {
+        return Err(format!(
+            "the frame has {} bytes with a stride of {}, {needed} are needed for its size",
+            frame.data.len(),
+            frame.stride
+        )
+        .into());
+    }
+
+    let mut data = Vec::with_capacity(needed);
+    let plane = |data: &[u8], stride, width, height, pixel_bytes, oriented: &mut Vec<u8>| {
+        orient_plane(
+            data,
+            stride,
+            width,
+            height,
+            pixel_bytes,
+            orientation,
+            oriented,
+        )
+    };
+
+    if width > 0 && height > 0 {
+        plane(&frame.data, stride, width, height, pixel_bytes, &mut data);
+
+        let chroma = &frame.data[stride * height..];
+
+        //the chroma of NV12 is a half size plane of pairs, I420 has a half size plane for each of the two channels
+        match frame.format {
+            PixelFormat::NV12 => plane(chroma, stride, width / 2, height / 2, 2, &mut data),
+            PixelFormat::I420 => {
+                let chroma_stride = stride / 2;
+                let plane_bytes = chroma_stride * (height / 2);
+
+                for chroma in [&chroma[..plane_bytes], &chroma[plane_bytes..]] {
+                    plane(chroma, chroma_stride, width / 2, height / 2, 1, &mut data);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let size = orientation.oriented_size(&Dimensions {
+        width: frame.width,
+        height: frame.height,
+    });
+    let (frame_width, frame_height) = (frame.width as i32, frame.height as i32);
+
+    let dirty_rects = frame
+        .dirty_rects
+        .iter()
+        .chain(
+            frame
+                .move_rects
+                .iter()
+                .map(|move_rect| &move_rect.destination),
+        )
+        .map(|rect| orientation.orient_rect(rect, frame_width, frame_height))
+        .collect();
+
+    let pointer = frame.pointer.clone().map(|pointer| {
+        let (x, y) = orientation.orient_point(pointer.x, pointer.y, frame_width, frame_height);
+
+        PointerPosition { x, y, ..pointer }
+    });
+
+    Ok(Frame {
+        width: size.width,
+        height: size.height,
+        stride: size.width * pixel_bytes as u32,
+        format: frame.format,
+        timestamp: frame.timestamp,
+        sequence: frame.sequence,
+        data: data.into(),
+        accumulated_frames: frame.accumulated_frames,
+        last_present_time: frame.last_present_time,
+        dirty_rects,
+        move_rects: vec![],
+        protected_content: frame.protected_content,
+        rects_coalesced: frame.rects_coalesced,
+        pointer,
+        dimensions_changed: frame.dimensions_changed,
+        color_space: frame.color_space,
+        sample_time: frame.sample_time,
+        sample_duration: frame.sample_duration,
+        stream_index: frame.stream_index,
+    })
+}
+
+// adds the plane of the size oriented to the end of the oriented buffer, without row padding
+fn orient_plane(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    pixel_bytes: usize,
+    orientation: Orientation,
+    oriented: &mut Vec<u8>,
+) {
+    let row_bytes = width * pixel_bytes;
+
+    //only flipped, the rows are copied whole
+    if orientation.rotation == Rotation::None && !orientation.mirror_horizontal {
+        for y in 0..height {
+            let y = if orientation.flip_vertical {
+                height - 1 - y
+            } else {
+                y
+            };
+
+            oriented.extend_from_slice(&data[y * stride..][..row_bytes]);
+        }
+
+        return;
+    }
+
+    let (out_width, out_height) = match orientation.rotation.swaps_dimensions() {
+        true => (height, width),
+        false => (width, height),
+    };
+
+    for out_y in 0..out_height {
+        for out_x in 0..out_width {
+            //the pixel of the mirrored and flipped plane the rotation moves to out_x, out_y
+            let (x, y) = match orientation.rotation {
+                Rotation::None => (out_x, out_y),
+                Rotation::Rotate90 => (out_y, height - 1 - out_x),
+                Rotation::Rotate180 => (width - 1 - out_x, height - 1 - out_y),
+                Rotation::Rotate270 => (width - 1 - out_y, out_x),
+            };
+
+            let x = if orientation.mirror_horizontal {
+                width - 1 - x
+            } else {
+                x
+            };
+            let y = if orientation.flip_vertical {
+                height - 1 - y
+            } else {
+                y
+            };
+
+            oriented.extend_from_slice(&data[y * stride + x * pixel_bytes..][..pixel_bytes]);
+        }
+    }
+}
+
+/// # Frame As U16
+///
+/// The data of a Gray16 or Depth16 frame as its 16 bit values, such as the distances of a depth frame. The values of row y start at y * stride / 2, the rows may be padded.
+///
+/// Fails for frames of other formats, for frames too short for their size, and when the data is not aligned to 2 bytes, frames sent by cameras always are.
+pub fn frame_as_u16(frame: &Frame) -> Result<&[u16], Box<dyn std::error::Error>> {
+    if !matches!(frame.format, PixelFormat::Gray16 | PixelFormat::Depth16) {
+        return Err(format!("{:?} frames do not hold 16 bit values", frame.format).into());
+    }
+
+    let row_bytes = frame.width as usize * 2;
+    let stride = frame.stride as usize;
+    let needed = match frame.height {
+        0 => 0,
+        height => stride * (height as usize - 1) + row_bytes,
+    };
+
+    if stride < row_bytes || !stride.is_multiple_of(2) || frame.data.len() < needed {
+        return Err(format!(
+            "the frame has {} bytes with a stride of {}, {needed} are needed for its size",
+            frame.data.len(),
+            frame.stride
+        )
+        .into());
+    }
+
+    //the values are little endian like Windows itself, only the alignment of the data has to be checked
+    let (prefix, values, _) = unsafe { frame.data[..needed].align_to::<u16>() };
+
+    if !prefix.is_empty() {
+        return Err("the frame's data is not aligned to 2 bytes".into());
+    }
+
+    Ok(values)
+}
+
+/// # Crop Frame
+///
+/// Copies the area of the frame inside the rect into a new frame without row padding, the rect is clipped to the frame.
+///
+/// The dirty and move rects and the pointer are moved to the coordinates of the cropped frame. Moves from outside of the area are sent as dirty instead, and the pointer is hidden when it is outside of the area.
+///
+/// NV12 and I420 frames are cropped to even coordinates, as every 2x2 block of pixels shares its color, and YUY2 frames to even columns. MJPG frames are returned as they are, decode them first.
+pub fn crop_frame(frame: &Frame, rect: &Rect) -> Frame {
+    crop_frame_into(frame, rect, Vec::new().into())
+}
+
+// crops the frame into the given buffer, so monitors can crop into buffers from their pool
+pub(crate) fn crop_frame_into(frame: &Frame, rect: &Rect, mut data: PooledBuffer) -> Frame {
+    if frame.format.is_compressed() {
+        return frame.clone();
+    }
+
+    let bounds = Rect {
+        left: 0,
+        top: 0,
+        right: frame.width as i32,
+        bottom: frame.height as i32,
+    };
+
+    let mut crop = rect.intersect(&bounds).unwrap_or_default();
+
+    if matches!(
+        frame.format,
+        PixelFormat::NV12 | PixelFormat::I420 | PixelFormat::YUY2
+    ) {
+        crop.left &= !1;
+        crop.right = (crop.right + 1).min(bounds.right) & !1;
+    }
+
+    if matches!(frame.format, PixelFormat::NV12 | PixelFormat::I420) {
+        crop.top &= !1;
+        crop.bottom = (crop.bottom + 1).min(bounds.bottom) & !1;
+    }
+
+    let pixel_bytes = frame.format.bytes_per_pixel() as usize;
+    let stride = frame.stride as usize;
+    let row_bytes = crop.width() as usize * pixel_bytes;
+    let (left, top) = (crop.left as usize * pixel_bytes, crop.top as usize);
+
+    data.clear();
+
+    for y in top..top + crop.height() as usize {
+        data.extend_from_slice(&frame.data[y * stride + left..][..row_bytes]);
+    }
+
+    //the chroma plane of NV12 is half the height, with a pair of bytes for every two pixels
+    if frame.format == PixelFormat::NV12 {
+        let chroma = &frame.data[stride * frame.height as usize..];
+
+        for y in top / 2..(top + crop.height() as usize) / 2 {
+            data.extend_from_slice(&chroma[y * stride + left..][..row_bytes]);
+        }
+    }
+
+    //I420 has a half width, half height plane for each of the two chroma channels
+    if frame.format == PixelFormat::I420 {
+        let chroma_stride = stride / 2;
+        let plane_bytes = chroma_stride * (frame.height as usize / 2);
+        let chroma = &frame.data[stride * frame.height as usize..];
+
+        for plane in [&chroma[..plane_bytes], &chroma[plane_bytes..]] {
+            for y in top / 2..(top + crop.height() as usize) / 2 {
+                data.extend_from_slice(&plane[y * chroma_stride + left / 2..][..row_bytes / 2]);
+            }
+        }
+    }
+
+    let to_crop = |rect: &Rect| {
+        rect.intersect(&crop)
+            .map(|rect| rect.offset(-crop.left, -crop.top))
+    };
+
+    let mut dirty_rects: Vec<Rect> = frame.dirty_rects.iter().filter_map(to_crop).collect();
+    let mut move_rects = vec![];
+
+    for move_rect in &frame.move_rects {
+        let inside = |rect: &Rect| rect.intersect(&crop) == Some(*rect);
+
+        if inside(&move_rect.source()) && inside(&move_rect.destination) {
+            move_rects.push(MoveRect {
+                source_x: move_rect.source_x - crop.left,
+                source_y: move_rect.source_y - crop.top,
+                destination: move_rect.destination.offset(-crop.left, -crop.top),
+            });
+        } else {
+            dirty_rects.extend(to_crop(&move_rect.destination));
+        }
+    }
+
+    let pointer = frame.pointer.clone().map(|pointer| {
+        let inside = pointer.x >= crop.left
+            && pointer.x < crop.right
+            && pointer.y >= crop.top
+            && pointer.y < crop.bottom;
+
+        PointerPosition {
+            x: pointer.x - crop.left,
+            y: pointer.y - crop.top,
+            visible: pointer.visible && inside,
+        }
+    });
+
+    Frame {
+        width: crop.width(),
+        height: crop.height(),
+        stride: row_bytes as u32,
+        format: frame.format,
+        timestamp: frame.timestamp,
+        sequence: frame.sequence,
+        data,
+        accumulated_frames: frame.accumulated_frames,
+        last_present_time: frame.last_present_time,
+        dirty_rects,
+        move_rects,
+        protected_content: frame.protected_content,
+        rects_coalesced: frame.rects_coalesced,
+        pointer,
+        dimensions_changed: frame.dimensions_changed,
+        color_space: frame.color_space,
+        sample_time: frame.sample_time,
+        sample_duration: frame.sample_duration,
+        stream_index: frame.stream_index,
+    }
+}
+
+/// # Scale Filter
+///
+/// How the pixels of a downscaled frame are made from the pixels they cover, see downscale_frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleFilter {
+    /// The pixel at the center of the area, the fastest but small details can flicker between frames.
+    #[default]
+    Nearest,
+    /// The average of up to 4x4 pixels spread over the area, smoother for a small amount of extra work.
+    Box,
+}
+
+// the most pixels averaged along each axis by the box filter, so the cost depends on the size of the scaled frame and not of the source
+const MAX_BOX_SAMPLES: usize = 4;
+
+/// # Downscale Frame
+///
+/// Scales the frame down to the size into a new frame without row padding, such as for previews of a capture.
+///
+/// Sizes larger than the frame are clamped to it, frames are never scaled up, and the aspect ratio is not kept. The dirty rects and pointer are scaled with the frame, move rects are sent as dirty instead.
+///
+/// NV12 and I420 frames are scaled to even sizes and YUY2 frames to even widths, all three and RGBA16F frames are always scaled with Nearest. MJPG frames are returned as they are, decode them first.
+pub fn downscale_frame(frame: &Frame, size: &Dimensions, filter: ScaleFilter) -> Frame {
+    if frame.format.is_compressed() {
+        return frame.clone();
+    }
+
+    let mut width = size.width.min(frame.width);
+    let mut height = size.height.min(frame.height);
+
+    if matches!(
+        frame.format,
+        PixelFormat::NV12 | PixelFormat::I420 | PixelFormat::YUY2
+    ) {
+        width &= !1;
+    }
+
+    if matches!(frame.format, PixelFormat::NV12 | PixelFormat::I420) {
+        height &= !1;
+    }
+
+    let max_samples = match (filter, frame.format) {
+        (
+            ScaleFilter::Box,
+            PixelFormat::BGRA
+            | PixelFormat::RGB32
+            | PixelFormat::RGBA
+            | PixelFormat::RGB24
+            | PixelFormat::BGR24
+            | PixelFormat::Gray8,
+        ) => MAX_BOX_SAMPLES,
+        _ => 1,
+    };
+
+    let (from_width, from_height) = (frame.width as usize, frame.height as usize);
+    let stride = frame.stride as usize;
+    let pixel_bytes = frame.format.bytes_per_pixel() as usize;
+
+    let mut data = Vec::with_capacity(width as usize * height as usize * pixel_bytes * 3 / 2);
+
+    //every two pixels of YUY2 share their chroma, so they are scaled as one 4 byte pixel
+    let (columns, sample_bytes) = match frame.format {
+        PixelFormat::YUY2 => (scale_samples(from_width / 2, width as usize / 2, 1), 4),
+        _ => (
+            scale_samples(from_width, width as usize, max_samples),
+            pixel_bytes,
+        ),
+    };
+
+    scale_plane(
+        &frame.data,
+        stride,
+        sample_bytes,
+        &columns,
+        &scale_samples(from_height, height as usize, max_samples),
+        &mut data,
+    );
+
+    //the chroma plane of NV12 is half the height, with a pair of bytes for every two pixels
+    if frame.format == PixelFormat::NV12 {
+        scale_plane(
+            &frame.data[stride * from_height..],
+            stride,
+            2,
+            &scale_samples(from_width / 2, width as usize / 2, 1),
+            &scale_samples(from_height / 2, height as usize / 2, 1),
+            &mut data,
+        );
+    }
+
+    //I420 has a half width, half height plane for each of the two chroma channels
+    if frame.format == PixelFormat::I420 {
+        let chroma_stride = stride / 2;
+        let plane_bytes = chroma_stride * (from_height / 2);
+        let chroma = &frame.data[stride * from_height..];
+
+        for plane in [&chroma[..plane_bytes], &chroma[plane_bytes..]] {
+            scale_plane(
+                plane,
+                chroma_stride,
+                1,
+                &scale_samples(from_width / 2, width as usize / 2, 1),
+                &scale_samples(from_height / 2, height as usize / 2, 1),
+                &mut data,
+            );
+        }
+    }
+
+    let from = Dimensions {
+        width: frame.width,
+        height: frame.height,
+    };
+    let to = Dimensions { width, height };
+
+    let dirty_rects = frame
+        .dirty_rects
+        .iter()
+        .chain(
+            frame
+                .move_rects
+                .iter()
+                .map(|move_rect| &move_rect.destination),
+        )
+        .map(|rect| rect.scale(&from, &to))
+        .filter(|rect| rect.width() > 0 && rect.height() > 0)
+        .collect();
+
+    let pointer = frame.pointer.clone().map(|pointer| PointerPosition {
+        x: (pointer.x as i64 * width as i64 / frame.width.max(1) as i64) as i32,
+        y: (pointer.y as i64 * height as i64 / frame.height.max(1) as i64) as i32,
+        visible: pointer.visible,
+    });
+
+    Frame {
+        width,
+        height,
+        stride: width * pixel_bytes as u32,
+        format: frame.format,
+        timestamp: frame.timestamp,
+        sequence: frame.sequence,
+        data: data.into(),
+        accumulated_frames: frame.accumulated_frames,
+        last_present_time: frame.last_present_time,
+        dirty_rects,
+        move_rects: vec![],
+        protected_content: frame.protected_content,
+        rects_coalesced: frame.rects_coalesced,
+        pointer,
+        dimensions_changed: frame.dimensions_changed,
+        color_space: frame.color_space,
+        sample_time: frame.sample_time,
+        sample_duration: frame.sample_duration,
+        stream_index: frame.stream_index,
+    }
+}
+
+// the source pixels each of the scaled pixels along an axis is made from, spread evenly over the area it covers
+fn scale_samples(from: usize, to: usize, max_samples: usize) -> Vec<Vec<usize>> {
+    (0..to)
+        .map(|i| {
+            let start = i * from / to;
+            let span = ((i + 1) * from / to - start).max(1);
+            let samples = span.min(max_samples);
+
+            (0..samples)
+                .map(|sample| start + (2 * sample + 1) * span / (2 * samples))
+                .collect()
+        })
+        .collect()
+}
+
+// adds the average of the sampled pixels of every scaled pixel to the end of the data, byte by byte
+fn scale_plane(
+    plane: &[u8],
+    stride: usize,
+    pixel_bytes: usize,
+    columns: &[Vec<usize>],
+    rows: &[Vec<usize>],
+    data: &mut Vec<u8>,
+) {
+    for row_samples in rows {
+        for column_samples in columns {
+            let count = (row_samples.len() * column_samples.len()) as u32;
+
+            for byte in 0..pixel_bytes {
+                let mut sum = 0;
+
+                for y in row_samples {
+                    for x in column_samples {
+                        sum += plane[y * stride + x * pixel_bytes + byte] as u32;
+                    }
+                }
+
+                data.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+}
+
+/// # Coalesce Rects
+///
+/// Merges rects that overlap or share an edge into the rect around both, a frame's dirty rects can then be encoded as a few larger areas instead of many small ones.
+///
+/// When max_regions is given and more rects are left than that, the two rects that add the least area when merged are merged until at most max_regions remain.
+///
+/// The merged rects may cover pixels that none of the given rects did. Empty rects are dropped.
+pub fn coalesce_rects(rects: &[Rect], max_regions: Option<usize>) -> Vec<Rect> {
+    let mut merged: Vec<Rect> = vec![];
+
+    for rect in rects
+        .iter()
+        .filter(|rect| rect.width() > 0 && rect.height() > 0)
+    {
+        push_merged(&mut merged, *rect);
+    }
+
+    //there is always one rect left while there is anything to cover
+    let max_regions = max_regions.unwrap_or(usize::MAX).max(1);
+
+    while merged.len() > max_regions {
+        let mut nearest = (0, 1, i64::MAX);
+
+        for a in 0..merged.len() {
+            for b in a + 1..merged.len() {
+                let added = merged_area(&merged[a], &merged[b]);
+
+                if added < nearest.2 {
+                    nearest = (a, b, added);
+                }
+            }
+        }
+
+        let (a, b, _) = nearest;
+        let rect = bounding_rect(&merged[a], &merged[b]);
+
+        merged.swap_remove(b);
+        merged.swap_remove(a);
+
+        //the bigger rect can now overlap others
+        push_merged(&mut merged, rect);
+    }
+
+    merged
+}
+
+// adds the rect to rects that never touch, merging it with every rect it touches
+fn push_merged(rects: &mut Vec<Rect>, mut rect: Rect) {
+    //each merge grows the rect, so look again from the start until nothing more touches it
+    while let Some(index) = rects.iter().position(|other| rects_touch(other, &rect)) {
+        rect = bounding_rect(&rect, &rects.swap_remove(index));
+    }
+
+    rects.push(rect);
+}
+
+// whether the rects overlap or share part of an edge, rects that only meet at a corner are left apart
+fn rects_touch(a: &Rect, b: &Rect) -> bool {
+    let overlap_x = a.left < b.right && b.left < a.right;
+    let overlap_y = a.top < b.bottom && b.top < a.bottom;
+    let touch_x = a.left <= b.right && b.left <= a.right;
+    let touch_y = a.top <= b.bottom && b.top <= a.bottom;
+
+    (overlap_x && touch_y) || (touch_x && overlap_y)
+}
+
+fn bounding_rect(a: &Rect, b: &Rect) -> Rect {
+    Rect {
+        left: a.left.min(b.left),
+        top: a.top.min(b.top),
+        right: a.right.max(b.right),
+        bottom: a.bottom.max(b.bottom),
+    }
+}
+
+// the area the rect around both adds to the two rects, they never overlap so this is never negative
+fn merged_area(a: &Rect, b: &Rect) -> i64 {
+    let area = |rect: &Rect| rect.width() as i64 * rect.height() as i64;
+
+    area(&bounding_rect(a, b)) - area(a) - area(b)
+}
+
+/// # Tone Map Frame
+///
+/// Converts an HDR frame (RGBA16F) to an 8 bit BGRA frame for consumers that only handle SDR.
+///
+/// The white level is the scRGB value that becomes full white, 1.0 keeps SDR content as it was. Brighter highlights are clipped.
+///
+/// Frames of any other format are returned as they are.
+pub fn tone_map_frame(frame: Frame, white_level: f32) -> Frame {
+    if frame.format != PixelFormat::RGBA16F {
+        return frame;
+    }
+
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let scale = 1.0 / white_level.max(f32::MIN_POSITIVE);
+
+    //scRGB is linear, SDR frames are sRGB encoded
+    let encode = |value: f32| -> u8 {
+        let value = (value * scale).clamp(0.0, 1.0);
+        let encoded = if value <= 0.003_130_8 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        };
+
+        (encoded * 255.0).round() as u8
+    };
+
+    let mut data = Vec::with_capacity(width * height * 4);
+
+    for row in frame.data.chunks(frame.stride as usize).take(height) {
+        for pixel in row[..width * 8].chunks_exact(8) {
+            let channel = |i: usize| f16_to_f32(u16::from_le_bytes([pixel[i], pixel[i + 1]]));
+
+            let (red, green, blue) = (channel(0), channel(2), channel(4));
+            let alpha = channel(6).clamp(0.0, 1.0);
+
+            data.extend_from_slice(&[
+                encode(blue),
+                encode(green),
+                encode(red),
+                (alpha * 255.0).round() as u8,
+            ]);
+        }
+    }
+
+    Frame {
+        width: frame.width,
+        height: frame.height,
+        stride: frame.width * 4,
+        format: PixelFormat::BGRA,
+        timestamp: frame.timestamp,
+        sequence: frame.sequence,
+        data: data.into(),
+        accumulated_frames: frame.accumulated_frames,
+        last_present_time: frame.last_present_time,
+        dirty_rects: frame.dirty_rects,
+        move_rects: frame.move_rects,
+        protected_content: frame.protected_content,
+        rects_coalesced: frame.rects_coalesced,
+        pointer: frame.pointer,
+        dimensions_changed: frame.dimensions_changed,
+        color_space: Some(ColorSpace::Srgb),
+        sample_time: frame.sample_time,
+        sample_duration: frame.sample_duration,
+        stream_index: frame.stream_index,
+    }
+}
+
+/// # QPC To Duration
+///
+/// Converts QueryPerformanceCounter ticks, such as the difference between two last present times, to a Duration.
+///
+/// Negative ticks are treated as 0.
+pub fn qpc_to_duration(ticks: i64) -> Duration {
+    let mut frequency = 0;
+
+    //the frequency is fixed at boot and never fails on Windows XP or later
+    unsafe {
+        let _ = QueryPerformanceFrequency(&mut frequency);
+    }
+
+    if ticks <= 0 || frequency <= 0 {
+        return Duration::ZERO;
+    }
+
+    //split into whole seconds first so large tick counts do not overflow
+    let (ticks, frequency) = (ticks as u64, frequency as u64);
+    let seconds = ticks / frequency;
+    let nanos = (ticks % frequency) * 1_000_000_000 / frequency;
+
+    Duration::new(seconds, nanos as u32)
+}
+
+/// The current value of QueryPerformanceCounter, in the same units as the last present time of a frame.
+pub fn qpc_now() -> i64 {
+    let mut ticks = 0;
+
+    unsafe {
+        let _ = QueryPerformanceCounter(&mut ticks);
+    }
+
+    ticks
+}
+
+// converts the bits of a 16 bit (half precision) float to a f32
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    match exponent {
+        //subnormal
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+// how BGRA pixels are converted by convert_pixels and convert_rows
+#[cfg(feature = "monitor")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PixelConversion {
+    // the format the pixels are converted to
+    pub(crate) format: PixelFormat,
+
+    // whether the alpha of every pixel becomes 255
+    pub(crate) opaque_alpha: bool,
+
+    // how Gray8 pixels are weighed
+    pub(crate) gray_weights: GrayWeights,
+}
+
+/// Converts BGRA pixels as set by the conversion, the destination must hold the same number of pixels in the format converted to.
+///
+/// Formats that need no conversion are copied as they are.
+#[cfg(feature = "monitor")]
+pub(crate) fn convert_pixels(source: &[u8], conversion: PixelConversion, destination: &mut [u8]) {
+    let PixelConversion {
+        format,
+        opaque_alpha,
+        gray_weights,
+    } = conversion;
+
+    //or'd into the alpha so the loops do not branch on every pixel
+    let alpha_mask = if opaque_alpha { 0xFF } else { 0 };
+
+    match format {
+        PixelFormat::BGRA if opaque_alpha => {
+            let converted = simd::swizzle_row(source, false, 0xFF, destination) * 4;
+            let (source, destination) = (&source[converted..], &mut destination[converted..]);
+
+            for (from, to) in source.chunks_exact(4).zip(destination.chunks_exact_mut(4)) {
+                to.copy_from_slice(&[from[0], from[1], from[2], 0xFF]);
+            }
+        }
+        PixelFormat::RGBA => {
+            let converted = simd::swizzle_row(source, true, alpha_mask, destination) * 4;
+            let (source, destination) = (&source[converted..], &mut destination[converted..]);
+
+            for (from, to) in source.chunks_exact(4).zip(destination.chunks_exact_mut(4)) {
+                to.copy_from_slice(&[from[2], from[1], from[0], from[3] | alpha_mask]);
+            }
+        }
+        PixelFormat::RGB24 => {
+            for (from, to) in source.chunks_exact(4).zip(destination.chunks_exact_mut(3)) {
+                to.copy_from_slice(&[from[2], from[1], from[0]]);
+            }
+        }
+        PixelFormat::Gray8 => {
+            let (red, green, blue) = gray_weights.fixed_point();
+
+            let converted = simd::gray_row(source, (red, green, blue), destination);
+            let (source, destination) = (&source[converted * 4..], &mut destination[converted..]);
+
+            for (from, to) in source.chunks_exact(4).zip(destination.iter_mut()) {
+                let luma = from[2] as u32 * red + from[1] as u32 * green + from[0] as u32 * blue;
+                *to = ((luma + 128) >> 8) as u8;
+            }
+        }
+        _ => destination.copy_from_slice(source),
+    }
+}
+
+/// Converts each BGRA row of the data as set by the conversion and adds it to the end of the converted buffer, dropping any padding at the end of the rows.
+#[cfg(feature = "monitor")]
+pub(crate) fn convert_rows(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    conversion: PixelConversion,
+    converted: &mut Vec<u8>,
+) {
+    let row_bytes = width * conversion.format.bytes_per_pixel() as usize;
+
+    for row in data.chunks(stride).take(height) {
+        let start = converted.len();
+        converted.resize(start + row_bytes, 0);
+
+        convert_pixels(&row[..width * 4], conversion, &mut converted[start..]);
+    }
+}
+
+/// Copies each row of the data to the end of the packed buffer, dropping any padding at the end of the rows.
+#[cfg(feature = "monitor")]
+pub(crate) fn pack_rows(
+    data: &[u8],
+    stride: usize,
+    row_bytes: usize,
+    height: usize,
+    packed: &mut Vec<u8>,
+) {
+    for row in data.chunks(stride).take(height) {
+        packed.extend_from_slice(&row[..row_bytes]);
+    }
+}
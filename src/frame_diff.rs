@@ -0,0 +1,329 @@
+use crate::frame::{Frame, PixelFormat, Rect};
+
+// the rows sample_hash_frame skips between the rows it hashes
+const SAMPLE_ROW_STEP: usize = 4;
+
+// odd and with well spread bits, from the golden ratio
+const HASH_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// # Frame Difference
+///
+/// How much of a frame changed compared to an earlier frame, see compare_frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameDifference {
+    /// The number of pixels where a channel changed by more than the threshold.
+    pub changed_pixels: u64,
+
+    /// The fraction of the pixels of the frame that changed, from 0.0 to 1.0.
+    pub change_ratio: f64,
+
+    /// The smallest rect around every changed pixel, None when nothing changed.
+    pub bounds: Option<Rect>,
+}
+
+/// # Compare Frames
+///
+/// Compares two frames of the same size and format, counting the pixels where any color channel differs by more than the threshold.
+///
+/// Alpha and unused bytes are not compared and NV12 and I420 frames are compared by their brightness (the luma plane) only. RGBA16F frames must be tone mapped first, see tone_map_frame, and MJPG frames decoded.
+///
+/// Identical rows are skipped with a single comparison and the pixels of changed rows are compared in fixed size chunks, so this is fast enough to run on every frame of a capture.
+pub fn compare_frames(
+    previous: &Frame,
+    current: &Frame,
+    threshold: u8,
+) -> Result<FrameDifference, Box<dyn std::error::Error>> {
+    if previous.width != current.width || previous.height != current.height {
+        return Err(format!(
+            "the frames are different sizes, {}x{} and {}x{}",
+            previous.width, previous.height, current.width, current.height
+        )
+        .into());
+    }
+
+    if previous.format != current.format {
+        return Err(format!(
+            "the frames are different formats, {:?} and {:?}",
+            previous.format, current.format
+        )
+        .into());
+    }
+
+    if current.format == PixelFormat::RGBA16F {
+        return Err("RGBA16F frames cannot be compared, tone map them first".into());
+    }
+
+    if current.format.is_compressed() {
+        return Err(format!(
+            "{:?} frames cannot be compared, decode them first",
+            current.format
+        )
+        .into());
+    }
+
+    let (width, height) = (current.width as usize, current.height as usize);
+    let pixel_bytes = current.format.bytes_per_pixel() as usize;
+    let row_bytes = width * pixel_bytes;
+
+    for frame in [previous, current] {
+        let needed = (frame.stride as usize * height.saturating_sub(1)) + row_bytes;
+
+        if height > 0 && frame.data.len() < needed {
+            return Err(format!(
+                "the frame has {} bytes, {needed} are needed for its size",
+                frame.data.len()
+            )
+            .into());
+        }
+    }
+
+    let mut difference = FrameDifference::default();
+
+    for y in 0..height {
+        let previous_row = &previous.data[y * previous.stride as usize..][..row_bytes];
+        let current_row = &current.data[y * current.stride as usize..][..row_bytes];
+
+        if previous_row == current_row {
+            continue;
+        }
+
+        let (changed, span) = match pixel_bytes {
+            4 => compare_row::<4>(previous_row, current_row, threshold),
+            3 => compare_row::<3>(previous_row, current_row, threshold),
+            2 => compare_row::<2>(previous_row, current_row, threshold),
+            _ => compare_row::<1>(previous_row, current_row, threshold),
+        };
+
+        let Some((first, last)) = span else {
+            continue;
+        };
+
+        difference.changed_pixels += changed;
+
+        let (y, first, last) = (y as i32, first as i32, last as i32 + 1);
+
+        difference.bounds = Some(match difference.bounds {
+            Some(bounds) => Rect {
+                left: bounds.left.min(first),
+                top: bounds.top,
+                right: bounds.right.max(last),
+                bottom: y + 1,
+            },
+            None => Rect {
+                left: first,
+                top: y,
+                right: last,
+                bottom: y + 1,
+            },
+        });
+    }
+
+    let pixels = (width * height) as u64;
+
+    if pixels > 0 {
+        difference.change_ratio = difference.changed_pixels as f64 / pixels as f64;
+    }
+
+    Ok(difference)
+}
+
+// the number of changed pixels in a row of N byte pixels and the first and last of them
+// N is known when compiled so the channel loop is unrolled, the color channels come before any alpha or unused byte
+fn compare_row<const N: usize>(
+    previous: &[u8],
+    current: &[u8],
+    threshold: u8,
+) -> (u64, Option<(usize, usize)>) {
+    let mut changed = 0;
+    let mut span: Option<(usize, usize)> = None;
+
+    for (x, (a, b)) in previous
+        .chunks_exact(N)
+        .zip(current.chunks_exact(N))
+        .enumerate()
+    {
+        let mut delta = 0;
+
+        for channel in 0..N.min(3) {
+            delta = delta.max(a[channel].abs_diff(b[channel]));
+        }
+
+        if delta > threshold {
+            changed += 1;
+            span = Some(span.map_or((x, x), |(first, _)| (first, x)));
+        }
+    }
+
+    (changed, span)
+}
+
+// holds back frames that changed less than the minimum from the last frame that was let through
+// see Monitor::set_min_change_ratio and Camera::set_min_change_ratio
+#[cfg(any(feature = "monitor", feature = "camera"))]
+#[derive(Debug, Default)]
+pub(crate) struct ChangeFilter {
+    // the minimum change ratio and the channel threshold, None lets every frame through
+    settings: Option<(f64, u8)>,
+
+    // the last frame let through, the next frames are compared to it
+    previous: Option<Frame>,
+}
+
+#[cfg(any(feature = "monitor", feature = "camera"))]
+impl ChangeFilter {
+    pub(crate) fn set(&mut self, min_change_ratio: Option<f64>, threshold: u8) {
+        self.settings = min_change_ratio.map(|ratio| (ratio.clamp(0.0, 1.0), threshold));
+        self.previous = None;
+    }
+
+    // forgets the last frame, the next frame is always let through
+    pub(crate) fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    // whether the frame changed enough to be sent, it is remembered when it did
+    pub(crate) fn passes(&mut self, frame: &Frame) -> bool {
+        let Some((min_change_ratio, threshold)) = self.settings else {
+            return true;
+        };
+
+        //frames that cannot be compared, such as the first frame after a resize, are always let through
+        if let Some(previous) = &self.previous
+            && let Ok(difference) = compare_frames(previous, frame, threshold)
+            && difference.change_ratio < min_change_ratio
+        {
+            return false;
+        }
+
+        //reuse the buffer of the last frame instead of allocating one for every frame
+        let mut data = self
+            .previous
+            .take()
+            .map(|previous| previous.data)
+            .unwrap_or_else(|| Vec::with_capacity(frame.data.len()).into());
+
+        data.clear();
+        data.extend_from_slice(&frame.data);
+
+        self.previous = Some(Frame::new(
+            frame.width,
+            frame.height,
+            frame.stride,
+            frame.format,
+            data,
+        ));
+
+        true
+    }
+}
+
+/// # Frame Hasher
+///
+/// A function hashing the pixels of a frame, frames that hash the same are treated as duplicates (see Monitor::set_duplicate_suppression).
+///
+/// hash_frame and sample_hash_frame are provided, any function of this type can be used instead.
+pub type FrameHasher = fn(&Frame) -> u64;
+
+/// # Hash Frame
+///
+/// A 64 bit hash of every pixel of the frame. Row padding is left out, so the same image hashes the same whatever its stride.
+///
+/// MJPG frames have no rows and their whole data is hashed, sample_hash_frame does the same for them.
+///
+/// The rows are hashed 8 bytes at a time with a multiply and rotate, which is much faster than the SipHash of std but not meant to resist collisions made on purpose.
+pub fn hash_frame(frame: &Frame) -> u64 {
+    hash_rows(frame, 1)
+}
+
+/// # Sample Hash Frame
+///
+/// Like hash_frame but only hashes every 4th row, for large frames where hashing every pixel costs too much.
+///
+/// Changes that only touch the rows in between are missed, such as a blinking text caret, so frames with them are treated as duplicates.
+pub fn sample_hash_frame(frame: &Frame) -> u64 {
+    hash_rows(frame, SAMPLE_ROW_STEP)
+}
+
+// hashes every row_step-th row of the image, NV12 and I420 frames include the rows of the chroma planes
+fn hash_rows(frame: &Frame, row_step: usize) -> u64 {
+    let row_bytes = frame.width as usize * frame.format.bytes_per_pixel() as usize;
+    let rows = match frame.format {
+        PixelFormat::NV12 | PixelFormat::I420 => {
+            frame.height as usize + (frame.height as usize).div_ceil(2)
+        }
+        _ => frame.height as usize,
+    };
+
+    let mut hash = HASH_MULTIPLIER;
+
+    if frame.stride == 0 {
+        return hash_bytes(hash, &frame.data);
+    }
+
+    for row in frame
+        .data
+        .chunks(frame.stride as usize)
+        .take(rows)
+        .step_by(row_step)
+    {
+        hash = hash_bytes(hash, &row[..row_bytes.min(row.len())]);
+    }
+
+    hash
+}
+
+// mixes the bytes into the hash, 8 at a time and the remainder padded with zeros
+fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut words = bytes.chunks_exact(8);
+
+    for word in &mut words {
+        hash = mix(hash, u64::from_le_bytes(word.try_into().unwrap()));
+    }
+
+    let remainder = words.remainder();
+    let mut last = [0u8; 8];
+    last[..remainder.len()].copy_from_slice(remainder);
+
+    //the length keeps trailing zeros from hashing the same as no bytes
+    mix(mix(hash, u64::from_le_bytes(last)), bytes.len() as u64)
+}
+
+fn mix(hash: u64, word: u64) -> u64 {
+    (hash ^ word).wrapping_mul(HASH_MULTIPLIER).rotate_left(29)
+}
+
+// holds back frames that hash the same as the frame captured before them
+// see Monitor::set_duplicate_suppression and Camera::set_duplicate_suppression
+#[cfg(any(feature = "monitor", feature = "camera"))]
+#[derive(Debug, Default)]
+pub(crate) struct DuplicateFilter {
+    // None lets every frame through
+    hasher: Option<FrameHasher>,
+
+    // the size, format and hash of the last frame
+    last: Option<(u32, u32, PixelFormat, u64)>,
+}
+
+#[cfg(any(feature = "monitor", feature = "camera"))]
+impl DuplicateFilter {
+    pub(crate) fn set(&mut self, hasher: Option<FrameHasher>) {
+        self.hasher = hasher;
+        self.last = None;
+    }
+
+    // forgets the last frame, the next frame is always let through
+    pub(crate) fn reset(&mut self) {
+        self.last = None;
+    }
+
+    // whether the frame differs from the last one, every frame is remembered
+    pub(crate) fn passes(&mut self, frame: &Frame) -> bool {
+        let Some(hasher) = self.hasher else {
+            return true;
+        };
+
+        let current = (frame.width, frame.height, frame.format, hasher(frame));
+
+        self.last.replace(current) != Some(current)
+    }
+}
@@ -1,6 +1,9 @@
 use std::{pin::Pin, sync::Arc};
 
-use tokio::sync::{Mutex, mpsc::Receiver};
+use tokio::{
+    sync::{Mutex, mpsc::Receiver},
+    task::JoinHandle,
+};
 
 use crate::devices::Dimensions;
 
@@ -33,4 +36,92 @@ pub trait ICapture: Send + Sync {
     /// 
     /// Get the receiver reference associated with sending data.
     fn clone_receiver(&self) -> Arc<Mutex<Receiver<Self::CaptureOutput>>>;
+
+    /// # Spawn Capturing
+    ///
+    /// Starts capturing on its own task and returns right away with a handle to control it.
+    ///
+    /// This is the easiest way to capture, the data can be read from the receiver while the handle is used to stop the capture.
+    fn spawn_capturing(self: Arc<Self>) -> CaptureHandle<Self>
+    where
+        Self: Sized + 'static,
+    {
+        CaptureHandle::spawn(self)
+    }
+}
+
+/// A capture that was started, such as the future returned by ICapture::start_capturing.
+pub type CaptureFuture =
+    Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error>>> + Send>>;
+
+/// # Capture Handle
+///
+/// Controls a capture that was started on its own task, see ICapture::spawn_capturing.
+///
+/// Dropping the handle does not stop the capture, call stop first.
+pub struct CaptureHandle<T: ICapture + ?Sized> {
+    capture: Arc<T>,
+
+    // the capture loop, errors are kept as strings since boxed errors cannot be sent between tasks
+    task: JoinHandle<Result<(), String>>,
+}
+
+impl<T: ICapture + ?Sized + 'static> CaptureHandle<T> {
+    /// # Spawn
+    ///
+    /// Starts capturing on its own task, this can be used with a `dyn ICapture`.
+    pub fn spawn(capture: Arc<T>) -> Self {
+        let capturing = capture.clone().start_capturing();
+
+        Self::from_future(capture, capturing)
+    }
+
+    // runs a capture that was already started, such as Monitor::start_snapshots, on its own task
+    pub(crate) fn from_future(
+        capture: Arc<T>,
+        capturing: CaptureFuture,
+    ) -> Self {
+        let task = tokio::spawn(async move { capturing.await.map_err(|e| e.to_string()) });
+
+        CaptureHandle { capture, task }
+    }
+}
+
+impl<T: ICapture + ?Sized> CaptureHandle<T> {
+    /// # Stop
+    ///
+    /// Tells the capture to stop, use wait to know when it has stopped.
+    pub async fn stop(&self) -> Result<(), Box<dyn std::error::Error>> {
+        while self.is_running() {
+            match self.capture.clone().stop_capturing().await {
+                Ok(()) => return Ok(()),
+                //the task may not have started capturing yet, give it a chance to
+                Err(_) => tokio::task::yield_now().await,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// # Is Running
+    ///
+    /// Whether the capture is still running, false once it has stopped or failed.
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// # Wait
+    ///
+    /// Waits for the capture to finish, returning the error that ended it if it failed.
+    pub async fn wait(self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.task.await {
+            Ok(result) => result.map_err(|e| e.into()),
+            Err(e) => Err(format!("capture task failed: {e}").into()),
+        }
+    }
+
+    /// The capture this handle controls.
+    pub fn capture(&self) -> &Arc<T> {
+        &self.capture
+    }
 }
@@ -1,73 +1,2647 @@
+pub mod buffer_pool;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod capture_manager;
+pub mod convert;
 pub mod devices;
+pub mod frame;
+pub mod frame_diff;
 pub mod i_capture;
+#[cfg(feature = "wgpu")]
+pub mod wgpu_texture;
 
 #[cfg(test)]
 mod tests {
 
-    use windows::Win32::System::Com::{COINIT_MULTITHREADED, CoInitializeEx};
+    use crate::{buffer_pool::BufferPool, capture_manager::FrameMatcher, convert::{YuvMatrix, bgra_to_nv12, convert_nv12, convert_yuy2, nv12_to_bgra, nv12_to_rgb, nv12_to_rgba, yuy2_to_bgra}, devices::{Dimensions, pointer::PointerPosition}, frame::{ColorSpace, Frame, MoveRect, Orientation, PixelFormat, Rect, Rotation, apply_move_rects, coalesce_rects, crop_frame, downscale_frame, flip_frame, frame_as_u16, orient_frame, qpc_now, qpc_to_duration, tone_map_frame, ScaleFilter}, frame_diff::{compare_frames, hash_frame, sample_hash_frame}};
 
-    use crate::{devices::{Cameras, Monitor, get_device_name}, i_capture::ICapture};
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    use crate::{devices::{delivery_policy::{Delivery, DeliveryPolicy, check_channel_capacity, queued, take_receiver, try_deliver}, fps_counter::FpsCounter, capture_stats::StatsCounters}, frame_diff::{ChangeFilter, DuplicateFilter}};
 
+    #[cfg(feature = "monitor")]
+    use crate::{convert::{set_simd_enabled, simd_enabled}, devices::latency_stats::{FrameLatency, LatencyRecorder}, frame::{GrayWeights, PixelConversion, convert_rows, pack_rows}};
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    use crate::i_capture::ICapture;
+
+    #[cfg(feature = "monitor")]
+    use crate::{capture_manager::{CaptureManager, SourceEvent}, devices::{CaptureEvent, CaptureState, CaptureStats, Monitor, SharedTexture, capture_event::AccessLostReason, VirtualDesktopCapture, list_adapters, virtual_desktop::place_frame, monitor_frame::{MonitorFrame, metadata_bytes, metadata_count, reserve_metadata}, rotation::{rotate_frame, rotate_rect, rotated_size}}};
+
+    #[cfg(feature = "monitor")]
+    use windows::Win32::{
+        Foundation::{E_FAIL, HWND, POINT, RECT},
+        Graphics::Dxgi::Common::{
+            DXGI_MODE_ROTATION_ROTATE90, DXGI_MODE_ROTATION_ROTATE180,
+            DXGI_MODE_ROTATION_ROTATE270,
+        },
+        Graphics::Dxgi::{DXGI_ERROR_ACCESS_LOST, DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_NOT_FOUND, DXGI_OUTDUPL_FRAME_INFO, DXGI_OUTDUPL_MOVE_RECT},
+        UI::WindowsAndMessaging::GetDesktopWindow,
+    };
+
+    #[cfg(feature = "camera")]
+    use crate::devices::{Camera, CameraFormat, CameraTransform, Cameras, camera::{Output, SampleRead}, get_device_name, get_device_symbolic_link};
+
+    #[cfg(feature = "graphics-capture")]
+    use crate::devices::GraphicsCapture;
+
+    #[cfg(feature = "wgpu")]
+    use crate::wgpu_texture::FrameTexture;
+
+    #[cfg(feature = "clipboard")]
+    use crate::clipboard::{copy_to_clipboard, frame_to_dib};
+
+    #[cfg(feature = "graphics-capture")]
+    use windows::Win32::System::WinRT::{RO_INIT_MULTITHREADED, RoInitialize};
+
+    #[cfg(feature = "camera")]
     use windows::Win32::{
         Media::MediaFoundation::{
             IMFActivate, IMFAttributes, MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
-            MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID, MFCreateAttributes,
-            MFEnumDeviceSources,
+            MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID, MF_SOURCE_READERF_ENDOFSTREAM,
+            MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED, MF_SOURCE_READERF_STREAMTICK, MFCreateAttributes,
+            CLSID_VideoProcessorMFT, MFCreateMemoryBuffer, MFEnumDeviceSources, MFMediaType_Video,
         },
-        System::Com::CoTaskMemFree,
+        System::Com::{COINIT_MULTITHREADED, CoInitializeEx, CoTaskMemFree},
     };
 
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn test_desktop_duplication() -> () {
+        let monitor_index = 1;
+        let monitor = Monitor::from_monitor(monitor_index);
+
+        assert!(
+            monitor.is_ok(),
+            "Desktop Duplicator failed: {:?}",
+            monitor.err()
+        );
+
+        let monitor = monitor.unwrap();
+
+        let monitor_clone = monitor.clone();
+
+        let recv = monitor_clone.receiver.clone();
+
+        tokio::spawn(async move {
+            let mut recv = recv.lock().await;
+
+            loop {
+                let data = recv.recv().await;
+
+                assert!(data.is_some());
+
+                let data = data.unwrap();
+
+                assert_eq!(data.data.len(), (data.stride * data.height) as usize);
+
+                //padding is stripped by default
+                assert_eq!(data.stride, data.width * 4);
+
+                for rect in &data.dirty_rects {
+                    assert!(rect.left >= 0 && rect.right <= data.width as i32);
+                    assert!(rect.top >= 0 && rect.bottom <= data.height as i32);
+                }
+
+                let mut had_data = false;
+                for d in &data.data {
+                    if *d != 0 {
+                        had_data = true;
+                        break;
+                    }
+                }
+
+                if had_data {
+                    break;
+                }
+            }
+
+            let stopped = monitor_clone.stop_capturing().await;
+            assert!(stopped.is_ok());
+        });
+
+        let clone = monitor.start_capturing().await;
+
+        assert!(clone.is_ok(), "{clone:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn enumerate_monitors() {
+        let monitors = Monitor::enumerate();
+
+        assert!(monitors.is_ok(), "{:?}", monitors.err());
+
+        let monitors = monitors.unwrap();
+
+        assert!(!monitors.is_empty());
+
+        for (i, info) in monitors.iter().enumerate() {
+            assert_eq!(info.index, i as u32);
+            println!("Monitor {}: '{}' on '{}'", info.index, info.name, info.description);
+        }
+
+        let monitor = Monitor::from_monitor(monitors[0].index);
+
+        assert!(monitor.is_ok(), "{:?}", monitor.err());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn all_monitor_info() {
+        let monitors = crate::devices::get_all_monitor_info();
+        let enumerated = Monitor::enumerate().unwrap();
+
+        assert_eq!(monitors.len(), enumerated.len());
+
+        //the primary monitor is at 0, 0 of the desktop
+        let primary: Vec<_> = monitors.iter().filter(|info| info.is_primary).collect();
+        assert_eq!(primary.len(), 1);
+        assert_eq!((primary[0].desktop_coordinates.left, primary[0].desktop_coordinates.top), (0, 0));
+
+        for (info, enumerated) in monitors.iter().zip(&enumerated) {
+            assert_eq!(info.name, enumerated.name);
+            assert_eq!(
+                (info.adapter_index, info.output_index),
+                (enumerated.adapter_index, enumerated.output_index)
+            );
+            assert!(info.refresh_rate > 0 && info.dpi > 0 && info.scale_factor > 0.0);
+        }
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_info_round_trip() {
+        //every entry opens the output it describes, by its info and by its index
+        for info in crate::devices::get_all_monitor_info() {
+            let monitor = Monitor::from_monitor_info(info.clone()).unwrap();
+            assert_eq!(monitor.name, info.name);
+
+            let monitor = Monitor::from_monitor(info.index).unwrap();
+            assert_eq!(monitor.name, info.name);
+        }
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_from_name() {
+        let monitors = Monitor::enumerate().unwrap();
+
+        assert!(!monitors.is_empty());
+
+        let monitor = Monitor::from_name(&monitors[0].name, false);
+
+        assert!(monitor.is_ok(), "{:?}", monitor.err());
+        assert_eq!(monitor.unwrap().name, monitors[0].name);
+
+        let partial = Monitor::from_name("display", true);
+
+        assert!(partial.is_ok(), "{:?}", partial.err());
+
+        let missing = Monitor::from_name("not a monitor", false);
+
+        assert!(missing.is_err());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_device_on_adapter() {
+        //desktop duplication only works with a device on the monitor's own adapter
+        for info in Monitor::enumerate().unwrap() {
+            let monitor = Monitor::from_monitor(info.index);
+            assert!(monitor.is_ok(), "{:?}", monitor.err());
+        }
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn list_adapter_monitors() {
+        let adapters = list_adapters();
+        assert!(adapters.is_ok(), "{:?}", adapters.err());
+
+        let adapters = adapters.unwrap();
+        let monitors = Monitor::enumerate().unwrap();
+
+        //the monitors of every adapter are the monitors from enumerate, in the same order
+        let listed: Vec<u32> = adapters
+            .iter()
+            .flat_map(|adapter| adapter.monitors.iter().map(|m| m.index))
+            .collect();
+        let enumerated: Vec<u32> = monitors.iter().map(|m| m.index).collect();
+        assert_eq!(listed, enumerated);
+
+        let adapter = adapters.iter().find(|a| !a.monitors.is_empty()).unwrap();
+        let info = &adapter.monitors[0];
+
+        //selecting by adapter and output round trips to the same monitor
+        let monitor = Monitor::from_adapter_output(info.adapter_index, info.output_index);
+        assert_eq!(monitor.unwrap().name, info.name);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_info_details() {
+        let monitors = Monitor::enumerate().unwrap();
+        let attached: Vec<_> = monitors.iter().filter(|m| m.attached_to_desktop).collect();
+
+        assert_eq!(attached.iter().filter(|m| m.is_primary).count(), 1);
+
+        for info in &attached {
+            assert!(info.refresh_rate > 0);
+            assert!(info.dpi > 0);
+            assert_eq!(info.scale_factor, info.dpi as f32 / 96.0);
+        }
+
+        //the size matches what the monitor reports once created
+        let info = attached[0];
+        let monitor = Monitor::from_monitor(info.index).unwrap();
+        assert_eq!(monitor.desktop_size(), info.size());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_friendly_names() {
+        let monitors = Monitor::enumerate().unwrap();
+
+        for info in &monitors {
+            assert!(!info.friendly_name.is_empty());
+            println!("Monitor {}: '{}' ({})", info.index, info.friendly_name, info.name);
+        }
+
+        //the monitor resolves the same name as enumeration
+        let monitor = Monitor::from_monitor(monitors[0].index).unwrap();
+        assert_eq!(monitor.friendly_name, monitors[0].friendly_name);
+    }
+
+    #[test]
+    fn color_spaces() {
+        assert_eq!(ColorSpace::from_dxgi(0), ColorSpace::Srgb);
+        assert_eq!(ColorSpace::from_dxgi(1), ColorSpace::Scrgb);
+        assert_eq!(ColorSpace::from_dxgi(12), ColorSpace::Hdr10);
+        assert_eq!(ColorSpace::from_dxgi(5), ColorSpace::Other(5));
+
+        //frames are tagged by their format
+        assert_eq!(Frame::new(1, 1, 4, PixelFormat::BGRA, vec![0; 4]).color_space, Some(ColorSpace::Srgb));
+        assert_eq!(Frame::new(2, 2, 2, PixelFormat::NV12, vec![0; 6]).color_space, None);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_color_info() {
+        let monitors = Monitor::enumerate().unwrap();
+        let monitor = Monitor::from_monitor(monitors[0].index).unwrap();
+
+        //the monitor reports what enumeration reported, unless HDR was toggled in between
+        assert_eq!(monitor.color_info().is_some(), monitors[0].color_info.is_some());
+
+        if let Some(color_info) = monitor.color_info() {
+            assert!(color_info.bits_per_color > 0);
+            println!("{}: {color_info:?}, SDR white {:?} nits", monitor.name, monitor.sdr_white_level());
+        }
+
+        //frames are in scRGB while HDR is on
+        let frame = monitor.capture_frame(std::time::Duration::from_secs(2)).await.unwrap();
+        let expected = match frame.format {
+            PixelFormat::RGBA16F => ColorSpace::Scrgb,
+            _ => ColorSpace::Srgb,
+        };
+        assert_eq!(frame.color_space, Some(expected));
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_from_adapter_output() {
+        let monitor = Monitor::from_adapter_output(0, 0);
+        assert!(monitor.is_ok(), "{:?}", monitor.err());
+
+        let missing_adapter = Monitor::from_adapter_output(999, 0);
+        assert!(missing_adapter.err().unwrap().to_string().starts_with("adapter not found"));
+
+        let missing_output = Monitor::from_adapter_output(0, 999);
+        assert!(missing_output.err().unwrap().to_string().starts_with("output not found"));
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_output_size() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        let scaled = monitor.set_output_size(640, 360);
+
+        assert!(scaled.is_ok(), "{:?}", scaled.err());
+
+        let dimensions = monitor.get_dimensions().unwrap();
+
+        assert_eq!(dimensions.width, 640);
+        assert_eq!(dimensions.height, 360);
+
+        let monitor_clone = monitor.clone();
+        let recv = monitor.clone_receiver();
+
+        tokio::spawn(async move {
+            let data = recv.lock().await.recv().await;
+
+            assert!(data.is_some());
+
+            let data = data.unwrap();
+
+            assert_eq!(data.width, 640);
+            assert_eq!(data.height, 360);
+            assert_eq!(data.data.len(), (data.stride * data.height) as usize);
+
+            let stopped = monitor_clone.stop_capturing().await;
+            assert!(stopped.is_ok());
+        });
+
+        let captured = monitor.start_capturing().await;
+
+        assert!(captured.is_ok(), "{captured:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn spawn_monitor_capture() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        let handle = monitor.clone().spawn_capturing();
+        let recv = monitor.clone_receiver();
+
+        let mut recv = recv.lock().await;
+
+        for _ in 0..3 {
+            assert!(recv.recv().await.is_some());
+        }
+
+        assert!(handle.is_running());
+
+        let stopped = handle.stop().await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+
+        //keep receiving so the capture is not stuck sending its last frame
+        while handle.is_running() {
+            let _ = tokio::time::timeout(std::time::Duration::from_millis(10), recv.recv()).await;
+        }
+
+        let finished = handle.wait().await;
+        assert!(finished.is_ok(), "{finished:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_stop_and_wait() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            assert!(recv.recv().await.is_some());
+        }
+
+        //frames are no longer received, the loop must still stop while the channel is full
+        let stopped = monitor
+            .stop_and_wait(std::time::Duration::from_secs(2))
+            .await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+
+        let finished = handle.wait().await;
+        assert!(finished.is_ok(), "{finished:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn restart_after_dropped_receiver() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            assert!(recv.recv().await.is_some());
+
+            //the receiver is dropped mid capture
+        }
+
+        let stopped = monitor
+            .stop_and_wait(std::time::Duration::from_secs(2))
+            .await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+        assert!(handle.wait().await.is_ok());
+
+        //every frame was released, so capturing can start again
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            assert!(recv.recv().await.is_some());
+        }
+
+        let stopped = monitor
+            .stop_and_wait(std::time::Duration::from_secs(2))
+            .await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn recreate_dropped_monitor() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                assert!(recv.recv().await.is_some());
+            }
+        }
+
+        let stopped = monitor
+            .stop_and_wait(std::time::Duration::from_secs(2))
+            .await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+
+        //waiting on the handle drops the monitor it held
+        assert!(handle.wait().await.is_ok());
+        drop(monitor);
+
+        //the duplication of the dropped monitor must be fully released
+        let recreated = Monitor::from_monitor(0);
+        assert!(recreated.is_ok(), "{:?}", recreated.err());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn manage_monitor_captures() {
+        let mut manager = CaptureManager::new();
+
+        let id = manager.add(Monitor::from_monitor(0).unwrap());
+
+        let started = manager.start_all().await;
+        assert!(started.is_ok(), "{started:?}");
+
+        {
+            let recv = manager.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                match recv.recv().await.unwrap() {
+                    SourceEvent::Frame { source, .. } => assert_eq!(source, id),
+                    SourceEvent::Stopped { error, .. } => panic!("source stopped: {error:?}"),
+                }
+            }
+        }
+
+        //waits for every source to exit
+        let stopped = manager.stop_all().await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn capture_virtual_desktop() {
+        let desktop = VirtualDesktopCapture::new();
+        assert!(desktop.is_ok(), "{:?}", desktop.err());
+
+        let desktop = desktop.unwrap();
+        let size = desktop.get_dimensions().unwrap();
+
+        //the combined frame covers every monitor
+        for monitor in desktop.monitors() {
+            assert!(monitor.desktop_coordinates().width() <= size.width);
+            assert!(monitor.desktop_coordinates().height() <= size.height);
+        }
+
+        let handle = desktop.clone().spawn_capturing();
+
+        {
+            let recv = desktop.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                let frame = recv.recv().await.unwrap();
+
+                assert_eq!((frame.width, frame.height), (size.width, size.height));
+                assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+            }
+        }
+
+        let stopped = handle.stop().await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+
+        let finished = handle.wait().await;
+        assert!(finished.is_ok(), "{finished:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn place_desktop_frames() {
+        let placement = Rect { left: 1, top: 1, right: 3, bottom: 2 };
+        let stride = 4 * 4;
+        let mut canvas = vec![0u8; stride * 2];
+
+        //rows are copied without the padding of the frame
+        let data: Vec<u8> = (1..=12).collect();
+        assert!(place_frame(&mut canvas, stride, &Frame::new(2, 1, 12, PixelFormat::BGRA, data), &placement));
+        assert_eq!(&canvas[stride + 4..stride + 12], &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        //other formats and short data are left to the caller instead of panicking
+        let mut canvas = vec![0u8; stride * 2];
+        assert!(!place_frame(&mut canvas, stride, &Frame::new(2, 1, 6, PixelFormat::RGB24, vec![1; 6]), &placement));
+        assert!(!place_frame(&mut canvas, stride, &Frame::new(2, 1, 8, PixelFormat::Gray8, vec![1; 8]), &placement));
+        assert!(!place_frame(&mut canvas, stride, &Frame::new(2, 1, 4, PixelFormat::BGRA, vec![1; 8]), &placement));
+        assert!(!place_frame(&mut canvas, stride, &Frame::new(2, 1, 8, PixelFormat::BGRA, vec![1; 6]), &placement));
+        assert!(canvas.iter().all(|&byte| byte == 0));
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn recover_lost_access() {
+        unsafe {
+            let monitor = Monitor::from_monitor(0).unwrap();
+            let events = monitor.events();
+
+            //errors that do not mean access was lost are returned as is
+            let other = monitor.recover_access(E_FAIL.into()).await;
+            assert_eq!(other.unwrap_err().code(), E_FAIL);
+
+            //as if the secure desktop was shown
+            let recovered = monitor.recover_access(DXGI_ERROR_ACCESS_LOST.into()).await;
+            assert!(recovered.is_ok(), "{recovered:?}");
+
+            {
+                let mut events = events.lock().await;
+
+                assert_eq!(
+                    events.try_recv().unwrap(),
+                    CaptureEvent::AccessLost {
+                        reason: AccessLostReason::DesktopSwitched
+                    }
+                );
+                assert_eq!(events.try_recv().unwrap(), CaptureEvent::Resumed);
+            }
+
+            //the output was duplicated again, so frames are captured as before
+            let handle = monitor.clone().spawn_capturing();
+
+            {
+                let recv = monitor.clone_receiver();
+                assert!(recv.lock().await.recv().await.is_some());
+            }
+
+            let stopped = monitor
+                .stop_and_wait(std::time::Duration::from_secs(2))
+                .await;
+            assert!(stopped.is_ok(), "{stopped:?}");
+            assert!(handle.wait().await.is_ok());
+        }
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_lifecycle_events() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let events = monitor.events();
+        let mut events = events.lock().await;
+
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+
+        assert_eq!(received.first(), Some(&CaptureEvent::Started));
+        assert_eq!(received.last(), Some(&CaptureEvent::Stopped));
+        assert!(
+            !received
+                .iter()
+                .any(|event| matches!(event, CaptureEvent::FatalError { .. }))
+        );
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_dimensions_changed() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let desktop_size = monitor.desktop_size();
+        let size = Dimensions { width: desktop_size.width / 2, height: desktop_size.height / 2 };
+
+        monitor.set_output_size(size.width, size.height).unwrap();
+
+        {
+            let events = monitor.events();
+            let event = events.lock().await.try_recv().unwrap();
+            assert_eq!(event, CaptureEvent::DimensionsChanged { size });
+        }
+
+        let timeout = std::time::Duration::from_secs(2);
+        let frame = monitor.capture_frame(timeout).await.unwrap();
+        assert!(frame.dimensions_changed);
+        assert_eq!((frame.width, frame.height), (size.width, size.height));
+
+        //the desktop size is still the size of the monitor
+        assert_eq!(monitor.desktop_size(), desktop_size);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_capture_frames() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let timeout = std::time::Duration::from_millis(500);
+
+        //an idle desktop may stop presenting, the frames captured before the timeout come back with the error
+        let frames = match monitor.capture_frames(3, timeout).await {
+            Ok(frames) => {
+                assert_eq!(frames.len(), 3);
+                frames
+            }
+            Err(e) => e.frames,
+        };
+
+        assert!(!frames.is_empty());
+        assert_eq!(frames[0].sequence, 1);
+        assert!(frames.windows(2).all(|pair| pair[1].sequence > pair[0].sequence));
+
+        let size = monitor.get_dimensions().unwrap();
+        assert!(frames.iter().all(|frame| (frame.width, frame.height) == (size.width, size.height)));
+
+        //every frame was released, capturing works as usual
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(monitor.capture_frames(1, timeout).await.is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_wait_for_content() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            //the empty frames of the new duplication are held back
+            let recv = monitor.clone_receiver();
+            let frame = recv.lock().await.recv().await.unwrap();
+
+            assert!(frame.last_present_time != 0 || !frame.dirty_rects.is_empty());
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        //every frame is sent once turned off
+        monitor.set_wait_for_content(false);
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_thumbnails() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let bounds = Dimensions { width: 320, height: 180 };
+
+        monitor.set_thumbnails(Some(bounds), std::time::Duration::from_millis(250), ScaleFilter::Box);
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            //full frames keep being sent next to the thumbnails
+            let recv = monitor.clone_receiver();
+            let frame = recv.lock().await.recv().await.unwrap();
+
+            let thumbnail = monitor.thumbnail_receiver.lock().await.recv().await.unwrap();
+            assert!(thumbnail.width <= bounds.width && thumbnail.height <= bounds.height);
+            assert!(thumbnail.width <= frame.width && thumbnail.height <= frame.height);
+            assert_eq!(thumbnail.stride, thumbnail.width * 4);
+            assert_eq!(thumbnail.data.len(), (thumbnail.stride * thumbnail.height) as usize);
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_delivery_policy() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        assert_eq!(monitor.get_delivery_policy(), DeliveryPolicy::DropOldest);
+
+        monitor.set_delivery_policy(DeliveryPolicy::DropNewest);
+        assert_eq!(monitor.get_delivery_policy(), DeliveryPolicy::DropNewest);
+
+        let handle = monitor.clone().spawn_capturing();
+
+        //the receiver falls behind on purpose, the capture keeps going and drops the frames it can not send
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        assert!(monitor.clone_receiver().lock().await.recv().await.is_some());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_take_receiver() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let mut receiver = monitor.take_receiver().unwrap();
+        assert!(monitor.take_receiver().is_none());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        let consumer = tokio::spawn(async move { receiver.recv().await.is_some() });
+        assert!(consumer.await.unwrap());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_subscribers() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        monitor.set_receiver_output(false);
+        assert!(!monitor.get_receiver_output());
+
+        let mut recorder = monitor.subscribe();
+        let mut preview = monitor.subscribe();
+
+        let handle = monitor.clone().spawn_capturing();
+
+        //both subscribers get the same frame without copying it
+        let recorded = recorder.recv().await.unwrap();
+        let previewed = preview.recv().await.unwrap();
+        assert!(std::sync::Arc::ptr_eq(&recorded, &previewed));
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_latest_frame() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let mut latest = monitor.watch_latest();
+        assert!(latest.borrow_and_update().is_none());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            //the receiver is not read, the latest frame keeps being replaced anyway
+            latest.changed().await.unwrap();
+            let frame = latest.borrow_and_update().clone().unwrap();
+            assert!(frame.width > 0 && frame.height > 0);
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_channel_capacity() {
+        assert!(Monitor::from_monitor(0).unwrap().with_channel_capacity(0).is_err());
+
+        let shared = Monitor::from_monitor(0).unwrap();
+        assert!(shared.clone().with_channel_capacity(4).is_err());
+
+        let monitor = Monitor::from_monitor(0).unwrap().with_channel_capacity(4).unwrap();
+        monitor.set_delivery_policy(DeliveryPolicy::DropNewest);
+
+        let handle = monitor.clone().spawn_capturing();
+
+        //the receiver is not read until the channel had time to fill up
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        let queued = monitor.stats().queued;
+        assert!((1..=4).contains(&queued));
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn capture_leaves_runtime_free() {
+        //a single threaded runtime, a blocking wait for the desktop in the capture would hold up the sleep below
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        for _ in 0..10 {
+            let started = std::time::Instant::now();
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            assert!(started.elapsed() < std::time::Duration::from_millis(250));
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_capture_state() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        assert_eq!(monitor.state(), CaptureState::Idle);
+        assert!(!monitor.is_running());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert_eq!(monitor.state(), CaptureState::Running);
+        assert!(monitor.is_running());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        assert_eq!(monitor.state(), CaptureState::Idle);
+        assert!(!monitor.is_running());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_reconnect() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        assert!(monitor.is_connected());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        //both would send through the same receiver
+        assert!(monitor.reconnect().is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let reconnected = monitor.reconnect().unwrap();
+        assert_eq!(reconnected.name, monitor.name);
+        assert!(std::sync::Arc::ptr_eq(&reconnected.receiver, &monitor.receiver));
+
+        //frames of the new monitor arrive through the receiver of the old one
+        let handle = reconnected.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_restart_after_error() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        //frames can not be sent once the receiver is gone, the capture fails on the first one
+        drop(monitor.take_receiver().unwrap());
+        let failed = monitor.clone().spawn_capturing().wait().await;
+        assert!(failed.is_err());
+        assert!(matches!(monitor.state(), CaptureState::Failed { .. }));
+
+        //nothing is capturing anymore, so there is nothing to stop
+        assert!(monitor.clone().stop_capturing().await.is_err());
+
+        //the monitor starts again, sending to subscribers only
+        monitor.set_receiver_output(false);
+        let mut frames = monitor.subscribe();
+        let handle = monitor.clone().spawn_capturing();
+        assert!(frames.recv().await.is_ok());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[tokio::test]
+    async fn capture_guard() {
+        use crate::devices::capture_state::{CaptureGuard, CaptureState};
+
+        let capturing = tokio::sync::Mutex::new(true);
+        let state = std::sync::Mutex::new(CaptureState::Running);
+
+        //a loop that finished sets the state for its result
+        let guard = CaptureGuard::new(&capturing, Some(&state));
+        guard.finish(&Err("failed to send frame")).await;
+        assert!(!*capturing.lock().await);
+        assert_eq!(*state.lock().unwrap(), CaptureState::Failed { error: "failed to send frame".into() });
+
+        //a loop that never finished, such as a dropped future, is failed as well
+        *capturing.lock().await = true;
+        *state.lock().unwrap() = CaptureState::Running;
+        drop(CaptureGuard::new(&capturing, Some(&state)));
+        assert!(!*capturing.lock().await);
+        assert!(matches!(*state.lock().unwrap(), CaptureState::Failed { .. }));
+
+        //a stop that settled the state first is kept
+        *capturing.lock().await = true;
+        *state.lock().unwrap() = CaptureState::Idle;
+        drop(CaptureGuard::new(&capturing, Some(&state)));
+        assert!(!*capturing.lock().await);
+        assert_eq!(*state.lock().unwrap(), CaptureState::Idle);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn capture_end_events() {
+        assert_eq!(CaptureEvent::ended(None), vec![CaptureEvent::Stopped]);
+
+        let removed: Box<dyn std::error::Error> =
+            Box::new(windows::core::Error::from(DXGI_ERROR_DEVICE_REMOVED));
+        let events = CaptureEvent::ended(Some(removed.as_ref()));
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], CaptureEvent::DeviceLost);
+        assert!(matches!(events[1], CaptureEvent::FatalError { .. }));
+        assert_eq!(events[2], CaptureEvent::Stopped);
+
+        //a disconnected monitor is lost too
+        let disconnected = windows::core::Error::new(DXGI_ERROR_NOT_FOUND, "monitor was disconnected");
+        assert!(CaptureEvent::is_device_lost(&disconnected));
+
+        let other: Box<dyn std::error::Error> = "failed to send frame".into();
+        assert_eq!(
+            CaptureEvent::ended(Some(other.as_ref())),
+            vec![
+                CaptureEvent::FatalError {
+                    error: "failed to send frame".to_string()
+                },
+                CaptureEvent::Stopped
+            ]
+        );
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_screenshot() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let timeout = std::time::Duration::from_secs(2);
+
+        let frame = monitor.capture_frame(timeout).await.unwrap();
+        let size = monitor.get_dimensions().unwrap();
+
+        assert_eq!(frame.width, size.width);
+        assert_eq!(frame.height, size.height);
+        assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+
+        //capturing afterwards works as usual, and screenshots are refused while it runs
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(monitor.capture_frame(timeout).await.is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_follow_window() {
+        unsafe {
+            //the desktop window covers the primary monitor
+            let monitors = Monitor::enumerate().unwrap();
+            let primary = monitors.iter().find(|m| m.is_primary).unwrap();
+            let monitor = Monitor::from_monitor(primary.index).unwrap();
+
+            assert!(monitor.follow_window(HWND(std::ptr::null_mut())).is_err());
+
+            let desktop = GetDesktopWindow();
+            assert!(monitor.follow_window(desktop).is_ok());
+            assert_eq!(monitor.followed_window(), Some(desktop));
+
+            let handle = monitor.clone().spawn_capturing();
+
+            {
+                let recv = monitor.clone_receiver();
+                let frame = recv.lock().await.recv().await.unwrap();
+                let size = monitor.get_dimensions().unwrap();
+
+                assert!(frame.width > 0 && frame.width <= size.width);
+                assert!(frame.height > 0 && frame.height <= size.height);
+                assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+            }
+
+            assert!(handle.stop().await.is_ok());
+            assert!(handle.wait().await.is_ok());
+
+            monitor.stop_following_window();
+            assert_eq!(monitor.followed_window(), None);
+        }
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_texture_output() {
+        unsafe {
+            let monitor = Monitor::from_monitor(0).unwrap();
+
+            monitor.set_texture_output(true);
+            assert!(monitor.get_texture_output());
+
+            let handle = monitor.clone().spawn_capturing();
+
+            {
+                let texture = monitor.texture_receiver.lock().await.recv().await.unwrap();
+                let size = Dimensions { width: texture.width, height: texture.height };
+
+                //the texture is not rotated, the desktop size is
+                assert_eq!(rotated_size(&size, texture.rotation), monitor.desktop_size());
+                assert!(!texture.handle().is_invalid());
+                assert!(texture.sequence >= 1);
+
+                //the texture is handed over with the read key
+                assert!(texture.keyed_mutex().AcquireSync(SharedTexture::READ_KEY, 1000).is_ok());
+                assert!(texture.keyed_mutex().ReleaseSync(SharedTexture::CAPTURE_KEY).is_ok());
+            }
+
+            assert!(handle.stop().await.is_ok());
+            assert!(handle.wait().await.is_ok());
+
+            //nothing is read back to the CPU
+            assert!(monitor.receiver.lock().await.try_recv().is_err());
+        }
+    }
+
+    #[cfg(feature = "graphics-capture")]
+    #[tokio::test]
+    async fn graphics_capture_monitor() {
+        if !GraphicsCapture::is_supported() {
+            println!("Windows.Graphics.Capture is not supported, skipping");
+            return;
+        }
+
+        unsafe {
+            let _ = RoInitialize(RO_INIT_MULTITHREADED);
+
+            let capture = GraphicsCapture::from_monitor(0);
+            assert!(capture.is_ok(), "{:?}", capture.err());
+
+            let capture = capture.unwrap();
+            let size = capture.get_dimensions().unwrap();
+            let handle = capture.clone().spawn_capturing();
+
+            {
+                let recv = capture.clone_receiver();
+                let frame = recv.lock().await.recv().await.unwrap();
+
+                assert_eq!(frame.format, PixelFormat::BGRA);
+                assert_eq!((frame.width, frame.height), (size.width, size.height));
+                assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+            }
+
+            assert!(handle.stop().await.is_ok());
+            assert!(handle.wait().await.is_ok());
+            assert!(capture.stats().delivered >= 1);
+
+            assert!(GraphicsCapture::from_monitor(999).is_err());
+        }
+    }
+
+    #[cfg(feature = "wgpu")]
+    #[tokio::test]
+    async fn wgpu_frame_round_trip() {
+        let instance = wgpu::Instance::default();
+
+        let Ok(adapter) = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await else {
+            println!("no wgpu adapter, skipping");
+            return;
+        };
+
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).await.unwrap();
+
+        //3x2 BGRA with 4 bytes of padding on each row
+        let data: Vec<u8> = (0..32).collect();
+        let frame = Frame::new(3, 2, 16, PixelFormat::BGRA, data.clone());
+
+        let mut frame_texture = FrameTexture::new(wgpu::TextureUsages::COPY_SRC);
+        let texture = frame_texture.upload(&device, &queue, &frame).unwrap().clone();
+
+        assert_eq!(texture.format(), wgpu::TextureFormat::Bgra8Unorm);
+
+        //the same size and format reuses the texture
+        frame_texture.upload(&device, &queue, &frame).unwrap();
+        assert!(frame_texture.texture().unwrap() == &texture);
+
+        //reading back needs rows aligned to 256 bytes
+        let aligned = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (aligned * 2) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(aligned), rows_per_image: Some(2) },
+            },
+            texture.size(),
+        );
+        queue.submit([encoder.finish()]);
+
+        buffer.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+
+        let read = buffer.slice(..).get_mapped_range();
+
+        for y in 0..2 {
+            assert_eq!(read[y * aligned as usize..][..12], data[y * 16..][..12]);
+        }
+
+        drop(read);
+
+        //frames with no wgpu format are refused
+        let rgb = Frame::new(3, 2, 9, PixelFormat::RGB24, vec![0; 18]);
+        assert!(frame_texture.upload(&device, &queue, &rgb).is_err());
+
+        //as are frames with too little data for their size
+        let short = Frame::new(3, 2, 16, PixelFormat::BGRA, vec![0; 20]);
+        assert!(frame_texture.upload(&device, &queue, &short).is_err());
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn frame_to_clipboard() {
+        // a 2x2 RGBA frame with 4 bytes of row padding
+        let data = vec![
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9, //
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let frame = Frame::new(2, 2, 12, PixelFormat::RGBA, data);
+        let dib = frame_to_dib(&frame).unwrap();
+
+        //the header is 124 bytes, with a positive height for rows from the bottom up
+        assert_eq!(dib.len(), 124 + 16);
+        assert_eq!(dib[0..4], 124u32.to_le_bytes());
+        assert_eq!(dib[4..8], 2i32.to_le_bytes());
+        assert_eq!(dib[8..12], 2i32.to_le_bytes());
+        assert_eq!(dib[14..16], 32u16.to_le_bytes());
+
+        //the bottom row comes first, as opaque BGRA
+        assert_eq!(dib[124..], [9, 8, 7, 255, 12, 11, 10, 255, 3, 2, 1, 255, 6, 5, 4, 255]);
+
+        let nv12 = Frame::new(2, 2, 2, PixelFormat::NV12, vec![0; 6]);
+        assert!(frame_to_dib(&nv12).is_err());
+
+        copy_to_clipboard(&frame).unwrap();
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_snapshots() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let interval = std::time::Duration::from_millis(200);
+
+        let handle = monitor.clone().spawn_snapshots(interval);
+        assert_eq!(monitor.get_snapshot_interval(), interval);
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            let first = recv.recv().await.unwrap();
+            let second = recv.recv().await.unwrap();
+
+            //a frame is sent every tick even when the screen did not change
+            let gap = second.timestamp.duration_since(first.timestamp);
+            assert!(gap >= interval / 2);
+
+            //adjustable while running
+            monitor.set_snapshot_interval(std::time::Duration::from_millis(50));
+            assert!(recv.recv().await.is_some());
+        }
+
+        assert!(monitor.is_running());
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+        assert_eq!(monitor.state(), CaptureState::Idle);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn monitor_concurrent_access() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let timeout = std::time::Duration::from_millis(500);
+
+        //screenshots and resizes race against captures starting and stopping on other threads
+        let screenshots = {
+            let monitor = monitor.clone();
+            tokio::spawn(async move {
+                for _ in 0..50 {
+                    if let Ok(frame) = monitor.capture_frame(timeout).await {
+                        assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+                    }
+
+                    let size = monitor.desktop_size();
+                    monitor.set_output_size(size.width / 2, size.height / 2).unwrap();
+                    monitor.reset_output_size().unwrap();
+                }
+            })
+        };
+
+        for _ in 0..20 {
+            let handle = monitor.clone().spawn_capturing();
+
+            {
+                let recv = monitor.clone_receiver();
+                let _ = tokio::time::timeout(timeout, recv.lock().await.recv()).await;
+            }
+
+            assert!(handle.stop().await.is_ok());
+            assert!(handle.wait().await.is_ok());
+        }
+
+        screenshots.await.unwrap();
+
+        assert!(!monitor.is_running());
+        assert_eq!(monitor.state(), CaptureState::Idle);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_max_fps() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        monitor.set_max_fps(Some(10));
+
+        assert_eq!(monitor.get_max_fps(), Some(10));
+
+        let monitor_clone = monitor.clone();
+        let recv = monitor.clone_receiver();
+
+        tokio::spawn(async move {
+            let mut recv = recv.lock().await;
+            let start = std::time::Instant::now();
+
+            for _ in 0..5 {
+                assert!(recv.recv().await.is_some());
+            }
+
+            //the first frame is sent right away, the next four are at least 100ms apart
+            assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+
+            let stopped = monitor_clone.stop_capturing().await;
+            assert!(stopped.is_ok());
+        });
+
+        let captured = monitor.start_capturing().await;
+
+        assert!(captured.is_ok(), "{captured:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_vsync() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        //vsync and the frame rate limit turn each other off
+        monitor.set_max_fps(Some(30));
+        monitor.set_vsync(true);
+        assert!(monitor.get_vsync());
+        assert_eq!(monitor.get_max_fps(), None);
+
+        monitor.set_max_fps(Some(30));
+        assert!(!monitor.get_vsync());
+
+        monitor.set_vsync(true);
+        assert!(monitor.get_frame_interval().is_none());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                assert!(recv.recv().await.is_some());
+            }
+        }
+
+        //no faster than the refresh rate of any monitor
+        let interval = monitor.get_frame_interval().unwrap();
+        assert!(interval >= std::time::Duration::from_millis(2), "{interval:?}");
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[test]
+    fn average_frame_rate() {
+        let counter = FpsCounter::new();
+        let start = std::time::Instant::now();
+        assert_eq!(counter.fps_at(start), 0.0);
+
+        //two seconds of frames at 60 fps
+        let interval = std::time::Duration::from_secs(1) / 60;
+        for frame in 0..120 {
+            counter.record_at(start + interval * frame);
+        }
+
+        let last = start + interval * 119;
+        assert!((counter.fps_at(last) - 60.0).abs() < 0.5, "{}", counter.fps_at(last));
+
+        //a second without frames reads as at most one frame per second
+        assert!(counter.fps_at(last + std::time::Duration::from_secs(1)) <= 1.0);
+
+        counter.reset();
+        assert_eq!(counter.fps_at(last), 0.0);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_current_fps() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        assert_eq!(monitor.current_fps(), 0.0);
+
+        let handle = monitor.clone().spawn_snapshots(std::time::Duration::from_millis(50));
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..10 {
+                assert!(recv.recv().await.is_some());
+            }
+        }
+
+        //snapshots are sent every tick, so the rate is about 20 fps
+        let fps = monitor.current_fps();
+        assert!(fps > 10.0 && fps < 30.0, "{fps}");
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+        assert_eq!(monitor.current_fps(), 0.0);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_frame_stats() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        assert_eq!(monitor.stats(), CaptureStats::default());
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            let mut last_sequence = 0;
+            for _ in 0..3 {
+                let frame = recv.recv().await.unwrap();
+
+                assert!(frame.sequence > last_sequence);
+                last_sequence = frame.sequence;
+            }
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let stats = monitor.stats();
+        assert!(stats.delivered >= 3);
+        assert!(stats.captured >= stats.delivered);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_present_times() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                let frame = recv.recv().await.unwrap();
+
+                //frames with a new image were presented after the capture started
+                if frame.last_present_time != 0 {
+                    assert!(frame.accumulated_frames > 0);
+
+                    let since_start = monitor.present_time_since_start(&frame);
+                    assert!(since_start.unwrap() < std::time::Duration::from_secs(10));
+                }
+            }
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[test]
+    fn convert_qpc_ticks() {
+        assert_eq!(qpc_to_duration(0), std::time::Duration::ZERO);
+        assert_eq!(qpc_to_duration(-5), std::time::Duration::ZERO);
+
+        let start = qpc_now();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let elapsed = qpc_to_duration(qpc_now() - start);
+
+        assert!(elapsed >= std::time::Duration::from_millis(20), "{elapsed:?}");
+        assert!(elapsed < std::time::Duration::from_secs(1), "{elapsed:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn summarize_latency() {
+        let recorder = LatencyRecorder::new(4);
+        assert_eq!(recorder.stats().frames, 0);
+
+        //only the last 4 of the 6 frames are kept
+        for ms in 1..=6 {
+            recorder.record(FrameLatency {
+                total: std::time::Duration::from_millis(ms),
+                ..Default::default()
+            });
+        }
+
+        let stats = recorder.stats();
+        assert_eq!(stats.frames, 4);
+        assert_eq!(stats.total.average, std::time::Duration::from_micros(4500));
+        assert_eq!(stats.total.p95, std::time::Duration::from_millis(6));
+        assert_eq!(stats.total.max, std::time::Duration::from_millis(6));
+        assert_eq!(stats.copy.max, std::time::Duration::ZERO);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_latency_stats() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            for _ in 0..3 {
+                assert!(recv.recv().await.is_some());
+            }
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let stats = monitor.latency_stats();
+        assert!(stats.frames >= 3);
+        assert!(stats.total.average <= stats.total.max);
+        assert!(stats.total.max >= stats.read.max);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_staging_textures() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        let staged = monitor.set_staging_textures(3);
+        assert!(staged.is_ok(), "{staged:?}");
+
+        let monitor_clone = monitor.clone();
+        let recv = monitor.clone_receiver();
+
+        tokio::spawn(async move {
+            let mut recv = recv.lock().await;
+            let mut last = None;
+
+            //frames come out of the staging textures in the order they were acquired
+            for _ in 0..5 {
+                let frame = recv.recv().await.unwrap();
+
+                if let Some(last) = last {
+                    assert!(frame.timestamp > last);
+                }
+
+                last = Some(frame.timestamp);
+            }
+
+            let stopped = monitor_clone.stop_capturing().await;
+            assert!(stopped.is_ok());
+        });
+
+        let captured = monitor.start_capturing().await;
+
+        assert!(captured.is_ok(), "{captured:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_partial_copies() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+
+        //always copy only the changed regions
+        monitor.set_partial_copy_threshold(1.0);
+
+        let monitor_clone = monitor.clone();
+        let recv = monitor.clone_receiver();
+
+        tokio::spawn(async move {
+            let mut recv = recv.lock().await;
+
+            //partially read frames are still whole frames
+            for _ in 0..5 {
+                let frame = recv.recv().await.unwrap();
+                assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+            }
+
+            let stopped = monitor_clone.stop_capturing().await;
+            assert!(stopped.is_ok());
+        });
+
+        let captured = monitor.start_capturing().await;
+
+        assert!(captured.is_ok(), "{captured:?}");
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn size_metadata_buffers() {
+        //room for 2 move rects (24 bytes each) and 3 dirty rects (16 bytes each)
+        let frame_info = DXGI_OUTDUPL_FRAME_INFO {
+            TotalMetadataBufferSize: 2 * 24 + 3 * 16,
+            ..Default::default()
+        };
+
+        let mut moved_buffer = vec![];
+        let mut dirty_buffer = vec![];
+        reserve_metadata(&frame_info, &mut moved_buffer, &mut dirty_buffer);
+
+        //either kind of rect may use all of the metadata
+        assert_eq!(moved_buffer.len(), 4);
+        assert_eq!(dirty_buffer.len(), 6);
+        assert_eq!(metadata_bytes(&moved_buffer), 96);
+        assert_eq!(metadata_bytes(&dirty_buffer), 96);
+
+        assert_eq!(metadata_count::<DXGI_OUTDUPL_MOVE_RECT>(48), 2);
+        assert_eq!(metadata_count::<RECT>(48), 3);
+
+        //buffers are never shrunk
+        reserve_metadata(&DXGI_OUTDUPL_FRAME_INFO::default(), &mut moved_buffer, &mut dirty_buffer);
+        assert_eq!(moved_buffer.len(), 4);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn monitor_frame_rects() {
+        //the buffers are larger than the rects returned, the entries past the counts are left over from earlier frames
+        let mut frame = MonitorFrame {
+            moved_buffer: vec![DXGI_OUTDUPL_MOVE_RECT::default(); 4],
+            dirty_buffer: vec![RECT::default(); 6],
+            dirty_count: 2,
+            moved_count: 1,
+            ..Default::default()
+        };
+        frame.dirty_buffer[0] = RECT { left: 1, top: 2, right: 3, bottom: 4 };
+        frame.moved_buffer[0] = DXGI_OUTDUPL_MOVE_RECT {
+            SourcePoint: POINT { x: 5, y: 6 },
+            DestinationRect: RECT { left: 7, top: 8, right: 9, bottom: 10 },
+        };
+
+        assert_eq!(frame.dirty_rects().len(), 2);
+        assert_eq!(frame.move_rects().len(), 1);
+
+        assert_eq!(frame.dirty_regions().collect::<Vec<_>>(), vec![
+            Rect { left: 1, top: 2, right: 3, bottom: 4 },
+            Rect::default(),
+        ]);
+        assert_eq!(frame.moved_regions().collect::<Vec<_>>(), vec![
+            MoveRect { source_x: 5, source_y: 6, destination: Rect { left: 7, top: 8, right: 9, bottom: 10 } },
+        ]);
+
+        //the metadata only keeps the rects that were returned
+        frame.metadata_size = 96;
+        let metadata = frame.clone_metadata();
+        assert_eq!(metadata.dirty_rects, frame.dirty_regions().collect::<Vec<_>>());
+        assert_eq!(metadata.move_rects.len(), 1);
+        assert_eq!(metadata.metadata_size, 96);
+
+        //the buffers are not printed
+        let debug = format!("{frame:?}");
+        assert!(debug.contains("dirty_count: 2") && !debug.contains("buffer"));
+
+        //counts past the end of the buffers are clamped to them
+        frame.dirty_count = 10;
+        assert_eq!(frame.dirty_rects().len(), 6);
+
+        assert!(MonitorFrame::default().dirty_rects().is_empty());
+        assert!(MonitorFrame::default().move_rects().is_empty());
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn rotate_monitor_frame() {
+        // a 2x1 frame with a row pitch of 12, the last 4 bytes are padding
+        let data = [1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0];
+        let size = Dimensions { width: 2, height: 1 };
+
+        let rotated = rotate_frame(&data, 12, &size, DXGI_MODE_ROTATION_ROTATE90);
+        assert_eq!(rotated, vec![1, 1, 1, 1, 2, 2, 2, 2]);
+
+        let rotated = rotate_frame(&data, 12, &size, DXGI_MODE_ROTATION_ROTATE180);
+        assert_eq!(rotated, vec![2, 2, 2, 2, 1, 1, 1, 1]);
+
+        let rotated = rotate_frame(&data, 12, &size, DXGI_MODE_ROTATION_ROTATE270);
+        assert_eq!(rotated, vec![2, 2, 2, 2, 1, 1, 1, 1]);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn transform_dirty_rect() {
+        let rect = Rect { left: 10, top: 20, right: 30, bottom: 40 };
+        let size = Dimensions { width: 100, height: 50 };
+
+        let scaled = rect.scale(&size, &Dimensions { width: 50, height: 25 });
+        assert_eq!(scaled, Rect { left: 5, top: 10, right: 15, bottom: 20 });
+
+        let rotated = rotate_rect(&rect, &size, DXGI_MODE_ROTATION_ROTATE90);
+        assert_eq!(rotated, Rect { left: 10, top: 10, right: 30, bottom: 30 });
+
+        let rotated = rotate_rect(&rect, &size, DXGI_MODE_ROTATION_ROTATE180);
+        assert_eq!(rotated, Rect { left: 70, top: 10, right: 90, bottom: 30 });
+
+        let rotated = rotate_rect(&rect, &size, DXGI_MODE_ROTATION_ROTATE270);
+        assert_eq!(rotated, Rect { left: 20, top: 70, right: 40, bottom: 90 });
+    }
+
+    #[test]
+    fn scale_pointer_position() {
+        let position = PointerPosition { x: 99, y: 40, visible: true };
+        let size = Dimensions { width: 200, height: 100 };
+
+        let scaled = position.scale(&size, &Dimensions { width: 100, height: 50 });
+        assert_eq!(scaled, PointerPosition { x: 49, y: 20, visible: true });
+
+        assert_eq!(position.scale(&size, &size), position);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_frame_pointer() {
+        let monitor = Monitor::from_monitor(0).unwrap();
+        let timeout = std::time::Duration::from_secs(2);
+
+        monitor.set_output_size(monitor.desktop_size().width / 2, monitor.desktop_size().height / 2).unwrap();
+
+        //without a pointer update the position is unknown, otherwise it must be inside the scaled frame
+        let frame = monitor.capture_frame(timeout).await.unwrap();
+        if let Some(pointer) = frame.pointer.filter(|pointer| pointer.visible) {
+            assert!(pointer.x < frame.width as i32);
+            assert!(pointer.y < frame.height as i32);
+        }
+    }
+
+    #[test]
+    fn apply_moves_to_frame() {
+        // a 3x1 frame, the first pixel is moved one to the right
+        let mut frame = Frame::new(3, 1, 12, PixelFormat::BGRA, vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3]);
+
+        let moves = [MoveRect {
+            source_x: 0,
+            source_y: 0,
+            destination: Rect { left: 1, top: 0, right: 3, bottom: 1 },
+        }];
+
+        apply_move_rects(&mut frame, &moves);
+
+        assert_eq!(*frame.data, vec![1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn crop_to_rect() {
+        // a 4x3 BGRA frame with 4 bytes of row padding, every pixel is filled with its index
+        let mut data = vec![0; 60];
+        for y in 0..3 {
+            for x in 0..4 {
+                data[y * 20 + x * 4..][..4].fill((y * 4 + x) as u8);
+            }
+        }
+
+        let mut frame = Frame::new(4, 3, 20, PixelFormat::BGRA, data);
+        frame.dirty_rects = vec![Rect { left: 0, top: 0, right: 2, bottom: 2 }];
+        frame.move_rects = vec![
+            //inside the crop
+            MoveRect { source_x: 1, source_y: 1, destination: Rect { left: 2, top: 1, right: 3, bottom: 2 } },
+            //from outside the crop
+            MoveRect { source_x: 0, source_y: 0, destination: Rect { left: 3, top: 2, right: 4, bottom: 3 } },
+        ];
+        frame.pointer = Some(PointerPosition { x: 2, y: 1, visible: true });
+
+        //the crop is clipped to the frame
+        let cropped = crop_frame(&frame, &Rect { left: 1, top: 1, right: 10, bottom: 10 });
+
+        assert_eq!((cropped.width, cropped.height, cropped.stride), (3, 2, 12));
+        assert_eq!(cropped.data[..4], [5; 4]);
+        assert_eq!(cropped.data[12..16], [9; 4]);
+        assert_eq!(cropped.data[20..], [11; 4]);
+
+        assert_eq!(cropped.move_rects, vec![MoveRect { source_x: 0, source_y: 0, destination: Rect { left: 1, top: 0, right: 2, bottom: 1 } }]);
+        assert_eq!(cropped.dirty_rects, vec![
+            Rect { left: 0, top: 0, right: 1, bottom: 1 },
+            Rect { left: 2, top: 1, right: 3, bottom: 2 },
+        ]);
+        assert_eq!(cropped.pointer, Some(PointerPosition { x: 1, y: 0, visible: true }));
+
+        //the pointer is hidden once it is outside of the crop
+        let cropped = crop_frame(&frame, &Rect { left: 3, top: 0, right: 4, bottom: 1 });
+        assert_eq!(cropped.pointer, Some(PointerPosition { x: -1, y: 1, visible: false }));
+        assert_eq!(*cropped.data, vec![3; 4]);
+    }
+
+    #[test]
+    fn flip_frames() {
+        // a 2x3 RGB32 frame stored bottom up with 4 bytes of row padding, every row is filled with its index
+        let mut data = vec![0xAA; 12 * 3];
+        for y in 0..3 {
+            data[y * 12..][..8].fill(y as u8);
+        }
+
+        let mut frame = Frame::new(2, 3, 12, PixelFormat::RGB32, data);
+        flip_frame(&mut frame).unwrap();
+
+        //the last row of the data is now row 0 at the top, the padding stays as it was
+        assert_eq!(frame.data[..8], [2; 8]);
+        assert_eq!(frame.data[12..20], [1; 8]);
+        assert_eq!(frame.data[24..32], [0; 8]);
+        assert!(frame.data.chunks(12).all(|row| row[8..] == [0xAA; 4]));
+
+        //flipping twice gives the frame back
+        flip_frame(&mut frame).unwrap();
+        assert_eq!(frame.data[..8], [0; 8]);
+
+        let mut planar = Frame::new(2, 2, 2, PixelFormat::NV12, vec![0; 6]);
+        assert!(flip_frame(&mut planar).is_err());
+
+        let mut short = Frame::new(2, 3, 12, PixelFormat::RGB32, vec![0; 12]);
+        assert!(flip_frame(&mut short).is_err());
+    }
+
+    #[test]
+    fn orient_frames() {
+        // a 3x2 Gray8 frame with a byte of row padding
+        let frame = Frame::new(3, 2, 4, PixelFormat::Gray8, vec![1, 2, 3, 9, 4, 5, 6, 9]);
+        let orient = |mirror_horizontal, flip_vertical, rotation| {
+            let oriented = orient_frame(&frame, Orientation { mirror_horizontal, flip_vertical, rotation }).unwrap();
+            (oriented.width, oriented.height, oriented.stride, oriented.data.to_vec())
+        };
+
+        assert_eq!(orient(false, false, Rotation::None), (3, 2, 3, vec![1, 2, 3, 4, 5, 6]));
+        assert_eq!(orient(true, false, Rotation::None), (3, 2, 3, vec![3, 2, 1, 6, 5, 4]));
+        assert_eq!(orient(false, true, Rotation::None), (3, 2, 3, vec![4, 5, 6, 1, 2, 3]));
+
+        //rotations are clockwise and swap the size for 90 and 270 degrees
+        assert_eq!(orient(false, false, Rotation::Rotate90), (2, 3, 2, vec![4, 1, 5, 2, 6, 3]));
+        assert_eq!(orient(false, false, Rotation::Rotate180), (3, 2, 3, vec![6, 5, 4, 3, 2, 1]));
+        assert_eq!(orient(false, false, Rotation::Rotate270), (2, 3, 2, vec![3, 6, 2, 5, 1, 4]));
+
+        //mirrored first, then rotated
+        assert_eq!(orient(true, false, Rotation::Rotate90), (2, 3, 2, vec![6, 3, 5, 2, 4, 1]));
+
+        let quarter = Orientation { rotation: Rotation::Rotate90, ..Orientation::default() };
+        assert_eq!(quarter.oriented_size(&Dimensions { width: 640, height: 480 }), Dimensions { width: 480, height: 640 });
+        assert!(Orientation::default().is_identity() && !quarter.is_identity());
+
+        //the dirty rects and pointer move with the pixels, the top left pixel ends up top right
+        let mut marked = frame.clone();
+        marked.dirty_rects = vec![Rect { left: 0, top: 0, right: 1, bottom: 1 }];
+        marked.pointer = Some(PointerPosition { x: 0, y: 0, visible: true });
+
+        let rotated = orient_frame(&marked, quarter).unwrap();
+        assert_eq!(rotated.dirty_rects, vec![Rect { left: 1, top: 0, right: 2, bottom: 1 }]);
+        assert_eq!(rotated.pointer, Some(PointerPosition { x: 1, y: 0, visible: true }));
+
+        // a 4x2 NV12 frame, the chroma pairs are mirrored as pixels of their own
+        let nv12 = Frame::new(4, 2, 4, PixelFormat::NV12, vec![1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 20, 21]);
+        let mirrored = orient_frame(&nv12, Orientation { mirror_horizontal: true, ..Orientation::default() }).unwrap();
+        assert_eq!(mirrored.data[..], [4, 3, 2, 1, 8, 7, 6, 5, 20, 21, 10, 11]);
+
+        let rotated = orient_frame(&nv12, quarter).unwrap();
+        assert_eq!((rotated.width, rotated.height, rotated.stride), (2, 4, 2));
+        assert_eq!(rotated.data[..], [5, 1, 6, 2, 7, 3, 8, 4, 10, 11, 20, 21]);
+
+        //formats sharing color between pixels, odd planar sizes and short data are refused
+        assert!(orient_frame(&Frame::new(2, 1, 4, PixelFormat::YUY2, vec![0; 4]), quarter).is_err());
+        assert!(orient_frame(&Frame::new(3, 2, 3, PixelFormat::NV12, vec![0; 9]), quarter).is_err());
+        assert!(orient_frame(&Frame::new(3, 2, 4, PixelFormat::Gray8, vec![0; 6]), quarter).is_err());
+    }
+
+    #[test]
+    fn match_frames() {
+        let start = std::time::Instant::now();
+        let at = |ms: u64| start + std::time::Duration::from_millis(ms);
+        let frame = |sequence: u64| {
+            let mut frame = Frame::new(1, 1, 4, PixelFormat::BGRA, vec![0; 4]);
+            frame.sequence = sequence;
+            frame
+        };
+
+        let mut matcher = FrameMatcher::new(2, std::time::Duration::from_millis(10));
+
+        //a frame is held until the other source sends one close enough to it
+        assert!(matcher.push(0, frame(1), at(0)).is_none());
+        let matched = matcher.push(1, frame(1), at(5)).unwrap();
+        assert_eq!(matched.iter().map(|frame| frame.sequence).collect::<Vec<_>>(), [1, 1]);
+
+        //the second source skipped a frame, the first one's frame at 33 can not be matched anymore
+        assert!(matcher.push(0, frame(2), at(33)).is_none());
+        assert!(matcher.push(0, frame(3), at(66)).is_none());
+        let matched = matcher.push(1, frame(3), at(70)).unwrap();
+        assert_eq!(matched[0].sequence, 3);
+        assert_eq!(matcher.unmatched(), 1);
+
+        //frames of unknown sources are ignored
+        assert!(matcher.push(2, frame(1), at(70)).is_none());
+    }
+
+    #[test]
+    fn convert_nv12_vectors() {
+        //limited range colors and the RGB they encode, of each matrix
+        let vectors = [
+            (YuvMatrix::Bt601, [235, 128, 128], [255, 255, 255]),
+            (YuvMatrix::Bt601, [16, 128, 128], [0, 0, 0]),
+            (YuvMatrix::Bt601, [81, 90, 240], [255, 0, 0]),
+            (YuvMatrix::Bt601, [145, 54, 34], [0, 255, 0]),
+            (YuvMatrix::Bt601, [41, 240, 110], [0, 0, 255]),
+            (YuvMatrix::Bt709, [63, 102, 240], [255, 0, 0]),
+            (YuvMatrix::Bt709, [173, 42, 26], [0, 255, 0]),
+            (YuvMatrix::Bt709, [32, 240, 118], [0, 0, 255]),
+        ];
+
+        for (matrix, [y, u, v], rgb) in vectors {
+            // a 2x2 frame with 2 bytes of row padding, the chroma plane starts after the 2 rows of luma
+            let data = [y, y, 0, 0, y, y, 0, 0, u, v, 0, 0];
+            let frame = Frame::new(2, 2, 4, PixelFormat::NV12, data.to_vec());
+
+            let converted = nv12_to_rgb(&frame, matrix).unwrap();
+            assert_eq!((converted.format, converted.stride), (PixelFormat::RGB24, 6));
+
+            for pixel in converted.data.chunks(3) {
+                assert!(pixel.iter().zip(rgb).all(|(a, b)| a.abs_diff(b) <= 2), "{matrix:?} {pixel:?} {rgb:?}");
+            }
+        }
+
+        //odd sizes share the chroma of the column and row before, 3x3 with a stride of 4
+        let mut data = vec![0; 4 * 3 + 4 * 2];
+        data[..12].copy_from_slice(&[81, 81, 235, 0, 81, 81, 235, 0, 235, 235, 235, 0]);
+        data[12..].copy_from_slice(&[90, 240, 128, 128, 128, 128, 128, 128]);
+        let frame = Frame::new(3, 3, 4, PixelFormat::NV12, data);
+
+        let bgra = nv12_to_bgra(&frame, YuvMatrix::Bt601).unwrap();
+        assert_eq!((bgra.width, bgra.height, bgra.stride), (3, 3, 12));
+        assert_eq!(bgra.data[..4], [0, 0, 255, 255]);
+        assert_eq!(bgra.data[32..], [255, 255, 255, 255]);
+
+        let rgba = nv12_to_rgba(&frame, YuvMatrix::Bt601).unwrap();
+        assert_eq!(rgba.data[..4], [255, 0, 0, 255]);
+
+        //too short for its size, or not NV12
+        assert!(convert_nv12(&[0; 19], 3, 3, 4, YuvMatrix::Bt601, PixelFormat::BGRA).is_err());
+        assert!(convert_nv12(&[0; 20], 3, 3, 4, YuvMatrix::Bt601, PixelFormat::NV12).is_err());
+        assert!(nv12_to_bgra(&bgra, YuvMatrix::Bt601).is_err());
+    }
+
+    #[test]
+    fn nv12_round_trip() {
+        for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+            // a 6x4 BGRA image with 4 bytes of row padding, every 2x2 block is one color so no chroma is lost
+            let (width, height, stride) = (6, 4, 28);
+            let mut data = vec![0; stride * height];
+            for y in 0..height {
+                for x in 0..width {
+                    let (block_x, block_y) = (x / 2, y / 2);
+                    let color = [(block_x * 90) as u8, (block_y * 200) as u8, (255 - block_x * 60) as u8, 255];
+                    data[y * stride + x * 4..][..4].copy_from_slice(&color);
+                }
+            }
+
+            let nv12 = bgra_to_nv12(&data, width as u32, height as u32, stride as u32, matrix).unwrap();
+            assert_eq!(nv12.len(), width * height * 3 / 2);
+
+            let back = convert_nv12(&nv12, width as u32, height as u32, width as u32, matrix, PixelFormat::BGRA).unwrap();
+
+            for y in 0..height {
+                let row = &data[y * stride..][..width * 4];
+                let back = &back[y * width * 4..][..width * 4];
+                assert!(row.iter().zip(back).all(|(a, b)| a.abs_diff(*b) <= 3), "{matrix:?} row {y}: {row:?} {back:?}");
+            }
+        }
+
+        //odd sizes are padded to an even stride, a single color comes back as it was
+        let data = [40, 120, 200, 255].repeat(5 * 3);
+        let nv12 = bgra_to_nv12(&data, 5, 3, 20, YuvMatrix::Bt709).unwrap();
+        assert_eq!(nv12.len(), 6 * 3 + 6 * 2);
+
+        let back = convert_nv12(&nv12, 5, 3, 6, YuvMatrix::Bt709, PixelFormat::BGRA).unwrap();
+        assert!(back.chunks(4).all(|pixel| pixel.iter().zip([40, 120, 200, 255]).all(|(a, b)| a.abs_diff(b) <= 3)));
+    }
+
+    #[test]
+    fn convert_yuy2_vectors() {
+        // a 3x1 YUY2 frame of BT.601 red, white and black, the last pair only has one pixel
+        let data = [81, 90, 81, 240, 235, 128, 16, 128];
+        let frame = Frame::new(3, 1, 8, PixelFormat::YUY2, data.to_vec());
+
+        let bgra = yuy2_to_bgra(&frame, YuvMatrix::Bt601).unwrap();
+        assert_eq!((bgra.format, bgra.stride), (PixelFormat::BGRA, 12));
+        assert!(bgra.data.iter().zip([0, 0, 255, 255, 0, 0, 255, 255, 255, 255, 255, 255]).all(|(a, b)| a.abs_diff(b) <= 2), "{:?}", bgra.data);
+
+        let rgb = convert_yuy2(&data, 3, 1, 8, YuvMatrix::Bt601, PixelFormat::RGB24).unwrap();
+        assert!(rgb[..3].iter().zip([255, 0, 0]).all(|(a, b)| a.abs_diff(b) <= 2));
+        assert_eq!(rgb[6..], [255, 255, 255]);
+
+        assert!(convert_yuy2(&data[..7], 3, 1, 8, YuvMatrix::Bt601, PixelFormat::BGRA).is_err());
+        assert!(yuy2_to_bgra(&bgra, YuvMatrix::Bt601).is_err());
+    }
+
+    //the data of the size, different on every byte
+    #[cfg(feature = "monitor")]
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_u32;
+
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn simd_matches_scalar() {
+        //widths that leave pixels to the scalar code after the 8 pixel kernels, and one row of padding in the stride
+        for width in [1, 7, 8, 37, 64] {
+            let (height, stride) = (5, width + 3);
+
+            let nv12 = noise(stride * height + stride * 3);
+            let yuy2 = noise((width * 2 + 8) * height);
+            let bgra = noise((width * 4 + 4) * height);
+
+            let convert = || {
+                let mut converted = vec![];
+
+                for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+                    for format in [PixelFormat::BGRA, PixelFormat::RGBA, PixelFormat::RGB24] {
+                        converted.push(convert_nv12(&nv12, width as u32, height as u32, stride as u32, matrix, format).unwrap());
+                        converted.push(convert_yuy2(&yuy2, width as u32, height as u32, width as u32 * 2 + 8, matrix, format).unwrap());
+                    }
+                }
+
+                for (format, opaque_alpha, gray_weights) in [
+                    (PixelFormat::RGBA, false, GrayWeights::Bt601),
+                    (PixelFormat::RGBA, true, GrayWeights::Bt601),
+                    (PixelFormat::BGRA, true, GrayWeights::Bt601),
+                    (PixelFormat::Gray8, false, GrayWeights::Bt601),
+                    (PixelFormat::Gray8, false, GrayWeights::Bt709),
+                ] {
+                    let mut rows = vec![];
+                    convert_rows(&bgra, width * 4 + 4, width, height, PixelConversion { format, opaque_alpha, gray_weights }, &mut rows);
+                    converted.push(rows);
+                }
+
+                converted
+            };
+
+            set_simd_enabled(false);
+            assert!(!simd_enabled());
+            let scalar = convert();
+
+            //the same results on CPUs without AVX2, where both are scalar
+            set_simd_enabled(true);
+            let simd = convert();
+
+            assert_eq!(scalar, simd, "width {width}");
+        }
+    }
+
+    //run with cargo test --release -- --ignored simd_throughput
+    #[cfg(feature = "monitor")]
+    #[test]
+    #[ignore]
+    fn simd_throughput() {
+        let (width, height) = (3840, 2160);
+        let nv12 = noise(width * height * 3 / 2);
+        let bgra = noise(width * height * 4);
+
+        let time = |simd: bool| {
+            set_simd_enabled(simd);
+            let start = std::time::Instant::now();
+
+            for _ in 0..10 {
+                let converted = convert_nv12(&nv12, width as u32, height as u32, width as u32, YuvMatrix::Bt709, PixelFormat::BGRA).unwrap();
+                let mut rgba = vec![];
+                convert_rows(&bgra, width * 4, width, height, PixelConversion { format: PixelFormat::RGBA, opaque_alpha: true, gray_weights: GrayWeights::default() }, &mut rgba);
+
+                assert_eq!(converted.len(), rgba.len());
+            }
+
+            start.elapsed()
+        };
+
+        let scalar = time(false);
+        let simd = time(true);
+
+        println!("10 4K frames of NV12 to BGRA and BGRA to RGBA: scalar {scalar:?}, simd {simd:?}");
+
+        if simd_enabled() {
+            assert!(simd * 2 < scalar, "scalar {scalar:?}, simd {simd:?}");
+        }
+    }
+
+    #[test]
+    fn frame_u16_values() {
+        // a 2x2 Depth16 frame with 2 bytes of row padding, the depths are 1000, 2000, 3000 and 4000
+        let mut data = vec![];
+        for row in [[1000u16, 2000], [3000, 4000]] {
+            row.iter().for_each(|depth| data.extend_from_slice(&depth.to_le_bytes()));
+            data.extend_from_slice(&[0xAA; 2]);
+        }
+
+        let frame = Frame::new(2, 2, 6, PixelFormat::Depth16, data);
+        assert_eq!(frame.format.bytes_per_pixel(), 2);
+        assert_eq!(frame.color_space, None);
+
+        //the values of a row start at y * stride / 2
+        let depths = frame_as_u16(&frame).unwrap();
+        assert_eq!(depths[..2], [1000, 2000]);
+        assert_eq!(depths[3..5], [3000, 4000]);
+
+        let gray = Frame::new(2, 1, 4, PixelFormat::Gray16, vec![0xFF; 4]);
+        assert_eq!(frame_as_u16(&gray).unwrap(), [0xFFFF, 0xFFFF]);
+
+        let color = Frame::new(2, 2, 8, PixelFormat::RGB32, vec![0; 16]);
+        assert!(frame_as_u16(&color).is_err());
+
+        let short = Frame::new(2, 2, 6, PixelFormat::Depth16, vec![0; 6]);
+        assert!(frame_as_u16(&short).is_err());
+    }
+
+    #[test]
+    fn downscale_frames() {
+        // a 4x2 BGRA frame with 4 bytes of row padding, every pixel is filled with its index * 10
+        let mut data = vec![0; 40];
+        for y in 0..2 {
+            for x in 0..4 {
+                data[y * 20 + x * 4..][..4].fill((y * 4 + x) as u8 * 10);
+            }
+        }
+
+        let mut frame = Frame::new(4, 2, 20, PixelFormat::BGRA, data);
+        frame.dirty_rects = vec![Rect { left: 0, top: 0, right: 1, bottom: 1 }];
+        frame.move_rects = vec![MoveRect { source_x: 0, source_y: 0, destination: Rect { left: 2, top: 0, right: 4, bottom: 2 } }];
+        frame.pointer = Some(PointerPosition { x: 3, y: 1, visible: true });
+
+        let half = Dimensions { width: 2, height: 1 };
+
+        //each pixel is the average of the 2x2 pixels it covers
+        let scaled = downscale_frame(&frame, &half, ScaleFilter::Box);
+        assert_eq!((scaled.width, scaled.height, scaled.stride), (2, 1, 8));
+        assert_eq!(*scaled.data, vec![25, 25, 25, 25, 45, 45, 45, 45]);
+
+        //or the pixel at the center of them
+        let scaled = downscale_frame(&frame, &half, ScaleFilter::Nearest);
+        assert_eq!(*scaled.data, vec![50, 50, 50, 50, 70, 70, 70, 70]);
+
+        //the moved area is sent as dirty
+        assert!(scaled.move_rects.is_empty());
+        assert_eq!(scaled.dirty_rects, vec![
+            Rect { left: 0, top: 0, right: 1, bottom: 1 },
+            Rect { left: 1, top: 0, right: 2, bottom: 1 },
+        ]);
+        assert_eq!(scaled.pointer, Some(PointerPosition { x: 1, y: 0, visible: true }));
+
+        //frames are never scaled up, only the padding is dropped
+        let scaled = downscale_frame(&frame, &Dimensions { width: 10, height: 10 }, ScaleFilter::Box);
+        assert_eq!((scaled.width, scaled.height, scaled.stride), (4, 2, 16));
+        assert_eq!(scaled.data[16..20], [40; 4]);
+
+        // a 4x4 NV12 frame, the luma is its index and the chroma 100 + its index
+        let mut data: Vec<u8> = (0..16).collect();
+        data.extend(100..108);
+
+        let frame = Frame::new(4, 4, 4, PixelFormat::NV12, data);
+        let scaled = downscale_frame(&frame, &Dimensions { width: 3, height: 2 }, ScaleFilter::Box);
+
+        //scaled to an even size with the nearest pixels
+        assert_eq!((scaled.width, scaled.height, scaled.stride), (2, 2, 2));
+        assert_eq!(*scaled.data, vec![5, 7, 13, 15, 106, 107]);
+    }
+
+    #[test]
+    fn yuv_frames() {
+        // a 4x4 I420 frame, the luma is its index, the blue chroma 100 + its index and the red chroma 200 + its index
+        let mut data: Vec<u8> = (0..16).collect();
+        data.extend(100..104);
+        data.extend(200..204);
+
+        let frame = Frame::new(4, 4, 4, PixelFormat::I420, data);
+        assert_eq!(frame.color_space, None);
+
+        //each chroma plane is cropped and scaled on its own
+        let cropped = crop_frame(&frame, &Rect { left: 2, top: 2, right: 4, bottom: 4 });
+        assert_eq!((cropped.width, cropped.height, cropped.stride), (2, 2, 2));
+        assert_eq!(*cropped.data, vec![10, 11, 14, 15, 103, 203]);
+
+        let scaled = downscale_frame(&frame, &Dimensions { width: 3, height: 2 }, ScaleFilter::Box);
+        assert_eq!((scaled.width, scaled.height), (2, 2));
+        assert_eq!(*scaled.data, vec![5, 7, 13, 15, 103, 203]);
+
+        // a 4x1 YUY2 frame, every two pixels are luma, blue chroma, luma, red chroma
+        let frame = Frame::new(4, 1, 8, PixelFormat::YUY2, (0..8).collect::<Vec<u8>>());
+
+        //pairs of pixels are kept together
+        let cropped = crop_frame(&frame, &Rect { left: 3, top: 0, right: 4, bottom: 1 });
+        assert_eq!((cropped.width, cropped.stride), (2, 4));
+        assert_eq!(*cropped.data, vec![4, 5, 6, 7]);
+
+        let scaled = downscale_frame(&frame, &Dimensions { width: 3, height: 1 }, ScaleFilter::Box);
+        assert_eq!((scaled.width, scaled.stride), (2, 4));
+        assert_eq!(*scaled.data, vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn compressed_frames() {
+        assert!(PixelFormat::MJPG.is_compressed());
+        assert!(!PixelFormat::YUY2.is_compressed());
+        assert_eq!(PixelFormat::MJPG.bytes_per_pixel(), 0);
+        assert_eq!(PixelFormat::YUY2.bytes_per_pixel(), 2);
+        assert_eq!(PixelFormat::BGR24.bytes_per_pixel(), 3);
+
+        // a JPEG of any length, with no rows
+        let frame = Frame::new(4, 4, 0, PixelFormat::MJPG, vec![0xFF, 0xD8, 1, 2, 0xFF, 0xD9]);
+        assert_eq!(frame.color_space, None);
+
+        //the pixels can not be reached, so the frame is left as it is
+        let cropped = crop_frame(&frame, &Rect { left: 0, top: 0, right: 2, bottom: 2 });
+        assert_eq!((cropped.width, cropped.height), (4, 4));
+        assert_eq!(*cropped.data, *frame.data);
+
+        assert!(compare_frames(&frame, &frame, 0).is_err());
+
+        //the whole image is hashed
+        let changed = Frame::new(4, 4, 0, PixelFormat::MJPG, vec![0xFF, 0xD8, 1, 3, 0xFF, 0xD9]);
+        assert_ne!(hash_frame(&frame), hash_frame(&changed));
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_output_formats() {
+        //media foundation's RGB24 and ARGB32 are stored blue first
+        assert_eq!(Output::RGB24.pixel_format(), PixelFormat::BGR24);
+        assert_eq!(Output::ARGB32.pixel_format(), PixelFormat::BGRA);
+        assert_eq!(Output::YUY2.pixel_format(), PixelFormat::YUY2);
+        assert_eq!(Output::I420.pixel_format(), PixelFormat::I420);
+        assert!(Output::MJPG.pixel_format().is_compressed());
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_formats() {
+        let format = CameraFormat::new(1920, 1080, 30, 1);
+        assert_eq!(format.to_string(), "1920x1080 at 30 fps");
+
+        //29.97 is close enough to 30, 25 and other sizes are not
+        let ntsc = CameraFormat::new(1920, 1080, 30000, 1001);
+        assert_eq!(ntsc.to_string(), "1920x1080 at 29.97 fps");
+        assert!(format.distance(&ntsc).is_some());
+        assert!(format.distance(&CameraFormat::new(1920, 1080, 25, 1)).is_none());
+        assert!(format.distance(&CameraFormat::new(1280, 720, 30, 1)).is_none());
+    }
+
+    #[test]
+    fn coalesce_overlapping_rects() {
+        let rects = [
+            Rect { left: 0, top: 0, right: 10, bottom: 10 },
+            Rect { left: 5, top: 5, right: 15, bottom: 15 },
+            //shares the right edge of the second rect, so it is merged too
+            Rect { left: 15, top: 10, right: 20, bottom: 12 },
+            //only meets the merged rects at a corner
+            Rect { left: 20, top: -5, right: 25, bottom: 0 },
+        ];
+
+        let mut merged = coalesce_rects(&rects, None);
+        merged.sort_by_key(|rect| rect.top);
+
+        assert_eq!(merged, vec![
+            Rect { left: 20, top: -5, right: 25, bottom: 0 },
+            Rect { left: 0, top: 0, right: 20, bottom: 15 },
+        ]);
+    }
+
+    #[test]
+    fn coalesce_contained_rects() {
+        let outer = Rect { left: 0, top: 0, right: 100, bottom: 100 };
+
+        let rects = [
+            Rect { left: 10, top: 10, right: 20, bottom: 20 },
+            outer,
+            Rect { left: 50, top: 50, right: 60, bottom: 60 },
+            Rect { left: 30, top: 30, right: 30, bottom: 40 },
+        ];
+
+        assert_eq!(coalesce_rects(&rects, None), vec![outer]);
+        assert!(coalesce_rects(&[], Some(4)).is_empty());
+    }
+
+    #[test]
+    fn coalesce_disjoint_rects() {
+        let rects = [
+            Rect { left: 0, top: 0, right: 10, bottom: 10 },
+            Rect { left: 12, top: 0, right: 22, bottom: 10 },
+            Rect { left: 500, top: 500, right: 510, bottom: 510 },
+        ];
+
+        //nothing touches, so nothing is merged
+        assert_eq!(coalesce_rects(&rects, None).len(), 3);
+
+        //capped at two the nearest rects are merged and the far one is left alone
+        let mut merged = coalesce_rects(&rects, Some(2));
+        merged.sort_by_key(|rect| rect.left);
+
+        assert_eq!(merged, vec![
+            Rect { left: 0, top: 0, right: 22, bottom: 10 },
+            Rect { left: 500, top: 500, right: 510, bottom: 510 },
+        ]);
+
+        assert_eq!(coalesce_rects(&rects, Some(0)), vec![Rect { left: 0, top: 0, right: 510, bottom: 510 }]);
+    }
+
+    #[test]
+    fn compare_changed_frames() {
+        // 4x2 BGRA frames with 4 bytes of row padding
+        let previous = Frame::new(4, 2, 20, PixelFormat::BGRA, vec![10; 40]);
+
+        let mut data = vec![10; 40];
+        //pixel 1, 0 changes a lot, pixel 2, 1 changes a little and pixel 3, 1 only changes alpha
+        data[4..7].copy_from_slice(&[200, 10, 10]);
+        data[20 + 8] = 13;
+        data[20 + 15] = 255;
+        //the padding is never compared
+        data[16] = 99;
+        let current = Frame::new(4, 2, 20, PixelFormat::BGRA, data);
+
+        let difference = compare_frames(&previous, &current, 0).unwrap();
+        assert_eq!(difference.changed_pixels, 2);
+        assert_eq!(difference.change_ratio, 0.25);
+        assert_eq!(difference.bounds, Some(Rect { left: 1, top: 0, right: 3, bottom: 2 }));
+
+        //a threshold above the small change leaves only the big one
+        let difference = compare_frames(&previous, &current, 3).unwrap();
+        assert_eq!(difference.changed_pixels, 1);
+        assert_eq!(difference.bounds, Some(Rect { left: 1, top: 0, right: 2, bottom: 1 }));
+
+        let same = compare_frames(&previous, &previous, 0).unwrap();
+        assert_eq!(same.change_ratio, 0.0);
+        assert_eq!(same.bounds, None);
+
+        let smaller = Frame::new(2, 2, 8, PixelFormat::BGRA, vec![10; 16]);
+        assert!(compare_frames(&previous, &smaller, 0).is_err());
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[test]
+    fn filter_unchanged_frames() {
+        let mut filter = ChangeFilter::default();
+        let frame = |value: u8| Frame::new(2, 1, 8, PixelFormat::BGRA, vec![value; 8]);
+
+        //every frame passes until a ratio is set
+        assert!(filter.passes(&frame(0)));
+        assert!(filter.passes(&frame(0)));
+
+        filter.set(Some(0.5), 8);
+
+        //the first frame is always let through, then only frames that changed enough from it
+        assert!(filter.passes(&frame(0)));
+        assert!(!filter.passes(&frame(5)));
+        assert!(filter.passes(&frame(9)));
+        assert!(!filter.passes(&frame(9)));
+
+        //a frame of another size cannot be compared and is let through
+        assert!(filter.passes(&Frame::new(1, 1, 4, PixelFormat::BGRA, vec![9; 4])));
+
+        filter.reset();
+        assert!(filter.passes(&frame(9)));
+    }
+
+    #[test]
+    fn hash_frames() {
+        //the same 2x2 image, packed and with 4 bytes of padding on each row
+        let pixels: Vec<u8> = (0..16).collect();
+        let packed = Frame::new(2, 2, 8, PixelFormat::BGRA, pixels.clone());
+        let padded = Frame::new(2, 2, 12, PixelFormat::BGRA, [&pixels[..8], &[1; 4], &pixels[8..], &[2; 4]].concat());
+
+        assert_eq!(hash_frame(&packed), hash_frame(&padded));
+
+        let mut changed = pixels.clone();
+        changed[13] ^= 1;
+        assert_ne!(hash_frame(&packed), hash_frame(&Frame::new(2, 2, 8, PixelFormat::BGRA, changed.clone())));
+
+        //only the first of every 4 rows is sampled, the second row is not
+        assert_eq!(sample_hash_frame(&packed), sample_hash_frame(&Frame::new(2, 2, 8, PixelFormat::BGRA, changed)));
+
+        let mut changed = pixels;
+        changed[1] ^= 1;
+        assert_ne!(sample_hash_frame(&packed), sample_hash_frame(&Frame::new(2, 2, 8, PixelFormat::BGRA, changed)));
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[test]
+    fn suppress_duplicate_frames() {
+        let mut filter = DuplicateFilter::default();
+        let frame = |value: u8| Frame::new(2, 1, 8, PixelFormat::BGRA, vec![value; 8]);
+
+        //every frame passes until a hasher is set
+        assert!(filter.passes(&frame(0)));
+        assert!(filter.passes(&frame(0)));
+
+        filter.set(Some(hash_frame));
+
+        assert!(filter.passes(&frame(0)));
+        assert!(!filter.passes(&frame(0)));
+        assert!(filter.passes(&frame(1)));
+        assert!(!filter.passes(&frame(1)));
+
+        //the same bytes at another size are a different frame
+        assert!(filter.passes(&Frame::new(1, 2, 4, PixelFormat::BGRA, vec![1; 8])));
+
+        filter.reset();
+        assert!(filter.passes(&Frame::new(1, 2, 4, PixelFormat::BGRA, vec![1; 8])));
+    }
+
+    #[test]
+    fn tone_map_hdr_frame() {
+        // a 2x1 frame of half floats with 8 bytes of padding, 1.0 is 0x3C00, 2.0 is 0x4000 and 0.5 is 0x3800
+        let data = [
+            0x00, 0x3C, 0x00, 0x00, 0x00, 0x40, 0x00, 0x3C, //
+            0x00, 0x38, 0x00, 0x38, 0x00, 0x38, 0x00, 0x3C, //
+            9, 9, 9, 9, 9, 9, 9, 9,
+        ];
+        let frame = Frame::new(2, 1, 24, PixelFormat::RGBA16F, data.to_vec());
+        assert_eq!(frame.color_space, Some(ColorSpace::Scrgb));
+
+        //highlights above white are clipped, linear 0.5 is 188 once sRGB encoded
+        let sdr = tone_map_frame(frame.clone(), 1.0);
+        assert_eq!(sdr.format, PixelFormat::BGRA);
+        assert_eq!(sdr.color_space, Some(ColorSpace::Srgb));
+        assert_eq!(sdr.stride, 2 * 4);
+        assert_eq!(*sdr.data, vec![255, 0, 255, 255, 188, 188, 188, 255]);
+
+        //a higher white level darkens everything
+        let sdr = tone_map_frame(frame, 2.0);
+        assert_eq!(sdr.data[..4], [255, 0, 188, 255]);
+
+        //SDR frames are left as they are
+        let bgra = Frame::new(1, 1, 4, PixelFormat::BGRA, vec![1, 2, 3, 4]);
+        assert_eq!(*tone_map_frame(bgra, 1.0).data, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn reuse_pooled_buffers() {
+        let pool = BufferPool::new(1);
+
+        let mut buffer = pool.take(16);
+        buffer.extend_from_slice(&[1; 16]);
+        let ptr = buffer.as_ptr();
+        drop(buffer);
+
+        //the dropped buffer is handed out again, empty
+        let reused = pool.take(16);
+        assert!(reused.is_empty());
+        assert_eq!(reused.as_ptr(), ptr);
+
+        //the reused buffer is still held, so a new one is allocated
+        let held = pool.take(16);
+        assert_ne!(held.as_ptr(), ptr);
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[tokio::test]
+    async fn deliver_by_policy() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let receiver = tokio::sync::Mutex::new(receiver);
+
+        assert!(matches!(try_deliver(DeliveryPolicy::Block, &sender, &receiver, 1), Delivery::Sent { replaced: false }));
+
+        //the channel is full from here on
+        assert!(matches!(try_deliver(DeliveryPolicy::Block, &sender, &receiver, 2), Delivery::Wait(2)));
+        assert!(matches!(try_deliver(DeliveryPolicy::DropNewest, &sender, &receiver, 2), Delivery::Dropped { replaced: false }));
+        assert!(matches!(try_deliver(DeliveryPolicy::DropOldest, &sender, &receiver, 3), Delivery::Sent { replaced: true }));
+
+        {
+            //a locked receiver is about to read, the new output is dropped instead
+            let _locked = receiver.lock().await;
+            assert!(matches!(try_deliver(DeliveryPolicy::DropOldest, &sender, &receiver, 4), Delivery::Dropped { replaced: false }));
+        }
+
+        assert_eq!(receiver.lock().await.try_recv().ok(), Some(3));
+
+        //once the receiver is gone the caller waits on the channel to get the error
+        let unused = tokio::sync::Mutex::new(tokio::sync::mpsc::channel(1).1);
+        drop(receiver);
+        assert!(matches!(try_deliver(DeliveryPolicy::DropOldest, &sender, &unused, 5), Delivery::Wait(5)));
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[test]
+    fn count_replaced_frames() {
+        let stats = StatsCounters::default();
+
+        stats.add_delivered(2);
+        stats.add_replaced(1);
+        let snapshot = stats.snapshot();
+        assert_eq!((snapshot.delivered, snapshot.dropped), (1, 1));
+
+        //a replacement counted before its delivery does not wrap the delivered count
+        stats.add_replaced(2);
+        let snapshot = stats.snapshot();
+        assert_eq!((snapshot.delivered, snapshot.dropped), (0, 3));
+    }
+
+    #[cfg(any(feature = "monitor", feature = "camera"))]
     #[tokio::test]
-    async fn test_desktop_duplication() -> () {
-        unsafe {
-            let monitor_index = 1;
-            let monitor = Monitor::from_monitor(monitor_index);
+    async fn frame_channel_capacity() {
+        assert!(check_channel_capacity(0).is_err());
+        assert!(check_channel_capacity(3).is_ok());
 
-            assert!(
-                monitor.is_ok(),
-                "Desktop Duplicator failed: {:?}",
-                monitor.err()
-            );
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(3);
+        assert_eq!(queued(&sender), 0);
 
-            let monitor = monitor.unwrap();
+        sender.send(1).await.unwrap();
+        sender.send(2).await.unwrap();
+        assert_eq!(queued(&sender), 2);
 
-            let monitor_clone = monitor.clone();
+        //a slow receiver can fall behind by the capacity before the sender has to wait
+        sender.send(3).await.unwrap();
+        assert!(sender.try_send(4).is_err());
 
-            let recv = monitor_clone.receiver.clone();
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(queued(&sender), 2);
+    }
 
-            tokio::spawn(async move {
-                let mut recv = recv.lock().await;
+    #[cfg(any(feature = "monitor", feature = "camera"))]
+    #[tokio::test]
+    async fn take_receiver_once() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let shared = tokio::sync::Mutex::new(receiver);
+
+        {
+            //not handed out while someone holds the shared receiver
+            let _locked = shared.lock().await;
+            assert!(take_receiver(&shared).is_none());
+        }
 
-                loop {
-                    let data = recv.recv().await;
+        let mut owned = take_receiver(&shared).unwrap();
+        assert!(take_receiver(&shared).is_none());
 
-                    assert!(data.is_some());
+        sender.send(1).await.unwrap();
+        assert_eq!(owned.recv().await, Some(1));
+        assert_eq!(shared.lock().await.recv().await, None);
+    }
 
-                    let data = data.unwrap();
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn pack_padded_rows() {
+        // a 2x2 frame with a row pitch of 12, the last 4 bytes of each row are padding
+        let data = [
+            1, 1, 1, 1, 2, 2, 2, 2, 9, 9, 9, 9, //
+            3, 3, 3, 3, 4, 4, 4, 4, 9, 9, 9, 9,
+        ];
+
+        let mut packed = vec![];
+        pack_rows(&data, 12, 2 * 4, 2, &mut packed);
+
+        assert_eq!(packed.len(), 2 * 2 * 4);
+        assert_eq!(packed, vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4]);
+    }
 
-                    let mut had_data = false;
-                    for d in &data {
-                        if *d != 0 {
-                            had_data = true;
-                            break;
-                        }
-                    }
+    #[cfg(feature = "monitor")]
+    #[test]
+    fn convert_padded_rows() {
+        // a 2x1 BGRA frame with a row pitch of 12, the last 4 bytes are padding
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9, 9, 9, 9];
+        let conversion = |format, opaque_alpha, gray_weights| PixelConversion { format, opaque_alpha, gray_weights };
+
+        let mut rgba = vec![];
+        convert_rows(&data, 12, 2, 1, conversion(PixelFormat::RGBA, false, GrayWeights::default()), &mut rgba);
+        assert_eq!(rgba, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+
+        let mut rgb = vec![];
+        convert_rows(&data, 12, 2, 1, conversion(PixelFormat::RGB24, false, GrayWeights::default()), &mut rgb);
+        assert_eq!(rgb, vec![3, 2, 1, 7, 6, 5]);
+
+        //BGRA is only packed
+        let mut bgra = vec![];
+        convert_rows(&data, 12, 2, 1, conversion(PixelFormat::BGRA, false, GrayWeights::default()), &mut bgra);
+        assert_eq!(bgra, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        //opaque alpha is set while converting
+        let mut opaque = vec![];
+        convert_rows(&data, 12, 2, 1, PixelConversion { opaque_alpha: true, ..conversion(PixelFormat::BGRA, false, GrayWeights::default()) }, &mut opaque);
+        assert_eq!(opaque, vec![1, 2, 3, 255, 5, 6, 7, 255]);
+
+        let mut opaque = vec![];
+        convert_rows(&data, 12, 2, 1, PixelConversion { opaque_alpha: true, ..conversion(PixelFormat::RGBA, false, GrayWeights::default()) }, &mut opaque);
+        assert_eq!(opaque, vec![3, 2, 1, 255, 7, 6, 5, 255]);
+
+        let mut gray = vec![];
+        convert_rows(&data, 12, 2, 1, conversion(PixelFormat::Gray8, false, GrayWeights::default()), &mut gray);
+        assert_eq!(gray, vec![2, 6]);
+
+        //pure red and white weighed both ways, white stays white
+        let data = [0, 0, 255, 255, 255, 255, 255, 255];
+
+        let mut gray = vec![];
+        convert_rows(&data, 8, 2, 1, PixelConversion { gray_weights: GrayWeights::Bt601, ..conversion(PixelFormat::Gray8, false, GrayWeights::default()) }, &mut gray);
+        assert_eq!(gray, vec![77, 255]);
+
+        let mut gray = vec![];
+        convert_rows(&data, 8, 2, 1, PixelConversion { gray_weights: GrayWeights::Bt709, ..conversion(PixelFormat::Gray8, false, GrayWeights::default()) }, &mut gray);
+        assert_eq!(gray, vec![54, 255]);
+    }
 
-                    if had_data {
-                        break;
-                    }
-                }
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn monitor_output_format() {
+        let monitor = Monitor::from_monitor(0).unwrap();
 
-                let stopped = monitor_clone.stop_capturing().await;
-                assert!(stopped.is_ok());
-            });
+        assert!(monitor.set_output_format(PixelFormat::NV12).is_err());
+        assert!(monitor.set_output_format(PixelFormat::RGB24).is_ok());
+        assert_eq!(monitor.get_output_format(), PixelFormat::RGB24);
+
+        let handle = monitor.clone().spawn_capturing();
+
+        {
+            let recv = monitor.clone_receiver();
+            let frame = recv.lock().await.recv().await.unwrap();
 
-            let clone = monitor.start_capturing().await;
+            //HDR monitors are not converted
+            if frame.format != PixelFormat::RGBA16F {
+                assert_eq!(frame.format, PixelFormat::RGB24);
+                assert_eq!(frame.stride, frame.width * 3);
+                assert_eq!(frame.data.len(), (frame.stride * frame.height) as usize);
+            }
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        //gray frames are a byte per pixel
+        assert!(monitor.set_output_format(PixelFormat::Gray8).is_ok());
+        monitor.set_gray_weights(GrayWeights::Bt709);
 
-            assert!(clone.is_ok(), "{clone:?}");
+        let frame = monitor.capture_frame(std::time::Duration::from_secs(2)).await.unwrap();
+
+        if frame.format != PixelFormat::RGBA16F {
+            assert_eq!(frame.format, PixelFormat::Gray8);
+            assert_eq!(frame.stride, frame.width);
+            assert_eq!(frame.data.len(), (frame.width * frame.height) as usize);
         }
     }
 
+    #[cfg(feature = "camera")]
     #[test]
     fn find_video_devices() -> () {
         unsafe {
@@ -116,6 +2690,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "camera")]
     #[test]
     fn collect_devices() {
         unsafe {
@@ -141,6 +2716,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "camera")]
     #[test]
     fn test_activation() {
         unsafe {
@@ -165,6 +2741,820 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "camera")]
+    #[test]
+    fn reads_without_a_sample() {
+        //a stream tick is a gap at its time, 100 ns units
+        let gap = SampleRead::without_sample(MF_SOURCE_READERF_STREAMTICK.0 as u32, 333_333);
+        assert_eq!(gap, SampleRead::Gap { timestamp: Some(std::time::Duration::from_nanos(33_333_300)) });
+
+        //times before the start of the stream are left out
+        let gap = SampleRead::without_sample(MF_SOURCE_READERF_STREAMTICK.0 as u32, -1);
+        assert_eq!(gap, SampleRead::Gap { timestamp: None });
+
+        assert_eq!(SampleRead::without_sample(MF_SOURCE_READERF_ENDOFSTREAM.0 as u32, 0), SampleRead::EndOfStream);
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_take_photo() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+
+        //the photo is at the largest photo size, a camera without a photo stream takes a frame of its video stream
+        let photo = camera.take_photo(std::time::Duration::from_secs(5)).await.unwrap();
+        let size = camera.max_photo_size().unwrap_or(camera.get_dimensions().unwrap());
+        assert_eq!((photo.width, photo.height), (size.width, size.height));
+        assert!(!photo.data.is_empty());
+        assert_eq!(camera.has_photo_stream(), !camera.photo_formats().is_empty());
+
+        //the video stream is captured as usual afterwards
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+        let frame = receiver.recv().await.unwrap();
+        assert_eq!(Dimensions { width: frame.width, height: frame.height }, camera.get_dimensions().unwrap());
+
+        assert!(camera.take_photo(std::time::Duration::from_secs(1)).await.is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_controls() {
+        use crate::devices::{CameraProperty, ControlError};
+
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        let controls = camera.controls();
+
+        for property in CameraProperty::ALL {
+            let range = match controls.range(property) {
+                Ok(range) => range,
+                //a property the camera does not have fails on its own
+                Err(e) => {
+                    assert!(matches!(e, ControlError::Unsupported(p) if p == property));
+                    assert!(!controls.is_supported(property));
+                    continue;
+                }
+            };
+
+            assert!(range.min <= range.max);
+            let current = controls.get(property).unwrap();
+
+            controls.set(property, range.default).unwrap();
+            let value = controls.get(property).unwrap();
+            assert_eq!(value.value, range.default);
+            assert!(!value.auto);
+
+            if range.max < i32::MAX {
+                assert!(matches!(
+                    controls.set(property, range.max + 1),
+                    Err(ControlError::OutOfRange { .. })
+                ));
+            }
+
+            if range.supports_auto {
+                controls.set_auto(property, true).unwrap();
+                assert!(controls.get(property).unwrap().auto);
+            } else {
+                assert!(matches!(
+                    controls.set_auto(property, true),
+                    Err(ControlError::AutoUnsupported(_))
+                ));
+            }
+
+            //leave the camera as it was
+            let _ = controls.set(property, current.value);
+            let _ = controls.set_auto(property, current.auto);
+        }
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_ptz_ranges() {
+        use crate::devices::{CameraProperty, ControlError};
+
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        let controls = camera.controls();
+
+        //most webcams can not move, their ranges are refused without the camera failing
+        for property in [CameraProperty::Pan, CameraProperty::Tilt, CameraProperty::Zoom, CameraProperty::Roll] {
+            match controls.range(property) {
+                Ok(range) => assert!(range.min <= range.max),
+                Err(e) => assert!(matches!(e, ControlError::Unsupported(_))),
+            }
+
+            match controls.relative_range(property) {
+                Ok(range) => assert!(range.min <= range.max),
+                Err(e) => assert!(matches!(e, ControlError::RelativeUnsupported(_))),
+            }
+        }
+
+        assert!(matches!(
+            controls.move_relative(CameraProperty::Brightness, 1),
+            Err(ControlError::RelativeUnsupported(CameraProperty::Brightness))
+        ));
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_focus_while_capturing() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        let controls = camera.controls();
+
+        //fixed focus webcams have no focus to set
+        let Ok(range) = controls.focus_range() else {
+            return;
+        };
+        let before = controls.focus().unwrap();
+
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+        assert!(receiver.recv().await.is_some());
+
+        //the focus is set on the device, the capture keeps sending frames
+        controls.set_focus(range.min).unwrap();
+        let focus = controls.focus().unwrap();
+        assert!(!focus.auto);
+        assert!(range.contains(focus.value));
+        assert!(receiver.recv().await.is_some());
+
+        if range.supports_auto {
+            controls.set_focus_auto(true).unwrap();
+            assert!(controls.focus().unwrap().auto);
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let _ = controls.set(crate::devices::CameraProperty::Focus, before.value);
+        let _ = controls.set_focus_auto(before.auto);
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_rgb32_stride() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], Some(Output::RGB32)).unwrap();
+        assert!(camera.get_flip_bottom_up());
+
+        let negotiated = camera.negotiated_format();
+        assert!(negotiated.format.width * 4 <= negotiated.stride);
+
+        //the frames are top down whichever way the driver sends them, with the negotiated stride
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(frame.stride, negotiated.stride);
+        assert!(frame.width * 4 <= frame.stride);
+        assert!(frame.data.len() >= (frame.stride * (frame.height - 1) + frame.width * 4) as usize);
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_activate_by_symbolic_link() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let info = devices.device_info();
+        assert_eq!(info[0].index, 0);
+        assert_eq!(info[0].name, get_device_name(devices.devices[0]).unwrap());
+
+        //the links are unique even for cameras with the same name
+        let mut links: Vec<&str> = info.iter().map(|info| info.symbolic_link.as_str()).collect();
+        links.sort();
+        links.dedup();
+        assert_eq!(links.len(), info.len());
+
+        let camera = Cameras::activate_by_symbolic_link(&info[0].symbolic_link, Some(Output::RGB32)).unwrap();
+        assert_eq!(camera.symbolic_link(), Some(info[0].symbolic_link.as_str()));
+        assert!(camera.is_connected());
+
+        assert!(Cameras::activate_by_symbolic_link("\\\\?\\not-a-camera", None).is_err());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_watch() {
+        use crate::devices::DeviceEvent;
+
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let info = devices.device_info();
+
+        let mut watcher = Cameras::watch().unwrap();
+        assert!(watcher.is_watching());
+
+        //every camera plugged in is sent once when watching starts
+        let mut added = vec![];
+        for _ in 0..info.len() {
+            match watcher.receiver.recv().await {
+                Some(DeviceEvent::Added(device)) => added.push(device.symbolic_link),
+                event => panic!("expected an added camera, got {event:?}"),
+            }
+        }
+
+        let mut links: Vec<String> = info.into_iter().map(|info| info.symbolic_link).collect();
+        links.sort();
+        added.sort();
+        assert_eq!(added, links);
+
+        //the receiver ends once the watcher stopped, stopping twice does nothing
+        watcher.stop();
+        watcher.stop();
+        assert!(!watcher.is_watching());
+        assert!(watcher.receiver.recv().await.is_none());
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn capture_error_busy() {
+        use crate::devices::CaptureError;
+        use windows::Win32::{
+            Foundation::{E_FAIL, ERROR_SHARING_VIOLATION},
+            Media::MediaFoundation::MF_E_HW_MFT_FAILED_START_STREAMING,
+        };
+
+        assert!(CaptureError::is_busy(&MF_E_HW_MFT_FAILED_START_STREAMING.into()));
+        assert!(CaptureError::is_busy(&ERROR_SHARING_VIOLATION.to_hresult().into()));
+        assert!(!CaptureError::is_busy(&E_FAIL.into()));
+
+        //a busy camera is named in the error, anything else is kept as it is
+        let busy = CaptureError::from_error(ERROR_SHARING_VIOLATION.to_hresult().into(), "HD Webcam");
+        assert!(matches!(&busy, CaptureError::DeviceBusy { name, .. } if name == "HD Webcam"));
+        assert_eq!(busy.to_string(), "HD Webcam is in use by another application");
+        assert_eq!(busy.windows_error().code(), ERROR_SHARING_VIOLATION.to_hresult());
+
+        let failed = CaptureError::from_error(E_FAIL.into(), "HD Webcam");
+        assert!(matches!(failed, CaptureError::Failed(_)));
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_auto_reconnect() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.camera_builder(0).unwrap().auto_reconnect(std::time::Duration::from_secs(10)).build().unwrap();
+        assert_eq!(camera.get_auto_reconnect(), Some(std::time::Duration::from_secs(10)));
+
+        //a camera that stays plugged in captures as usual
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+        assert!(receiver.recv().await.is_some());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        camera.set_auto_reconnect(None);
+        assert_eq!(camera.get_auto_reconnect(), None);
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_streams() {
+        use crate::devices::StreamKind;
+
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], Some(Output::RGB32)).unwrap();
+        assert_eq!(camera.selected_stream(), None);
+
+        //the first video stream is the only one read
+        let streams = camera.streams();
+        let video = streams.iter().find(|stream| stream.major_type == MFMediaType_Video).unwrap();
+        assert!(video.selected);
+        assert_eq!(streams.iter().filter(|stream| stream.selected).count(), 1);
+        assert!(streams.iter().enumerate().all(|(index, stream)| stream.index == index as u32));
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(frame.stream_index, Some(video.index));
+
+        //selecting the same stream by its index reads it as before
+        camera.select_stream(video.index).unwrap();
+        assert_eq!(camera.selected_stream(), Some(video.index));
+        assert_eq!(camera.negotiated_format().pixel_format, PixelFormat::RGB32);
+
+        //streams that are not video can not be selected, the selected one is kept
+        if let Some(other) = streams.iter().find(|stream| stream.kind == StreamKind::Other && stream.major_type != MFMediaType_Video) {
+            assert!(camera.select_stream(other.index).is_err());
+        }
+        assert!(camera.select_stream(u32::MAX - 16).is_err());
+        assert_eq!(camera.selected_stream(), Some(video.index));
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(frame.stream_index, Some(video.index));
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_group_capture() {
+        use crate::{capture_manager::SourceEvent, devices::CameraGroup};
+
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        assert!(CameraGroup::activate(&devices, &[devices.devices.len()], None).is_err());
+        assert!(CameraGroup::activate(&devices, &[], None).is_err());
+
+        let group = CameraGroup::activate(&devices, &[0], Some(Output::RGB32)).unwrap();
+        let mut matcher = FrameMatcher::new(group.cameras().len(), std::time::Duration::from_millis(20));
+
+        let started = group.start_all().await;
+        assert!(started.is_ok(), "{started:?}");
+        assert!(group.start_all().await.is_err());
+
+        {
+            let recv = group.clone_receiver();
+            let mut recv = recv.lock().await;
+
+            //with a single camera every frame matches on its own
+            for _ in 0..3 {
+                match recv.recv().await.unwrap() {
+                    SourceEvent::Frame { source, frame } => {
+                        assert_eq!(source, 0);
+                        let captured = group.capture_instant(source, &frame);
+                        assert!(matcher.push(source, frame, captured).is_some());
+                    }
+                    SourceEvent::Stopped { error, .. } => panic!("camera stopped: {error:?}"),
+                }
+            }
+        }
+
+        let stopped = group.stop_all().await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+        assert!(group.stop_all().await.is_err());
+
+        //a camera whose receiver is held elsewhere does not hold up the group, and it can still be stopped
+        let held = group.cameras()[0].receiver.clone();
+        let held = held.lock().await;
+
+        group.start_all().await.unwrap();
+        let stopped = tokio::time::timeout(std::time::Duration::from_secs(2), group.stop_all()).await;
+        assert!(matches!(stopped, Ok(Ok(()))));
+
+        drop(held);
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_gpu_conversion() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+
+        //only RGB32 and ARGB32 are converted on the GPU
+        let nv12 = devices.camera_builder(0).unwrap().output(Output::NV12).gpu_conversion(true).build();
+        assert!(nv12.err().unwrap().to_string().contains("NV12"));
+
+        let off = devices.camera_builder(0).unwrap().output(Output::RGB32).gpu_conversion(true).video_processing(false).build();
+        assert!(off.is_err());
+
+        //without a D3D11 video device the camera converts on the CPU, either way frames arrive as RGB32
+        let camera = devices.camera_builder(0).unwrap().output(Output::RGB32).gpu_conversion(true).build().unwrap();
+        println!("converting on the GPU: {}", camera.is_gpu_converting());
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(frame.format, PixelFormat::RGB32);
+        assert_eq!(frame.data.len(), frame.stride as usize * frame.height as usize);
+
+        let cpu = devices.camera_builder(0).unwrap().output(Output::RGB32).build().unwrap();
+        assert!(!cpu.is_gpu_converting());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_transforms() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+
+        //a CLSID nothing is registered under fails building, naming the transform
+        let missing = CameraTransform::Clsid(windows::core::GUID::from_u128(0x1234));
+        let error = devices.camera_builder(0).unwrap().transform(missing.clone()).build().err().unwrap();
+        assert!(error.to_string().contains(&missing.name()), "{error}");
+        assert!(error.to_string().contains("transform 0"), "{error}");
+
+        //the video processor of Windows passes NV12 frames through
+        let camera = devices
+            .camera_builder(0)
+            .unwrap()
+            .output(Output::NV12)
+            .transform(CameraTransform::Clsid(CLSID_VideoProcessorMFT))
+            .build()
+            .unwrap();
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(frame.format, camera.negotiated_format().pixel_format);
+        assert!(!frame.data.is_empty());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_orientation() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let orientation = Orientation { mirror_horizontal: true, rotation: Rotation::Rotate90, ..Orientation::default() };
+
+        let camera = devices.camera_builder(0).unwrap().output(Output::RGB32).orientation(orientation).build().unwrap();
+        assert_eq!(camera.get_orientation(), orientation);
+
+        //the reader sends the camera's size, frames and get_dimensions are rotated
+        let negotiated = camera.negotiated_format();
+        assert_eq!(camera.get_dimensions().unwrap(), Dimensions { width: negotiated.format.height, height: negotiated.format.width });
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!((frame.width, frame.height), (negotiated.format.height, negotiated.format.width));
+        assert_eq!(frame.stride, frame.width * 4);
+
+        //turning the rotation back sends the camera's size again
+        camera.set_orientation(Orientation::default()).unwrap();
+        assert_eq!(camera.get_dimensions().unwrap(), Dimensions { width: negotiated.format.width, height: negotiated.format.height });
+        let events = camera.events();
+        let mut events = events.lock().await;
+        let mut changed = false;
+        while let Ok(event) = events.try_recv() {
+            changed |= matches!(event, crate::devices::CaptureEvent::DimensionsChanged { .. });
+        }
+        assert!(changed);
+
+        //YUY2 frames can not be oriented
+        let yuy2 = devices.camera_builder(0).unwrap().output(Output::YUY2).orientation(orientation).build();
+        assert!(yuy2.is_err());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_shutdown() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+
+        //a dropped camera releases the device, so it can be activated again right away
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        camera.capture_frame(timeout).await.unwrap();
+        drop(camera);
+
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        camera.capture_frame(timeout).await.unwrap();
+
+        //shutting down stops the capture and the camera can not be used afterwards
+        let capture = camera.clone().spawn_capturing();
+        camera.receiver.lock().await.recv().await.unwrap();
+
+        camera.shutdown().await.unwrap();
+        assert!(camera.is_shut_down());
+        assert!(!camera.is_running());
+        let _ = capture.wait().await;
+
+        assert!(camera.capture_frame(timeout).await.is_err());
+        assert!(camera.clone().start_capturing().await.is_err());
+        camera.shutdown().await.unwrap();
+
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        camera.capture_frame(timeout).await.unwrap();
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_warmup_frames() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.camera_builder(0).unwrap().warmup_frames(5).build().unwrap();
+        assert_eq!(camera.get_warmup_frames(), 5);
+
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+
+        //the first frame sent comes after the warm-up frames, which are not dropped frames
+        assert!(receiver.recv().await.is_some());
+        let stats = camera.stats();
+        assert_eq!(stats.warmup, 5);
+        assert_eq!(stats.dropped, 0);
+        assert!(stats.captured > stats.warmup);
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        //every capture warms up again, frames left from the first one are read first
+        while receiver.try_recv().is_ok() {}
+        let handle = camera.clone().spawn_capturing();
+        assert!(receiver.recv().await.is_some());
+        assert_eq!(camera.stats().warmup, 10);
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_capture_frame() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], Some(Output::RGB32)).unwrap();
+        camera.set_warmup_frames(5);
+        assert_eq!(camera.get_warmup_frames(), 5);
+
+        let frame = camera.capture_frame(std::time::Duration::from_secs(5)).await.unwrap();
+        let negotiated = camera.negotiated_format();
+        assert_eq!((frame.width, frame.height), (negotiated.format.width, negotiated.format.height));
+        assert_eq!(frame.format, PixelFormat::RGB32);
+        assert!(!frame.data.is_empty());
+
+        //no camera sends a frame this quickly, the read is cancelled and the camera is left idle
+        assert!(camera.capture_frame(std::time::Duration::from_nanos(1)).await.is_err());
+
+        //capturing works as usual afterwards
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+        assert!(receiver.recv().await.is_some());
+
+        //a frame can not be taken while capturing
+        assert!(camera.capture_frame(std::time::Duration::from_secs(1)).await.is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_builder() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        assert!(devices.camera_builder(devices.devices.len()).is_err());
+
+        //conflicting options fail before the camera is activated
+        let both = devices.camera_builder(0).unwrap().format(CameraFormat::new(640, 480, 30, 1)).frame_rate(15, 1).build();
+        assert!(both.err().unwrap().to_string().contains("640x480 at 15 fps"));
+
+        let mjpg = devices.camera_builder(0).unwrap().output(Output::MJPG).video_processing(true).build();
+        assert!(mjpg.err().unwrap().to_string().contains("MJPG"));
+
+        assert!(devices.camera_builder(0).unwrap().channel_capacity(0).build().is_err());
+
+        //the camera is activated with every option applied
+        let format = devices.activate_device(devices.devices[0], None).unwrap().supported_formats()[0];
+        let camera = devices
+            .camera_builder(0)
+            .unwrap()
+            .output(Output::RGB32)
+            .format(format)
+            .hardware_transforms(false)
+            .channel_capacity(4)
+            .build()
+            .unwrap();
+
+        assert_eq!(camera.output, Output::RGB32);
+        assert_eq!(camera.negotiated_format().pixel_format, PixelFormat::RGB32);
+        assert_eq!(camera.get_dimensions().unwrap(), Dimensions { width: format.width, height: format.height });
+        assert_eq!(camera.symbolic_link().unwrap(), get_device_symbolic_link(devices.devices[0]).unwrap());
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_buffer_unlocked() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let buffer = unsafe { MFCreateMemoryBuffer(16) }.unwrap();
+
+        unsafe {
+            let mut data: *mut u8 = std::ptr::null_mut();
+            buffer.Lock(&mut data, None, None).unwrap();
+            std::ptr::copy_nonoverlapping([1u8, 2, 3, 4].as_ptr(), data, 4);
+            buffer.Unlock().unwrap();
+            buffer.SetCurrentLength(4).unwrap();
+        }
+
+        //each copy unlocks the buffer again, so it can be copied and locked any number of times
+        assert_eq!(Camera::get_frame_data(&buffer).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(Camera::get_frame_data(&buffer).unwrap(), vec![1, 2, 3, 4]);
+        assert!(unsafe { buffer.SetCurrentLength(2) }.is_ok());
+        assert_eq!(Camera::get_frame_data(&buffer).unwrap(), vec![1, 2]);
+
+        //the samples of a camera are read one after another without MF_E_INVALIDREQUEST
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+
+        for _ in 0..30 {
+            let read = camera.read_sample(None);
+            assert!(read.is_ok(), "{read:?}");
+        }
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_restart_after_error() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+
+        //frames can not be sent once the receiver is gone, the capture fails on the first one
+        drop(camera.take_receiver().unwrap());
+        let failed = camera.clone().spawn_capturing().wait().await;
+        assert!(failed.is_err());
+        assert!(!camera.is_running());
+
+        //nothing is capturing anymore, so there is nothing to stop
+        assert!(camera.clone().stop_capturing().await.is_err());
+
+        //the camera starts again, sending to subscribers only
+        camera.set_receiver_output(false);
+        let mut frames = camera.subscribe();
+        let handle = camera.clone().spawn_capturing();
+        assert!(frames.recv().await.is_ok());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_stop_and_wait() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        let mut receiver = camera.take_receiver().unwrap();
+
+        let handle = camera.clone().spawn_capturing();
+        assert!(receiver.recv().await.is_some());
+
+        //the read the loop is waiting on is flushed, the loop ends without waiting for the next frame
+        let started = std::time::Instant::now();
+        let stopped = camera.stop_and_wait(std::time::Duration::from_secs(2)).await;
+        assert!(stopped.is_ok(), "{stopped:?}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        assert!(!camera.is_running());
+
+        let finished = handle.wait().await;
+        assert!(finished.is_ok(), "{finished:?}");
+
+        //stopping again fails, nothing is capturing
+        assert!(camera.stop_and_wait(std::time::Duration::from_secs(2)).await.is_err());
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_reconnect() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], Some(Output::RGB32)).unwrap();
+        assert_eq!(camera.symbolic_link().unwrap(), get_device_symbolic_link(devices.devices[0]).unwrap());
+        assert!(camera.is_connected());
+
+        let handle = camera.clone().spawn_capturing();
+
+        {
+            let recv = camera.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        //both would send through the same receiver
+        assert!(camera.reconnect().is_err());
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        let reconnected = camera.reconnect().unwrap();
+        assert_eq!(reconnected.output, camera.output);
+        assert_eq!(reconnected.negotiated_format(), camera.negotiated_format());
+        assert!(std::sync::Arc::ptr_eq(&reconnected.receiver, &camera.receiver));
+
+        //frames of the new camera arrive through the receiver of the old one
+        let handle = reconnected.clone().spawn_capturing();
+
+        {
+            let recv = camera.clone_receiver();
+            assert!(recv.lock().await.recv().await.is_some());
+        }
+
+        assert!(handle.stop().await.is_ok());
+        assert!(handle.wait().await.is_ok());
+
+        //the stream failing is the camera being lost as well
+        let lost = windows::core::Error::from(MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED);
+        assert!(crate::devices::CaptureEvent::is_device_lost(&lost));
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn activate_with_format() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let formats = devices.activate_device(devices.devices[0], None).unwrap().supported_formats();
+        assert!(!formats.is_empty());
+
+        //the camera sends the largest of its formats once asked to
+        let largest = *formats.iter().max_by_key(|format| format.width * format.height).unwrap();
+        let camera = devices.activate_device_with_format(devices.devices[0], Some(Output::RGB32), &largest).unwrap();
+        assert_eq!(camera.get_dimensions().unwrap(), Dimensions { width: largest.width, height: largest.height });
+        drop(camera);
+
+        //a format the camera does not have names the ones it has
+        let error = devices.activate_device_with_format(devices.devices[0], None, &CameraFormat::new(1, 1, 1000, 1)).err().unwrap();
+        assert!(error.message().contains(&formats[0].to_string()), "{error}");
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn camera_frame_rate() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        let size = camera.get_dimensions().unwrap();
+
+        //every rate the camera has at its size can be set and read back
+        for format in camera.supported_formats().iter().filter(|format| format.width == size.width && format.height == size.height) {
+            camera.set_frame_rate(format.frame_rate_numerator, format.frame_rate_denominator).unwrap();
+            assert!((camera.frame_rate().unwrap() - format.frame_rate()).abs() <= 0.5);
+        }
+
+        //a rate no camera has names the closest one
+        let error = camera.set_frame_rate(1000, 1).unwrap_err();
+        assert!(error.to_string().contains("the closest it has is"), "{error}");
+    }
+
+    #[cfg(feature = "camera")]
+    #[test]
+    fn negotiated_camera_format() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], Some(Output::RGB32)).unwrap();
+        let negotiated = camera.negotiated_format();
+
+        //the reader converts to RGB32, with rows of at least 4 bytes a pixel
+        assert_eq!(negotiated.pixel_format, PixelFormat::RGB32);
+        assert!(negotiated.stride >= negotiated.format.width * 4);
+        assert_eq!(camera.get_stride().unwrap(), negotiated.stride);
+
+        let size = camera.get_dimensions().unwrap();
+        assert_eq!((negotiated.format.width, negotiated.format.height), (size.width, size.height));
+        println!("{negotiated:?}");
+    }
+
+    #[cfg(feature = "camera")]
+    #[tokio::test]
+    async fn camera_sample_times() {
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+        let devices = Cameras::new().unwrap();
+        let camera = devices.activate_device(devices.devices[0], None).unwrap();
+        assert!(camera.sample_instant(std::time::Duration::ZERO).is_none());
+
+        let mut receiver = camera.take_receiver().unwrap();
+        let handle = camera.clone().spawn_capturing();
+
+        //the sample times keep increasing, stream ticks without a sample are not sent
+        let mut last = None;
+
+        for _ in 0..3 {
+            let frame = receiver.recv().await.unwrap();
+            assert!(!frame.data.is_empty());
+            assert!(!frame.dimensions_changed);
+
+            let sample_time = frame.sample_time.unwrap();
+            assert!(last < Some(sample_time), "{last:?} {sample_time:?}");
+            last = Some(sample_time);
+
+            //samples are captured before they are received
+            let instant = camera.sample_instant(sample_time).unwrap();
+            assert!(instant <= std::time::Instant::now() + std::time::Duration::from_secs(1));
+            assert!(camera.sample_system_time(sample_time).is_some());
+        }
+
+        handle.stop().await.unwrap();
+
+        //keep receiving so the capture is not stuck sending its last frame
+        while handle.is_running() {
+            let _ = tokio::time::timeout(std::time::Duration::from_millis(10), receiver.recv()).await;
+        }
+    }
+
+    #[cfg(feature = "camera")]
     #[tokio::test]
     async fn capture_image() {
         unsafe {
@@ -203,7 +3593,15 @@ mod tests {
 
                     let data = data.unwrap();
 
-                    if data.len() > 0 {
+                    if data.data.len() > 0 {
+                        //NV12 has a half height chroma plane after the luma plane
+                        let expected_len = match data.format {
+                            PixelFormat::NV12 => data.stride * data.height * 3 / 2,
+                            _ => data.stride * data.height,
+                        };
+
+                        assert_eq!(data.data.len(), expected_len as usize);
+
                         let stopped = activated_device_clone.stop_capturing().await;
 
                         assert!(stopped.is_ok());
@@ -0,0 +1,138 @@
+use crate::frame::{Frame, PixelFormat};
+
+/// # Wgpu Format
+///
+/// The wgpu texture format a frame of the pixel format is uploaded as, None for formats wgpu has no texture format for (24 bit, YUV and MJPG).
+///
+/// RGB32 is uploaded as Bgra8Unorm, its unused byte becomes the alpha. Gray16 and Depth16 are uploaded as R16Uint, read them with an unsigned integer sampler.
+pub fn wgpu_format(format: PixelFormat) -> Option<wgpu::TextureFormat> {
+    match format {
+        PixelFormat::BGRA | PixelFormat::RGB32 => Some(wgpu::TextureFormat::Bgra8Unorm),
+        PixelFormat::RGBA => Some(wgpu::TextureFormat::Rgba8Unorm),
+        PixelFormat::RGBA16F => Some(wgpu::TextureFormat::Rgba16Float),
+        PixelFormat::Gray8 => Some(wgpu::TextureFormat::R8Unorm),
+        PixelFormat::Gray16 | PixelFormat::Depth16 => Some(wgpu::TextureFormat::R16Uint),
+        PixelFormat::NV12
+        | PixelFormat::RGB24
+        | PixelFormat::BGR24
+        | PixelFormat::YUY2
+        | PixelFormat::I420
+        | PixelFormat::MJPG => None,
+    }
+}
+
+/// # Frame Texture
+///
+/// A wgpu texture that frames are uploaded to, kept between frames so a texture is not created for each one.
+///
+/// The texture is only recreated when the size or format of the frames changes, check texture after each upload when holding bind groups or views of it.
+pub struct FrameTexture {
+    texture: Option<wgpu::Texture>,
+    usage: wgpu::TextureUsages,
+    label: Option<String>,
+}
+
+impl FrameTexture {
+    /// # New
+    ///
+    /// Creates a frame texture with the usages the texture is created with, COPY_DST is always added so frames can be written to it.
+    ///
+    /// No texture is created until the first frame is uploaded.
+    pub fn new(usage: wgpu::TextureUsages) -> Self {
+        FrameTexture {
+            texture: None,
+            usage: usage | wgpu::TextureUsages::COPY_DST,
+            label: None,
+        }
+    }
+
+    /// # With Label
+    ///
+    /// Sets the debug label of the texture.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// # Texture
+    ///
+    /// The texture the last frame was uploaded to, None before the first upload.
+    pub fn texture(&self) -> Option<&wgpu::Texture> {
+        self.texture.as_ref()
+    }
+
+    /// # Upload
+    ///
+    /// Writes the frame to the texture, creating it first when there is none yet or the frame is a different size or format.
+    ///
+    /// The rows are written straight from the frame data with its stride, so frames with row padding (see Monitor::set_strip_padding) are uploaded without being repacked.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        frame: &Frame,
+    ) -> Result<&wgpu::Texture, Box<dyn std::error::Error>> {
+        let Some(format) = wgpu_format(frame.format) else {
+            return Err(format!(
+                "{:?} frames can not be uploaded to a wgpu texture, convert them first",
+                frame.format
+            )
+            .into());
+        };
+
+        if frame.width == 0 || frame.height == 0 {
+            return Err("the frame is empty".into());
+        }
+
+        let row_bytes = frame.width as usize * frame.format.bytes_per_pixel() as usize;
+        let needed = frame.stride as usize * (frame.height as usize - 1) + row_bytes;
+
+        if (frame.stride as usize) < row_bytes || frame.data.len() < needed {
+            return Err(format!(
+                "the frame has {} bytes with a stride of {}, {needed} are needed for its size",
+                frame.data.len(),
+                frame.stride
+            )
+            .into());
+        }
+
+        let size = wgpu::Extent3d {
+            width: frame.width,
+            height: frame.height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = match self.texture.take() {
+            Some(texture) if texture.size() == size && texture.format() == format => texture,
+            _ => device.create_texture(&wgpu::TextureDescriptor {
+                label: self.label.as_deref(),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: self.usage,
+                view_formats: &[],
+            }),
+        };
+
+        //writes from the queue have no row alignment, unlike buffer copies, so the stride is used as it is
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &frame.data[..needed],
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(frame.stride),
+                rows_per_image: Some(frame.height),
+            },
+            size,
+        );
+
+        Ok(self.texture.insert(texture))
+    }
+}